@@ -0,0 +1,118 @@
+//! Сумма прописью для счетов и квитанций: "Одна тысяча двести рублей 50 копеек"
+
+const UNITS_MASCULINE: [&str; 10] = [
+    "", "один", "два", "три", "четыре", "пять", "шесть", "семь", "восемь", "девять",
+];
+const UNITS_FEMININE: [&str; 10] = [
+    "", "одна", "две", "три", "четыре", "пять", "шесть", "семь", "восемь", "девять",
+];
+const TEENS: [&str; 10] = [
+    "десять",
+    "одиннадцать",
+    "двенадцать",
+    "тринадцать",
+    "четырнадцать",
+    "пятнадцать",
+    "шестнадцать",
+    "семнадцать",
+    "восемнадцать",
+    "девятнадцать",
+];
+const TENS: [&str; 10] = [
+    "", "", "двадцать", "тридцать", "сорок", "пятьдесят", "шестьдесят", "семьдесят", "восемьдесят",
+    "девяносто",
+];
+const HUNDREDS: [&str; 10] = [
+    "", "сто", "двести", "триста", "четыреста", "пятьсот", "шестьсот", "семьсот", "восемьсот",
+    "девятьсот",
+];
+
+/// Разряды: формы для 1, 2–4, 5+ и род числительного
+const SCALES: [([&str; 3], bool); 3] = [
+    (["тысяча", "тысячи", "тысяч"], true),
+    (["миллион", "миллиона", "миллионов"], false),
+    (["миллиард", "миллиарда", "миллиардов"], false),
+];
+
+/// Форма слова после числа: 1 рубль, 2 рубля, 5 рублей
+fn plural(n: u64, forms: [&str; 3]) -> &str {
+    match (n % 100, n % 10) {
+        (11..=19, _) => forms[2],
+        (_, 1) => forms[0],
+        (_, 2..=4) => forms[1],
+        _ => forms[2],
+    }
+}
+
+// Число от 0 до 999 словами
+fn triple_to_words(n: u64, feminine: bool) -> Vec<&'static str> {
+    let mut words = Vec::new();
+    let hundreds = (n / 100) as usize;
+    let rest = n % 100;
+    if hundreds > 0 {
+        words.push(HUNDREDS[hundreds]);
+    }
+    if (10..20).contains(&rest) {
+        words.push(TEENS[(rest - 10) as usize]);
+    } else {
+        let tens = (rest / 10) as usize;
+        let units = (rest % 10) as usize;
+        if tens > 0 {
+            words.push(TENS[tens]);
+        }
+        if units > 0 {
+            words.push(if feminine { UNITS_FEMININE[units] } else { UNITS_MASCULINE[units] });
+        }
+    }
+    words
+}
+
+/// Целое число словами в мужском роде ("сто двадцать один")
+fn number_to_words(n: u64) -> String {
+    if n == 0 {
+        return "ноль".to_string();
+    }
+
+    let mut words = Vec::new();
+    let mut triples = Vec::new();
+    let mut rest = n;
+    while rest > 0 {
+        triples.push(rest % 1000);
+        rest /= 1000;
+    }
+
+    for (index, &triple) in triples.iter().enumerate().rev() {
+        if triple == 0 {
+            continue;
+        }
+        if index == 0 {
+            words.extend(triple_to_words(triple, false));
+        } else if let Some((forms, feminine)) = SCALES.get(index - 1) {
+            words.extend(triple_to_words(triple, *feminine));
+            words.push(plural(triple, *forms));
+        }
+    }
+
+    words.join(" ")
+}
+
+/// Сумма в рублях прописью с копейками цифрами, с заглавной буквы
+pub fn amount_in_words(amount: f64) -> String {
+    let total_kopecks = (amount.abs() * 100.0).round() as u64;
+    let rubles = total_kopecks / 100;
+    let kopecks = total_kopecks % 100;
+
+    let text = format!(
+        "{} {} {:02} {}",
+        number_to_words(rubles),
+        plural(rubles, ["рубль", "рубля", "рублей"]),
+        kopecks,
+        plural(kopecks, ["копейка", "копейки", "копеек"])
+    );
+
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text,
+    }
+}
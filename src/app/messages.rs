@@ -1,6 +1,7 @@
 use std::path::PathBuf;
+use iced::widget::text_editor;
 use iced_aw::date_picker::Date;
-use crate::app::state::{Assignment, AssignmentType, BackupInterval, Certificate, Course, CoursePickListItem, Group, GroupPickListItem, GroupStatus, LessonWithAssignments, Level, PastSession, Payment, ReportType, StudentAttendance, StudentPickListItem, TextInputOrEditorInput, UserInfo};
+use crate::app::state::{Assignment, LockedAccount, LoginAttempt, Permission, RegistrationRequest, Role, AuditAction, AuditActor, AuditEntry, AssignmentType, BackupCompression, BackupFile, BackupInterval, Certificate, Course, CoursePickListItem, Enrollment, EnrollmentPickListItem, EnrollmentStatus, EnrollmentTerms, Group, GroupPickListItem, GroupStatus, LessonWithAssignments, Level, OrganizationField, PastSession, Payment, PaymentDocument, PaymentDocumentKind, Payroll, PdfBackend, PlannedSession, PromoCode, RateType, ReportType, ReversalKind, ScheduleSlot, StatementLine, StudentAssignment, StudentBalance, StudentAttendance, StudentPickListItem, StudentProgress, Submission, TeacherRate, TextInputOrEditorInput, UserInfo, WaitlistEntry, Weekday};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -25,6 +26,9 @@ pub enum Message {
     GoToClasses,
     GoToPayment,
     GoToCertificates,
+    GoToStudentProgress,
+    GoToPayroll,
+    GoToAuditLog,
     Logout,
     //
     ThemeSelected(&'static str),
@@ -67,8 +71,8 @@ pub enum Message {
     EditUserNameChanged(String),
     EditUserEmailChanged(String),
     EditUserBirthdayChanged(String),
-    EditUserTypeChanged(String),
-    UserTypeFilterChanged(Option<String>),
+    EditUserTypeChanged(Role),
+    UserTypeFilterChanged(Option<Role>),
     //
     CourseFilterChanged(String),
     // Для групп
@@ -85,6 +89,7 @@ pub enum Message {
 
     SubmitNewGroup,
     SubmitEditedGroup,
+    GroupUpdated(Result<(), String>),
     StartEditingGroup(Group),
     CancelEditingGroup,
     DeleteGroup(i32),
@@ -153,13 +158,63 @@ pub enum Message {
     ErrorOccurred(String),
     LoadStudentGroupInfo, // Для загрузки группы студента
     StudentGroupInfoLoaded(Result<Option<Group>, String>),
+    LoadStudentProgress, // Сводка обучения для студента или всех детей родителя
+    StudentProgressLoaded(Result<Vec<StudentProgress>, String>),
+    // Сдача работ студентом
+    OpenSubmissionModal(StudentAssignment),
+    CloseSubmissionModal,
+    SubmissionTextEdited(text_editor::Action),
+    AttachSubmissionFile,
+    SubmissionFileAttached(Result<(String, Vec<u8>), String>),
+    RemoveSubmissionFile(usize),
+    SubmitAssignment,
+    AssignmentSubmitted(Result<(), String>),
+    // Проверка работ преподавателем
+    OpenGroupSubmissionsModal,
+    GroupSubmissionsLoaded(Result<Vec<Submission>, String>),
+    CloseGroupSubmissionsModal,
+    StartGradingSubmission(Submission),
+    GradingScoreChanged(String),
+    GradingCommentChanged(String),
+    SaveSubmissionGrade,
+    SubmissionGraded(Result<(), String>),
+    OpenSubmissionFile(i32), // id файла
+    SubmissionFileOpened(Result<PathBuf, String>),
+    // Расписание группы
+    OpenScheduleModal(Group),
+    CloseScheduleModal,
+    ScheduleLoaded(Result<(Vec<ScheduleSlot>, Vec<PlannedSession>), String>),
+    NewSlotWeekdaySelected(Weekday),
+    NewSlotStartChanged(String),
+    NewSlotDurationChanged(String),
+    NewSlotRoomChanged(String),
+    AddScheduleSlot,
+    DeleteScheduleSlot(i32), // id слота
+    ScheduleStartDateChanged(String),
+    GeneratePlannedSessions,
+    ScheduleUpdated(Result<(), String>),
+    // Календарь занятий на неделю
+    OpenWeekCalendar,
+    CloseWeekCalendar,
+    CalendarPrevWeek,
+    CalendarNextWeek,
+    CalendarSessionsLoaded(Result<Vec<PlannedSession>, String>),
     ShowGroupStudents(i32), // Показать модальное окно с студентами группы (передаем group_id)
     //LoadGroupStudents(i32), // Сообщение для асинхронной загрузки студентов
     GroupStudentsLoaded(Result<(i32, Vec<UserInfo>), String>), // i32 - group_id, Vec<StudentInfo> - студенты
     CloseGroupStudentsModal, // Закрыть модальное окно
 
-    AddStudentToGroup(i32, i32),
+    EnrollStudent(EnrollmentStatus), // Зачислить выбранного студента (или подать заявку) в текущую группу
     SelectedStudentToAddChanged(Option<UserInfo>),
+    NewEnrollmentTermsSelected(EnrollmentTerms),
+    NewEnrollmentDueChanged(String),
+    ChangeEnrollmentStatus(i32, EnrollmentStatus), // (enrollment_id, новый этап)
+    EnrollmentChanged(i32, Result<(), String>),    // (group_id, результат)
+    StartTransfer(i32), // Выбор группы для перевода зачисления (enrollment_id)
+    CancelTransfer,
+    TransferTargetSelected(Group),
+    ConfirmTransfer,
+    GroupEnrollmentsLoaded(Result<Vec<Enrollment>, String>),
     StudentsAndGroupsReloaded(i32, i32), // (group_id, teacher_id)
     AllGroupsLoaded(Result<Vec<Group>, String>),
     // Payment
@@ -173,6 +228,156 @@ pub enum Message {
     PaymentAdded(Result<(), String>),
     PaymentsUpdated(Vec<Payment>),
     DeletePayment(i32),
+    // Возвраты и корректировки
+    OpenPaymentReversal(Payment, ReversalKind),
+    ClosePaymentReversal,
+    ReversalAmountChanged(String),
+    ReversalReasonChanged(String),
+    ConfirmPaymentReversal,
+    PaymentReversed(Result<(), String>),
+    // Скидки при зачислении
+    NewPaymentSiblingChecked(Result<Option<String>, String>),
+    NewPaymentPromoInputChanged(String),
+    ApplyPromoCode,
+    PromoCodeChecked(Result<Option<PromoCode>, String>),
+    ClearPromoCode,
+    NewPaymentManualDiscountChanged(String),
+    NewPaymentManualReasonChanged(String),
+    // Промокоды
+    TogglePromoCodes,
+    PromoCodesLoaded(Result<Vec<PromoCode>, String>),
+    NewPromoCodeChanged(String),
+    NewPromoIsPercentToggled(bool),
+    NewPromoValueChanged(String),
+    NewPromoValidFromChanged(String),
+    NewPromoValidToChanged(String),
+    NewPromoMaxUsesChanged(String),
+    CreatePromoCode,
+    PromoCodeSaved(Result<(), String>),
+    DeletePromoCode(i32),
+    // Счета и квитанции
+    PaymentDocumentsLoaded(Result<Vec<PaymentDocument>, String>),
+    GeneratePaymentDocument(Payment, PaymentDocumentKind),
+    PaymentDocumentGenerated(Result<PathBuf, String>),
+    // Импорт банковской выписки
+    ImportBankStatement,
+    BankStatementLoaded(Result<(Vec<StatementLine>, Vec<EnrollmentPickListItem>), String>),
+    BankLineTargetSelected(usize, EnrollmentPickListItem),
+    BankLineToggled(usize, bool),
+    ConfirmBankImport,
+    BankImportFinished(Result<usize, String>),
+    CloseBankImport,
+    // Лист ожидания
+    WaitlistLoaded(Result<Vec<WaitlistEntry>, String>),
+    OpenWaitlist(Course),
+    CloseWaitlist,
+    AddToWaitlist,
+    WaitlistEntryAdded(Result<(), String>),
+    CloseWaitlistEntry(i32),
+    WaitlistEntryClosed(Result<(), String>),
+    EnrollFromWaitlist(WaitlistEntry),
+    WaitlistOfferDaysSelected(u32),
+    // Зарплата преподавателей
+    PayrollLoaded(Result<Payroll, String>),
+    TeacherRatesLoaded(Result<Vec<TeacherRate>, String>),
+    PayrollPrevMonth,
+    PayrollNextMonth,
+    RateTeacherSelected(TeacherRate),
+    RateTypeSelected(RateType),
+    RateValueChanged(String),
+    SaveTeacherRate,
+    TeacherRateSaved(Result<(), String>),
+    ApprovePayroll,
+    CancelPayrollApproval,
+    ConfirmPayrollApproval,
+    PayrollApproved(Result<(), String>),
+    ExportPayroll(ReportType),
+    PayrollExported(Result<PathBuf, String>),
+    // Журнал изменений
+    AuditLogLoaded(Result<(Vec<AuditEntry>, Vec<AuditActor>), String>), // Записи и пользователи для фильтра
+    AuditActorSelected(AuditActor),
+    AuditEntitySelected(&'static str),
+    AuditActionSelected(AuditAction),
+    AuditDateFromChanged(String),
+    AuditDateToChanged(String),
+    ApplyAuditFilter,
+    ResetAuditFilter,
+    ExportAuditLog,
+    AuditLogExported(Result<PathBuf, String>),
+    // Права доступа
+    TogglePermissionsModal(bool),
+    TogglePermission(Role, Permission, bool), // (роль, действие, разрешить)
+    DismissAccessDenied,
+    // Заявки на регистрацию
+    RegistrationRequestsLoaded(Result<(Vec<RegistrationRequest>, Vec<UserInfo>), String>), // Заявки и родители для привязки
+    OpenRegistrationReview(UserInfo),
+    CloseRegistrationReview,
+    ReviewRoleSelected(Role),
+    ReviewParentSelected(UserInfo),
+    ReviewGroupSelected(Group),
+    ReviewReasonChanged(String),
+    ApproveRegistration,
+    RejectRegistration,
+    RegistrationReviewed(Result<(), String>),
+    // Смена, сброс и восстановление пароля
+    OpenChangePassword,
+    CloseChangePassword,
+    CurrentPasswordInputChanged(String),
+    NewPasswordInputChanged(String),
+    NewPasswordRepeatInputChanged(String),
+    SubmitPasswordChange,
+    PasswordChangeFinished(Result<(), String>),
+    ResetUserPassword(i32),
+    UserPasswordReset(Result<(String, String), String>), // ФИО и временный пароль
+    DismissTemporaryPassword,
+    GenerateRecoveryCodes,
+    RecoveryCodesGenerated(Result<Vec<String>, String>),
+    CopyRecoveryCodes,
+    DismissRecoveryCodes,
+    SwitchToPasswordRecovery,
+    RecoveryCodeChanged(String),
+    SubmitPasswordRecovery,
+    PasswordRecoveryFinished(Result<(), String>),
+    // История входов и блокировка по бездействию
+    GoToLoginHistory,
+    LoginHistoryLoaded(Result<(Vec<LoginAttempt>, Vec<LockedAccount>), String>),
+    LoginHistoryEmailChanged(String),
+    LoginHistoryFailuresOnlyToggled(bool),
+    ApplyLoginHistoryFilter,
+    UnlockAccount(i32),
+    AccountUnlocked(Result<(), String>),
+    UserActivity,
+    IdleLockTick,
+    IdleLockMinutesSelected(u32),
+    // Двухфакторная аутентификация
+    TwoFactorCodeChanged(String),
+    SubmitTwoFactorCode,
+    TwoFactorVerified(Result<(), String>),
+    CancelTwoFactorLogin,
+    StartTwoFactorSetup,
+    CancelTwoFactorSetup,
+    ConfirmTwoFactorSetup,
+    TwoFactorSetupFinished(Result<Vec<String>, String>), // Новые коды восстановления
+    DisableTwoFactor,
+    TwoFactorDisabled(Result<(), String>),
+    RequireAdminTwoFactorToggled(bool),
+    // Балансы студентов
+    BalancesLoaded(Result<Vec<StudentBalance>, String>),
+    ToggleBalances,
+    DebtorsOnlyToggled(bool),
+    OpenSettleBalance(StudentBalance),
+    CloseSettleBalance,
+    SettleAmountChanged(String),
+    SettlePaymentTypeSelected(String),
+    ConfirmSettleBalance,
+    BalanceSettled(Result<(), String>),
+    // Рассрочка
+    NewPaymentFormInstallmentsSelected(u32),
+    OpenInstallmentSchedule(StudentBalance),
+    CloseInstallmentSchedule,
+    InstallmentPartsSelected(u32),
+    CreateInstallmentPlan,
+    InstallmentPlanCreated(Result<(), String>),
     GroupsFetched(Result<Vec<Group>, String>),
     // Сообщения для получения данных в модальном окне
     StudentsWithoutGroupFetched(Result<Vec<UserInfo>, String>),
@@ -223,7 +428,23 @@ pub enum Message {
     GenerateGroupReport,
     BackupIntervalSelected(Option<BackupInterval>),
     BackupNowPressed,
+    BackupTimeChanged(String),
+    BackupSchedulerTick, // Периодическая проверка, не пора ли делать автоматический бэкап
+    BackupFinished(Result<PathBuf, String>),
     SelectBackupFolder,
     MaxBackupCountSelected(Option<usize>),
+    BackupCompressionSelected(Option<BackupCompression>),
+    PdfBackendSelected(Option<PdfBackend>),
+    PaymentLockDaysSelected(u32),
+    SiblingDiscountSelected(u32),
+    OrganizationFieldChanged(OrganizationField, String),
+    SaveOrganization,
     OpenBackupFolder,
+    // Восстановление из резервной копии
+    LoadBackupList,
+    BackupListLoaded(Result<Vec<BackupFile>, String>),
+    RequestRestoreBackup(BackupFile),
+    CancelRestoreBackup,
+    ConfirmRestoreBackup,
+    BackupRestored(Result<String, String>), // Имя файла копии или ошибка
 }
\ No newline at end of file
@@ -2,6 +2,8 @@ pub mod state;
 pub mod messages;
 pub mod update;
 pub mod view;
+pub mod subscription;
+pub mod permissions;
 
 pub use state::App;
 pub use messages::Message;
\ No newline at end of file
@@ -0,0 +1,446 @@
+use super::{App, Message};
+use crate::app::state::{PATH_TO_DB, Permission, Role};
+use crate::db;
+use rusqlite::Connection;
+
+impl App {
+    /// Разрешено ли действие роли текущего пользователя по матрице прав
+    pub fn can(&self, permission: Permission) -> bool {
+        let Some(user) = &self.current_user else {
+            return false;
+        };
+        permission.is_locked_for(user.user_type) || self.permissions.contains(&(user.user_type, permission))
+    }
+
+    /// Разрешено ли действие роли — для редактора матрицы
+    pub fn role_can(&self, role: Role, permission: Permission) -> bool {
+        permission.is_locked_for(role) || self.permissions.contains(&(role, permission))
+    }
+
+    /// Перечитывает матрицу прав из БД
+    pub(crate) fn reload_permissions(&mut self) {
+        let result = Connection::open(PATH_TO_DB).and_then(|conn| db::get_role_permissions(&conn));
+        match result {
+            Ok(permissions) => {
+                self.permissions = permissions;
+                self.permissions_error = None;
+            }
+            Err(e) => {
+                // Без матрицы ничего, кроме просмотра, не разрешаем
+                self.permissions.clear();
+                self.permissions_error = Some(format!("Не удалось загрузить права доступа: {}", e));
+            }
+        }
+    }
+
+    /// Какое право нужно, чтобы выполнить сообщение. `None` — сообщение ничего не меняет
+    /// в данных (навигация, ввод в формы, результаты фоновых задач) или касается только
+    /// собственного профиля и оформления. Такие сообщения перечислены явно, без `_`,
+    /// чтобы новое изменяющее данные сообщение не оказалось разрешённым всем по умолчанию.
+    pub(crate) fn required_permission(&self, message: &Message) -> Option<Permission> {
+        let permission = match message {
+            Message::SubmitEditedUser
+            | Message::DeleteUser(_)
+            | Message::AddChildToParent
+            | Message::DeleteChild { .. }
+            | Message::ApproveRegistration
+            | Message::RejectRegistration
+            | Message::ResetUserPassword(_)
+            | Message::UnlockAccount(_) => Permission::ManageUsers,
+
+            Message::SubmitNewCourse
+            | Message::SubmitEditedCourse
+            | Message::DeleteCourse(_)
+            | Message::AddLesson
+            | Message::DeleteLesson(_)
+            | Message::AddAssignment
+            | Message::DeleteAssignment(_)
+            | Message::SaveEditedAssignment => Permission::ManageCourses,
+
+            Message::SubmitNewGroup
+            | Message::SubmitEditedGroup
+            | Message::DeleteGroup(_)
+            | Message::EnrollStudent(_)
+            | Message::ChangeEnrollmentStatus(..)
+            | Message::ConfirmTransfer
+            | Message::RemoveStudentFromGroup(..)
+            | Message::AddScheduleSlot
+            | Message::DeleteScheduleSlot(_)
+            | Message::GeneratePlannedSessions => Permission::ManageGroups,
+
+            Message::AddToWaitlist | Message::EnrollFromWaitlist(_) => Permission::ManageWaitlist,
+            // Студент может отказаться от своего места в очереди, чужие записи снимает администратор
+            Message::CloseWaitlistEntry(entry_id) => {
+                let own_entry = self.waitlist.iter().any(|entry| {
+                    entry.id == *entry_id && self.current_user.as_ref().is_some_and(|u| u.id == entry.student_id)
+                });
+                if own_entry {
+                    Permission::RespondToWaitlist
+                } else {
+                    Permission::ManageWaitlist
+                }
+            }
+
+            Message::AddPaymentConfirmed
+            | Message::DeletePayment(_)
+            | Message::ConfirmPaymentReversal
+            | Message::ConfirmBankImport
+            | Message::ConfirmSettleBalance
+            | Message::CreateInstallmentPlan
+            | Message::GeneratePaymentDocument(..) => Permission::ManagePayments,
+
+            Message::CreatePromoCode | Message::DeletePromoCode(_) => Permission::ManageDiscounts,
+
+            Message::SaveTeacherRate | Message::ConfirmPayrollApproval => Permission::ManagePayroll,
+
+            Message::ConductLessonClicked(..) | Message::SaveAttendance => Permission::ConductLessons,
+            Message::SaveSubmissionGrade => Permission::GradeSubmissions,
+            Message::SubmitAssignment => Permission::SubmitAssignments,
+
+            Message::PdfBackendSelected(_)
+            | Message::PaymentLockDaysSelected(_)
+            | Message::SiblingDiscountSelected(_)
+            | Message::WaitlistOfferDaysSelected(_)
+            | Message::IdleLockMinutesSelected(_)
+            | Message::RequireAdminTwoFactorToggled(_)
+            | Message::SaveOrganization => Permission::ManageSettings,
+
+            Message::BackupNowPressed
+            | Message::BackupIntervalSelected(_)
+            | Message::BackupTimeChanged(_)
+            | Message::SelectBackupFolder
+            | Message::MaxBackupCountSelected(_)
+            | Message::BackupCompressionSelected(_)
+            | Message::ConfirmRestoreBackup => Permission::ManageBackups,
+
+            Message::TogglePermission(..) => Permission::ManagePermissions,
+
+            // Вход, регистрация и собственная учётная запись: пароль, коды восстановления,
+            // второй фактор, аватар и оформление
+            Message::LoginPressed
+            | Message::RegisterPressed
+            | Message::Logout
+            | Message::ThemeSelected(..)
+            | Message::ChangeTheme(..)
+            | Message::ChooseAvatar
+            | Message::AvatarChosen(..)
+            | Message::SubmitPasswordChange
+            | Message::GenerateRecoveryCodes
+            | Message::SubmitPasswordRecovery
+            | Message::SubmitTwoFactorCode
+            | Message::CancelTwoFactorLogin
+            | Message::StartTwoFactorSetup
+            | Message::ConfirmTwoFactorSetup
+            | Message::DisableTwoFactor => return None,
+
+            // Фоновые задачи по расписанию, которое задаёт администратор
+            Message::BackupSchedulerTick
+            | Message::IdleLockTick
+            | Message::UserActivity => return None,
+
+            // Навигация, ввод в формы, выгрузка файлов и результаты фоновых задач.
+            // Здесь нет `_`: новое сообщение без права не скомпилируется, пока его не отнесут сюда
+            Message::UserLoggedIn(..)
+            | Message::FirstNameChanged(..)
+            | Message::LastNameChanged(..)
+            | Message::MiddleNameChanged(..)
+            | Message::EmailChanged(..)
+            | Message::PasswordChanged(..)
+            | Message::PasswordRepeatChanged(..)
+            | Message::SwitchToLogin
+            | Message::SwitchToRegister
+            | Message::GoToProfile
+            | Message::GoToSettings
+            | Message::GoToCourses
+            | Message::GoToUserList
+            | Message::GoToGroupList
+            | Message::GoToClasses
+            | Message::GoToPayment
+            | Message::GoToCertificates
+            | Message::GoToStudentProgress
+            | Message::GoToPayroll
+            | Message::GoToAuditLog
+            | Message::ChooseDate
+            | Message::SubmitDate(..)
+            | Message::CancelDate
+            | Message::Er(..)
+            | Message::NewCourseLevelChanged(..)
+            | Message::ToggleAddCourseModal(..)
+            | Message::NewCourseTitleChanged(..)
+            | Message::NewCourseDescriptionChanged(..)
+            | Message::NewCourseTotalSeatsChanged(..)
+            | Message::NewCourseSeatsChanged(..)
+            | Message::NewCoursePriceChanged(..)
+            | Message::StartEditingCourse(..)
+            | Message::EditCourseTitleChanged(..)
+            | Message::EditCourseDescriptionChanged(..)
+            | Message::EditCourseLevelChanged(..)
+            | Message::EditCourseTotalSeatsChanged(..)
+            | Message::EditCourseSeatsChanged(..)
+            | Message::EditCoursePriceChanged(..)
+            | Message::CancelEditingCourse
+            | Message::StartEditingUser(..)
+            | Message::CancelEditingUser
+            | Message::UserDeleted(..)
+            | Message::EditUserNameChanged(..)
+            | Message::EditUserEmailChanged(..)
+            | Message::EditUserBirthdayChanged(..)
+            | Message::EditUserTypeChanged(..)
+            | Message::UserTypeFilterChanged(..)
+            | Message::CourseFilterChanged(..)
+            | Message::ToggleAddGroupModal(..)
+            | Message::NewGroupNameChanged(..)
+            | Message::NewGroupCourseChanged(..)
+            | Message::NewGroupTeacherChanged(..)
+            | Message::NewGroupStatusChanged(..)
+            | Message::EditGroupNameChanged(..)
+            | Message::EditGroupCourseChanged(..)
+            | Message::EditGroupTeacherChanged(..)
+            | Message::EditGroupStatusChanged(..)
+            | Message::StartEditingGroup(..)
+            | Message::CancelEditingGroup
+            | Message::GroupFilterChanged(..)
+            | Message::OpenManageStudentsModal(..)
+            | Message::ShowParentChildren(..)
+            | Message::CloseParentChildrenModal
+            | Message::SelectedChildToAddChanged(..)
+            | Message::ShowLessonsModal(..)
+            | Message::CloseLessonsModal
+            | Message::NewLessonNumberChanged(..)
+            | Message::NewLessonTitleChanged(..)
+            | Message::ShowAssignmentsModal(..)
+            | Message::CloseAssignmentsModal
+            | Message::NewAssignmentTitleChanged(..)
+            | Message::NewAssignmentDescriptionChanged(..)
+            | Message::NewAssignmentTypeSelected(..)
+            | Message::ShowAssignmentDetailModal(..)
+            | Message::CloseAssignmentDetailModal
+            | Message::EditingAssignmentTitleChanged(..)
+            | Message::EditingAssignmentDescriptionChanged(..)
+            | Message::LoadTeacherGroups(..)
+            | Message::TeacherGroupsLoaded(..)
+            | Message::GroupUpdated(..)
+            | Message::SelectGroupForClasses(..)
+            | Message::AssignmentsLoaded(..)
+            | Message::GroupLessonsWithAssignmentsLoaded(..)
+            | Message::PastSessionsLoaded(..)
+            | Message::CourseLessonsLoaded(..)
+            | Message::LoadAllCourses
+            | Message::AllCoursesLoaded(..)
+            | Message::ConductLessonResult(..)
+            | Message::OpenGroupLessonsModal(..)
+            | Message::GroupLessonsModalLoaded(..)
+            | Message::CloseGroupLessonsModal
+            | Message::LoadAllGroups
+            | Message::StudentsInGroupLoaded(..)
+            | Message::StudentsWithoutGroupLoaded(..)
+            | Message::CoursesForPicklistLoaded(..)
+            | Message::UsersForPicklistLoaded(..)
+            | Message::ErrorOccurred(..)
+            | Message::LoadStudentGroupInfo
+            | Message::StudentGroupInfoLoaded(..)
+            | Message::LoadStudentProgress
+            | Message::StudentProgressLoaded(..)
+            | Message::OpenSubmissionModal(..)
+            | Message::CloseSubmissionModal
+            | Message::SubmissionTextEdited(..)
+            | Message::AttachSubmissionFile
+            | Message::SubmissionFileAttached(..)
+            | Message::RemoveSubmissionFile(..)
+            | Message::AssignmentSubmitted(..)
+            | Message::OpenGroupSubmissionsModal
+            | Message::GroupSubmissionsLoaded(..)
+            | Message::CloseGroupSubmissionsModal
+            | Message::StartGradingSubmission(..)
+            | Message::GradingScoreChanged(..)
+            | Message::GradingCommentChanged(..)
+            | Message::SubmissionGraded(..)
+            | Message::OpenSubmissionFile(..)
+            | Message::SubmissionFileOpened(..)
+            | Message::OpenScheduleModal(..)
+            | Message::CloseScheduleModal
+            | Message::ScheduleLoaded(..)
+            | Message::NewSlotWeekdaySelected(..)
+            | Message::NewSlotStartChanged(..)
+            | Message::NewSlotDurationChanged(..)
+            | Message::NewSlotRoomChanged(..)
+            | Message::ScheduleStartDateChanged(..)
+            | Message::ScheduleUpdated(..)
+            | Message::OpenWeekCalendar
+            | Message::CloseWeekCalendar
+            | Message::CalendarPrevWeek
+            | Message::CalendarNextWeek
+            | Message::CalendarSessionsLoaded(..)
+            | Message::ShowGroupStudents(..)
+            | Message::GroupStudentsLoaded(..)
+            | Message::CloseGroupStudentsModal
+            | Message::SelectedStudentToAddChanged(..)
+            | Message::NewEnrollmentTermsSelected(..)
+            | Message::NewEnrollmentDueChanged(..)
+            | Message::EnrollmentChanged(..)
+            | Message::StartTransfer(..)
+            | Message::CancelTransfer
+            | Message::TransferTargetSelected(..)
+            | Message::GroupEnrollmentsLoaded(..)
+            | Message::StudentsAndGroupsReloaded(..)
+            | Message::AllGroupsLoaded(..)
+            | Message::PaymentsFetched(..)
+            | Message::ToggleAddPaymentModal
+            | Message::NewPaymentFormStudentSelected(..)
+            | Message::NewPaymentFormCourseSelected(..)
+            | Message::NewPaymentFormGroupSelected(..)
+            | Message::NewPaymentFormTypeChanged(..)
+            | Message::PaymentAdded(..)
+            | Message::PaymentsUpdated(..)
+            | Message::OpenPaymentReversal(..)
+            | Message::ClosePaymentReversal
+            | Message::ReversalAmountChanged(..)
+            | Message::ReversalReasonChanged(..)
+            | Message::PaymentReversed(..)
+            | Message::NewPaymentSiblingChecked(..)
+            | Message::NewPaymentPromoInputChanged(..)
+            | Message::ApplyPromoCode
+            | Message::PromoCodeChecked(..)
+            | Message::ClearPromoCode
+            | Message::NewPaymentManualDiscountChanged(..)
+            | Message::NewPaymentManualReasonChanged(..)
+            | Message::TogglePromoCodes
+            | Message::PromoCodesLoaded(..)
+            | Message::NewPromoCodeChanged(..)
+            | Message::NewPromoIsPercentToggled(..)
+            | Message::NewPromoValueChanged(..)
+            | Message::NewPromoValidFromChanged(..)
+            | Message::NewPromoValidToChanged(..)
+            | Message::NewPromoMaxUsesChanged(..)
+            | Message::PromoCodeSaved(..)
+            | Message::PaymentDocumentsLoaded(..)
+            | Message::PaymentDocumentGenerated(..)
+            | Message::ImportBankStatement
+            | Message::BankStatementLoaded(..)
+            | Message::BankLineTargetSelected(..)
+            | Message::BankLineToggled(..)
+            | Message::BankImportFinished(..)
+            | Message::CloseBankImport
+            | Message::WaitlistLoaded(..)
+            | Message::OpenWaitlist(..)
+            | Message::CloseWaitlist
+            | Message::WaitlistEntryAdded(..)
+            | Message::WaitlistEntryClosed(..)
+            | Message::PayrollLoaded(..)
+            | Message::TeacherRatesLoaded(..)
+            | Message::PayrollPrevMonth
+            | Message::PayrollNextMonth
+            | Message::RateTeacherSelected(..)
+            | Message::RateTypeSelected(..)
+            | Message::RateValueChanged(..)
+            | Message::TeacherRateSaved(..)
+            | Message::ApprovePayroll
+            | Message::CancelPayrollApproval
+            | Message::PayrollApproved(..)
+            | Message::ExportPayroll(..)
+            | Message::PayrollExported(..)
+            | Message::AuditLogLoaded(..)
+            | Message::AuditActorSelected(..)
+            | Message::AuditEntitySelected(..)
+            | Message::AuditActionSelected(..)
+            | Message::AuditDateFromChanged(..)
+            | Message::AuditDateToChanged(..)
+            | Message::ApplyAuditFilter
+            | Message::ResetAuditFilter
+            | Message::ExportAuditLog
+            | Message::AuditLogExported(..)
+            | Message::TogglePermissionsModal(..)
+            | Message::DismissAccessDenied
+            | Message::RegistrationRequestsLoaded(..)
+            | Message::OpenRegistrationReview(..)
+            | Message::CloseRegistrationReview
+            | Message::ReviewRoleSelected(..)
+            | Message::ReviewParentSelected(..)
+            | Message::ReviewGroupSelected(..)
+            | Message::ReviewReasonChanged(..)
+            | Message::RegistrationReviewed(..)
+            | Message::OpenChangePassword
+            | Message::CloseChangePassword
+            | Message::CurrentPasswordInputChanged(..)
+            | Message::NewPasswordInputChanged(..)
+            | Message::NewPasswordRepeatInputChanged(..)
+            | Message::PasswordChangeFinished(..)
+            | Message::UserPasswordReset(..)
+            | Message::DismissTemporaryPassword
+            | Message::RecoveryCodesGenerated(..)
+            | Message::CopyRecoveryCodes
+            | Message::DismissRecoveryCodes
+            | Message::SwitchToPasswordRecovery
+            | Message::RecoveryCodeChanged(..)
+            | Message::PasswordRecoveryFinished(..)
+            | Message::GoToLoginHistory
+            | Message::LoginHistoryLoaded(..)
+            | Message::LoginHistoryEmailChanged(..)
+            | Message::LoginHistoryFailuresOnlyToggled(..)
+            | Message::ApplyLoginHistoryFilter
+            | Message::AccountUnlocked(..)
+            | Message::TwoFactorCodeChanged(..)
+            | Message::TwoFactorVerified(..)
+            | Message::CancelTwoFactorSetup
+            | Message::TwoFactorSetupFinished(..)
+            | Message::TwoFactorDisabled(..)
+            | Message::BalancesLoaded(..)
+            | Message::ToggleBalances
+            | Message::DebtorsOnlyToggled(..)
+            | Message::OpenSettleBalance(..)
+            | Message::CloseSettleBalance
+            | Message::SettleAmountChanged(..)
+            | Message::SettlePaymentTypeSelected(..)
+            | Message::BalanceSettled(..)
+            | Message::NewPaymentFormInstallmentsSelected(..)
+            | Message::OpenInstallmentSchedule(..)
+            | Message::CloseInstallmentSchedule
+            | Message::InstallmentPartsSelected(..)
+            | Message::InstallmentPlanCreated(..)
+            | Message::GroupsFetched(..)
+            | Message::StudentsWithoutGroupFetched(..)
+            | Message::CoursesWithSeatsFetched(..)
+            | Message::GroupsForCourseFetched(..)
+            | Message::NoOp
+            | Message::OpenConductLessonModal(..)
+            | Message::ToggleStudentAttendance(..)
+            | Message::StudentsForAttendanceLoaded(..)
+            | Message::AttendanceSavedResult(..)
+            | Message::CourseCompletionChecked(..)
+            | Message::StudentsWithCertificatesLoaded(..)
+            | Message::OpenStudentCertificatesModal(..)
+            | Message::StudentCertificatesLoaded(..)
+            | Message::CloseStudentCertificatesModal
+            | Message::GenerateCertificatePdf(..)
+            | Message::CertificatePdfGenerated(..)
+            | Message::ToggleReportModal
+            | Message::GeneratePaymentReport
+            | Message::ChooseStartDate
+            | Message::ChooseEndDate
+            | Message::SubmitStartDate(..)
+            | Message::SubmitEndDate(..)
+            | Message::CancelDatePicker
+            | Message::ReportTypeSelected(..)
+            | Message::ReportGenerated(..)
+            | Message::ToggleCertificateReportModal
+            | Message::ChooseCertificateReportStartDate
+            | Message::ChooseCertificateReportEndDate
+            | Message::SubmitCertificateReportStartDate(..)
+            | Message::SubmitCertificateReportEndDate(..)
+            | Message::GenerateCertificateReport
+            | Message::CertificateReportGenerated(..)
+            | Message::ToggleGroupReportModal
+            | Message::GroupReportGenerated(..)
+            | Message::GenerateGroupReport
+            | Message::BackupFinished(..)
+            | Message::OrganizationFieldChanged(..)
+            | Message::OpenBackupFolder
+            | Message::LoadBackupList
+            | Message::BackupListLoaded(..)
+            | Message::RequestRestoreBackup(..)
+            | Message::CancelRestoreBackup
+            | Message::BackupRestored(..) => return None,
+        };
+        Some(permission)
+    }
+}
@@ -1,14 +1,18 @@
-use crate::config::{get_last_backup_time, load_config, start_backup_scheduler, theme_from_str};
+use crate::config::{get_last_backup_time, load_config, next_backup_due, theme_from_str};
+use crate::schedule::{WEEKDAY_NAMES, week_start};
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime};
 use iced::Theme;
-use iced::widget::text_editor;
+use iced::widget::{image, text_editor};
 use iced_anim::{Animated, spring};
 use iced_aw::date_picker::Date;
 use rusqlite::ToSql;
 use rusqlite::types::{FromSql, FromSqlError, ToSqlOutput, ValueRef};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt;
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::Instant;
 
 pub const PATH_TO_DB: &str = "db_platform";
 pub const CONFIG_FILE: &str = "config.json";
@@ -26,14 +30,17 @@ impl fmt::Display for BackupInterval {
 }
 
 impl BackupInterval {
-    pub fn duration(&self) -> Option<Duration> {
-        match self.value {
-            "daily" => Some(Duration::from_secs(60 * 60 * 24)),
-            "weekly" => Some(Duration::from_secs(60 * 60 * 24 * 7)),
-            "monthly" => Some(Duration::from_secs(60 * 60 * 24 * 30)),
+    /// Время следующего бэкапа после `last`. Если задано время суток, копия делается в это время,
+    /// иначе — в то же время, что и предыдущая. Месяц считается календарным.
+    pub fn next_run(&self, last: NaiveDateTime, time_of_day: Option<NaiveTime>) -> Option<NaiveDateTime> {
+        let date = match self.value {
+            "daily" => last.date().checked_add_days(Days::new(1)),
+            "weekly" => last.date().checked_add_days(Days::new(7)),
+            "monthly" => last.date().checked_add_months(Months::new(1)),
             "never" => None,
             _ => None,
-        }
+        }?;
+        Some(date.and_time(time_of_day.unwrap_or(last.time())))
     }
 }
 // Возможные интервалы (отображение → значение)
@@ -56,6 +63,67 @@ pub const BACKUP_INTERVALS: [BackupInterval; 4] = [
     },
 ];
 
+/// Сжатие файлов резервных копий
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupCompression {
+    pub display: &'static str,
+    pub value: &'static str,
+    pub extension: &'static str, // Добавляется к имени файла после ".db"
+}
+impl fmt::Display for BackupCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display)
+    }
+}
+pub const BACKUP_COMPRESSIONS: [BackupCompression; 3] = [
+    BackupCompression {
+        display: "Без сжатия",
+        value: "none",
+        extension: "",
+    },
+    BackupCompression {
+        display: "gzip",
+        value: "gzip",
+        extension: ".gz",
+    },
+    BackupCompression {
+        display: "zstd",
+        value: "zstd",
+        extension: ".zst",
+    },
+];
+
+/// Способы оплаты, доступные при внесении платежа
+pub const PAYMENT_TYPES: [&str; 2] = ["Карта", "QR-Код"];
+
+/// Способ формирования PDF (сертификаты и отчёты)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfBackend {
+    pub display: &'static str,
+    pub value: &'static str,
+}
+impl fmt::Display for PdfBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display)
+    }
+}
+impl PdfBackend {
+    /// Встроенный генератор не требует Chrome и доступа в интернет
+    pub fn is_native(&self) -> bool {
+        self.value == "native"
+    }
+}
+pub const PDF_BACKENDS: [PdfBackend; 2] = [
+    PdfBackend {
+        display: "Встроенный",
+        value: "native",
+    },
+    PdfBackend {
+        display: "Chrome (headless)",
+        value: "chrome",
+    },
+];
+
 pub struct App {
     pub date: Date,
     pub show_picker: bool,
@@ -120,8 +188,8 @@ pub struct App {
     pub edit_user_name: String,
     pub edit_user_email: String,
     pub edit_user_birthday: String,
-    pub edit_user_type: String,
-    pub user_type_filter: Option<String>,
+    pub edit_user_type: Role,
+    pub user_type_filter: Option<Role>,
     //
     pub course_filter_text: String,
     // Группы
@@ -141,6 +209,11 @@ pub struct App {
 
     pub is_manage_students_modal_open: bool,
     pub selected_student_to_add: Option<UserInfo>,
+    pub group_enrollments: Vec<Enrollment>, // Зачисления группы в окне «Состав», включая заявки и историю
+    pub new_enrollment_terms: EnrollmentTerms,
+    pub new_enrollment_due: String, // Срок оплаты для пробного обучения или отсрочки, ГГГГ-ММ-ДД
+    pub transfer_enrollment: Option<i32>, // Зачисление, которое переводится в другую группу
+    pub transfer_target: Option<Group>,
 
     pub show_children_modal: bool,
     pub parent_children: Vec<UserInfo>,
@@ -193,6 +266,38 @@ pub struct App {
     pub group_error_message: Option<String>,
 
     pub student_group_info: Option<Group>,
+    pub student_progress: Vec<StudentProgress>, // Для студента — он сам, для родителя — все дети
+
+    // Сдача работы студентом
+    pub submission_assignment: Option<StudentAssignment>,
+    pub submission_text: text_editor::Content,
+    pub submission_files: Vec<(String, Vec<u8>)>, // Имя файла и содержимое, ещё не сохранённые в БД
+    pub submission_error: Option<String>,
+
+    // Расписание группы
+    pub show_schedule_modal: bool,
+    pub schedule_group: Option<Group>,
+    pub schedule_slots: Vec<ScheduleSlot>,
+    pub schedule_planned_sessions: Vec<PlannedSession>,
+    pub new_slot_weekday: Option<Weekday>,
+    pub new_slot_start: String,
+    pub new_slot_duration: String,
+    pub new_slot_room: String,
+    pub schedule_start_date: String, // С какой даты формировать занятия, "ГГГГ-ММ-ДД"
+    pub schedule_error: Option<String>,
+
+    // Календарь на неделю
+    pub show_week_calendar_modal: bool,
+    pub calendar_week_start: NaiveDate,
+    pub calendar_sessions: Vec<PlannedSession>,
+
+    // Проверка работ преподавателем
+    pub show_group_submissions_modal: bool,
+    pub group_submissions: Vec<Submission>,
+    pub grading_submission_id: Option<i32>,
+    pub grading_score: String,
+    pub grading_comment: String,
+    pub grading_error: Option<String>,
     pub show_group_students_modal: bool,
     pub selected_group_for_students_name: Option<String>, // Для отображения названия группы в модалке
     pub selected_group_students: Vec<UserInfo>,
@@ -210,6 +315,77 @@ pub struct App {
     pub groups_for_selected_course: Vec<Group>,
     // Для picklist'ов может понадобиться отслеживать выбранный индекс
     pub selected_payment_type_idx: Option<usize>,
+    // Балансы студентов (начислено / оплачено / долг)
+    pub balances: Vec<StudentBalance>,
+    pub show_balances: bool,
+    pub show_debtors_only: bool,
+    pub settle_balance: Option<StudentBalance>, // Группа, по которой вносится доплата
+    pub settle_amount: String,
+    pub settle_payment_type: Option<String>,
+    pub settle_error: Option<String>,
+    // Возвраты и корректировки
+    pub payment_error: Option<String>,
+    pub payment_lock_days: u32,
+    pub reversal_payment: Option<Payment>, // Платёж, к которому оформляется встречная запись
+    pub reversal_kind: ReversalKind,
+    pub reversal_amount: String,
+    pub reversal_reason: String,
+    pub reversal_error: Option<String>,
+    // Рассрочка
+    pub new_payment_installments: u32, // На сколько частей разбить оплату нового зачисления
+    pub installment_balance: Option<StudentBalance>, // Зачисление, чей график открыт
+    pub installment_parts: u32,
+    pub installment_error: Option<String>,
+    // Скидки при зачислении
+    pub sibling_discount_percent: u32,
+    pub new_payment_sibling: Option<String>, // Уже обучающийся брат/сестра выбранного студента
+    pub new_payment_promo_input: String,
+    pub new_payment_promo: Option<PromoCode>, // Проверенный и применённый промокод
+    pub new_payment_manual_discount: String,
+    pub new_payment_manual_reason: String,
+    pub new_payment_error: Option<String>,
+    // Управление промокодами
+    pub show_promo_codes: bool,
+    pub promo_codes: Vec<PromoCode>,
+    pub new_promo_code: String,
+    pub new_promo_is_percent: bool,
+    pub new_promo_value: String,
+    pub new_promo_valid_from: String,
+    pub new_promo_valid_to: String,
+    pub new_promo_max_uses: String,
+    pub promo_error: Option<String>,
+    // Счета и квитанции
+    pub payment_documents: Vec<PaymentDocument>,
+    pub payment_notice: Option<String>,
+    pub organization: Organization,
+    // Импорт банковской выписки
+    pub show_bank_import: bool,
+    pub bank_import_file: String,
+    pub bank_import_lines: Vec<StatementLine>,
+    pub bank_import_options: Vec<EnrollmentPickListItem>,
+    pub bank_import_error: Option<String>,
+    // Лист ожидания
+    pub waitlist: Vec<WaitlistEntry>,
+    pub waitlist_offer_days: u32,
+    pub waitlist_course: Option<Course>, // Курс, лист ожидания которого открыт
+    pub waitlist_error: Option<String>,
+    pub waitlist_enrollment: Option<WaitlistEntry>, // Предложение, по которому оформляется зачисление
+    // Зарплата преподавателей
+    pub payroll_month: NaiveDate, // Первое число расчётного месяца
+    pub payroll: Option<Payroll>,
+    pub teacher_rates: Vec<TeacherRate>,
+    pub rate_teacher: Option<TeacherRate>, // Преподаватель, ставка которого редактируется
+    pub rate_type: Option<RateType>,
+    pub rate_value: String,
+    pub payroll_confirm_approve: bool,
+    pub payroll_notice: Option<String>,
+    pub payroll_error: Option<String>,
+    // Журнал изменений
+    pub audit_entries: Vec<AuditEntry>,
+    pub audit_actors: Vec<AuditActor>, // Пользователи, встречающиеся в журнале, — варианты фильтра
+    pub audit_filter: AuditFilter,
+    pub audit_notice: Option<String>,
+    pub audit_error: Option<String>,
     //
     pub show_conduct_lesson_modal: bool, // Для управления видимостью модального окна
     pub students_for_attendance: Vec<StudentAttendance>, // Для хранения данных о студентах в модальном окне
@@ -235,7 +411,66 @@ pub struct App {
     pub backup_interval: Option<BackupInterval>,
     pub backup_folder: Option<String>,
     pub max_backup_count: Option<usize>,
+    pub backup_compression: Option<BackupCompression>,
+    pub pdf_backend: Option<PdfBackend>,
+    pub backup_time: String, // Время суток для автоматического бэкапа "ЧЧ:ММ", пусто — любое
     pub last_backup_time: Option<String>,
+    pub next_backup_time: Option<NaiveDateTime>,
+    pub backup_in_progress: bool,
+    pub backup_status: Option<String>,
+    pub backup_error: Option<String>,
+    pub backup_failed_at: Option<NaiveDateTime>, // Чтобы не повторять неудачный бэкап каждую минуту
+    // Восстановление из резервной копии
+    pub backup_files: Vec<BackupFile>,
+    pub restore_candidate: Option<BackupFile>, // Копия, ожидающая подтверждения восстановления
+    pub restore_in_progress: bool,
+    pub restore_error: Option<String>,
+    // Права доступа
+    pub permissions: HashSet<(Role, Permission)>, // Разрешённые пары роль — действие из RolePermission
+    pub access_denied: Option<String>,            // Последнее отклонённое из-за прав действие
+    pub permissions_error: Option<String>,
+    pub show_permissions_modal: bool,
+    // Заявки на регистрацию
+    pub registration_requests: Vec<RegistrationRequest>,
+    pub registration_parents: Vec<UserInfo>, // Родители, к которым можно привязать студента
+    pub reviewing_registration: Option<UserInfo>,
+    pub review_role: Option<Role>,
+    pub review_parent: Option<UserInfo>,
+    pub review_group: Option<Group>,
+    pub review_reason: String,
+    pub review_error: Option<String>,
+    pub registration_review: Option<RegistrationReview>, // Решение по заявке текущего пользователя
+    // Смена и восстановление пароля
+    pub password_change_required: bool, // Вход по временному паролю — до смены доступна только форма смены
+    pub show_change_password_modal: bool,
+    pub current_password_input: String,
+    pub new_password_input: String,
+    pub new_password_repeat_input: String,
+    pub change_password_error: Option<String>,
+    pub change_password_notice: Option<String>,
+    pub temporary_password: Option<(String, String)>, // ФИО пользователя и выданный временный пароль
+    pub reset_password_error: Option<String>,
+    pub recovery_codes: Vec<String>, // Только что выданные коды, показываются один раз
+    pub recovery_codes_left: i64,
+    pub recovery_code_input: String,
+    pub recovery_error: Option<String>,
+    // История входов и блокировка по бездействию
+    pub login_history: Vec<LoginAttempt>,
+    pub locked_accounts: Vec<LockedAccount>,
+    pub login_history_email_filter: String,
+    pub login_history_failures_only: bool,
+    pub login_history_error: Option<String>,
+    pub idle_lock_minutes: u32,
+    pub last_activity: Instant, // Последнее нажатие клавиши, кнопки мыши или прокрутка
+    // Двухфакторная аутентификация
+    pub require_admin_two_factor: bool, // Администраторы обязаны включить второй фактор
+    pub pending_two_factor_user: Option<UserInfo>, // Пароль верный, ждём код подтверждения
+    pub two_factor_enabled: bool,
+    pub two_factor_setup_required: bool,
+    pub two_factor_setup_secret: Option<String>,
+    pub two_factor_qr: Option<image::Handle>,
+    pub two_factor_code_input: String,
+    pub two_factor_error: Option<String>,
 }
 impl Default for App {
     fn default() -> Self {
@@ -258,18 +493,61 @@ impl Default for App {
 
         let max_backup_count = config.as_ref().and_then(|c| c.max_backup_count);
 
-        let interval = config.as_ref().and_then(|c| {
-            let val = c.backup_interval.as_deref().unwrap_or("never");
-            BACKUP_INTERVALS.iter().find(|i| i.value == val).cloned()
+        let backup_compression = config.as_ref().and_then(|c| {
+            let val = c.backup_compression.as_deref().unwrap_or("none");
+            BACKUP_COMPRESSIONS.iter().find(|comp| comp.value == val).cloned()
         });
 
-        let last_backup_time = get_last_backup_time("backup");
+        // Без настройки используется встроенный генератор: он работает без Chrome и сети
+        let pdf_backend = config
+            .as_ref()
+            .and_then(|c| c.pdf_backend.as_deref())
+            .and_then(|val| PDF_BACKENDS.iter().find(|backend| backend.value == val))
+            .or(PDF_BACKENDS.first())
+            .cloned();
+
+        let payment_lock_days = config
+            .as_ref()
+            .and_then(|c| c.payment_lock_days)
+            .unwrap_or(7);
+
+        let sibling_discount_percent = config
+            .as_ref()
+            .and_then(|c| c.sibling_discount_percent)
+            .unwrap_or(10);
+
+        let organization = config
+            .as_ref()
+            .and_then(|c| c.organization.clone())
+            .unwrap_or_default();
+
+        let waitlist_offer_days = config
+            .as_ref()
+            .and_then(|c| c.waitlist_offer_days)
+            .unwrap_or(3);
+
+        let idle_lock_minutes = config
+            .as_ref()
+            .and_then(|c| c.idle_lock_minutes)
+            .unwrap_or(15);
+
+        let require_admin_two_factor = config
+            .as_ref()
+            .and_then(|c| c.require_admin_two_factor)
+            .unwrap_or(false);
+
+        let backup_time = config
+            .as_ref()
+            .and_then(|c| c.backup_time.clone())
+            .unwrap_or_default();
+
+        let backup_dir = backup_folder.clone().unwrap_or_else(|| "backup".to_string());
+        let last_backup_time = get_last_backup_time(&backup_dir);
 
-        start_backup_scheduler(
-            interval,
-            config.clone().unwrap().backup_folder.clone(),
-            config.unwrap().max_backup_count,
-        );
+        // Сами бэкапы запускает подписка App::subscription, здесь только расчёт для экрана настроек
+        let next_backup_time = backup_interval
+            .as_ref()
+            .and_then(|interval| next_backup_due(interval, &backup_time, &backup_dir));
         Self {
             error_message: "".to_string(),
             date: Date::today(),
@@ -286,7 +564,60 @@ impl Default for App {
             backup_interval,
             backup_folder,
             max_backup_count,
+            backup_compression,
+            pdf_backend,
+            backup_time,
             last_backup_time,
+            next_backup_time,
+            backup_in_progress: false,
+            backup_status: None,
+            backup_error: None,
+            backup_failed_at: None,
+            backup_files: vec![],
+            restore_candidate: None,
+            restore_in_progress: false,
+            restore_error: None,
+            permissions: HashSet::new(),
+            access_denied: None,
+            permissions_error: None,
+            show_permissions_modal: false,
+            registration_requests: Vec::new(),
+            registration_parents: Vec::new(),
+            reviewing_registration: None,
+            review_role: None,
+            review_parent: None,
+            review_group: None,
+            review_reason: String::new(),
+            review_error: None,
+            registration_review: None,
+            password_change_required: false,
+            show_change_password_modal: false,
+            current_password_input: String::new(),
+            new_password_input: String::new(),
+            new_password_repeat_input: String::new(),
+            change_password_error: None,
+            change_password_notice: None,
+            temporary_password: None,
+            reset_password_error: None,
+            recovery_codes: Vec::new(),
+            recovery_codes_left: 0,
+            recovery_code_input: String::new(),
+            recovery_error: None,
+            login_history: Vec::new(),
+            locked_accounts: Vec::new(),
+            login_history_email_filter: String::new(),
+            login_history_failures_only: false,
+            login_history_error: None,
+            idle_lock_minutes,
+            last_activity: Instant::now(),
+            require_admin_two_factor,
+            pending_two_factor_user: None,
+            two_factor_enabled: false,
+            two_factor_setup_required: false,
+            two_factor_setup_secret: None,
+            two_factor_qr: None,
+            two_factor_code_input: String::new(),
+            two_factor_error: None,
             register_error: None,
             registration_success: false,
             logged_in_user: "".to_string(),
@@ -325,7 +656,7 @@ impl Default for App {
             edit_user_name: "".to_string(),
             edit_user_email: "".to_string(),
             edit_user_birthday: "".to_string(),
-            edit_user_type: "".to_string(),
+            edit_user_type: Role::Unconfirmed,
             user_type_filter: None,
             course_filter_text: "".to_string(),
             show_add_group_modal: false,
@@ -341,6 +672,11 @@ impl Default for App {
             group_filter_text: "".to_string(),
             is_manage_students_modal_open: false,
             selected_student_to_add: None,
+            group_enrollments: vec![],
+            new_enrollment_terms: EnrollmentTerms::Standard,
+            new_enrollment_due: String::new(),
+            transfer_enrollment: None,
+            transfer_target: None,
             show_children_modal: false,
             parent_children: vec![],
             available_children: vec![],
@@ -379,6 +715,30 @@ impl Default for App {
             users_for_picklist: vec![],
             group_error_message: None,
             student_group_info: None,
+            student_progress: vec![],
+            submission_assignment: None,
+            submission_text: text_editor::Content::new(),
+            submission_files: vec![],
+            submission_error: None,
+            show_schedule_modal: false,
+            schedule_group: None,
+            schedule_slots: vec![],
+            schedule_planned_sessions: vec![],
+            new_slot_weekday: None,
+            new_slot_start: "".to_string(),
+            new_slot_duration: "90".to_string(),
+            new_slot_room: "".to_string(),
+            schedule_start_date: "".to_string(),
+            schedule_error: None,
+            show_week_calendar_modal: false,
+            calendar_week_start: week_start(Local::now().date_naive()),
+            calendar_sessions: vec![],
+            show_group_submissions_modal: false,
+            group_submissions: vec![],
+            grading_submission_id: None,
+            grading_score: "".to_string(),
+            grading_comment: "".to_string(),
+            grading_error: None,
             show_group_students_modal: false,
             selected_group_for_students_name: None,
             selected_group_students: vec![],
@@ -393,6 +753,67 @@ impl Default for App {
             courses_with_seats: vec![],
             groups_for_selected_course: vec![],
             selected_payment_type_idx: None,
+            balances: vec![],
+            show_balances: false,
+            show_debtors_only: false,
+            settle_balance: None,
+            settle_amount: "".to_string(),
+            settle_payment_type: None,
+            settle_error: None,
+            payment_error: None,
+            payment_lock_days,
+            reversal_payment: None,
+            reversal_kind: ReversalKind::Refund,
+            reversal_amount: "".to_string(),
+            reversal_reason: "".to_string(),
+            reversal_error: None,
+            sibling_discount_percent,
+            new_payment_sibling: None,
+            new_payment_promo_input: "".to_string(),
+            new_payment_promo: None,
+            new_payment_manual_discount: "".to_string(),
+            new_payment_manual_reason: "".to_string(),
+            new_payment_error: None,
+            show_promo_codes: false,
+            promo_codes: Vec::new(),
+            new_promo_code: "".to_string(),
+            new_promo_is_percent: true,
+            new_promo_value: "".to_string(),
+            new_promo_valid_from: "".to_string(),
+            new_promo_valid_to: "".to_string(),
+            new_promo_max_uses: "".to_string(),
+            promo_error: None,
+            payment_documents: Vec::new(),
+            payment_notice: None,
+            organization,
+            show_bank_import: false,
+            bank_import_file: "".to_string(),
+            bank_import_lines: Vec::new(),
+            bank_import_options: Vec::new(),
+            bank_import_error: None,
+            waitlist: Vec::new(),
+            waitlist_offer_days,
+            waitlist_course: None,
+            waitlist_error: None,
+            waitlist_enrollment: None,
+            payroll_month: Local::now().date_naive().with_day(1).unwrap_or_default(),
+            payroll: None,
+            teacher_rates: Vec::new(),
+            rate_teacher: None,
+            rate_type: None,
+            rate_value: "".to_string(),
+            payroll_confirm_approve: false,
+            payroll_notice: None,
+            payroll_error: None,
+            audit_entries: Vec::new(),
+            audit_actors: Vec::new(),
+            audit_filter: AuditFilter::default(),
+            audit_notice: None,
+            audit_error: None,
+            new_payment_installments: 1,
+            installment_balance: None,
+            installment_parts: 1,
+            installment_error: None,
             show_conduct_lesson_modal: false,
             students_for_attendance: vec![],
             current_lesson_to_conduct: None,
@@ -507,223 +928,1254 @@ pub struct Payment {
     pub student_name: String,
     pub course_title: String,
     pub group_name: String,
+    // Для возвратов и корректировок: исходный платёж, причина, кто и когда оформил
+    pub reverses_payment_id: Option<i32>,
+    pub reason: Option<String>,
+    pub operator_name: Option<String>,
+    pub created_at: Option<String>,
+    // Скидка, предоставленная при зачислении (отражается в первом платеже)
+    pub discount: f64,
+    pub discount_note: Option<String>,
 }
 
-#[derive(Debug, Clone, Eq, Hash, PartialOrd, Ord)]
-pub struct UserInfo {
-    pub id: i32,
-    pub name: String,
-    pub email: String,
-    pub birthday: String,
-    pub user_type: String, // Теперь соответствует полю "Type" в БД
-    pub avatar_data: Option<Vec<u8>>,
-    pub group_id: Option<String>, // Это поле может быть, но оно всегда будет None из Users
-    pub child_count: Option<i32>, // Это поле может быть, но оно всегда будет None из Users
-}
+impl Payment {
+    pub fn is_reversal(&self) -> bool {
+        self.reverses_payment_id.is_some()
+    }
 
-// Для PickList:
-// Чтобы PickList мог отобразить пользователя
-impl fmt::Display for UserInfo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.name)
+    /// Сумма платежа с учётом всех оформленных к нему возвратов и корректировок
+    pub fn net_amount(&self, payments: &[Payment]) -> f64 {
+        self.amount
+            + payments
+                .iter()
+                .filter(|p| p.reverses_payment_id == Some(self.id))
+                .map(|p| p.amount)
+                .sum::<f64>()
     }
 }
 
-// Чтобы PickList мог сравнивать выбранный элемент
-// Обновите PartialEq для UserInfo, чтобы сравнивать по ID
-impl PartialEq for UserInfo {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id // <--- Сравниваем по ID
-    }
+/// Вид встречной записи к платежу
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReversalKind {
+    Refund,     // Возврат части или всей суммы (отрицательная сумма)
+    Correction, // Исправление суммы в любую сторону
 }
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct Group {
-    pub id: i32,
-    pub name: String,
-    pub course_id: Option<i32>,       // Сохраняем ID курса
-    pub course_name: Option<String>,  // Новое поле для названия курса
-    pub teacher_id: Option<i32>,      // Сохраняем ID преподавателя
-    pub teacher_name: Option<String>, // Новое поле для имени преподавателя
-    pub student_count: u8,
-    pub status: GroupStatus,
+impl ReversalKind {
+    /// Значение поля Payment.type
+    pub fn payment_type(&self) -> &'static str {
+        match self {
+            ReversalKind::Refund => "Возврат",
+            ReversalKind::Correction => "Корректировка",
+        }
+    }
 }
 
-impl fmt::Display for Group {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Начинаем с имени группы
-        write!(f, "{}", self.name)?;
+/// Через сколько дней платёж нельзя удалить (только оформить возврат или корректировку)
+pub const PAYMENT_LOCK_OPTIONS: [u32; 5] = [1, 3, 7, 14, 30];
 
-        let mut parts = Vec::new();
+/// Сколько дней студент может думать над предложенным местом из листа ожидания
+pub const WAITLIST_OFFER_OPTIONS: [u32; 5] = [1, 2, 3, 5, 7];
 
-        // Добавляем название курса, если оно есть
-        if let Some(course_name) = &self.course_name {
-            parts.push(format!("Курс: {}", course_name));
-        }
+/// Состояние записи в листе ожидания
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitlistStatus {
+    Waiting,   // В очереди
+    Offered,   // Освободилось место, ждём ответа студента
+    Enrolled,  // Студент зачислен в группу курса
+    Declined,  // Студент отказался от предложенного места
+    Expired,   // Предложение не принято вовремя
+    Cancelled, // Запись снята из очереди
+}
 
-        // Добавляем название преподавателя, если оно есть
-        if let Some(teacher_name) = &self.teacher_name {
-            parts.push(format!("Преподаватель: {}", teacher_name));
+impl WaitlistStatus {
+    pub const ALL: [WaitlistStatus; 6] = [
+        WaitlistStatus::Waiting,
+        WaitlistStatus::Offered,
+        WaitlistStatus::Enrolled,
+        WaitlistStatus::Declined,
+        WaitlistStatus::Expired,
+        WaitlistStatus::Cancelled,
+    ];
+
+    /// Значение поля Waitlist.status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WaitlistStatus::Waiting => "waiting",
+            WaitlistStatus::Offered => "offered",
+            WaitlistStatus::Enrolled => "enrolled",
+            WaitlistStatus::Declined => "declined",
+            WaitlistStatus::Expired => "expired",
+            WaitlistStatus::Cancelled => "cancelled",
         }
+    }
 
-        // Если есть дополнительные части (курс или преподаватель), добавляем их в скобках
-        if !parts.is_empty() {
-            write!(f, " ({})", parts.join(", "))?;
-        }
+    /// Запись ещё занимает место в очереди
+    pub fn is_active(&self) -> bool {
+        matches!(self, WaitlistStatus::Waiting | WaitlistStatus::Offered)
+    }
+}
 
-        Ok(())
+impl fmt::Display for WaitlistStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitlistStatus::Waiting => write!(f, "в очереди"),
+            WaitlistStatus::Offered => write!(f, "предложено место"),
+            WaitlistStatus::Enrolled => write!(f, "зачислен"),
+            WaitlistStatus::Declined => write!(f, "отказ"),
+            WaitlistStatus::Expired => write!(f, "предложение истекло"),
+            WaitlistStatus::Cancelled => write!(f, "снят с очереди"),
+        }
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct GroupForReport {
-    pub id: i32,
-    pub name: String,
-    pub course_id: Option<i32>,       // Сохраняем ID курса
-    pub course_name: Option<String>,  // Новое поле для названия курса
-    pub teacher_id: Option<i32>,      // Сохраняем ID преподавателя
-    pub teacher_name: Option<String>, // Новое поле для имени преподавателя
-    pub student_count: u8,
-    pub status: GroupStatus,
-    pub students: Vec<String>,
+impl FromSql for WaitlistStatus {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        let s = value.as_str()?;
+        WaitlistStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Неизвестный статус листа ожидания: {}", s).into()))
+    }
 }
 
-impl fmt::Display for GroupForReport {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Начинаем с имени группы
-        write!(f, "{}", self.name)?;
+impl ToSql for WaitlistStatus {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(self.as_str().into())
+    }
+}
 
-        let mut parts = Vec::new();
+/// Этап жизненного цикла зачисления студента в группу (строка GroupStudent)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnrollmentStatus {
+    Applied,     // Заявка, место ещё не занято
+    Approved,    // Заявка одобрена, место закреплено, обучение не началось
+    Active,      // Студент учится
+    Completed,   // Курс пройден
+    Dropped,     // Отчислен или заявка отклонена
+    Transferred, // Переведён в другую группу
+}
 
-        // Добавляем название курса, если оно есть
-        if let Some(course_name) = &self.course_name {
-            parts.push(format!("Курс: {}", course_name));
+impl EnrollmentStatus {
+    pub const ALL: [EnrollmentStatus; 6] = [
+        EnrollmentStatus::Applied,
+        EnrollmentStatus::Approved,
+        EnrollmentStatus::Active,
+        EnrollmentStatus::Completed,
+        EnrollmentStatus::Dropped,
+        EnrollmentStatus::Transferred,
+    ];
+
+    /// Значение поля GroupStudent.status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnrollmentStatus::Applied => "applied",
+            EnrollmentStatus::Approved => "approved",
+            EnrollmentStatus::Active => "active",
+            EnrollmentStatus::Completed => "completed",
+            EnrollmentStatus::Dropped => "dropped",
+            EnrollmentStatus::Transferred => "transferred",
         }
+    }
 
-        // Добавляем название преподавателя, если оно есть
-        if let Some(teacher_name) = &self.teacher_name {
-            parts.push(format!("Преподаватель: {}", teacher_name));
-        }
+    /// Студент числится в группе и занимает место на курсе
+    pub fn holds_seat(&self) -> bool {
+        matches!(self, EnrollmentStatus::Approved | EnrollmentStatus::Active)
+    }
 
-        // Если есть дополнительные части (курс или преподаватель), добавляем их в скобках
-        if !parts.is_empty() {
-            write!(f, " ({})", parts.join(", "))?;
+    /// Допустимые переходы из текущего этапа (перевод оформляется отдельно)
+    pub fn next_statuses(&self) -> &'static [EnrollmentStatus] {
+        match self {
+            EnrollmentStatus::Applied => &[EnrollmentStatus::Approved, EnrollmentStatus::Active, EnrollmentStatus::Dropped],
+            EnrollmentStatus::Approved => &[EnrollmentStatus::Active, EnrollmentStatus::Dropped],
+            EnrollmentStatus::Active => &[EnrollmentStatus::Completed, EnrollmentStatus::Dropped],
+            _ => &[],
         }
+    }
 
-        Ok(())
+    /// Подпись кнопки перехода в этот этап
+    pub fn action_label(&self) -> &'static str {
+        match self {
+            EnrollmentStatus::Applied => "Заявка",
+            EnrollmentStatus::Approved => "Одобрить",
+            EnrollmentStatus::Active => "Начать обучение",
+            EnrollmentStatus::Completed => "Завершить",
+            EnrollmentStatus::Dropped => "Отчислить",
+            EnrollmentStatus::Transferred => "Перевести",
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Lesson {
-    pub id: i32,
+impl fmt::Display for EnrollmentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnrollmentStatus::Applied => write!(f, "заявка"),
+            EnrollmentStatus::Approved => write!(f, "одобрено"),
+            EnrollmentStatus::Active => write!(f, "обучается"),
+            EnrollmentStatus::Completed => write!(f, "завершил обучение"),
+            EnrollmentStatus::Dropped => write!(f, "отчислен"),
+            EnrollmentStatus::Transferred => write!(f, "переведён"),
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Assignment {
-    pub id: i32,
-    pub lesson_id: i32,
-    pub title: String,
-    pub description: String,
-    pub assignment_type: String, // В таблице колонка "type"
+impl FromSql for EnrollmentStatus {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        let s = value.as_str()?;
+        EnrollmentStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Неизвестный статус зачисления: {}", s).into()))
+    }
 }
 
-impl fmt::Display for Assignment {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Отображаем название задания и его тип
-        write!(f, "{} ({})", self.title, self.assignment_type)
+impl ToSql for EnrollmentStatus {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(self.as_str().into())
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum TextInputOrEditorInput {
-    TextEditor(text_editor::Action), // Действие из TextEditor
-    TextInput(String),               // Строка из TextInput
-}
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)] // Добавил Default
-pub enum AssignmentType {
-    #[default] // Задаем значение по умолчанию, если потребуется
-    Lecture,
-    Practice,
-    // Добавьте другие типы по необходимости
+/// Условия оплаты зачисления
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnrollmentTerms {
+    Standard, // Оплата при зачислении
+    Trial,    // Пробное обучение, оплата после пробного периода
+    Deferred, // Отсрочка оплаты до указанной даты
 }
 
-impl AssignmentType {
-    pub const ALL: &'static [AssignmentType] = &[AssignmentType::Lecture, AssignmentType::Practice];
+impl EnrollmentTerms {
+    pub const ALL: [EnrollmentTerms; 3] = [
+        EnrollmentTerms::Standard,
+        EnrollmentTerms::Trial,
+        EnrollmentTerms::Deferred,
+    ];
+
+    /// Значение поля GroupStudent.terms
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnrollmentTerms::Standard => "standard",
+            EnrollmentTerms::Trial => "trial",
+            EnrollmentTerms::Deferred => "deferred",
+        }
+    }
+
+    /// Для пробного обучения и отсрочки нужна дата, до которой ждём оплату
+    pub fn needs_due_date(&self) -> bool {
+        !matches!(self, EnrollmentTerms::Standard)
+    }
 }
 
-// Реализация Display для отображения в PickList и преобразования в строку
-impl fmt::Display for AssignmentType {
+impl fmt::Display for EnrollmentTerms {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                AssignmentType::Lecture => "Лекция",
-                AssignmentType::Practice => "Практика",
-            }
-        )
+        match self {
+            EnrollmentTerms::Standard => write!(f, "Обычное"),
+            EnrollmentTerms::Trial => write!(f, "Пробное"),
+            EnrollmentTerms::Deferred => write!(f, "Отсрочка оплаты"),
+        }
     }
 }
 
-impl PartialEq for Lesson {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id // Сравниваем по ID
+impl FromSql for EnrollmentTerms {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        let s = value.as_str()?;
+        EnrollmentTerms::ALL
+            .into_iter()
+            .find(|terms| terms.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Неизвестные условия зачисления: {}", s).into()))
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct LessonWithAssignments {
-    pub id: i32, // ID урока из таблицы Lessons
-    pub course_id: i32,
-    pub number: i32,
-    pub title: String,
-    pub assignments: Vec<Assignment>, // Список заданий для этого урока
+impl ToSql for EnrollmentTerms {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(self.as_str().into())
+    }
 }
 
+/// Зачисление студента в группу со статусом, условиями оплаты и датами
 #[derive(Debug, Clone)]
-pub struct PastSession {
+pub struct Enrollment {
     pub id: i32,
-    pub group_id: i32,
-    pub date: String,
-    pub lesson_id: i32,
-    pub lesson_number: Option<i32>,
-    pub lesson_title: Option<String>,
-    pub attendance_records: Vec<StudentAttendanceStatus>,
+    pub student_name: String,
+    pub group_name: String,
+    pub status: EnrollmentStatus,
+    pub terms: EnrollmentTerms,
+    pub payment_due: Option<String>,
+    pub enrolled_at: Option<String>,
+    pub status_changed_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub transferred_from: Option<String>, // Группа, из которой студент переведён
+    pub transferred_to: Option<String>,   // Группа, в которую студент переведён
+    pub charged: f64,
+    pub paid: f64, // Включая оплату, перенесённую при переводе
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct ProvenLesson {
-    pub id: i32,
-    pub group_id: i32,
-    pub lesson_id: i32, // Новое поле для связи с Lesson
-    pub date: String,
-    pub topic: String,
-    // Поля из связанного Lesson для отображения
-    pub lesson_number: i32,
-    pub lesson_title: String,
-    pub assignments: Vec<Assignment>,
+impl Enrollment {
+    /// Срок оплаты по пробному обучению или отсрочке прошёл, а обучение не оплачено
+    pub fn is_payment_overdue(&self, today: &str) -> bool {
+        self.status.holds_seat()
+            && self.paid + 0.01 < self.charged
+            && self.payment_due.as_deref().is_some_and(|due| due < today)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Course {
+/// Запись студента в лист ожидания курса (группа — пожелание студента, необязательно)
+#[derive(Debug, Clone)]
+pub struct WaitlistEntry {
     pub id: i32,
-    pub title: String,
-    pub description: Option<String>,
-    pub level: Option<String>,
-    pub total_seats: Option<i32>,
-    pub seats: Option<i32>,
-    pub price: Option<f64>,
-    pub lesson_count: i32,
+    pub student_id: i32,
+    pub student_name: String,
+    pub course_id: i32,
+    pub course_title: String,
+    pub group_id: Option<i32>,
+    pub group_name: Option<String>,
+    pub status: WaitlistStatus,
+    pub created_at: String,         // "ГГГГ-ММ-ДД ЧЧ:ММ:СС"
+    pub offered_at: Option<String>, // Когда освободилось и было предложено место
+    pub closed_at: Option<String>,  // Когда запись покинула очередь
+    pub position: Option<i32>,      // Место в очереди среди ожидающих
 }
 
-impl fmt::Display for Course {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.title) // Что будет отображаться в PickList
+impl WaitlistEntry {
+    /// До какого момента действует предложение места
+    pub fn offer_deadline(&self, offer_days: u32) -> Option<NaiveDateTime> {
+        self.offered_at
+            .as_deref()
+            .and_then(|offered_at| NaiveDateTime::parse_from_str(offered_at, "%Y-%m-%d %H:%M:%S").ok())
+            .map(|offered_at| offered_at + chrono::Duration::days(offer_days as i64))
     }
 }
+
+/// Вид изменения в журнале
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Level {
+pub enum AuditAction {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl AuditAction {
+    pub const ALL: [AuditAction; 3] = [AuditAction::Insert, AuditAction::Update, AuditAction::Delete];
+
+    /// Значение поля AuditLog.action
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Insert => "insert",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditAction::Insert => write!(f, "Создание"),
+            AuditAction::Update => write!(f, "Изменение"),
+            AuditAction::Delete => write!(f, "Удаление"),
+        }
+    }
+}
+
+impl FromSql for AuditAction {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        let s = value.as_str()?;
+        AuditAction::ALL
+            .into_iter()
+            .find(|action| action.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Неизвестное действие в журнале: {}", s).into()))
+    }
+}
+
+impl ToSql for AuditAction {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(self.as_str().into())
+    }
+}
+
+/// Запись журнала изменений: кто, когда и что сделал с записью, её состояние до и после (JSON)
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub created_at: String,         // "ГГГГ-ММ-ДД ЧЧ:ММ:СС"
+    pub actor_name: Option<String>, // Пусто — изменение сделано без входа в систему
+    pub action: AuditAction,
+    pub entity: String, // Имя таблицы
+    pub entity_id: Option<i64>,
+    pub before_json: Option<String>,
+    pub after_json: Option<String>,
+}
+
+impl AuditEntry {
+    /// Поля, значения которых отличаются до и после изменения: (поле, было, стало).
+    /// Для создания и удаления — все поля записи.
+    pub fn changes(&self) -> Vec<(String, String, String)> {
+        let parse = |json: &Option<String>| {
+            json.as_deref()
+                .and_then(|j| serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(j).ok())
+                .unwrap_or_default()
+        };
+        let show = |value: Option<&serde_json::Value>| match value {
+            None | Some(serde_json::Value::Null) => "—".to_string(),
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(other) => other.to_string(),
+        };
+        let before = parse(&self.before_json);
+        let after = parse(&self.after_json);
+
+        let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+        fields.sort();
+        fields.dedup();
+        fields
+            .into_iter()
+            .filter(|field| before.get(*field) != after.get(*field))
+            .map(|field| (field.clone(), show(before.get(field)), show(after.get(field))))
+            .collect()
+    }
+
+    /// Изменения в виде строк «поле: было → стало» (для создания и удаления — «поле: значение»)
+    pub fn change_lines(&self) -> Vec<String> {
+        self.changes()
+            .into_iter()
+            .map(|(field, before, after)| match self.action {
+                AuditAction::Insert => format!("{}: {}", field, after),
+                AuditAction::Delete => format!("{}: {}", field, before),
+                AuditAction::Update => format!("{}: {} → {}", field, before, after),
+            })
+            .collect()
+    }
+}
+
+/// Пользователь, от имени которого есть записи в журнале, — вариант фильтра.
+/// Почта нужна, чтобы различать однофамильцев.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditActor {
+    pub id: i32,
+    pub name: String,
+    pub email: Option<String>, // Пусто, если пользователь удалён
+}
+
+impl fmt::Display for AuditActor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.email {
+            Some(email) => write!(f, "{} ({})", self.name, email),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Фильтр журнала изменений; пустые поля не ограничивают выборку
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub actor: Option<AuditActor>,
+    pub entity: Option<&'static str>, // Подпись таблицы из audit::AUDITED_TABLES
+    pub action: Option<AuditAction>,
+    pub date_from: String, // ГГГГ-ММ-ДД
+    pub date_to: String,
+}
+
+/// Промокод: процент или фиксированная сумма, срок действия и лимит применений
+#[derive(Debug, Clone)]
+pub struct PromoCode {
+    pub id: i32,
+    pub code: String,
+    pub is_percent: bool,
+    pub value: f64,
+    pub valid_from: Option<String>, // ГГГГ-ММ-ДД, включительно
+    pub valid_to: Option<String>,
+    pub max_uses: Option<i32>,
+    pub used_count: i32,
+}
+
+impl PromoCode {
+    /// Можно ли применить промокод в указанный день (`today` в формате ГГГГ-ММ-ДД)
+    pub fn check(&self, today: &str) -> Result<(), String> {
+        if self.valid_from.as_deref().is_some_and(|from| today < from) {
+            return Err(format!("Промокод {} ещё не действует.", self.code));
+        }
+        if self.valid_to.as_deref().is_some_and(|to| today > to) {
+            return Err(format!("Срок действия промокода {} истёк.", self.code));
+        }
+        if self.max_uses.is_some_and(|max| self.used_count >= max) {
+            return Err(format!("Промокод {} уже использован максимальное число раз.", self.code));
+        }
+        Ok(())
+    }
+
+    /// Размер скидки от указанной стоимости
+    pub fn discount_for(&self, price: f64) -> f64 {
+        if self.is_percent {
+            price * self.value / 100.0
+        } else {
+            self.value
+        }
+    }
+
+    pub fn value_display(&self) -> String {
+        if self.is_percent {
+            format!("{}%", self.value)
+        } else {
+            format!("{:.2} ₽", self.value)
+        }
+    }
+}
+
+/// Одна из скидок, применённых к стоимости обучения
+#[derive(Debug, Clone)]
+pub struct DiscountLine {
+    pub label: String,
+    pub amount: f64,
+}
+
+/// Первая оплата при зачислении со скидками и рассрочкой
+#[derive(Debug, Clone)]
+pub struct EnrollmentPayment {
+    pub date: NaiveDate,
+    pub payment_type: String,
+    pub course_id: i32,
+    /// Стоимость обучения после скидок
+    pub total: f64,
+    /// Сумма, вносимая сейчас (при рассрочке — первая часть)
+    pub first_payment: f64,
+    pub installments: u32,
+    pub discount: f64,
+    pub discount_note: String,
+    pub promo_code_id: Option<i32>,
+}
+
+/// Складывает скидки, не давая итоговой сумме превысить стоимость обучения
+pub fn cap_discounts(price: f64, lines: Vec<DiscountLine>) -> Vec<DiscountLine> {
+    let mut left = price.max(0.0);
+    lines
+        .into_iter()
+        .filter_map(|line| {
+            let amount = (line.amount.min(left) * 100.0).round() / 100.0;
+            left -= amount;
+            (amount > 0.0).then_some(DiscountLine { amount, ..line })
+        })
+        .collect()
+}
+
+/// Примечание к платежу: перечень скидок с суммами
+pub fn discount_note(lines: &[DiscountLine]) -> String {
+    lines
+        .iter()
+        .map(|line| format!("{}: {:.2} ₽", line.label, line.amount))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Варианты скидки для братьев и сестёр, %
+pub const SIBLING_DISCOUNT_OPTIONS: [u32; 5] = [0, 5, 10, 15, 20];
+
+/// Роль пользователя (поле Users.Type)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Role {
+    Admin,
+    Teacher,
+    Student,
+    Parent,
+    Unconfirmed, // Зарегистрировался, но администратор ещё не назначил роль
+}
+
+impl Role {
+    pub const ALL: [Role; 5] = [Role::Admin, Role::Teacher, Role::Student, Role::Parent, Role::Unconfirmed];
+
+    /// Значение поля Users.Type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Teacher => "teacher",
+            Role::Student => "student",
+            Role::Parent => "parent",
+            Role::Unconfirmed => "unconfirmed",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Role> {
+        Role::ALL.into_iter().find(|role| role.as_str() == value)
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Admin => write!(f, "Администратор"),
+            Role::Teacher => write!(f, "Преподаватель"),
+            Role::Student => write!(f, "Студент"),
+            Role::Parent => write!(f, "Родитель"),
+            Role::Unconfirmed => write!(f, "Не подтверждён"),
+        }
+    }
+}
+
+impl FromSql for Role {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        let s = value.as_str()?;
+        Role::from_db(s).ok_or_else(|| FromSqlError::Other(format!("Неизвестная роль пользователя: {}", s).into()))
+    }
+}
+
+impl ToSql for Role {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(self.as_str().into())
+    }
+}
+
+/// Роли, которые администратор может выдать при подтверждении регистрации
+pub const APPROVAL_ROLES: [Role; 3] = [Role::Student, Role::Parent, Role::Teacher];
+
+/// Решение по заявке на регистрацию
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistrationDecision {
+    Approved,
+    Rejected,
+}
+
+impl RegistrationDecision {
+    pub const ALL: [RegistrationDecision; 2] = [RegistrationDecision::Approved, RegistrationDecision::Rejected];
+
+    /// Значение поля RegistrationReview.decision
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RegistrationDecision::Approved => "approved",
+            RegistrationDecision::Rejected => "rejected",
+        }
+    }
+}
+
+impl fmt::Display for RegistrationDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistrationDecision::Approved => write!(f, "Подтверждена"),
+            RegistrationDecision::Rejected => write!(f, "Отклонена"),
+        }
+    }
+}
+
+impl FromSql for RegistrationDecision {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        let s = value.as_str()?;
+        RegistrationDecision::ALL
+            .into_iter()
+            .find(|decision| decision.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Неизвестное решение по заявке: {}", s).into()))
+    }
+}
+
+impl ToSql for RegistrationDecision {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(self.as_str().into())
+    }
+}
+
+/// Рассмотрение заявки на регистрацию: решение, выданная роль и причина (комментарий)
+#[derive(Debug, Clone)]
+pub struct RegistrationReview {
+    pub decision: RegistrationDecision,
+    pub role: Option<Role>,
+    pub reason: Option<String>,
+    pub decided_at: String,         // "ГГГГ-ММ-ДД ЧЧ:ММ:СС"
+    pub decided_by: Option<String>, // Имя администратора
+}
+
+/// Неподтверждённая учётная запись в очереди заявок (review — если заявку уже отклоняли)
+#[derive(Debug, Clone)]
+pub struct RegistrationRequest {
+    pub user: UserInfo,
+    pub review: Option<RegistrationReview>,
+}
+
+/// Чем закончилась попытка входа (или восстановления пароля) — для истории входов
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginOutcome {
+    Success,
+    WrongPassword,
+    UnknownUser,
+    Locked,
+    Recovered,
+    RecoveryFailed,
+    WrongSecondFactor,
+}
+
+impl LoginOutcome {
+    pub const ALL: [LoginOutcome; 7] = [
+        LoginOutcome::Success,
+        LoginOutcome::WrongPassword,
+        LoginOutcome::UnknownUser,
+        LoginOutcome::Locked,
+        LoginOutcome::Recovered,
+        LoginOutcome::RecoveryFailed,
+        LoginOutcome::WrongSecondFactor,
+    ];
+
+    /// Значение поля LoginHistory.outcome
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginOutcome::Success => "success",
+            LoginOutcome::WrongPassword => "wrong_password",
+            LoginOutcome::UnknownUser => "unknown_user",
+            LoginOutcome::Locked => "locked",
+            LoginOutcome::Recovered => "recovered",
+            LoginOutcome::RecoveryFailed => "recovery_failed",
+            LoginOutcome::WrongSecondFactor => "wrong_second_factor",
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, LoginOutcome::Success | LoginOutcome::Recovered)
+    }
+}
+
+impl fmt::Display for LoginOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoginOutcome::Success => write!(f, "Вход выполнен"),
+            LoginOutcome::WrongPassword => write!(f, "Неверный пароль"),
+            LoginOutcome::UnknownUser => write!(f, "Неизвестный email"),
+            LoginOutcome::Locked => write!(f, "Учётная запись заблокирована"),
+            LoginOutcome::Recovered => write!(f, "Пароль восстановлен по коду"),
+            LoginOutcome::RecoveryFailed => write!(f, "Неверный код восстановления"),
+            LoginOutcome::WrongSecondFactor => write!(f, "Неверный код подтверждения"),
+        }
+    }
+}
+
+impl FromSql for LoginOutcome {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        let s = value.as_str()?;
+        LoginOutcome::ALL
+            .into_iter()
+            .find(|outcome| outcome.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Неизвестный результат входа: {}", s).into()))
+    }
+}
+
+impl ToSql for LoginOutcome {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(self.as_str().into())
+    }
+}
+
+/// Запись истории входов
+#[derive(Debug, Clone)]
+pub struct LoginAttempt {
+    pub attempted_at: String, // "ГГГГ-ММ-ДД ЧЧ:ММ:СС"
+    pub email: String,
+    pub user_name: Option<String>, // None — email не принадлежит ни одному пользователю
+    pub outcome: LoginOutcome,
+}
+
+/// Учётная запись, временно заблокированная после неудачных попыток входа
+#[derive(Debug, Clone)]
+pub struct LockedAccount {
+    pub user_id: i32,
+    pub name: String,
+    pub email: String,
+    pub failed_count: i64,
+    pub locked_until: String,
+}
+
+/// Через сколько минут бездействия экран блокируется (0 — не блокировать)
+pub const IDLE_LOCK_OPTIONS: [u32; 6] = [0, 5, 10, 15, 30, 60];
+
+/// Действие, на которое роли выдаётся разрешение (строка матрицы прав)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    ManageUsers,       // Редактирование и удаление пользователей, связи родитель — ребёнок
+    ManageCourses,     // Курсы, уроки и задания
+    ManageGroups,      // Группы, зачисления, переводы и расписание
+    ManageWaitlist,    // Запись в лист ожидания, зачисление и снятие с очереди
+    ManagePayments,    // Платежи, возвраты, рассрочки, банковские выписки, счета и квитанции
+    ManageDiscounts,   // Промокоды
+    ManagePayroll,     // Ставки преподавателей и утверждение ведомостей
+    ConductLessons,    // Проведение занятий и отметка посещаемости
+    GradeSubmissions,  // Проверка сданных работ
+    SubmitAssignments, // Сдача своих работ
+    RespondToWaitlist, // Отказ от своего места в листе ожидания
+    ManageSettings,    // Реквизиты организации, правила оплаты и листа ожидания
+    ManageBackups,     // Резервное копирование и восстановление БД
+    ManagePermissions, // Изменение матрицы прав
+}
+
+impl Permission {
+    pub const ALL: [Permission; 14] = [
+        Permission::ManageUsers,
+        Permission::ManageCourses,
+        Permission::ManageGroups,
+        Permission::ManageWaitlist,
+        Permission::ManagePayments,
+        Permission::ManageDiscounts,
+        Permission::ManagePayroll,
+        Permission::ConductLessons,
+        Permission::GradeSubmissions,
+        Permission::SubmitAssignments,
+        Permission::RespondToWaitlist,
+        Permission::ManageSettings,
+        Permission::ManageBackups,
+        Permission::ManagePermissions,
+    ];
+
+    /// Значение поля RolePermission.permission
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ManageUsers => "manage_users",
+            Permission::ManageCourses => "manage_courses",
+            Permission::ManageGroups => "manage_groups",
+            Permission::ManageWaitlist => "manage_waitlist",
+            Permission::ManagePayments => "manage_payments",
+            Permission::ManageDiscounts => "manage_discounts",
+            Permission::ManagePayroll => "manage_payroll",
+            Permission::ConductLessons => "conduct_lessons",
+            Permission::GradeSubmissions => "grade_submissions",
+            Permission::SubmitAssignments => "submit_assignments",
+            Permission::RespondToWaitlist => "respond_to_waitlist",
+            Permission::ManageSettings => "manage_settings",
+            Permission::ManageBackups => "manage_backups",
+            Permission::ManagePermissions => "manage_permissions",
+        }
+    }
+
+    /// Право администратора менять матрицу нельзя снять — иначе её больше никто не исправит
+    pub fn is_locked_for(&self, role: Role) -> bool {
+        *self == Permission::ManagePermissions && role == Role::Admin
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Permission::ManageUsers => write!(f, "Управление пользователями"),
+            Permission::ManageCourses => write!(f, "Курсы, уроки и задания"),
+            Permission::ManageGroups => write!(f, "Группы, зачисления и расписание"),
+            Permission::ManageWaitlist => write!(f, "Лист ожидания"),
+            Permission::ManagePayments => write!(f, "Платежи и документы"),
+            Permission::ManageDiscounts => write!(f, "Промокоды"),
+            Permission::ManagePayroll => write!(f, "Зарплата преподавателей"),
+            Permission::ConductLessons => write!(f, "Проведение занятий"),
+            Permission::GradeSubmissions => write!(f, "Проверка работ"),
+            Permission::SubmitAssignments => write!(f, "Сдача работ"),
+            Permission::RespondToWaitlist => write!(f, "Отказ от места в очереди"),
+            Permission::ManageSettings => write!(f, "Настройки организации"),
+            Permission::ManageBackups => write!(f, "Резервные копии"),
+            Permission::ManagePermissions => write!(f, "Матрица прав"),
+        }
+    }
+}
+
+impl FromSql for Permission {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        let s = value.as_str()?;
+        Permission::ALL
+            .into_iter()
+            .find(|permission| permission.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Неизвестное право доступа: {}", s).into()))
+    }
+}
+
+impl ToSql for Permission {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(self.as_str().into())
+    }
+}
+
+#[derive(Debug, Clone, Eq, Hash, PartialOrd, Ord)]
+pub struct UserInfo {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub birthday: String,
+    pub user_type: Role, // Поле "Type" в БД
+    pub avatar_data: Option<Vec<u8>>,
+    pub group_id: Option<String>, // Это поле может быть, но оно всегда будет None из Users
+    pub child_count: Option<i32>, // Это поле может быть, но оно всегда будет None из Users
+}
+
+// Для PickList:
+// Чтобы PickList мог отобразить пользователя
+impl fmt::Display for UserInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+// Чтобы PickList мог сравнивать выбранный элемент
+// Обновите PartialEq для UserInfo, чтобы сравнивать по ID
+impl PartialEq for UserInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id // <--- Сравниваем по ID
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Group {
+    pub id: i32,
+    pub name: String,
+    pub course_id: Option<i32>,       // Сохраняем ID курса
+    pub course_name: Option<String>,  // Новое поле для названия курса
+    pub teacher_id: Option<i32>,      // Сохраняем ID преподавателя
+    pub teacher_name: Option<String>, // Новое поле для имени преподавателя
+    pub student_count: u8,
+    pub status: GroupStatus,
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Начинаем с имени группы
+        write!(f, "{}", self.name)?;
+
+        let mut parts = Vec::new();
+
+        // Добавляем название курса, если оно есть
+        if let Some(course_name) = &self.course_name {
+            parts.push(format!("Курс: {}", course_name));
+        }
+
+        // Добавляем название преподавателя, если оно есть
+        if let Some(teacher_name) = &self.teacher_name {
+            parts.push(format!("Преподаватель: {}", teacher_name));
+        }
+
+        // Если есть дополнительные части (курс или преподаватель), добавляем их в скобках
+        if !parts.is_empty() {
+            write!(f, " ({})", parts.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupForReport {
+    pub id: i32,
+    pub name: String,
+    pub course_id: Option<i32>,       // Сохраняем ID курса
+    pub course_name: Option<String>,  // Новое поле для названия курса
+    pub teacher_id: Option<i32>,      // Сохраняем ID преподавателя
+    pub teacher_name: Option<String>, // Новое поле для имени преподавателя
+    pub student_count: u8,
+    pub status: GroupStatus,
+    pub students: Vec<String>,
+}
+
+impl fmt::Display for GroupForReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Начинаем с имени группы
+        write!(f, "{}", self.name)?;
+
+        let mut parts = Vec::new();
+
+        // Добавляем название курса, если оно есть
+        if let Some(course_name) = &self.course_name {
+            parts.push(format!("Курс: {}", course_name));
+        }
+
+        // Добавляем название преподавателя, если оно есть
+        if let Some(teacher_name) = &self.teacher_name {
+            parts.push(format!("Преподаватель: {}", teacher_name));
+        }
+
+        // Если есть дополнительные части (курс или преподаватель), добавляем их в скобках
+        if !parts.is_empty() {
+            write!(f, " ({})", parts.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Lesson {
+    pub id: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub id: i32,
+    pub lesson_id: i32,
+    pub title: String,
+    pub description: String,
+    pub assignment_type: String, // В таблице колонка "type"
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Отображаем название задания и его тип
+        write!(f, "{} ({})", self.title, self.assignment_type)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TextInputOrEditorInput {
+    TextEditor(text_editor::Action), // Действие из TextEditor
+    TextInput(String),               // Строка из TextInput
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)] // Добавил Default
+pub enum AssignmentType {
+    #[default] // Задаем значение по умолчанию, если потребуется
+    Lecture,
+    Practice,
+    // Добавьте другие типы по необходимости
+}
+
+impl AssignmentType {
+    pub const ALL: &'static [AssignmentType] = &[AssignmentType::Lecture, AssignmentType::Practice];
+}
+
+// Реализация Display для отображения в PickList и преобразования в строку
+impl fmt::Display for AssignmentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AssignmentType::Lecture => "Лекция",
+                AssignmentType::Practice => "Практика",
+            }
+        )
+    }
+}
+
+impl PartialEq for Lesson {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id // Сравниваем по ID
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LessonWithAssignments {
+    pub id: i32, // ID урока из таблицы Lessons
+    pub course_id: i32,
+    pub number: i32,
+    pub title: String,
+    pub assignments: Vec<Assignment>, // Список заданий для этого урока
+}
+
+#[derive(Debug, Clone)]
+pub struct PastSession {
+    pub id: i32,
+    pub group_id: i32,
+    pub date: String,
+    pub lesson_id: i32,
+    pub lesson_number: Option<i32>,
+    pub lesson_title: Option<String>,
+    pub attendance_records: Vec<StudentAttendanceStatus>,
+}
+
+/// Файл, прикреплённый к сданной работе (содержимое загружается отдельно при открытии)
+#[derive(Debug, Clone)]
+pub struct SubmissionFile {
+    pub id: i32,
+    pub file_name: String,
+}
+
+/// Работа студента по заданию вместе с результатом проверки
+#[derive(Debug, Clone)]
+pub struct Submission {
+    pub id: i32,
+    pub assignment_title: String,
+    pub lesson_number: i32,
+    pub student_name: String,
+    pub text: String,
+    pub submitted_at: String,
+    pub score: Option<i32>, // 0..=100, None — ещё не проверена
+    pub comment: Option<String>,
+    pub graded_at: Option<String>,
+    pub files: Vec<SubmissionFile>,
+}
+
+/// Задание курса с работой студента по нему (если она сдана)
+#[derive(Debug, Clone)]
+pub struct StudentAssignment {
+    pub assignment: Assignment,
+    pub lesson_number: i32,
+    pub lesson_title: String,
+    pub submission: Option<Submission>,
+}
+
+/// День недели для расписания (1 — понедельник, 7 — воскресенье)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weekday(pub u32);
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday(1),
+        Weekday(2),
+        Weekday(3),
+        Weekday(4),
+        Weekday(5),
+        Weekday(6),
+        Weekday(7),
+    ];
+}
+
+impl fmt::Display for Weekday {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = WEEKDAY_NAMES
+            .get(self.0.saturating_sub(1) as usize)
+            .copied()
+            .unwrap_or("?");
+        write!(f, "{}", name)
+    }
+}
+
+/// Еженедельный слот расписания группы
+#[derive(Debug, Clone)]
+pub struct ScheduleSlot {
+    pub id: i32,
+    pub weekday: u32,       // 1 — понедельник
+    pub start_time: String, // "ЧЧ:ММ"
+    pub duration_minutes: i32,
+    pub room: String,
+}
+
+/// Запланированное занятие группы, сформированное по слотам расписания
+#[derive(Debug, Clone)]
+pub struct PlannedSession {
+    pub group_name: String,
+    pub teacher_name: Option<String>,
+    pub lesson_number: i32,
+    pub lesson_title: String,
+    pub date: String, // "ГГГГ-ММ-ДД"
+    pub start_time: String,
+    pub duration_minutes: i32,
+    pub room: String,
+    pub conducted: bool,
+}
+
+/// Взнос по графику платежей; `paid` — какая часть взноса уже покрыта платежами
+#[derive(Debug, Clone)]
+pub struct Installment {
+    pub number: i32,
+    pub due_date: String,
+    pub amount: f64,
+    pub paid: f64,
+}
+
+impl Installment {
+    pub fn remaining(&self) -> f64 {
+        self.amount - self.paid
+    }
+
+    pub fn is_paid(&self) -> bool {
+        self.remaining() < 0.01
+    }
+
+    /// Срок прошёл, а взнос оплачен не полностью (`today` в формате ГГГГ-ММ-ДД)
+    pub fn is_overdue(&self, today: &str) -> bool {
+        !self.is_paid() && self.due_date.as_str() < today
+    }
+}
+
+/// Варианты числа частей при оплате в рассрочку
+pub const INSTALLMENT_OPTIONS: [u32; 6] = [1, 2, 3, 4, 6, 10];
+
+/// Расчёт со студентом по одной группе: начислено при зачислении, оплачено и остаток
+#[derive(Debug, Clone)]
+pub struct StudentBalance {
+    pub enrollment_id: i32, // ID строки GroupStudent
+    pub student_id: i32,
+    pub student_name: String,
+    pub group_id: i32,
+    pub group_name: String,
+    pub course_id: Option<i32>,
+    pub course_title: String,
+    pub charged: f64,
+    pub paid: f64,
+    pub installments: Vec<Installment>, // Пусто, если оплата без рассрочки
+}
+
+impl StudentBalance {
+    /// Сколько осталось доплатить (отрицательное значение — переплата)
+    pub fn outstanding(&self) -> f64 {
+        self.charged - self.paid
+    }
+
+    /// Задолженность на сегодня: при рассрочке — только просроченные взносы
+    pub fn debt_amount(&self) -> f64 {
+        if self.installments.is_empty() {
+            self.outstanding().max(0.0)
+        } else {
+            self.overdue_amount(&Local::now().format("%Y-%m-%d").to_string())
+        }
+    }
+
+    /// Долг меньше копейки не считаем
+    pub fn is_debtor(&self) -> bool {
+        self.debt_amount() >= 0.01
+    }
+
+    /// Сумма просроченных взносов
+    pub fn overdue_amount(&self, today: &str) -> f64 {
+        self.installments
+            .iter()
+            .filter(|i| i.is_overdue(today))
+            .map(Installment::remaining)
+            .sum()
+    }
+
+    /// Ближайший неоплаченный взнос
+    pub fn next_installment(&self) -> Option<&Installment> {
+        self.installments.iter().find(|i| !i.is_paid())
+    }
+}
+
+/// Сводка обучения студента для экранов студента и родителя
+#[derive(Debug, Clone)]
+pub struct StudentProgress {
+    pub student: UserInfo,
+    pub group: Option<Group>,
+    pub total_lessons: i32,
+    pub past_sessions: Vec<PastSession>,
+    pub attended_lessons: i32,
+    pub assignments: Vec<StudentAssignment>,
+    pub certificates: Vec<Certificate>,
+    pub balances: Vec<StudentBalance>,
+    pub carried_attendance: i32, // Посещённые занятия курса в прежних группах до перевода
+    pub enrollments: Vec<Enrollment>, // История зачислений, переводов и отчислений
+}
+
+impl StudentProgress {
+    /// Отметка посещаемости студента на конкретном проведённом занятии
+    pub fn was_present(&self, session: &PastSession) -> Option<bool> {
+        session
+            .attendance_records
+            .iter()
+            .find(|a| a.student_id == self.student.id)
+            .map(|a| a.present_status == "Present")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvenLesson {
+    pub id: i32,
+    pub group_id: i32,
+    pub lesson_id: i32, // Новое поле для связи с Lesson
+    pub date: String,
+    pub topic: String,
+    // Поля из связанного Lesson для отображения
+    pub lesson_number: i32,
+    pub lesson_title: String,
+    pub assignments: Vec<Assignment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Course {
+    pub id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    pub level: Option<String>,
+    pub total_seats: Option<i32>,
+    pub seats: Option<i32>,
+    pub price: Option<f64>,
+    pub lesson_count: i32,
+}
+
+impl fmt::Display for Course {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title) // Что будет отображаться в PickList
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
     Beginner,
     Intermediate,
     Advanced,
@@ -778,6 +2230,87 @@ impl fmt::Display for StudentPickListItem {
     }
 }
 
+/// Зачисление студента в группу как вариант выбора при разборе банковской выписки
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnrollmentPickListItem {
+    pub student_id: i32,
+    pub group_id: i32,
+    pub course_id: i32,
+    pub label: String, // "Студент — группа (остаток)"
+}
+
+impl fmt::Display for EnrollmentPickListItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+impl EnrollmentPickListItem {
+    pub fn from_balance(balance: &StudentBalance) -> Option<Self> {
+        Some(Self {
+            student_id: balance.student_id,
+            group_id: balance.group_id,
+            course_id: balance.course_id?,
+            label: format!(
+                "{} — {} (остаток {:.2} ₽)",
+                balance.student_name,
+                balance.group_name,
+                balance.outstanding().max(0.0)
+            ),
+        })
+    }
+}
+
+/// Входящий перевод из банковской выписки
+#[derive(Debug, Clone)]
+pub struct BankTransaction {
+    pub reference: String, // Уникальный ключ строки выписки, защищает от повторного импорта
+    pub date: String,      // ГГГГ-ММ-ДД
+    pub amount: f64,
+    pub payer: String,
+    pub purpose: String,
+}
+
+/// Результат сопоставления строки выписки со студентами
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchStatus {
+    Matched,         // Совпали плательщик и сумма — платёж будет создан
+    Suggested,       // Найден вероятный студент, нужно подтверждение
+    Unmatched,       // Студента нужно выбрать вручную
+    AlreadyImported, // Строка уже была загружена ранее
+    Duplicate,       // Повтор другой строки той же выписки
+}
+
+impl MatchStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MatchStatus::Matched => "сопоставлено",
+            MatchStatus::Suggested => "требует проверки",
+            MatchStatus::Unmatched => "не сопоставлено",
+            MatchStatus::AlreadyImported => "уже загружено",
+            MatchStatus::Duplicate => "повтор в выписке",
+        }
+    }
+
+    /// Можно ли создать платёж по строке с таким статусом
+    pub fn is_importable(&self) -> bool {
+        !matches!(self, MatchStatus::AlreadyImported | MatchStatus::Duplicate)
+    }
+}
+
+/// Строка выписки вместе с выбранным зачислением и решением, создавать ли платёж
+#[derive(Debug, Clone)]
+pub struct StatementLine {
+    pub transaction: BankTransaction,
+    pub status: MatchStatus,
+    pub target: Option<EnrollmentPickListItem>,
+    pub include: bool,
+    pub match_note: String, // Почему выбран именно этот студент
+}
+
+/// Тип платежа для переводов, загруженных из выписки
+pub const BANK_TRANSFER_TYPE: &str = "Банковский перевод";
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct CoursePickListItem {
     pub id: i32,
@@ -809,12 +2342,348 @@ pub struct Config {
     pub backup_interval: Option<String>,
     pub backup_folder: Option<String>,
     pub max_backup_count: Option<usize>,
+    #[serde(default)]
+    pub backup_compression: Option<String>,
+    #[serde(default)]
+    pub backup_time: Option<String>,
+    #[serde(default)]
+    pub pdf_backend: Option<String>,
+    #[serde(default)]
+    pub payment_lock_days: Option<u32>,
+    #[serde(default)]
+    pub sibling_discount_percent: Option<u32>,
+    #[serde(default)]
+    pub organization: Option<Organization>,
+    #[serde(default)]
+    pub waitlist_offer_days: Option<u32>,
+    #[serde(default)]
+    pub idle_lock_minutes: Option<u32>,
+    #[serde(default)]
+    pub require_admin_two_factor: Option<bool>,
+}
+
+/// Реквизиты организации для счетов и квитанций
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Organization {
+    pub name: String,
+    pub inn: String,
+    pub kpp: String,
+    pub address: String,
+    pub phone: String,
+    pub bank: String,
+    pub bik: String,
+    pub account: String,      // Расчётный счёт
+    pub corr_account: String, // Корреспондентский счёт
+}
+
+/// Поле реквизитов, редактируемое в настройках
+#[derive(Debug, Clone, Copy)]
+pub enum OrganizationField {
+    Name,
+    Inn,
+    Kpp,
+    Address,
+    Phone,
+    Bank,
+    Bik,
+    Account,
+    CorrAccount,
+}
+
+impl OrganizationField {
+    pub const ALL: [OrganizationField; 9] = [
+        OrganizationField::Name,
+        OrganizationField::Inn,
+        OrganizationField::Kpp,
+        OrganizationField::Address,
+        OrganizationField::Phone,
+        OrganizationField::Bank,
+        OrganizationField::Bik,
+        OrganizationField::Account,
+        OrganizationField::CorrAccount,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OrganizationField::Name => "Наименование",
+            OrganizationField::Inn => "ИНН",
+            OrganizationField::Kpp => "КПП",
+            OrganizationField::Address => "Адрес",
+            OrganizationField::Phone => "Телефон",
+            OrganizationField::Bank => "Банк",
+            OrganizationField::Bik => "БИК",
+            OrganizationField::Account => "Расчётный счёт",
+            OrganizationField::CorrAccount => "Корр. счёт",
+        }
+    }
+}
+
+impl Organization {
+    pub fn field(&self, field: OrganizationField) -> &str {
+        match field {
+            OrganizationField::Name => &self.name,
+            OrganizationField::Inn => &self.inn,
+            OrganizationField::Kpp => &self.kpp,
+            OrganizationField::Address => &self.address,
+            OrganizationField::Phone => &self.phone,
+            OrganizationField::Bank => &self.bank,
+            OrganizationField::Bik => &self.bik,
+            OrganizationField::Account => &self.account,
+            OrganizationField::CorrAccount => &self.corr_account,
+        }
+    }
+
+    pub fn field_mut(&mut self, field: OrganizationField) -> &mut String {
+        match field {
+            OrganizationField::Name => &mut self.name,
+            OrganizationField::Inn => &mut self.inn,
+            OrganizationField::Kpp => &mut self.kpp,
+            OrganizationField::Address => &mut self.address,
+            OrganizationField::Phone => &mut self.phone,
+            OrganizationField::Bank => &mut self.bank,
+            OrganizationField::Bik => &mut self.bik,
+            OrganizationField::Account => &mut self.account,
+            OrganizationField::CorrAccount => &mut self.corr_account,
+        }
+    }
+
+    /// Заполненные реквизиты в виде строк "Подпись: значение"
+    pub fn requisites(&self) -> Vec<(&'static str, &str)> {
+        OrganizationField::ALL
+            .iter()
+            .skip(1)
+            .map(|field| (field.label(), self.field(*field)))
+            .filter(|(_, value)| !value.trim().is_empty())
+            .collect()
+    }
+}
+
+/// Вид платёжного документа
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentDocumentKind {
+    Invoice, // Счёт на оплату
+    Receipt, // Квитанция об оплате
+}
+
+impl PaymentDocumentKind {
+    pub const ALL: [PaymentDocumentKind; 2] = [PaymentDocumentKind::Invoice, PaymentDocumentKind::Receipt];
+
+    /// Значение поля PaymentDocument.kind
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentDocumentKind::Invoice => "invoice",
+            PaymentDocumentKind::Receipt => "receipt",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            PaymentDocumentKind::Invoice => "Счёт на оплату",
+            PaymentDocumentKind::Receipt => "Квитанция об оплате",
+        }
+    }
+
+    fn prefix(&self) -> &'static str {
+        match self {
+            PaymentDocumentKind::Invoice => "СЧ",
+            PaymentDocumentKind::Receipt => "КВ",
+        }
+    }
+}
+
+impl FromSql for PaymentDocumentKind {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        let s = value.as_str()?;
+        PaymentDocumentKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Неизвестный вид документа: {}", s).into()))
+    }
+}
+
+impl ToSql for PaymentDocumentKind {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(self.as_str().into())
+    }
+}
+
+/// Счёт или квитанция по платежу; номер сквозной в пределах вида документа и года
+#[derive(Debug, Clone)]
+pub struct PaymentDocument {
+    pub payment_id: i32,
+    pub kind: PaymentDocumentKind,
+    pub year: i32,
+    pub number: i32,
+    pub issued_at: String, // "ГГГГ-ММ-ДД ЧЧ:ММ:СС"
+}
+
+impl PaymentDocument {
+    /// Номер для печати, например "КВ-2025-0007"
+    pub fn display_number(&self) -> String {
+        format!("{}-{}-{:04}", self.kind.prefix(), self.year, self.number)
+    }
+
+    pub fn issue_date(&self) -> &str {
+        self.issued_at.get(..10).unwrap_or(&self.issued_at)
+    }
+}
+/// Способ начисления зарплаты преподавателю
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateType {
+    PerSession,     // Фиксированная сумма за проведённое занятие
+    PerStudentHour, // За каждый час присутствия студента на занятии
+    RevenuePercent, // Процент от оплат по группам преподавателя
+}
+
+impl RateType {
+    pub const ALL: [RateType; 3] = [
+        RateType::PerSession,
+        RateType::PerStudentHour,
+        RateType::RevenuePercent,
+    ];
+
+    /// Значение полей TeacherRate.rate_type и PayrollLine.rate_type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateType::PerSession => "per_session",
+            RateType::PerStudentHour => "per_student_hour",
+            RateType::RevenuePercent => "revenue_percent",
+        }
+    }
+
+    /// Ставка с единицей измерения, например "1500.00 ₽ за занятие"
+    pub fn format_rate(&self, rate: f64) -> String {
+        match self {
+            RateType::PerSession => format!("{:.2} ₽ за занятие", rate),
+            RateType::PerStudentHour => format!("{:.2} ₽ за студенто-час", rate),
+            RateType::RevenuePercent => format!("{}% от выручки", rate),
+        }
+    }
+}
+
+impl fmt::Display for RateType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateType::PerSession => write!(f, "За занятие"),
+            RateType::PerStudentHour => write!(f, "За студенто-час"),
+            RateType::RevenuePercent => write!(f, "Процент от выручки"),
+        }
+    }
+}
+
+impl FromSql for RateType {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        let s = value.as_str()?;
+        RateType::ALL
+            .into_iter()
+            .find(|rate_type| rate_type.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Неизвестный вид ставки: {}", s).into()))
+    }
+}
+
+impl ToSql for RateType {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(self.as_str().into())
+    }
+}
+
+/// Ставка преподавателя; у преподавателя без настроенной ставки rate_type = None
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeacherRate {
+    pub teacher_id: i32,
+    pub teacher_name: String,
+    pub rate_type: Option<RateType>,
+    pub rate: f64,
+}
+
+impl TeacherRate {
+    pub fn rate_display(&self) -> String {
+        self.rate_type
+            .map_or("ставка не задана".to_string(), |rate_type| rate_type.format_rate(self.rate))
+    }
+}
+
+impl fmt::Display for TeacherRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.teacher_name)
+    }
+}
+
+/// Длительность занятия, проведённого без расписания, для расчёта студенто-часов
+pub const DEFAULT_SESSION_MINUTES: i32 = 90;
+
+/// Строка расчётной ведомости: объём работы преподавателя за месяц и начисление по ставке
+#[derive(Debug, Clone)]
+pub struct PayrollLine {
+    pub teacher_id: i32,
+    pub teacher_name: String,
+    pub rate_type: Option<RateType>,
+    pub rate: f64,
+    pub sessions: i32,      // Проведённые занятия
+    pub student_hours: f64, // Часы присутствия студентов на этих занятиях
+    pub revenue: f64,       // Оплаты по группам преподавателя за месяц (с учётом возвратов)
+    pub amount: f64,
+}
+
+impl PayrollLine {
+    /// Начисление по ставке преподавателя; без ставки начислять нечего
+    pub fn accrue(&mut self) {
+        self.amount = match self.rate_type {
+            Some(RateType::PerSession) => self.sessions as f64 * self.rate,
+            Some(RateType::PerStudentHour) => self.student_hours * self.rate,
+            Some(RateType::RevenuePercent) => self.revenue * self.rate / 100.0,
+            None => 0.0,
+        };
+        self.amount = (self.amount * 100.0).round() / 100.0;
+    }
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "Январь", "Февраль", "Март", "Апрель", "Май", "Июнь", "Июль", "Август", "Сентябрь", "Октябрь",
+    "Ноябрь", "Декабрь",
+];
+
+/// Ведомость за месяц. После утверждения период закрыт: строки берутся из PayrollLine
+/// и не пересчитываются при изменении ставок, занятий или платежей
+#[derive(Debug, Clone)]
+pub struct Payroll {
+    pub month: String, // "ГГГГ-ММ"
+    pub lines: Vec<PayrollLine>,
+    pub approved_at: Option<String>, // "ГГГГ-ММ-ДД ЧЧ:ММ:СС"
+    pub approved_by: Option<String>,
+}
+
+impl Payroll {
+    pub fn is_locked(&self) -> bool {
+        self.approved_at.is_some()
+    }
+
+    pub fn total(&self) -> f64 {
+        self.lines.iter().map(|line| line.amount).sum()
+    }
+
+    /// Название периода, например "Октябрь 2025"
+    pub fn period_title(&self) -> String {
+        NaiveDate::parse_from_str(&format!("{}-01", self.month), "%Y-%m-%d")
+            .map(|date| format!("{} {}", MONTH_NAMES[date.month0() as usize], date.year()))
+            .unwrap_or_else(|_| self.month.clone())
+    }
+}
+/// Файл резервной копии БД в папке бэкапов
+#[derive(Debug, Clone)]
+pub struct BackupFile {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub modified: String, // "ГГГГ-ММ-ДД ЧЧ:ММ:СС"
 }
 #[derive(PartialEq, Default)]
 pub enum Screen {
     #[default]
     Login,
     Register,
+    PasswordRecovery,
+    TwoFactor,
     Profile,
     Settings,
     CoursesList,
@@ -823,4 +2692,8 @@ pub enum Screen {
     Classes,
     Payment,
     Certificates,
+    StudentProgress,
+    Payroll,
+    AuditLog,
+    LoginHistory,
 }
@@ -0,0 +1,37 @@
+use super::{App, Message};
+use iced::{Event, Subscription, event, keyboard, mouse, window};
+use std::time::Duration;
+
+impl App {
+    pub fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = Vec::new();
+
+        // Раз в минуту проверяем расписание бэкапов, пока автоматическое копирование включено
+        let backups_enabled = self
+            .backup_interval
+            .as_ref()
+            .is_some_and(|interval| interval.value != "never");
+
+        if backups_enabled {
+            subscriptions.push(iced::time::every(Duration::from_secs(60)).map(|_| Message::BackupSchedulerTick));
+        }
+
+        // Пока кто-то вошёл, следим за действиями пользователя и блокируем экран после простоя
+        if self.current_user.is_some() && self.idle_lock_minutes > 0 {
+            subscriptions.push(event::listen_with(user_activity));
+            subscriptions.push(iced::time::every(Duration::from_secs(15)).map(|_| Message::IdleLockTick));
+        }
+
+        Subscription::batch(subscriptions)
+    }
+}
+
+// Активностью считаются нажатия клавиш и кнопок мыши и прокрутка; простое движение курсора — нет
+fn user_activity(event: Event, _status: event::Status, _window: window::Id) -> Option<Message> {
+    match event {
+        Event::Keyboard(keyboard::Event::KeyPressed { .. })
+        | Event::Mouse(mouse::Event::ButtonPressed(_))
+        | Event::Mouse(mouse::Event::WheelScrolled { .. }) => Some(Message::UserActivity),
+        _ => None,
+    }
+}
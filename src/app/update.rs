@@ -1,33 +1,55 @@
 use super::{App, Message};
 use crate::app::state::{
-    Assignment, AssignmentType, Course, DEFAULT_AVATAR, DatePickerOpen, Group,
-    LessonWithAssignments, Level, PATH_TO_DB, ReportType, Screen, StudentAttendance,
-    TextInputOrEditorInput, UserInfo,
+    Assignment, AssignmentType, BankTransaction, Config, Course, CoursePickListItem, DEFAULT_AVATAR, DatePickerOpen, DiscountLine, EnrollmentPayment,
+    EnrollmentPickListItem, EnrollmentTerms, Group, GroupPickListItem,
+    LessonWithAssignments, Level, PATH_TO_DB, PAYMENT_TYPES, PDF_BACKENDS, PdfBackend, PromoCode, RateType, ReportType, ReversalKind, Role, Screen, StudentAttendance, StudentPickListItem,
+    TextInputOrEditorInput, UserInfo, cap_discounts, discount_note,
 };
 use crate::config::{
-    backup_database_now_with_config, get_last_backup_time, save_config, theme_from_str,
+    get_last_backup_time, list_backups, next_backup_due, perform_backup, restore_backup,
+    save_config, theme_from_str, theme_to_str,
 };
+use crate::audit;
+use crate::bank_import;
 use crate::db;
+use crate::password::{check_password_strength, hash_password};
+use crate::schedule::{fits_in_day, parse_time, week_start};
+use crate::totp;
 use crate::doc_gen::{
-    generate_certificate_excel_report, generate_certificate_html, generate_certificate_report,
+    generate_certificate_excel_report, generate_certificate_report,
     generate_group_excel_report, generate_group_report, generate_payment_excel_report,
-    generate_payment_report, generate_pdf_from_html,
+    generate_certificate_pdf, generate_payment_document_pdf, generate_payment_report,
+    generate_audit_excel, generate_payroll_excel, generate_payroll_pdf,
 };
-use chrono::{Local, NaiveDate};
-use iced::widget::{Row, text, text_editor};
+use chrono::{Duration, Local, Months, NaiveDate, NaiveTime};
+use iced::widget::{Row, image, text, text_editor};
 use iced::{Alignment, Element, Renderer, Task, Theme};
 use regex::Regex;
 use rfd::FileDialog;
 use rusqlite::Connection;
-use sha2::{Digest, Sha256};
-use sha2::digest::FixedOutput;
 use std::fs;
 use std::path::Path;
 use std::str::FromStr;
+use std::time::Instant;
 use tokio::task::spawn_blocking;
 
 impl App {
     pub fn update(&mut self, message: Message) -> Task<Message> {
+        // Всё, что меняет данные, сначала сверяем с матрицей прав роли текущего пользователя
+        if let Some(permission) = self.required_permission(&message)
+            && !self.can(permission)
+        {
+            let role = self
+                .current_user
+                .as_ref()
+                .map_or("без входа".to_string(), |user| user.user_type.to_string());
+            println!("DEBUG: действие {:?} отклонено, у роли «{}» нет права {:?}", message, role, permission);
+            self.access_denied = Some(format!(
+                "Недостаточно прав: действие «{}» недоступно для роли «{}».",
+                permission, role
+            ));
+            return Task::none();
+        }
         let current_user_for_task_clone = self.current_user.clone();
         match message {
             Message::LoginPressed => {
@@ -39,55 +61,26 @@ impl App {
                 let password_clone = self.user_password.clone();
 
                 Task::perform(
-                    db::authenticate_and_get_user_data(email_clone, hash_password(&password_clone)),
+                    db::authenticate_and_get_user_data(email_clone, password_clone),
                     Message::UserLoggedIn,
                 )
             }
             Message::UserLoggedIn(result) => {
                 match result {
                     Ok(user_info_data) => {
-                        self.current_user = Some(UserInfo {
-                            id: user_info_data.id,
-                            name: user_info_data.name,
-                            email: user_info_data.email,
-                            birthday: user_info_data.birthday,
-                            user_type: user_info_data.user_type.clone(),
-                            group_id: user_info_data.group_id,
-                            avatar_data: user_info_data.avatar_data,
-                            child_count: user_info_data.child_count,
-                        });
-                        self.error_message = "".to_string();
-                        self.current_screen = Screen::Profile;
-
-                        if let Some(user) = &self.current_user {
-                            if user.user_type == "admin" {
-                                println!(
-                                    "DEBUG: Пользователь является АДМИНИСТРАТОРОМ. Запускаем загрузку ВСЕХ групп."
-                                );
-                                // !!! АДМИНИСТРАТОР: отправляем LoadAllGroups
-                                return self.update(Message::LoadAllGroups);
-                            } else if user.user_type == "teacher" {
-                                println!(
-                                    "DEBUG: Пользователь является ПРЕПОДАВАТЕЛЕМ. Запускаем загрузку его групп."
-                                );
-                                let teacher_id_for_task = user.id;
-                                // !!! ПРЕПОДАВАТЕЛЬ: отправляем LoadTeacherGroups с ID
-                                return self
-                                    .update(Message::LoadTeacherGroups(teacher_id_for_task));
-                            } else if user.user_type == "student" {
-                                // <-- ДОБАВЛЕН ВАРИАНТ ДЛЯ СТУДЕНТА
-                                println!(
-                                    "DEBUG: Пользователь является СТУДЕНТОМ. Запускаем загрузку его группы."
-                                );
-                                // !!! СТУДЕНТ: отправляем LoadStudentGroupInfo
-                                return self.update(Message::LoadStudentGroupInfo);
-                            } else {
-                                // Если это родитель или другой тип пользователя, для которого нет специфичной загрузки
-                                println!(
-                                    "DEBUG: Пользователь {} (ID: {}) не преподаватель, не администратор и не студент. Группы не загружаются автоматически.",
-                                    user.user_type, user.id
-                                );
+                        // С включённым вторым фактором сеанс начнётся только после ввода кода
+                        let two_factor = Connection::open(PATH_TO_DB)
+                            .and_then(|conn| db::two_factor_enabled(&conn, user_info_data.id));
+                        match two_factor {
+                            Ok(true) => {
+                                self.pending_two_factor_user = Some(user_info_data);
+                                self.two_factor_code_input.clear();
+                                self.two_factor_error = None;
+                                self.error_message = "".to_string();
+                                self.current_screen = Screen::TwoFactor;
                             }
+                            Ok(false) => return self.start_session(user_info_data),
+                            Err(e) => self.error_message = format!("Ошибка проверки настроек входа: {}", e),
                         }
                     }
                     Err(e) => {
@@ -164,25 +157,8 @@ impl App {
                     return Task::none();
                 }
 
-                let password = &self.user_password;
-
-                if password.len() < 8 {
-                    self.register_error =
-                        Some("Пароль должен содержать минимум 8 символов".to_string());
-                    return Task::none();
-                }
-
-                let has_uppercase = password.chars().any(|c| c.is_ascii_uppercase());
-                if !has_uppercase {
-                    self.register_error =
-                        Some("Пароль должен содержать хотя бы одну заглавную букву".to_string());
-                    return Task::none();
-                }
-
-                let has_digit = password.chars().any(|c| c.is_ascii_digit());
-                if !has_digit {
-                    self.register_error =
-                        Some("Пароль должен содержать хотя бы одну цифру".to_string());
+                if let Err(e) = check_password_strength(&self.user_password) {
+                    self.register_error = Some(e);
                     return Task::none();
                 }
 
@@ -246,7 +222,13 @@ impl App {
                     "{} {} {}",
                     self.user_surname, self.user_name, self.user_patronymic
                 );
-                let password_hash = hash_password(&self.user_password);
+                let password_hash = match hash_password(&self.user_password) {
+                    Ok(hash) => hash,
+                    Err(e) => {
+                        self.register_error = Some(e);
+                        return Task::none();
+                    }
+                };
 
                 if let Err(_) = db::register_user(
                     &conn,
@@ -276,7 +258,7 @@ impl App {
                     self.user_avatar_data = Some(fs::read(DEFAULT_AVATAR).unwrap());
                 }
                 Task::perform(
-                    db::authenticate_and_get_user_data(self.user_email.clone(), password_hash),
+                    db::authenticate_and_get_user_data(self.user_email.clone(), self.user_password.clone()),
                     Message::UserLoggedIn,
                 )
             }
@@ -286,37 +268,49 @@ impl App {
             }
             Message::GoToSettings => {
                 self.current_screen = Screen::Settings;
+                self.restore_error = None;
+                if self.current_user.as_ref().is_some_and(|u| u.user_type == Role::Admin) {
+                    return self.update(Message::LoadBackupList);
+                }
                 Task::none()
             }
             Message::GoToCourses => {
                 self.current_screen = Screen::CoursesList;
-                Task::none()
+                self.load_waitlist()
             }
             Message::GoToUserList => {
                 self.current_screen = Screen::UserList;
-                Task::none()
+                // Группы нужны для зачисления студента при подтверждении заявки
+                Task::batch(vec![self.load_registration_requests(), self.update(Message::LoadAllGroups)])
             }
             Message::GoToGroupList => {
                 self.current_screen = Screen::GroupList;
                 Task::none()
             }
             Message::Logout => {
+                self.current_user = None;
+                self.set_audit_actor(None);
+                self.access_denied = None;
+                self.password_change_required = false;
+                self.show_change_password_modal = false;
+                self.pending_two_factor_user = None;
+                self.two_factor_setup_required = false;
+                self.two_factor_setup_secret = None;
+                self.two_factor_qr = None;
+                self.two_factor_enabled = false;
+                self.recovery_codes.clear();
+                self.temporary_password = None;
                 self.clear_fields();
                 self.user_avatar_data = None;
                 self.user_email.clear();
+                self.student_progress.clear();
                 self.current_screen = Screen::Login;
                 Task::none()
             }
             Message::ThemeSelected(name) => {
                 if let Some(new_theme) = theme_from_str(name) {
                     self.theme.update(new_theme.into());
-                    save_config(
-                        &self.theme.target(), // Сохранить целевую тему
-                        self.backup_interval.as_ref().map(|interval| interval.value),
-                        self.backup_folder.clone(),
-                        self.max_backup_count,
-                    )
-                    .ok();
+                    self.save_settings();
                 }
                 Task::none()
             }
@@ -501,308 +495,842 @@ impl App {
                 }
                 Task::none()
             }
-            Message::ShowGroupStudents(group_id) => {
-                self.show_group_students_modal = true;
-                // Находим название группы, чтобы отобразить его в модальном окне
-                if let Some(group) = self.teacher_groups.iter().find(|g| g.id == group_id) {
-                    self.selected_group_for_students_name = Some(group.name.clone());
-                } else {
-                    self.selected_group_for_students_name = None;
-                }
+            Message::GoToStudentProgress => {
+                self.current_screen = Screen::StudentProgress;
+                self.error_message = "".to_string();
+                Task::batch(vec![self.update(Message::LoadStudentProgress), self.load_waitlist()])
+            }
+            Message::LoadStudentProgress => {
+                let Some(user) = self.current_user.clone() else {
+                    return Task::none();
+                };
                 Task::perform(
                     async move {
                         spawn_blocking(move || {
-                            // 1. Открываем соединение, обрабатывая Result и преобразуя ошибку в String
-                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?; // <-- ИСПРАВЛЕНО
+                            let conn = Connection::open(PATH_TO_DB)
+                                .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
 
-                            // 2. Вызываем функцию БД, обрабатывая Result и преобразуя ошибку в String
-                            let students = db::get_students_in_group(&conn, group_id)
-                                .map_err(|e| e.to_string())?; // <-- ИСПРАВЛЕНО
+                            // Родитель видит сводку по каждому привязанному ребёнку, студент — по себе
+                            let students = if user.user_type == Role::Parent {
+                                db::get_children_for_parent(&conn, &user.email)
+                                    .map_err(|e| format!("Не удалось загрузить список детей: {}", e))?
+                            } else {
+                                vec![user]
+                            };
 
-                            Ok((group_id, students))
+                            students
+                                .into_iter()
+                                .map(|student| db::get_student_progress(&conn, student))
+                                .collect::<rusqlite::Result<Vec<_>>>()
+                                .map_err(|e| format!("Не удалось загрузить сводку обучения: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (загрузка сводки обучения) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
                         })
-                        .await // Ждем завершения блокирующей задачи
-                        .map_err(|e| format!("Failed to run blocking task: {:?}", e))? // Обработка ошибки от tokio::task::JoinError
                     },
-                    Message::GroupStudentsLoaded,
+                    Message::StudentProgressLoaded,
                 )
             }
-            Message::GroupStudentsLoaded(result) => {
+            Message::StudentProgressLoaded(result) => {
                 match result {
-                    Ok((_group_id, students)) => {
-                        self.selected_group_students = students; // Теперь 'students' будет Vec<UserInfo>
-                        println!(
-                            "DEBUG: Students for group loaded: {} students",
-                            self.selected_group_students.len()
-                        );
+                    Ok(progress) => {
+                        println!("DEBUG: Загружена сводка обучения для {} студентов", progress.len());
+                        self.student_progress = progress;
                     }
                     Err(e) => {
+                        eprintln!("ERROR: {}", e);
                         self.error_message = e;
-                        println!(
-                            "ERROR: Failed to load students for group: {}",
-                            self.error_message
-                        );
-                        self.show_group_students_modal = false;
                     }
                 }
-                self.selected_student_to_add = None;
                 Task::none()
             }
-            Message::CloseGroupStudentsModal => {
-                self.show_group_students_modal = false;
-                self.selected_group_for_students_name = None;
-                self.selected_group_students.clear();
+            Message::OpenSubmissionModal(student_assignment) => {
+                self.submission_text = text_editor::Content::with_text(
+                    student_assignment
+                        .submission
+                        .as_ref()
+                        .map_or("", |s| s.text.as_str()),
+                );
+                self.submission_files.clear();
+                self.submission_error = None;
+                self.submission_assignment = Some(student_assignment);
                 Task::none()
             }
-            Message::LoadAllCourses => {
-                Task::perform(
-                    async move {
-                        spawn_blocking(move || {
-                            let conn = Connection::open(PATH_TO_DB)
-                                .map_err(|e| format!("Не удалось открыть БД для курсов: {}", e))?;
-                            db::get_courses(&conn)
-                                .map_err(|e| format!("Ошибка загрузки курсов: {}", e))
-                        })
-                        .await
-                        .map_err(|join_err| {
-                            format!("Ошибка выполнения задачи загрузки курсов: {}", join_err)
-                        })?
-                    },
-                    Message::AllCoursesLoaded, // <-- Когда задача завершится, отправь это сообщение
-                )
+            Message::CloseSubmissionModal => {
+                self.submission_assignment = None;
+                self.submission_files.clear();
+                self.submission_error = None;
+                Task::none()
             }
-            Message::AllCoursesLoaded(result) => {
+            Message::SubmissionTextEdited(action) => {
+                self.submission_text.perform(action);
+                Task::none()
+            }
+            Message::AttachSubmissionFile => Task::perform(
+                async move {
+                    spawn_blocking(move || {
+                        let Some(path_buf) = FileDialog::new().pick_file() else {
+                            return Err("Выбор файла отменён.".to_string());
+                        };
+                        let file_name = path_buf
+                            .file_name()
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_else(|| "файл".to_string());
+                        let data = fs::read(&path_buf)
+                            .map_err(|e| format!("Ошибка чтения файла: {}", e))?;
+                        Ok((file_name, data))
+                    })
+                    .await
+                    .unwrap_or_else(|join_err| {
+                        Err(format!(
+                            "Блокирующая задача (выбор файла) завершилась ошибкой: {:?}",
+                            join_err
+                        ))
+                    })
+                },
+                Message::SubmissionFileAttached,
+            ),
+            Message::SubmissionFileAttached(result) => {
                 match result {
-                    Ok(courses) => {
-                        self.all_courses = courses.clone();
-                        self.course_id_to_title =
-                            courses.into_iter().map(|c| (c.id, c.title)).collect();
-                        println!(
-                            "DEBUG: course_id_to_title заполнена: {:?}",
-                            self.course_id_to_title
-                        ); // <--- ВАЖНО!
-                        self.error_message = "".to_string(); // Очищаем сообщение об ошибке
-                    }
-                    Err(e) => {
-                        eprintln!("Ошибка при загрузке курсов: {}", e);
-                        self.error_message = e.to_string();
+                    Ok(file) => {
+                        self.submission_files.push(file);
+                        self.submission_error = None;
                     }
+                    Err(e) => self.submission_error = Some(e),
                 }
-                Task::none() // Возвращаем Task::none(), так как это конечный обработчик
+                Task::none()
             }
-            Message::SubmitNewCourse => {
-                // Проверки на пустые поля
-                if self.new_course_title.is_empty() {
-                    self.course_error_message =
-                        Some("Название курса не может быть пустым.".to_string());
-                    return Task::none();
-                }
-                if self.new_course_description.is_empty() {
-                    self.course_error_message =
-                        Some("Описание курса не может быть пустым.".to_string());
-                    return Task::none();
-                }
-                // Проверка на ошибки парсинга, установленные ранее
-                if self.course_error_message.is_some() {
-                    // Если уже есть ошибка парсинга (например, введено нечисловое значение),
-                    // то не продолжаем.
-                    return Task::none();
+            Message::RemoveSubmissionFile(index) => {
+                if index < self.submission_files.len() {
+                    self.submission_files.remove(index);
                 }
-                // Дополнительные логические проверки числовых значений
-                if self.new_course_total_seats <= 0 {
-                    self.course_error_message =
-                        Some("Запланированные места должны быть больше 0.".to_string());
+                Task::none()
+            }
+            Message::SubmitAssignment => {
+                let Some(student_assignment) = self.submission_assignment.clone() else {
                     return Task::none();
-                }
-                if self.new_course_seats < 0 {
-                    self.course_error_message =
-                        Some("Свободные места не могут быть отрицательными.".to_string());
+                };
+                let Some(user) = self.current_user.clone() else {
                     return Task::none();
-                }
-                if self.new_course_seats > self.new_course_total_seats {
-                    self.course_error_message =
-                        Some("Свободные места не могут превышать запланированные.".to_string());
+                };
+                let Some(group_id) = self
+                    .student_progress
+                    .iter()
+                    .find(|p| p.student.id == user.id)
+                    .and_then(|p| p.group.as_ref())
+                    .map(|g| g.id)
+                else {
+                    self.submission_error =
+                        Some("Вы не состоите в группе, сдать работу нельзя.".to_string());
                     return Task::none();
-                }
-                if self.new_course_price <= 0.0 {
-                    self.course_error_message =
-                        Some("Цена курса должна быть больше 0.".to_string());
+                };
+
+                let text = self.submission_text.text().trim().to_string();
+                let files = self.submission_files.clone();
+                if text.is_empty() && files.is_empty() {
+                    self.submission_error =
+                        Some("Добавьте текст ответа или прикрепите файл.".to_string());
                     return Task::none();
                 }
 
-                // Если все проверки пройдены, очищаем сообщение об ошибке
-                self.course_error_message = None;
-
-                // Клонируем значения, которые будут перемещены в асинхронный блок
-                let new_course_title_clone = self.new_course_title.clone();
-                let new_course_description_clone = self.new_course_description.clone();
-                let new_course_level_string = self.new_course_level.to_string(); // Преобразуем Level в String для БД
-                let new_course_seats_val = self.new_course_seats; // Берем само значение i32
-                let new_course_total_seats_val = self.new_course_total_seats; // Берем само значение i32
-                let new_course_price_val = self.new_course_price; // Берем само значение f64
-
-                // Очищаем поля формы после успешной проверки, но до выполнения Task
-                self.new_course_title.clear();
-                self.new_course_description.clear();
-                self.new_course_level = Level::default(); // Сброс к дефолту. Используем Level::default()
-                self.new_course_seats = 0;
-                self.new_course_total_seats = 0;
-                self.new_course_price = 0.0; // Сброс цены
-
-                // Также очищаем строковые поля, чтобы они не сохраняли старые значения при повторном открытии модалки
-                self.new_course_total_seats_str.clear();
-                self.new_course_seats_str.clear();
-                self.new_course_price_str.clear();
-
-                self.show_add_course_modal = false; // Закрываем модалку
-
                 Task::perform(
                     async move {
                         spawn_blocking(move || {
-                            let conn = Connection::open(PATH_TO_DB)
+                            let mut conn = Connection::open(PATH_TO_DB)
                                 .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
-
-                            db::add_course(
-                                &conn,
-                                &new_course_title_clone,
-                                &new_course_description_clone,
-                                &new_course_level_string, // level уже String
-                                new_course_seats_val,     // i32
-                                new_course_price_val,     // f64
-                                new_course_total_seats_val, // i32
+                            let saved = db::submit_assignment(
+                                &mut conn,
+                                student_assignment.assignment.id,
+                                user.id,
+                                group_id,
+                                &text,
+                                &files,
                             )
-                            .map_err(|e| format!("Ошибка добавления курса: {}", e))?;
-                            Ok(()) // Возвращаем Ok(()) если все успешно
+                            .map_err(|e| format!("Не удалось сохранить работу: {}", e))?;
+                            if saved {
+                                Ok(())
+                            } else {
+                                Err("Работа уже проверена преподавателем и не может быть изменена."
+                                    .to_string())
+                            }
                         })
                         .await
-                        .map_err(|join_err| format!("Ошибка выполнения задачи: {:?}", join_err))?
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (сдача работы) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
                     },
-                    |result: Result<(), String>| {
-                        match result {
-                            Ok(_) => Message::LoadAllCourses, // Обновить список курсов
-                            Err(e) => Message::ErrorOccurred(e),
-                        }
+                    Message::AssignmentSubmitted,
+                )
+            }
+            Message::AssignmentSubmitted(result) => match result {
+                Ok(()) => {
+                    self.submission_assignment = None;
+                    self.submission_files.clear();
+                    self.submission_error = None;
+                    self.update(Message::LoadStudentProgress)
+                }
+                Err(e) => {
+                    self.submission_error = Some(e);
+                    Task::none()
+                }
+            },
+            Message::OpenGroupSubmissionsModal => {
+                let Some(group) = self.selected_group_for_classes.clone() else {
+                    return Task::none();
+                };
+                self.show_group_submissions_modal = true;
+                self.grading_submission_id = None;
+                self.grading_error = None;
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB)
+                                .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+                            db::get_submissions_for_group(&conn, group.id)
+                                .map_err(|e| format!("Не удалось загрузить работы группы: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (загрузка работ) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
                     },
+                    Message::GroupSubmissionsLoaded,
                 )
             }
-            Message::DeleteCourse(course_id) => {
-                // Эта операция должна быть асинхронной
-                let conn = Connection::open(PATH_TO_DB).unwrap();
-                if let Err(err) = db::delete_course(&conn, course_id) {
-                    eprintln!("Ошибка удаления группы: {:?}", err);
+            Message::GroupSubmissionsLoaded(result) => {
+                match result {
+                    Ok(submissions) => {
+                        self.group_submissions = submissions;
+                        self.grading_error = None;
+                    }
+                    Err(e) => self.grading_error = Some(e),
                 }
                 Task::none()
             }
-            Message::NewCourseLevelChanged(level) => {
-                self.new_course_level = level;
+            Message::CloseGroupSubmissionsModal => {
+                self.show_group_submissions_modal = false;
+                self.group_submissions.clear();
+                self.grading_submission_id = None;
+                self.grading_error = None;
                 Task::none()
             }
-            Message::StartEditingCourse(course) => {
-                self.edit_course_title = course.title.clone();
-                self.edit_course_description = course.description.clone().expect("REASON");
-
-                self.edit_course_level = course
-                    .level
-                    .clone()
-                    .and_then(|level_str| Level::from_str(&level_str).ok())
-                    .unwrap_or(Level::Beginner);
-                self.edit_course_total_seats = course.total_seats.clone().expect("REASON");
-                self.edit_course_seats = course.seats.clone().expect("REASON");
-                self.edit_course_price = course.price.clone().expect("REASON");
-                self.edit_course_total_seats_str = course
-                    .total_seats
-                    .map_or("0".to_string(), |val| val.to_string());
-                // .unwrap_or_default().to_string();
-
-                self.edit_course_seats_str =
-                    course.seats.map_or("0".to_string(), |val| val.to_string());
-                // .unwrap_or_default().to_string(); // Альтернативный вариант
+            Message::StartGradingSubmission(submission) => {
+                self.grading_submission_id = Some(submission.id);
+                self.grading_score = submission.score.map_or("".to_string(), |s| s.to_string());
+                self.grading_comment = submission.comment.unwrap_or_default();
+                self.grading_error = None;
+                Task::none()
+            }
+            Message::GradingScoreChanged(value) => {
+                self.grading_score = value;
+                Task::none()
+            }
+            Message::GradingCommentChanged(value) => {
+                self.grading_comment = value;
+                Task::none()
+            }
+            Message::SaveSubmissionGrade => {
+                let Some(submission_id) = self.grading_submission_id else {
+                    return Task::none();
+                };
+                let Some(teacher_id) = self.current_user.as_ref().map(|u| u.id) else {
+                    return Task::none();
+                };
+                let score = match self.grading_score.trim().parse::<i32>() {
+                    Ok(score) if (0..=100).contains(&score) => score,
+                    _ => {
+                        self.grading_error =
+                            Some("Оценка должна быть целым числом от 0 до 100.".to_string());
+                        return Task::none();
+                    }
+                };
+                let comment = self.grading_comment.trim().to_string();
 
-                self.edit_course_price_str = course
-                    .price
-                    .map_or("0.0".to_string(), |val| val.to_string());
-                self.editing_course = Some(course);
-                self.show_add_course_modal = true;
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB)
+                                .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+                            db::grade_submission(&conn, submission_id, score, &comment, teacher_id)
+                                .map_err(|e| format!("Не удалось сохранить оценку: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (сохранение оценки) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::SubmissionGraded,
+                )
+            }
+            Message::SubmissionGraded(result) => match result {
+                Ok(()) => {
+                    self.grading_submission_id = None;
+                    self.grading_score.clear();
+                    self.grading_comment.clear();
+                    self.update(Message::OpenGroupSubmissionsModal)
+                }
+                Err(e) => {
+                    self.grading_error = Some(e);
+                    Task::none()
+                }
+            },
+            Message::OpenSubmissionFile(file_id) => Task::perform(
+                async move {
+                    spawn_blocking(move || {
+                        let conn = Connection::open(PATH_TO_DB)
+                            .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+                        let (file_name, data) = db::get_submission_file_data(&conn, file_id)
+                            .map_err(|e| format!("Не удалось загрузить файл: {}", e))?;
+
+                        // Файл выгружается рядом с исполняемым файлом, как и сертификаты
+                        let exe_dir = std::env::current_exe()
+                            .map_err(|e| {
+                                format!("Не удалось получить путь к исполняемому файлу: {}", e)
+                            })?
+                            .parent()
+                            .ok_or("Не удалось получить директорию исполняемого файла")?
+                            .to_path_buf();
+                        let dir = exe_dir.join("submissions");
+                        fs::create_dir_all(&dir)
+                            .map_err(|e| format!("Не удалось создать папку: {}", e))?;
+
+                        let path = dir.join(format!("{}_{}", file_id, file_name));
+                        fs::write(&path, data)
+                            .map_err(|e| format!("Не удалось сохранить файл: {}", e))?;
+                        Ok(path)
+                    })
+                    .await
+                    .unwrap_or_else(|join_err| {
+                        Err(format!(
+                            "Блокирующая задача (открытие файла) завершилась ошибкой: {:?}",
+                            join_err
+                        ))
+                    })
+                },
+                Message::SubmissionFileOpened,
+            ),
+            Message::SubmissionFileOpened(result) => {
+                match result.and_then(|path| {
+                    open::that(&path).map_err(|e| format!("Ошибка при открытии файла: {}", e))
+                }) {
+                    Ok(()) => self.grading_error = None,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        self.grading_error = Some(e);
+                    }
+                }
                 Task::none()
             }
-            Message::EditCourseTitleChanged(title) => {
-                self.edit_course_title = title;
+            Message::OpenScheduleModal(group) => {
+                self.show_schedule_modal = true;
+                self.schedule_group = Some(group);
+                self.schedule_slots.clear();
+                self.schedule_planned_sessions.clear();
+                self.new_slot_weekday = None;
+                self.new_slot_start.clear();
+                self.new_slot_duration = "90".to_string();
+                self.new_slot_room.clear();
+                self.schedule_start_date = Local::now().format("%Y-%m-%d").to_string();
+                self.schedule_error = None;
+                self.load_schedule()
+            }
+            Message::CloseScheduleModal => {
+                self.show_schedule_modal = false;
+                self.schedule_group = None;
+                self.schedule_slots.clear();
+                self.schedule_planned_sessions.clear();
+                self.schedule_error = None;
                 Task::none()
             }
-            Message::EditCourseDescriptionChanged(desc) => {
-                self.edit_course_description = desc;
+            Message::ScheduleLoaded(result) => {
+                match result {
+                    Ok((slots, planned_sessions)) => {
+                        self.schedule_slots = slots;
+                        self.schedule_planned_sessions = planned_sessions;
+                    }
+                    Err(e) => self.schedule_error = Some(e),
+                }
                 Task::none()
             }
-            Message::EditCourseLevelChanged(level) => {
-                self.edit_course_level = level;
+            Message::NewSlotWeekdaySelected(weekday) => {
+                self.new_slot_weekday = Some(weekday);
                 Task::none()
             }
-            Message::EditCourseTotalSeatsChanged(s) => {
-                self.edit_course_total_seats_str = s;
-                if let Ok(value) = self.edit_course_total_seats_str.parse::<i32>() {
-                    self.edit_course_total_seats = value;
-                    self.course_error_message = None;
-                } else {
-                    self.course_error_message =
-                        Some("Запланированные места должны быть целым числом.".to_string());
+            Message::NewSlotStartChanged(value) => {
+                self.new_slot_start = value;
+                Task::none()
+            }
+            Message::NewSlotDurationChanged(value) => {
+                self.new_slot_duration = value;
+                Task::none()
+            }
+            Message::NewSlotRoomChanged(value) => {
+                self.new_slot_room = value;
+                Task::none()
+            }
+            Message::AddScheduleSlot => {
+                let Some(group_id) = self.schedule_group.as_ref().map(|g| g.id) else {
+                    return Task::none();
+                };
+                let Some(weekday) = self.new_slot_weekday else {
+                    self.schedule_error = Some("Выберите день недели.".to_string());
+                    return Task::none();
+                };
+                let Some(start) = parse_time(&self.new_slot_start) else {
+                    self.schedule_error =
+                        Some("Время начала должно быть в формате ЧЧ:ММ.".to_string());
+                    return Task::none();
+                };
+                let duration = match self.new_slot_duration.trim().parse::<i32>() {
+                    Ok(duration) if duration > 0 => duration,
+                    _ => {
+                        self.schedule_error = Some(
+                            "Длительность должна быть положительным числом минут.".to_string(),
+                        );
+                        return Task::none();
+                    }
+                };
+                if !fits_in_day(start, duration) {
+                    self.schedule_error = Some("Занятие должно закончиться не позже 24:00.".to_string());
+                    return Task::none();
                 }
+                let start_time = start.format("%H:%M").to_string();
+                let room = self.new_slot_room.trim().to_string();
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB)
+                                .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+                            db::add_schedule_slot(&mut conn, group_id, weekday.0, &start_time, duration, &room)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (добавление слота) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::ScheduleUpdated,
+                )
+            }
+            Message::DeleteScheduleSlot(slot_id) => Task::perform(
+                async move {
+                    spawn_blocking(move || {
+                        let conn = Connection::open(PATH_TO_DB)
+                            .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+                        db::delete_schedule_slot(&conn, slot_id)
+                            .map_err(|e| format!("Не удалось удалить слот расписания: {}", e))
+                    })
+                    .await
+                    .unwrap_or_else(|join_err| {
+                        Err(format!(
+                            "Блокирующая задача (удаление слота) завершилась ошибкой: {:?}",
+                            join_err
+                        ))
+                    })
+                },
+                Message::ScheduleUpdated,
+            ),
+            Message::ScheduleStartDateChanged(value) => {
+                self.schedule_start_date = value;
                 Task::none()
             }
-            Message::EditCourseSeatsChanged(s) => {
-                self.edit_course_seats_str = s;
-                if let Ok(value) = self.edit_course_seats_str.parse::<i32>() {
-                    self.edit_course_seats = value;
-                    self.course_error_message = None;
-                } else {
-                    self.course_error_message =
-                        Some("Свободные места должны быть целым числом.".to_string());
+            Message::GeneratePlannedSessions => {
+                let Some(group_id) = self.schedule_group.as_ref().map(|g| g.id) else {
+                    return Task::none();
+                };
+                if self.schedule_slots.is_empty() {
+                    self.schedule_error =
+                        Some("Сначала добавьте хотя бы один слот расписания.".to_string());
+                    return Task::none();
+                }
+                let Ok(from) =
+                    NaiveDate::parse_from_str(self.schedule_start_date.trim(), "%Y-%m-%d")
+                else {
+                    self.schedule_error =
+                        Some("Дата начала должна быть в формате ГГГГ-ММ-ДД.".to_string());
+                    return Task::none();
+                };
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB)
+                                .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+                            let count = db::generate_planned_sessions(&mut conn, group_id, from)
+                                .map_err(|e| format!("Не удалось сформировать занятия: {}", e))?;
+                            println!("DEBUG: запланировано занятий для группы {}: {}", group_id, count);
+                            Ok(())
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (формирование занятий) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::ScheduleUpdated,
+                )
+            }
+            Message::ScheduleUpdated(result) => match result {
+                Ok(()) => {
+                    self.schedule_error = None;
+                    self.new_slot_start.clear();
+                    self.new_slot_room.clear();
+                    self.load_schedule()
                 }
+                Err(e) => {
+                    self.schedule_error = Some(e);
+                    Task::none()
+                }
+            },
+            Message::OpenWeekCalendar => {
+                self.show_week_calendar_modal = true;
+                self.calendar_week_start = week_start(Local::now().date_naive());
+                self.load_calendar_sessions()
+            }
+            Message::CloseWeekCalendar => {
+                self.show_week_calendar_modal = false;
+                self.calendar_sessions.clear();
                 Task::none()
             }
-            Message::EditCoursePriceChanged(s) => {
-                self.edit_course_price_str = s.clone();
-                let s_cleaned = s.replace(',', ".");
-                if let Ok(value) = s_cleaned.parse::<f64>() {
-                    self.edit_course_price = value;
-                    self.course_error_message = None;
+            Message::CalendarPrevWeek => {
+                self.calendar_week_start -= Duration::days(7);
+                self.load_calendar_sessions()
+            }
+            Message::CalendarNextWeek => {
+                self.calendar_week_start += Duration::days(7);
+                self.load_calendar_sessions()
+            }
+            Message::CalendarSessionsLoaded(result) => {
+                match result {
+                    Ok(sessions) => self.calendar_sessions = sessions,
+                    Err(e) => self.error_message = e,
+                }
+                Task::none()
+            }
+            Message::ShowGroupStudents(group_id) => {
+                self.show_group_students_modal = true;
+                // Находим название группы, чтобы отобразить его в модальном окне
+                if let Some(group) = self.teacher_groups.iter().find(|g| g.id == group_id) {
+                    self.selected_group_for_students_name = Some(group.name.clone());
                 } else {
-                    self.course_error_message =
-                        Some("Цена должна быть числом (например, 123.45).".to_string());
+                    self.selected_group_for_students_name = None;
+                }
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            // 1. Открываем соединение, обрабатывая Result и преобразуя ошибку в String
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?; // <-- ИСПРАВЛЕНО
+
+                            // 2. Вызываем функцию БД, обрабатывая Result и преобразуя ошибку в String
+                            let students = db::get_students_in_group(&conn, group_id)
+                                .map_err(|e| e.to_string())?; // <-- ИСПРАВЛЕНО
+
+                            Ok((group_id, students))
+                        })
+                        .await // Ждем завершения блокирующей задачи
+                        .map_err(|e| format!("Failed to run blocking task: {:?}", e))? // Обработка ошибки от tokio::task::JoinError
+                    },
+                    Message::GroupStudentsLoaded,
+                )
+            }
+            Message::GroupStudentsLoaded(result) => {
+                match result {
+                    Ok((_group_id, students)) => {
+                        self.selected_group_students = students; // Теперь 'students' будет Vec<UserInfo>
+                        println!(
+                            "DEBUG: Students for group loaded: {} students",
+                            self.selected_group_students.len()
+                        );
+                    }
+                    Err(e) => {
+                        self.error_message = e;
+                        println!(
+                            "ERROR: Failed to load students for group: {}",
+                            self.error_message
+                        );
+                        self.show_group_students_modal = false;
+                    }
                 }
+                self.selected_student_to_add = None;
                 Task::none()
             }
-            Message::SubmitEditedCourse => {
-                // Эта операция ДОЛЖНА быть асинхронной.
-                // Проверки на пустые поля и логические проверки
-                if self.edit_course_title.is_empty() {
+            Message::CloseGroupStudentsModal => {
+                self.show_group_students_modal = false;
+                self.selected_group_for_students_name = None;
+                self.selected_group_students.clear();
+                self.group_enrollments.clear();
+                self.group_error_message = None;
+                self.transfer_enrollment = None;
+                self.transfer_target = None;
+                Task::none()
+            }
+            Message::LoadAllCourses => {
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB)
+                                .map_err(|e| format!("Не удалось открыть БД для курсов: {}", e))?;
+                            db::get_courses(&conn)
+                                .map_err(|e| format!("Ошибка загрузки курсов: {}", e))
+                        })
+                        .await
+                        .map_err(|join_err| {
+                            format!("Ошибка выполнения задачи загрузки курсов: {}", join_err)
+                        })?
+                    },
+                    Message::AllCoursesLoaded, // <-- Когда задача завершится, отправь это сообщение
+                )
+            }
+            Message::AllCoursesLoaded(result) => {
+                match result {
+                    Ok(courses) => {
+                        self.all_courses = courses.clone();
+                        self.course_id_to_title =
+                            courses.into_iter().map(|c| (c.id, c.title)).collect();
+                        println!(
+                            "DEBUG: course_id_to_title заполнена: {:?}",
+                            self.course_id_to_title
+                        ); // <--- ВАЖНО!
+                        self.error_message = "".to_string(); // Очищаем сообщение об ошибке
+                    }
+                    Err(e) => {
+                        eprintln!("Ошибка при загрузке курсов: {}", e);
+                        self.error_message = e.to_string();
+                    }
+                }
+                Task::none() // Возвращаем Task::none(), так как это конечный обработчик
+            }
+            Message::SubmitNewCourse => {
+                // Проверки на пустые поля
+                if self.new_course_title.is_empty() {
                     self.course_error_message =
                         Some("Название курса не может быть пустым.".to_string());
                     return Task::none();
                 }
-                if self.edit_course_description.is_empty() {
+                if self.new_course_description.is_empty() {
                     self.course_error_message =
                         Some("Описание курса не может быть пустым.".to_string());
                     return Task::none();
                 }
-                // Проверка на ошибки парсинга, установленные ранее в *Changed сообщениях
+                // Проверка на ошибки парсинга, установленные ранее
                 if self.course_error_message.is_some() {
                     // Если уже есть ошибка парсинга (например, введено нечисловое значение),
                     // то не продолжаем.
                     return Task::none();
                 }
-                // Дополнительные логические проверки числовых значений, которые уже распарсены
-                if self.edit_course_total_seats <= 0 {
-                    // Теперь это соответствует вашей предыдущей логике
+                // Дополнительные логические проверки числовых значений
+                if self.new_course_total_seats <= 0 {
                     self.course_error_message =
                         Some("Запланированные места должны быть больше 0.".to_string());
                     return Task::none();
                 }
-                if self.edit_course_seats < 0 {
+                if self.new_course_seats < 0 {
                     self.course_error_message =
                         Some("Свободные места не могут быть отрицательными.".to_string());
                     return Task::none();
                 }
-                if self.edit_course_seats > self.edit_course_total_seats {
+                if self.new_course_seats > self.new_course_total_seats {
+                    self.course_error_message =
+                        Some("Свободные места не могут превышать запланированные.".to_string());
+                    return Task::none();
+                }
+                if self.new_course_price <= 0.0 {
+                    self.course_error_message =
+                        Some("Цена курса должна быть больше 0.".to_string());
+                    return Task::none();
+                }
+
+                // Если все проверки пройдены, очищаем сообщение об ошибке
+                self.course_error_message = None;
+
+                // Клонируем значения, которые будут перемещены в асинхронный блок
+                let new_course_title_clone = self.new_course_title.clone();
+                let new_course_description_clone = self.new_course_description.clone();
+                let new_course_level_string = self.new_course_level.to_string(); // Преобразуем Level в String для БД
+                let new_course_seats_val = self.new_course_seats; // Берем само значение i32
+                let new_course_total_seats_val = self.new_course_total_seats; // Берем само значение i32
+                let new_course_price_val = self.new_course_price; // Берем само значение f64
+
+                // Очищаем поля формы после успешной проверки, но до выполнения Task
+                self.new_course_title.clear();
+                self.new_course_description.clear();
+                self.new_course_level = Level::default(); // Сброс к дефолту. Используем Level::default()
+                self.new_course_seats = 0;
+                self.new_course_total_seats = 0;
+                self.new_course_price = 0.0; // Сброс цены
+
+                // Также очищаем строковые поля, чтобы они не сохраняли старые значения при повторном открытии модалки
+                self.new_course_total_seats_str.clear();
+                self.new_course_seats_str.clear();
+                self.new_course_price_str.clear();
+
+                self.show_add_course_modal = false; // Закрываем модалку
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB)
+                                .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+
+                            db::add_course(
+                                &conn,
+                                &new_course_title_clone,
+                                &new_course_description_clone,
+                                &new_course_level_string, // level уже String
+                                new_course_seats_val,     // i32
+                                new_course_price_val,     // f64
+                                new_course_total_seats_val, // i32
+                            )
+                            .map_err(|e| format!("Ошибка добавления курса: {}", e))?;
+                            Ok(()) // Возвращаем Ok(()) если все успешно
+                        })
+                        .await
+                        .map_err(|join_err| format!("Ошибка выполнения задачи: {:?}", join_err))?
+                    },
+                    |result: Result<(), String>| {
+                        match result {
+                            Ok(_) => Message::LoadAllCourses, // Обновить список курсов
+                            Err(e) => Message::ErrorOccurred(e),
+                        }
+                    },
+                )
+            }
+            Message::DeleteCourse(course_id) => {
+                // Эта операция должна быть асинхронной
+                let conn = Connection::open(PATH_TO_DB).unwrap();
+                if let Err(err) = db::delete_course(&conn, course_id) {
+                    eprintln!("Ошибка удаления группы: {:?}", err);
+                }
+                Task::none()
+            }
+            Message::NewCourseLevelChanged(level) => {
+                self.new_course_level = level;
+                Task::none()
+            }
+            Message::StartEditingCourse(course) => {
+                self.edit_course_title = course.title.clone();
+                self.edit_course_description = course.description.clone().expect("REASON");
+
+                self.edit_course_level = course
+                    .level
+                    .clone()
+                    .and_then(|level_str| Level::from_str(&level_str).ok())
+                    .unwrap_or(Level::Beginner);
+                self.edit_course_total_seats = course.total_seats.clone().expect("REASON");
+                self.edit_course_seats = course.seats.clone().expect("REASON");
+                self.edit_course_price = course.price.clone().expect("REASON");
+                self.edit_course_total_seats_str = course
+                    .total_seats
+                    .map_or("0".to_string(), |val| val.to_string());
+                // .unwrap_or_default().to_string();
+
+                self.edit_course_seats_str =
+                    course.seats.map_or("0".to_string(), |val| val.to_string());
+                // .unwrap_or_default().to_string(); // Альтернативный вариант
+
+                self.edit_course_price_str = course
+                    .price
+                    .map_or("0.0".to_string(), |val| val.to_string());
+                self.editing_course = Some(course);
+                self.show_add_course_modal = true;
+                Task::none()
+            }
+            Message::EditCourseTitleChanged(title) => {
+                self.edit_course_title = title;
+                Task::none()
+            }
+            Message::EditCourseDescriptionChanged(desc) => {
+                self.edit_course_description = desc;
+                Task::none()
+            }
+            Message::EditCourseLevelChanged(level) => {
+                self.edit_course_level = level;
+                Task::none()
+            }
+            Message::EditCourseTotalSeatsChanged(s) => {
+                self.edit_course_total_seats_str = s;
+                if let Ok(value) = self.edit_course_total_seats_str.parse::<i32>() {
+                    self.edit_course_total_seats = value;
+                    self.course_error_message = None;
+                } else {
+                    self.course_error_message =
+                        Some("Запланированные места должны быть целым числом.".to_string());
+                }
+                Task::none()
+            }
+            Message::EditCourseSeatsChanged(s) => {
+                self.edit_course_seats_str = s;
+                if let Ok(value) = self.edit_course_seats_str.parse::<i32>() {
+                    self.edit_course_seats = value;
+                    self.course_error_message = None;
+                } else {
+                    self.course_error_message =
+                        Some("Свободные места должны быть целым числом.".to_string());
+                }
+                Task::none()
+            }
+            Message::EditCoursePriceChanged(s) => {
+                self.edit_course_price_str = s.clone();
+                let s_cleaned = s.replace(',', ".");
+                if let Ok(value) = s_cleaned.parse::<f64>() {
+                    self.edit_course_price = value;
+                    self.course_error_message = None;
+                } else {
+                    self.course_error_message =
+                        Some("Цена должна быть числом (например, 123.45).".to_string());
+                }
+                Task::none()
+            }
+            Message::SubmitEditedCourse => {
+                // Эта операция ДОЛЖНА быть асинхронной.
+                // Проверки на пустые поля и логические проверки
+                if self.edit_course_title.is_empty() {
+                    self.course_error_message =
+                        Some("Название курса не может быть пустым.".to_string());
+                    return Task::none();
+                }
+                if self.edit_course_description.is_empty() {
+                    self.course_error_message =
+                        Some("Описание курса не может быть пустым.".to_string());
+                    return Task::none();
+                }
+                // Проверка на ошибки парсинга, установленные ранее в *Changed сообщениях
+                if self.course_error_message.is_some() {
+                    // Если уже есть ошибка парсинга (например, введено нечисловое значение),
+                    // то не продолжаем.
+                    return Task::none();
+                }
+                // Дополнительные логические проверки числовых значений, которые уже распарсены
+                if self.edit_course_total_seats <= 0 {
+                    // Теперь это соответствует вашей предыдущей логике
+                    self.course_error_message =
+                        Some("Запланированные места должны быть больше 0.".to_string());
+                    return Task::none();
+                }
+                if self.edit_course_seats < 0 {
+                    self.course_error_message =
+                        Some("Свободные места не могут быть отрицательными.".to_string());
+                    return Task::none();
+                }
+                if self.edit_course_seats > self.edit_course_total_seats {
                     self.course_error_message =
                         Some("Свободные места не могут превышать запланированные.".to_string());
                     return Task::none();
@@ -911,7 +1439,7 @@ impl App {
                 self.edit_user_name.clear();
                 self.edit_user_email.clear();
                 self.edit_user_birthday.clear();
-                self.edit_user_type.clear();
+                self.edit_user_type = Role::Unconfirmed;
                 Task::none()
             }
             Message::EditUserNameChanged(value) => {
@@ -996,7 +1524,7 @@ impl App {
                                 &self.edit_user_name,
                                 email,
                                 &self.edit_user_birthday,
-                                &self.edit_user_type,
+                                self.edit_user_type,
                             );
                             self.editing_user = None;
                             self.show_edit_user_modal = false;
@@ -1088,7 +1616,7 @@ impl App {
                         self.users_for_picklist = users
                             .clone()
                             .into_iter()
-                            .filter(|u| u.user_type == "teacher")
+                            .filter(|u| u.user_type == Role::Teacher)
                             .collect();
                         println!(
                             "DEBUG: Пользователи для PickList загружены: {} шт. (из них преподавателей: {})",
@@ -1264,41 +1792,43 @@ impl App {
                 let group_teacher_id = self.edit_group_teacher.unwrap_or_default(); // ID преподавателя (уже i32)
                 let group_status_string = self.edit_group_status;
 
-                // Очищаем поля ввода и сбрасываем состояние редактирования до выполнения Task
-                self.edit_group_name.clear();
-                self.edit_group_course = None;
-                self.edit_group_teacher = None;
-                self.group_error_message = None;
-                self.editing_group = None; // Сброс редактируемой группы
-                self.show_add_group_modal = false; // Закрываем модалку
-
+                // Форму закрываем только после сохранения: при пересечении расписания
+                // ошибка показывается в той же модалке
                 Task::perform(
                     async move {
                         spawn_blocking(move || {
-                            let conn = Connection::open(PATH_TO_DB)
+                            let mut conn = Connection::open(PATH_TO_DB)
                                 .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
                             db::update_group(
-                                &conn,
+                                &mut conn,
                                 group_id,
                                 &group_name_clone,
                                 group_course_id,
                                 group_teacher_id,
                                 group_status_string,
                             )
-                            .map_err(|e| format!("Ошибка обновления группы: {}", e))?;
-                            Ok(()) // Возвращаем Ok(()) если все успешно
                         })
                         .await
                         .map_err(|join_err| format!("Ошибка выполнения задачи: {:?}", join_err))?
                     },
-                    |result: Result<(), String>| {
-                        match result {
-                            Ok(_) => Message::LoadAllGroups, // Обновить список групп
-                            Err(e) => Message::ErrorOccurred(e),
-                        }
-                    },
+                    Message::GroupUpdated,
                 )
             }
+            Message::GroupUpdated(result) => match result {
+                Ok(()) => {
+                    self.edit_group_name.clear();
+                    self.edit_group_course = None;
+                    self.edit_group_teacher = None;
+                    self.group_error_message = None;
+                    self.editing_group = None; // Сброс редактируемой группы
+                    self.show_add_group_modal = false; // Закрываем модалку
+                    self.update(Message::LoadAllGroups) // Обновить список групп
+                }
+                Err(e) => {
+                    self.group_error_message = Some(e);
+                    Task::none()
+                }
+            },
             Message::SubmitNewGroup => {
                 // Проверки на пустые поля
                 if self.new_group_name.is_empty() {
@@ -1469,8 +1999,12 @@ impl App {
                     Message::StudentsWithoutGroupLoaded, // Сообщение, когда студенты без группы загружены
                 );
 
-                // Возвращаем обе задачи, чтобы они выполнялись параллельно
-                Task::batch(vec![task_students_in_group, task_students_without_group])
+                // Возвращаем задачи, чтобы они выполнялись параллельно
+                Task::batch(vec![
+                    task_students_in_group,
+                    task_students_without_group,
+                    self.load_group_enrollments(group_id),
+                ])
             }
             Message::StudentsInGroupLoaded(result) => {
                 self.is_loading_group_students = false;
@@ -1506,61 +2040,179 @@ impl App {
                 }
                 Task::none()
             }
-            Message::AddStudentToGroup(student_id, group_id) => {
-                Task::perform(
-                    async move {
-                        let mut conn = Connection::open(PATH_TO_DB).unwrap();
-                        db::add_student_to_group(&mut conn, student_id, group_id)
-                            .map_err(|e| format!("Ошибка добавления студента: {}", e))
-                    },
-                    move |result| {
-                        if let Err(e) = result {
-                            Message::ErrorOccurred(e) // Или ваше сообщение об ошибке
-                        } else {
-                            println!(
-                                "DEBUG: Студент успешно добавлен/удален. Запускаем перезагрузку..."
-                            );
-                            Message::StudentsAndGroupsReloaded(group_id, 0) // Отправляем новое сообщение
+            Message::EnrollStudent(status) => {
+                let Some(group_id) = self.current_manage_students_group_id else {
+                    return Task::none();
+                };
+                let Some(student_id) = self.selected_student_to_add.as_ref().map(|s| s.id) else {
+                    self.group_error_message = Some("Выберите студента.".to_string());
+                    return Task::none();
+                };
+                let terms = self.new_enrollment_terms;
+                let payment_due = if terms.needs_due_date() {
+                    match NaiveDate::parse_from_str(self.new_enrollment_due.trim(), "%Y-%m-%d") {
+                        Ok(date) => Some(date.format("%Y-%m-%d").to_string()),
+                        Err(_) => {
+                            self.group_error_message =
+                                Some("Укажите срок оплаты в формате ГГГГ-ММ-ДД.".to_string());
+                            return Task::none();
                         }
-                    },
-                )
-            }
-            Message::SelectedStudentToAddChanged(student_opt) => {
-                if let Some(student) = &student_opt {
-                    println!("DEBUG: Выбран студент для добавления: {:?}", student.name);
-                }
-                self.selected_student_to_add = student_opt;
+                    }
+                } else {
+                    None
+                };
                 self.group_error_message = None;
-                Task::none()
-            }
-            Message::RemoveStudentFromGroup(student_id, group_id) => {
-                println!(
-                    "DEBUG: Попытка удалить студента ID: {} из группы ID: {}",
-                    student_id, group_id
-                );
-
-                let group_id_for_async_task = group_id; // Для асинхронной задачи db
-                let teacher_id = self.current_user.as_ref().map(|u| u.id).unwrap_or(0);
 
                 Task::perform(
                     async move {
-                        // 'move' здесь гарантирует, что student_id и group_id_for_async_task перемещаются в этот async блок
                         spawn_blocking(move || {
-                            // 'move' здесь гарантирует, что student_id и group_id_for_async_task перемещаются в этот blocking блок
-                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| {
-                                format!("Не удалось открыть БД для удаления студента: {}", e)
-                            })?;
-                            db::remove_student_from_group(
-                                &mut conn,
-                                student_id,
-                                group_id_for_async_task,
-                            ) // Используем переданные значения
-                            .map_err(|e| format!("Ошибка удаления студента из группы: {}", e))
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::enroll_student(&mut conn, student_id, group_id, status, terms, payment_due.as_deref())
+                                .map(|_| ())
                         })
                         .await
                         .unwrap_or_else(|join_err| {
                             Err(format!(
-                                "Ошибка выполнения задачи удаления студента: {:?}",
+                                "Блокирующая задача (зачисление студента) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    move |result| Message::EnrollmentChanged(group_id, result),
+                )
+            }
+            Message::NewEnrollmentTermsSelected(terms) => {
+                self.new_enrollment_terms = terms;
+                self.group_error_message = None;
+                Task::none()
+            }
+            Message::NewEnrollmentDueChanged(value) => {
+                self.new_enrollment_due = value;
+                Task::none()
+            }
+            Message::ChangeEnrollmentStatus(enrollment_id, status) => {
+                let Some(group_id) = self.current_manage_students_group_id else {
+                    return Task::none();
+                };
+                self.group_error_message = None;
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::set_enrollment_status(&mut conn, enrollment_id, status)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (смена этапа зачисления) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    move |result| Message::EnrollmentChanged(group_id, result),
+                )
+            }
+            Message::StartTransfer(enrollment_id) => {
+                self.transfer_enrollment = Some(enrollment_id);
+                self.transfer_target = None;
+                self.group_error_message = None;
+                Task::none()
+            }
+            Message::CancelTransfer => {
+                self.transfer_enrollment = None;
+                self.transfer_target = None;
+                Task::none()
+            }
+            Message::TransferTargetSelected(group) => {
+                self.transfer_target = Some(group);
+                self.group_error_message = None;
+                Task::none()
+            }
+            Message::ConfirmTransfer => {
+                let (Some(group_id), Some(enrollment_id)) =
+                    (self.current_manage_students_group_id, self.transfer_enrollment)
+                else {
+                    return Task::none();
+                };
+                let Some(target_group_id) = self.transfer_target.as_ref().map(|g| g.id) else {
+                    self.group_error_message = Some("Выберите группу для перевода.".to_string());
+                    return Task::none();
+                };
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::transfer_student(&mut conn, enrollment_id, target_group_id).map(|_| ())
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (перевод студента) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    move |result| Message::EnrollmentChanged(group_id, result),
+                )
+            }
+            Message::EnrollmentChanged(group_id, result) => match result {
+                Ok(()) => {
+                    self.transfer_enrollment = None;
+                    self.transfer_target = None;
+                    self.selected_student_to_add = None;
+                    self.new_enrollment_terms = EnrollmentTerms::Standard;
+                    self.new_enrollment_due.clear();
+                    self.update(Message::StudentsAndGroupsReloaded(group_id, 0))
+                }
+                Err(e) => {
+                    eprintln!("ERROR: Не удалось изменить зачисление: {}", e);
+                    self.group_error_message = Some(e);
+                    Task::none()
+                }
+            },
+            Message::GroupEnrollmentsLoaded(result) => {
+                match result {
+                    Ok(enrollments) => self.group_enrollments = enrollments,
+                    Err(e) => self.group_error_message = Some(e),
+                }
+                Task::none()
+            }
+            Message::SelectedStudentToAddChanged(student_opt) => {
+                if let Some(student) = &student_opt {
+                    println!("DEBUG: Выбран студент для добавления: {:?}", student.name);
+                }
+                self.selected_student_to_add = student_opt;
+                self.group_error_message = None;
+                Task::none()
+            }
+            Message::RemoveStudentFromGroup(student_id, group_id) => {
+                println!(
+                    "DEBUG: Попытка удалить студента ID: {} из группы ID: {}",
+                    student_id, group_id
+                );
+
+                let group_id_for_async_task = group_id; // Для асинхронной задачи db
+                let teacher_id = self.current_user.as_ref().map(|u| u.id).unwrap_or(0);
+
+                Task::perform(
+                    async move {
+                        // 'move' здесь гарантирует, что student_id и group_id_for_async_task перемещаются в этот async блок
+                        spawn_blocking(move || {
+                            // 'move' здесь гарантирует, что student_id и group_id_for_async_task перемещаются в этот blocking блок
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| {
+                                format!("Не удалось открыть БД для удаления студента: {}", e)
+                            })?;
+                            db::remove_student_from_group(
+                                &mut conn,
+                                student_id,
+                                group_id_for_async_task,
+                            ) // Используем переданные значения
+                            .map_err(|e| format!("Ошибка удаления студента из группы: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Ошибка выполнения задачи удаления студента: {:?}",
                                 join_err
                             ))
                         })
@@ -1625,7 +2277,7 @@ impl App {
                     Message::StudentsWithoutGroupLoaded,
                 );
 
-                Task::batch(vec![command1, command2, command3])
+                Task::batch(vec![command1, command2, command3, self.load_group_enrollments(group_id)])
             }
             Message::ShowParentChildren(parent_email) => {
                 // Эта операция должна быть асинхронной
@@ -1820,7 +2472,7 @@ impl App {
             Message::GoToClasses => {
                 self.current_screen = Screen::Classes;
                 if let Some(user) = &self.current_user {
-                    if user.user_type == "teacher" {
+                    if user.user_type == Role::Teacher {
                         println!(
                             "Teacher logged in, attempting to load groups for: {}",
                             user.email
@@ -1835,14 +2487,257 @@ impl App {
             }
             Message::GoToPayment => {
                 self.current_screen = Screen::Payment;
-                Task::perform(
-                    async {
+                Task::batch(vec![
+                    Task::perform(
+                        async {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::get_all_payments_with_details(&conn).map_err(|e| e.to_string())
+                        },
+                        Message::PaymentsFetched,
+                    ),
+                    self.load_balances(),
+                    self.load_payment_documents(),
+                ])
+            }
+            Message::GoToPayroll => {
+                self.current_screen = Screen::Payroll;
+                self.payroll_confirm_approve = false;
+                self.payroll_notice = None;
+                self.payroll_error = None;
+                Task::batch(vec![self.load_teacher_rates(), self.load_payroll()])
+            }
+            Message::GoToAuditLog => {
+                self.current_screen = Screen::AuditLog;
+                self.audit_notice = None;
+                self.audit_error = None;
+                self.load_audit_log()
+            }
+            Message::GoToLoginHistory => {
+                self.current_screen = Screen::LoginHistory;
+                self.login_history_error = None;
+                self.load_login_history()
+            }
+            Message::LoginHistoryLoaded(result) => {
+                match result {
+                    Ok((attempts, locked)) => {
+                        self.login_history = attempts;
+                        self.locked_accounts = locked;
+                        self.login_history_error = None;
+                    }
+                    Err(e) => self.login_history_error = Some(e),
+                }
+                Task::none()
+            }
+            Message::LoginHistoryEmailChanged(value) => {
+                self.login_history_email_filter = value;
+                Task::none()
+            }
+            Message::LoginHistoryFailuresOnlyToggled(value) => {
+                self.login_history_failures_only = value;
+                self.load_login_history()
+            }
+            Message::ApplyLoginHistoryFilter => self.load_login_history(),
+            Message::UnlockAccount(user_id) => Task::perform(
+                async move {
+                    spawn_blocking(move || {
                         let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
-                        db::get_all_payments_with_details(&conn).map_err(|e| e.to_string())
+                        db::unlock_account(&conn, user_id).map_err(|e| format!("Ошибка снятия блокировки: {}", e))
+                    })
+                    .await
+                    .unwrap_or_else(|join_err| {
+                        Err(format!("Блокирующая задача (снятие блокировки) завершилась ошибкой: {:?}", join_err))
+                    })
+                },
+                Message::AccountUnlocked,
+            ),
+            Message::AccountUnlocked(result) => match result {
+                Ok(()) => self.load_login_history(),
+                Err(e) => {
+                    self.login_history_error = Some(e);
+                    Task::none()
+                }
+            },
+            Message::UserActivity => {
+                self.last_activity = Instant::now();
+                Task::none()
+            }
+            Message::IdleLockTick => {
+                let idle_limit = std::time::Duration::from_secs(u64::from(self.idle_lock_minutes) * 60);
+                if self.current_user.is_none() || self.idle_lock_minutes == 0 || self.last_activity.elapsed() < idle_limit {
+                    return Task::none();
+                }
+                // Возвращаемся к форме входа, email оставляем, чтобы войти снова было быстрее
+                let email = self.current_user.as_ref().map(|user| user.email.clone()).unwrap_or_default();
+                println!("DEBUG: сеанс {} заблокирован после {} мин бездействия", email, self.idle_lock_minutes);
+                let task = self.update(Message::Logout);
+                self.user_email = email;
+                self.error_message = format!(
+                    "Сеанс заблокирован после {} мин бездействия. Войдите снова.",
+                    self.idle_lock_minutes
+                );
+                task
+            }
+            Message::IdleLockMinutesSelected(minutes) => {
+                self.idle_lock_minutes = minutes;
+                self.save_settings();
+                Task::none()
+            }
+            Message::TwoFactorCodeChanged(value) => {
+                self.two_factor_code_input = value;
+                Task::none()
+            }
+            Message::SubmitTwoFactorCode => {
+                let Some(user) = &self.pending_two_factor_user else {
+                    return Task::none();
+                };
+                let code = self.two_factor_code_input.trim().to_string();
+                if code.is_empty() {
+                    self.two_factor_error = Some("Введите код из приложения или код восстановления.".to_string());
+                    return Task::none();
+                }
+                let user_id = user.id;
+                let email = user.email.clone();
+                self.two_factor_error = None;
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::verify_second_factor(&conn, user_id, &email, &code)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!("Блокирующая задача (проверка кода входа) завершилась ошибкой: {:?}", join_err))
+                        })
+                    },
+                    Message::TwoFactorVerified,
+                )
+            }
+            Message::TwoFactorVerified(result) => match result {
+                Ok(()) => {
+                    self.two_factor_code_input.clear();
+                    match self.pending_two_factor_user.take() {
+                        Some(user) => self.start_session(user),
+                        None => Task::none(),
+                    }
+                }
+                Err(e) => {
+                    self.two_factor_error = Some(e);
+                    self.two_factor_code_input.clear();
+                    Task::none()
+                }
+            },
+            Message::CancelTwoFactorLogin => {
+                self.pending_two_factor_user = None;
+                self.two_factor_code_input.clear();
+                self.two_factor_error = None;
+                self.user_password.clear();
+                self.current_screen = Screen::Login;
+                Task::none()
+            }
+            Message::StartTwoFactorSetup => {
+                self.begin_two_factor_setup();
+                Task::none()
+            }
+            Message::CancelTwoFactorSetup => {
+                // Обязательную настройку отменить нельзя — только выйти
+                if !self.two_factor_setup_required {
+                    self.two_factor_setup_secret = None;
+                    self.two_factor_qr = None;
+                    self.two_factor_error = None;
+                }
+                Task::none()
+            }
+            Message::ConfirmTwoFactorSetup => {
+                let (Some(user), Some(secret)) = (&self.current_user, &self.two_factor_setup_secret) else {
+                    return Task::none();
+                };
+                let user_id = user.id;
+                let secret = secret.clone();
+                let code = self.two_factor_code_input.trim().to_string();
+                self.two_factor_error = None;
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::enable_two_factor(&mut conn, user_id, &secret, &code)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (включение двухфакторной аутентификации) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::TwoFactorSetupFinished,
+                )
+            }
+            Message::TwoFactorSetupFinished(result) => {
+                match result {
+                    Ok(codes) => {
+                        self.two_factor_enabled = true;
+                        self.two_factor_setup_required = false;
+                        self.two_factor_setup_secret = None;
+                        self.two_factor_qr = None;
+                        self.two_factor_code_input.clear();
+                        // Коды восстановления заменяют пропавший телефон — показываем их сразу
+                        self.recovery_codes_left = codes.len() as i64;
+                        self.recovery_codes = codes;
+                        self.current_screen = Screen::Profile;
+                    }
+                    Err(e) => self.two_factor_error = Some(e),
+                }
+                Task::none()
+            }
+            Message::DisableTwoFactor => {
+                let Some(user) = &self.current_user else {
+                    return Task::none();
+                };
+                if self.require_admin_two_factor && user.user_type == Role::Admin {
+                    self.two_factor_error =
+                        Some("Для администраторов двухфакторная аутентификация обязательна.".to_string());
+                    return Task::none();
+                }
+                let user_id = user.id;
+                let code = self.two_factor_code_input.trim().to_string();
+                self.two_factor_error = None;
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::disable_two_factor(&conn, user_id, &code)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (отключение двухфакторной аутентификации) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
                     },
-                    Message::PaymentsFetched,
+                    Message::TwoFactorDisabled,
                 )
             }
+            Message::TwoFactorDisabled(result) => {
+                match result {
+                    Ok(()) => {
+                        self.two_factor_enabled = false;
+                        self.two_factor_code_input.clear();
+                    }
+                    Err(e) => self.two_factor_error = Some(e),
+                }
+                Task::none()
+            }
+            Message::RequireAdminTwoFactorToggled(value) => {
+                self.require_admin_two_factor = value;
+                self.save_settings();
+                // Требование касается и администратора, который его включил
+                self.load_two_factor_state();
+                Task::none()
+            }
             Message::GoToCertificates => {
                 self.current_screen = Screen::Certificates;
                 self.error_message = "".to_string();
@@ -2307,506 +3202,2019 @@ impl App {
 
                 Task::none()
             }
-            Message::CloseAssignmentDetailModal => {
-                self.show_assignment_detail_modal = false;
-                self.selected_assignment_for_detail = None; // Очищаем выбранное задание
+            Message::CloseAssignmentDetailModal => {
+                self.show_assignment_detail_modal = false;
+                self.selected_assignment_for_detail = None; // Очищаем выбранное задание
+                Task::none()
+            }
+            Message::EditingAssignmentDescriptionChanged(input) => {
+                match input {
+                    TextInputOrEditorInput::TextEditor(action) => {
+                        // Если пришло действие из TextEditor, примените его к TextEditor контенту
+                        self.editing_assignment_description_content.perform(action);
+                        Task::none()
+                        // Опционально: если вам нужно, чтобы String поле всегда отражало TextEditor, синхронизируйте их:
+                        // self.editing_assignment_description_text_input = self.editing_assignment_description_content.text();
+                    }
+                    TextInputOrEditorInput::TextInput(text) => {
+                        // Если пришла строка из TextInput, обновите String поле
+                        self.editing_assignment_description_text_input = text;
+                        Task::none()
+                        // Опционально: если вам нужно, чтобы TextEditor контент отражал String поле (например, при переключении типов), синхронизируйте их:
+                        // self.editing_assignment_description_content = text_editor::Content::with_text(&text); // Осторожно: это сбрасывает историю действий TextEditor
+                    }
+                }
+            }
+            Message::EditingAssignmentTitleChanged(title) => {
+                self.editing_assignment_title = title;
+                Task::none()
+            }
+            Message::SaveEditedAssignment => {
+                let conn = Connection::open(PATH_TO_DB).unwrap();
+                if let Some(selected_assignment) = &self.selected_assignment_for_detail {
+                    if self.editing_assignment_title.is_empty() {
+                        self.assignment_edit_error_message =
+                            Some("Название задания не может быть пустым.".to_string());
+                        return Task::none(); // <-- Правильный возврат!
+                    }
+
+                    let description_to_save = if selected_assignment.assignment_type
+                        == AssignmentType::Lecture.to_string()
+                        || selected_assignment.assignment_type
+                            == AssignmentType::Practice.to_string()
+                    {
+                        self.editing_assignment_description_content.text()
+                    } else {
+                        self.editing_assignment_description_text_input.clone()
+                    };
+
+                    let updated_assignment = Assignment {
+                        id: selected_assignment.id,
+                        lesson_id: selected_assignment.lesson_id,
+                        title: self.editing_assignment_title.clone(),
+                        description: description_to_save,
+                        assignment_type: selected_assignment.assignment_type.clone(),
+                    };
+
+                    match db::update_assignment(&conn, &updated_assignment) {
+                        Ok(_) => {
+                            self.selected_assignment_for_detail = Some(updated_assignment.clone());
+                            self.assignment_edit_error_message = None;
+                            self.show_assignment_detail_modal = false;
+
+                            // Важно: Обновить список заданий, чтобы изменения отобразились.
+                            // Возвращаем Task, чтобы выполнить асинхронную операцию
+                            if self.show_assignments_modal {
+                                if let Some(lesson) = &self.current_lesson_for_assignments {
+                                    let lesson_id_clone = lesson.id;
+                                    return Task::perform(
+                                        async move {
+                                            // <-- Асинхронный блок - это Future, передаваемый в Task::perform
+                                            // Этот фьючер spawn_blocking сам по себе возвращает Result<Result<Vec<Assignment>, String>, JoinError>
+                                            let blocking_result = spawn_blocking(move || {
+                                                let conn_task = Connection::open(PATH_TO_DB)
+                                                    .map_err(|e| format!("Не удалось открыть БД для загрузки заданий: {}", e))?;
+                                                db::get_assignments_for_lesson(&conn_task, lesson_id_clone)
+                                                    .map_err(|e| format!("Ошибка загрузки заданий: {}", e))
+                                            }).await; // <-- Ожидаем завершения блокирующей задачи, чтобы получить ее Result<T, JoinError>
+
+                                            // Теперь разворачиваем внешний Result от spawn_blocking.
+                                            // Если spawn_blocking сам по себе завершился с ошибкой (например, паникой),
+                                            // преобразуем JoinError в String.
+                                            // В противном случае у нас есть внутренний Result<Vec<Assignment>, String>.
+                                            blocking_result.unwrap_or_else(|join_err| {
+                                                // Обрабатываем JoinError (например, если блокирующая задача запаниковала)
+                                                eprintln!("Блокирующая задача запаниковала или была отменена: {:?}", join_err);
+                                                Err(format!("Ошибка выполнения операции: {}", join_err))
+                                            })
+                                        },
+                                        Message::AssignmentsLoaded, // <-- Теперь это сообщение корректно ожидает Result<Vec<Assignment>, String>
+                                    );
+                                }
+                            }
+                            Task::none()
+                        }
+                        Err(e) => {
+                            self.assignment_edit_error_message =
+                                Some(format!("Ошибка сохранения задания: {}", e));
+                            Task::none()
+                        }
+                    }
+                } else {
+                    eprintln!("Попытка сохранить детали задания, но задание не выбрано.");
+                    self.assignment_edit_error_message =
+                        Some("Ошибка: Не выбрано задание для сохранения.".to_string());
+                    Task::none()
+                }
+            }
+            Message::AssignmentsLoaded(result) => {
+                match result {
+                    Ok(assignments) => {
+                        self.lesson_assignments = assignments;
+                        self.assignment_error_message = None; // Очищаем ошибки после успешной загрузки
+                    }
+                    Err(e) => {
+                        self.assignment_error_message =
+                            Some(format!("Ошибка загрузки заданий: {}", e));
+                    }
+                }
+                Task::none()
+            }
+            Message::LoadTeacherGroups(teacher_id_to_load) => {
+                println!(
+                    "DEBUG: Запущена асинхронная загрузка групп для преподавателя ID: {}",
+                    teacher_id_to_load
+                );
+                Task::perform(
+                    async move {
+                        // Вызываем `spawn_blocking` напрямую из `task`
+                        spawn_blocking(move || {
+                            // <-- Меняем на `task::spawn_blocking`
+                            let conn = Connection::open(PATH_TO_DB)
+                                .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+                            db::get_teacher_groups_with_details(&conn, teacher_id_to_load)
+                                .map_err(|e| format!("Ошибка загрузки групп из БД: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Ошибка выполнения блокирующей задачи: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::TeacherGroupsLoaded,
+                )
+            }
+            Message::LoadAllGroups => {
+                println!("DEBUG: Запущена асинхронная загрузка ВСЕХ групп (для администратора).");
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| {
+                                format!("Не удалось открыть БД для загрузки ВСЕХ групп: {}", e)
+                            })?;
+                            db::get_all_groups(&conn) // <--- ВЫЗЫВАЕМ НОВУЮ ФУНКЦИЮ
+                                .map_err(|e| format!("Ошибка загрузки ВСЕХ групп из БД: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Ошибка выполнения блокирующей задачи загрузки ВСЕХ групп: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::AllGroupsLoaded, // <--- ИСПОЛЬЗУЕМ НОВОЕ СООБЩЕНИЕ
+                )
+            }
+            Message::AllGroupsLoaded(result) => {
+                // <--- НОВЫЙ ОБРАБОТЧИК
+                match result {
+                    Ok(groups) => {
+                        self.all_groups = groups; // Обновляем новое поле
+                        self.group_error_message = None; // Очищаем ошибку, если она была
+                        println!(
+                            "DEBUG: AllGroupsLoaded успешно. Загружено ВСЕХ групп: {} шт.",
+                            self.all_groups.len()
+                        );
+                    }
+                    Err(e) => {
+                        self.group_error_message =
+                            Some(format!("Ошибка загрузки ВСЕХ групп: {}", e));
+                        eprintln!("Ошибка загрузки ВСЕХ групп для администратора: {}", e);
+                    }
+                }
+                Task::none()
+            }
+            Message::TeacherGroupsLoaded(result) => {
+                match result {
+                    Ok(groups) => {
+                        println!(
+                            "DEBUG: TeacherGroupsLoaded успешно. Загружено групп: {}",
+                            groups.len()
+                        );
+                        self.teacher_groups = groups; // <-- Теперь self.teacher_groups будет содержать либо группы преподавателя, либо ВСЕ группы
+                        println!(
+                            "DEBUG: Группы успешно загружены: {} шт.",
+                            self.teacher_groups.len()
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!("ERROR: Не удалось загрузить группы: {}", e);
+                        self.error_message = e.to_string();
+                    }
+                }
+                Task::none()
+            }
+            Message::OpenGroupLessonsModal(group_id, course_id) => {
+                self.show_group_lessons_modal = true;
+                self.group_lessons_modal_lessons.clear();
+                self.group_lessons_modal_past_sessions.clear();
+
+                // Ищем название группы для заголовка модального окна
+                if let Some(group_found) = self.all_groups.iter().find(|g| g.id == group_id) {
+                    self.group_lessons_modal_group_name = group_found.name.clone();
+                } else {
+                    self.group_lessons_modal_group_name = "Неизвестная группа".to_string();
+                }
+
+                Task::perform(
+                    async move {
+                        let result = spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| {
+                                format!("Не удалось открыть БД для загрузки занятий группы: {}", e)
+                            })?;
+
+                            // Загружаем уроки, которые ЕЩЕ НЕ ПРОВЕДЕНЫ для этой группы
+                            let available_lessons =
+                                db::get_lessons_for_course_and_group(&conn, course_id, group_id)
+                                    .map_err(|e| {
+                                        format!("Ошибка загрузки доступных уроков: {}", e)
+                                    })?;
+
+                            // Загружаем уроки, которые УЖЕ ПРОВЕДЕНЫ для этой группы
+                            let past_sessions = db::get_past_sessions_for_group(&conn, group_id)
+                                .map_err(|e| format!("Ошибка загрузки прошедших занятий: {}", e))?;
+
+                            Ok((available_lessons, past_sessions))
+                        })
+                        .await;
+
+                        // Обработка ошибок из spawn_blocking
+                        result.unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::GroupLessonsModalLoaded, // Отправляем результат в новое сообщение
+                )
+            }
+            Message::GroupLessonsModalLoaded(result) => {
+                match result {
+                    Ok((lessons, past_sessions)) => {
+                        self.group_lessons_modal_lessons = lessons;
+                        self.group_lessons_modal_past_sessions = past_sessions;
+                        Task::none() // Больше никаких задач не нужно
+                    }
+                    Err(e) => {
+                        eprintln!("Ошибка загрузки занятий для модального окна: {}", e);
+                        self.error_message = e.to_string();
+                        self.show_group_lessons_modal = false; // Закрываем модальное окно при ошибке
+                        Task::none()
+                    }
+                }
+            }
+            Message::CloseGroupLessonsModal => {
+                self.show_group_lessons_modal = false;
+                self.group_lessons_modal_lessons.clear();
+                self.group_lessons_modal_past_sessions.clear();
+                self.group_lessons_modal_group_name.clear();
+                Task::none()
+            }
+            Message::ErrorOccurred(_) => Task::none(),
+            Message::PaymentsFetched(Ok(payments)) => {
+                self.payments = payments;
+                Task::none()
+            }
+            Message::PaymentsFetched(Err(e)) => {
+                eprintln!("Ошибка загрузки платежей: {}", e);
+                // Тут можно показать ошибку пользователю
+                Task::none()
+            }
+            Message::ToggleAddPaymentModal => {
+                self.show_add_payment_modal = !self.show_add_payment_modal;
+                // При открытии модального окна загружаем необходимые данные
+                if self.show_add_payment_modal {
+                    self.reset_new_payment_form(); // Сбросить форму
+                    Task::batch(vec![
+                        Task::perform(
+                            async {
+                                // Используйте tokio::task::spawn_blocking для блокирующих DB-операций
+                                let conn =
+                                    Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                                db::get_students_not_in_any_group(&conn).map_err(|e| e.to_string())
+                            },
+                            Message::StudentsWithoutGroupFetched,
+                        ),
+                        Task::perform(
+                            async {
+                                let conn =
+                                    Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                                db::get_courses_with_available_seats(&conn)
+                                    .map_err(|e| e.to_string())
+                            },
+                            Message::CoursesWithSeatsFetched,
+                        ),
+                        self.load_waitlist(),
+                    ])
+                } else {
+                    Task::none()
+                }
+            }
+            Message::StudentsWithoutGroupFetched(Ok(students)) => {
+                self.students_without_group = students;
+                Task::none()
+            }
+            Message::StudentsWithoutGroupFetched(Err(e)) => {
+                eprintln!("Ошибка загрузки студентов без группы: {}", e);
+                Task::none()
+            }
+            Message::CoursesWithSeatsFetched(Ok(courses)) => {
+                self.courses_with_seats = courses;
+                // Зачисление по предложению из листа ожидания: курс выбираем сразу
+                let course_item = self.waitlist_enrollment.as_ref().and_then(|entry| {
+                    self.courses_with_seats
+                        .iter()
+                        .find(|c| c.id == entry.course_id)
+                        .map(|c| CoursePickListItem {
+                            id: c.id,
+                            title: c.title.clone(),
+                            price_display: c
+                                .price
+                                .map(|p| format!("{:.2} €", p))
+                                .unwrap_or_else(|| "Цена не указана".to_string()),
+                        })
+                });
+                match course_item {
+                    Some(item) => self.update(Message::NewPaymentFormCourseSelected(item)),
+                    None => Task::none(),
+                }
+            }
+            Message::CoursesWithSeatsFetched(Err(e)) => {
+                eprintln!("Ошибка загрузки курсов со свободными местами: {}", e);
+                Task::none()
+            }
+            Message::NewPaymentFormStudentSelected(selected_student_item) => {
+                let student_id = selected_student_item.id;
+                self.new_payment_student = Some(selected_student_item);
+                self.new_payment_sibling = None;
+
+                // Скидка для братьев и сестёр: ищем уже обучающегося ребёнка того же родителя
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::find_enrolled_sibling(&conn, student_id).map_err(|e| e.to_string())
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (поиск братьев и сестёр) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::NewPaymentSiblingChecked,
+                )
+            }
+            Message::NewPaymentSiblingChecked(result) => {
+                match result {
+                    Ok(sibling) => self.new_payment_sibling = sibling,
+                    Err(e) => eprintln!("Ошибка поиска братьев и сестёр: {}", e),
+                }
+                Task::none()
+            }
+            Message::NewPaymentPromoInputChanged(value) => {
+                self.new_payment_promo_input = value;
+                self.new_payment_error = None;
+                Task::none()
+            }
+            Message::ApplyPromoCode => {
+                let code = self.new_payment_promo_input.trim().to_string();
+                if code.is_empty() {
+                    self.new_payment_error = Some("Введите промокод.".to_string());
+                    return Task::none();
+                }
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::find_promo_code(&conn, &code).map_err(|e| e.to_string())
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (проверка промокода) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::PromoCodeChecked,
+                )
+            }
+            Message::PromoCodeChecked(result) => {
+                let today = Local::now().format("%Y-%m-%d").to_string();
+                match result {
+                    Ok(Some(promo)) => match promo.check(&today) {
+                        Ok(()) => {
+                            self.new_payment_promo = Some(promo);
+                            self.new_payment_error = None;
+                        }
+                        Err(e) => self.new_payment_error = Some(e),
+                    },
+                    Ok(None) => self.new_payment_error = Some("Промокод не найден.".to_string()),
+                    Err(e) => self.new_payment_error = Some(format!("Ошибка проверки промокода: {}", e)),
+                }
+                Task::none()
+            }
+            Message::ClearPromoCode => {
+                self.new_payment_promo = None;
+                self.new_payment_promo_input.clear();
+                Task::none()
+            }
+            Message::NewPaymentManualDiscountChanged(value) => {
+                self.new_payment_manual_discount = value;
+                self.new_payment_error = None;
+                Task::none()
+            }
+            Message::NewPaymentManualReasonChanged(value) => {
+                self.new_payment_manual_reason = value;
+                self.new_payment_error = None;
+                Task::none()
+            }
+            Message::TogglePromoCodes => {
+                self.show_promo_codes = !self.show_promo_codes;
+                self.promo_error = None;
+                if self.show_promo_codes {
+                    self.load_promo_codes()
+                } else {
+                    Task::none()
+                }
+            }
+            Message::PromoCodesLoaded(result) => {
+                match result {
+                    Ok(codes) => self.promo_codes = codes,
+                    Err(e) => self.promo_error = Some(format!("Не удалось загрузить промокоды: {}", e)),
+                }
+                Task::none()
+            }
+            Message::NewPromoCodeChanged(value) => {
+                self.new_promo_code = value;
+                self.promo_error = None;
+                Task::none()
+            }
+            Message::NewPromoIsPercentToggled(value) => {
+                self.new_promo_is_percent = value;
+                Task::none()
+            }
+            Message::NewPromoValueChanged(value) => {
+                self.new_promo_value = value;
+                self.promo_error = None;
+                Task::none()
+            }
+            Message::NewPromoValidFromChanged(value) => {
+                self.new_promo_valid_from = value;
+                self.promo_error = None;
+                Task::none()
+            }
+            Message::NewPromoValidToChanged(value) => {
+                self.new_promo_valid_to = value;
+                self.promo_error = None;
+                Task::none()
+            }
+            Message::NewPromoMaxUsesChanged(value) => {
+                self.new_promo_max_uses = value;
+                self.promo_error = None;
+                Task::none()
+            }
+            Message::CreatePromoCode => {
+                let code = self.new_promo_code.trim().to_string();
+                if code.is_empty() {
+                    self.promo_error = Some("Введите промокод.".to_string());
+                    return Task::none();
+                }
+                let value = match self.new_promo_value.trim().replace(',', ".").parse::<f64>() {
+                    Ok(value) if value > 0.0 && (!self.new_promo_is_percent || value <= 100.0) => value,
+                    _ => {
+                        self.promo_error = Some(if self.new_promo_is_percent {
+                            "Процент скидки должен быть от 0 до 100.".to_string()
+                        } else {
+                            "Введите сумму скидки больше нуля.".to_string()
+                        });
+                        return Task::none();
+                    }
+                };
+                // Пустая дата — без ограничения
+                let parse_date = |input: &str| match input.trim() {
+                    "" => Ok(None),
+                    date => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                        .map(|_| Some(date.to_string())),
+                };
+                let (Ok(valid_from), Ok(valid_to)) = (
+                    parse_date(&self.new_promo_valid_from),
+                    parse_date(&self.new_promo_valid_to),
+                ) else {
+                    self.promo_error = Some("Даты указываются в формате ГГГГ-ММ-ДД.".to_string());
+                    return Task::none();
+                };
+                if let (Some(from), Some(to)) = (&valid_from, &valid_to)
+                    && from > to
+                {
+                    self.promo_error = Some("Дата начала позже даты окончания.".to_string());
+                    return Task::none();
+                }
+                let max_uses = match self.new_promo_max_uses.trim() {
+                    "" => None,
+                    input => match input.parse::<i32>() {
+                        Ok(max) if max > 0 => Some(max),
+                        _ => {
+                            self.promo_error = Some("Лимит применений — целое число больше нуля.".to_string());
+                            return Task::none();
+                        }
+                    },
+                };
+                let promo = PromoCode {
+                    id: 0,
+                    code,
+                    is_percent: self.new_promo_is_percent,
+                    value,
+                    valid_from,
+                    valid_to,
+                    max_uses,
+                    used_count: 0,
+                };
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::add_promo_code(&conn, &promo).map_err(|e| match e {
+                                rusqlite::Error::SqliteFailure(err, _)
+                                    if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+                                {
+                                    format!("Промокод {} уже существует.", promo.code)
+                                }
+                                e => format!("Не удалось сохранить промокод: {}", e),
+                            })
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (сохранение промокода) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::PromoCodeSaved,
+                )
+            }
+            Message::PromoCodeSaved(result) => match result {
+                Ok(()) => {
+                    self.new_promo_code.clear();
+                    self.new_promo_value.clear();
+                    self.new_promo_valid_from.clear();
+                    self.new_promo_valid_to.clear();
+                    self.new_promo_max_uses.clear();
+                    self.promo_error = None;
+                    self.load_promo_codes()
+                }
+                Err(e) => {
+                    self.promo_error = Some(e);
+                    Task::none()
+                }
+            },
+            Message::DeletePromoCode(promo_id) => Task::perform(
+                async move {
+                    spawn_blocking(move || {
+                        let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                        db::delete_promo_code(&conn, promo_id)
+                            .map_err(|e| format!("Не удалось удалить промокод: {}", e))
+                    })
+                    .await
+                    .unwrap_or_else(|join_err| {
+                        Err(format!(
+                            "Блокирующая задача (удаление промокода) завершилась ошибкой: {:?}",
+                            join_err
+                        ))
+                    })
+                },
+                Message::PromoCodeSaved,
+            ),
+            Message::NewPaymentFormCourseSelected(selected_course_item) => {
+                // Теперь мы получаем выбранный CoursePickListItem
+                self.new_payment_course = Some(selected_course_item.clone());
+
+                // Автоматически подтягиваем цену курса из выбранного элемента
+                if let Some(course_price_str) =
+                    selected_course_item.price_display.strip_suffix(" €")
+                {
+                    if let Ok(price) = course_price_str.parse::<f64>() {
+                        self.new_payment_amount = Some(price);
+                    }
+                } else if selected_course_item.price_display == "Цена не указана" {
+                    self.new_payment_amount = None; // Или 0.0, в зависимости от логики
+                }
+
+                // Загружаем группы для выбранного курса, используя его ID
+                Task::perform(
+                    async move {
+                        let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                        db::get_groups_by_course_id(&conn, selected_course_item.id)
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::GroupsForCourseFetched,
+                )
+            }
+            Message::GroupsForCourseFetched(Ok(groups)) => {
+                self.groups_for_selected_course = groups;
+                // Группу, которую студент выбрал при записи в лист ожидания, подставляем в форму
+                if let Some(entry) = self.waitlist_enrollment.take()
+                    && let Some(group) = self
+                        .groups_for_selected_course
+                        .iter()
+                        .find(|g| Some(g.id) == entry.group_id)
+                {
+                    self.new_payment_group = Some(GroupPickListItem {
+                        id: group.id,
+                        name: group.name.clone(),
+                    });
+                }
+                Task::none()
+            }
+            Message::GroupsForCourseFetched(Err(e)) => {
+                eprintln!("Ошибка загрузки групп для курса: {}", e);
+                Task::none()
+            }
+            Message::NewPaymentFormGroupSelected(selected_group_item) => {
+                self.new_payment_group = Some(selected_group_item);
+                Task::none()
+            }
+            Message::NewPaymentFormTypeChanged(selected_type_string) => {
+                let payment_types_options = vec!["Карта".to_string(), "QR-Код".to_string()];
+
+                self.selected_payment_type_idx = payment_types_options
+                    .iter()
+                    .position(|s| s == &selected_type_string);
+
+                self.new_payment_type = selected_type_string;
+
+                Task::none() // Возвращаем пустую команду
+            }
+            Message::AddPaymentConfirmed => {
+                if let (Some(student), Some(course), Some(group), Some(amount)) = (
+                    &self.new_payment_student,
+                    &self.new_payment_course,
+                    &self.new_payment_group,
+                    self.new_payment_amount,
+                ) {
+                    let student_id = student.id;
+                    let course_id = course.id;
+                    let group_id = group.id;
+                    let today = Local::now().date_naive();
+
+                    // Ручная скидка необязательна, но требует причину
+                    if !self.new_payment_manual_discount.trim().is_empty() {
+                        match self.new_payment_manual_discount.trim().replace(',', ".").parse::<f64>() {
+                            Ok(value) if value >= 0.0 => {}
+                            _ => {
+                                self.new_payment_error = Some("Некорректная сумма скидки.".to_string());
+                                return Task::none();
+                            }
+                        }
+                        if self.new_payment_manual_reason.trim().is_empty() {
+                            self.new_payment_error = Some("Укажите причину скидки.".to_string());
+                            return Task::none();
+                        }
+                    }
+                    let discounts = self.new_payment_discounts();
+                    let discount: f64 = discounts.iter().map(|d| d.amount).sum();
+                    let amount = ((amount - discount).max(0.0) * 100.0).round() / 100.0;
+
+                    // При рассрочке сейчас вносится только первая часть
+                    let parts = self.new_payment_installments.max(1);
+                    let payment = EnrollmentPayment {
+                        date: today,
+                        payment_type: self.new_payment_type.clone(),
+                        course_id,
+                        total: amount,
+                        first_payment: (amount / parts as f64 * 100.0).round() / 100.0,
+                        installments: parts,
+                        discount,
+                        discount_note: discount_note(&discounts),
+                        promo_code_id: self.new_payment_promo.as_ref().map(|p| p.id),
+                    };
+
+                    Task::perform(
+                        async move {
+                            let mut conn =
+                                Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+
+                            // Промокод, зачисление, платёж, документы и рассрочка — одной транзакцией
+                            db::enroll_with_payment(&mut conn, student_id, group_id, &payment)?;
+                            Ok(())
+                        },
+                        Message::PaymentAdded,
+                    )
+                } else {
+                    eprintln!("Не все поля для нового платежа заполнены.");
+                    Task::none()
+                }
+            }
+            Message::PaymentAdded(Ok(_)) => {
+                println!("Платеж успешно добавлен.");
+                self.show_add_payment_modal = false;
+                self.reset_new_payment_form();
+                self.error_message = "".to_string();
+                Task::batch(vec![
+                    Task::perform(
+                        async {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::get_all_payments_with_details(&conn).map_err(|e| e.to_string())
+                        },
+                        Message::PaymentsFetched,
+                    ),
+                    Task::perform(
+                        async {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::get_students_not_in_any_group(&conn) // Перезагружаем этот список
+                                .map_err(|e| e.to_string())
+                        },
+                        Message::StudentsWithoutGroupFetched,
+                    ),
+                    Task::perform(
+                        async {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::get_courses_with_available_seats(&conn) // Перезагружаем курсы (места)
+                                .map_err(|e| e.to_string())
+                        },
+                        Message::CoursesWithSeatsFetched,
+                    ),
+                    Task::perform(
+                        async {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+
+                            db::get_all_groups(&conn).map_err(|e| e.to_string())
+                        },
+                        Message::GroupsFetched, // Это новое сообщение
+                    ),
+                    self.load_balances(),
+                    self.load_payment_documents(),
+                ])
+            }
+            Message::GroupsFetched(Ok(groups)) => {
+                self.all_groups = groups;
+                Task::none()
+            }
+            Message::GroupsFetched(Err(e)) => {
+                eprintln!("Ошибка загрузки всех групп: {}", e);
+                Task::none()
+            }
+            Message::PaymentAdded(Err(e)) => {
+                eprintln!("Ошибка добавления платежа: {}", e);
+                self.new_payment_error = Some(e);
+                Task::none()
+            }
+            Message::DeletePayment(payment_id) => {
+                let mut conn = match Connection::open(PATH_TO_DB) {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        self.payment_error = Some(format!("Не удалось открыть БД: {}", e));
+                        return Task::none();
+                    }
+                };
+
+                if let Err(err) = db::delete_payment(&mut conn, payment_id, self.payment_lock_days) {
+                    eprintln!("Ошибка удаления платежа: {}", err);
+                    self.payment_error = Some(err);
+                    Task::none()
+                } else {
+                    self.payment_error = None;
+                    // Загружаем обновлённый список платежей асинхронно
+                    Task::batch(vec![
+                        Task::perform(
+                            async {
+                                let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                                db::load_payments(&conn).map_err(|e| e.to_string())
+                            },
+                            |result| match result {
+                                Ok(payments) => Message::PaymentsUpdated(payments),
+                                Err(e) => {
+                                    eprintln!("Ошибка загрузки платежей после удаления: {}", e);
+                                    Message::NoOp
+                                }
+                            },
+                        ),
+                        self.load_balances(),
+                        self.load_payment_documents(),
+                    ])
+                }
+            }
+            Message::PaymentsUpdated(new_list) => {
+                self.payments = new_list;
+                Task::none()
+            }
+            Message::BalancesLoaded(Ok(balances)) => {
+                // Открытый график платежей показывает свежие данные
+                if let Some(open) = &self.installment_balance {
+                    self.installment_balance = balances
+                        .iter()
+                        .find(|b| b.enrollment_id == open.enrollment_id)
+                        .cloned();
+                }
+                self.balances = balances;
+                Task::none()
+            }
+            Message::BalancesLoaded(Err(e)) => {
+                eprintln!("Ошибка загрузки балансов студентов: {}", e);
+                self.error_message = e;
+                Task::none()
+            }
+            Message::ToggleBalances => {
+                self.show_balances = !self.show_balances;
+                Task::none()
+            }
+            Message::DebtorsOnlyToggled(value) => {
+                self.show_debtors_only = value;
+                Task::none()
+            }
+            Message::OpenSettleBalance(balance) => {
+                // По умолчанию предлагаем закрыть долг, а при рассрочке без просрочки — ближайший взнос
+                let suggested = if balance.is_debtor() {
+                    balance.debt_amount()
+                } else {
+                    balance
+                        .next_installment()
+                        .map_or(balance.outstanding(), |next| next.remaining())
+                };
+                self.settle_amount = format!("{:.2}", suggested.max(0.0));
+                self.settle_payment_type = PAYMENT_TYPES.first().map(|t| t.to_string());
+                self.settle_error = None;
+                self.settle_balance = Some(balance);
+                Task::none()
+            }
+            Message::CloseSettleBalance => {
+                self.settle_balance = None;
+                self.settle_error = None;
+                Task::none()
+            }
+            Message::SettleAmountChanged(value) => {
+                self.settle_amount = value;
+                self.settle_error = None;
+                Task::none()
+            }
+            Message::SettlePaymentTypeSelected(payment_type) => {
+                self.settle_payment_type = Some(payment_type);
+                Task::none()
+            }
+            Message::ConfirmSettleBalance => {
+                let Some(balance) = self.settle_balance.clone() else {
+                    return Task::none();
+                };
+                let Some(course_id) = balance.course_id else {
+                    self.settle_error = Some("У группы не указан курс — платёж не к чему привязать.".to_string());
+                    return Task::none();
+                };
+                let amount = match self.settle_amount.trim().replace(',', ".").parse::<f64>() {
+                    Ok(amount) if amount > 0.0 => amount,
+                    _ => {
+                        self.settle_error = Some("Введите сумму больше нуля.".to_string());
+                        return Task::none();
+                    }
+                };
+                let Some(payment_type) = self.settle_payment_type.clone() else {
+                    self.settle_error = Some("Выберите тип платежа.".to_string());
+                    return Task::none();
+                };
+                let current_date = Local::now().format("%Y-%m-%d").to_string();
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            let payment_id = db::add_payment(
+                                &conn,
+                                balance.student_id,
+                                &current_date,
+                                amount,
+                                &payment_type,
+                                course_id,
+                                balance.group_id,
+                            )
+                            .map_err(|e| format!("Не удалось сохранить платёж: {}", e))?;
+                            db::issue_payment_documents(&mut conn, payment_id as i32)
+                                .map_err(|e| format!("Не удалось выписать документы по платежу: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (оплата задолженности) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::BalanceSettled,
+                )
+            }
+            Message::OpenPaymentReversal(payment, kind) => {
+                // Возврат по умолчанию — на весь остаток платежа
+                self.reversal_amount = match kind {
+                    ReversalKind::Refund => format!("{:.2}", payment.net_amount(&self.payments)),
+                    ReversalKind::Correction => "".to_string(),
+                };
+                self.reversal_kind = kind;
+                self.reversal_reason = "".to_string();
+                self.reversal_error = None;
+                self.reversal_payment = Some(payment);
+                Task::none()
+            }
+            Message::ClosePaymentReversal => {
+                self.reversal_payment = None;
+                self.reversal_error = None;
+                Task::none()
+            }
+            Message::ReversalAmountChanged(value) => {
+                self.reversal_amount = value;
+                self.reversal_error = None;
+                Task::none()
+            }
+            Message::ReversalReasonChanged(value) => {
+                self.reversal_reason = value;
+                self.reversal_error = None;
+                Task::none()
+            }
+            Message::ConfirmPaymentReversal => {
+                let Some(payment) = self.reversal_payment.clone() else {
+                    return Task::none();
+                };
+                let reason = self.reversal_reason.trim().to_string();
+                if reason.is_empty() {
+                    self.reversal_error = Some("Укажите причину.".to_string());
+                    return Task::none();
+                }
+                let Ok(value) = self.reversal_amount.trim().replace(',', ".").parse::<f64>() else {
+                    self.reversal_error = Some("Некорректная сумма.".to_string());
+                    return Task::none();
+                };
+                let net = payment.net_amount(&self.payments);
+                let kind = self.reversal_kind;
+                // Возврат вводится положительным числом и сохраняется с минусом
+                let amount = match kind {
+                    ReversalKind::Refund => {
+                        if value <= 0.0 {
+                            self.reversal_error = Some("Сумма возврата должна быть больше нуля.".to_string());
+                            return Task::none();
+                        }
+                        if value > net + 0.005 {
+                            self.reversal_error =
+                                Some(format!("Можно вернуть не больше {:.2} ₽.", net));
+                            return Task::none();
+                        }
+                        -value
+                    }
+                    ReversalKind::Correction => {
+                        if value.abs() < 0.005 {
+                            self.reversal_error = Some("Корректировка не может быть нулевой.".to_string());
+                            return Task::none();
+                        }
+                        if net + value < -0.005 {
+                            self.reversal_error = Some(format!(
+                                "После корректировки сумма платежа станет отрицательной (сейчас {:.2} ₽).",
+                                net
+                            ));
+                            return Task::none();
+                        }
+                        value
+                    }
+                };
+                let operator_id = self.current_user.as_ref().map(|u| u.id);
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::add_payment_reversal(
+                                &conn,
+                                payment.id,
+                                amount,
+                                kind.payment_type(),
+                                &reason,
+                                operator_id,
+                            )
+                            .map_err(|e| format!("Не удалось сохранить запись: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (возврат платежа) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::PaymentReversed,
+                )
+            }
+            Message::PaymentReversed(result) => match result {
+                Ok(()) => {
+                    self.reversal_payment = None;
+                    self.reversal_error = None;
+                    self.payment_error = None;
+                    Task::batch(vec![
+                        Task::perform(
+                            async {
+                                let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                                db::get_all_payments_with_details(&conn).map_err(|e| e.to_string())
+                            },
+                            Message::PaymentsFetched,
+                        ),
+                        self.load_balances(),
+                    ])
+                }
+                Err(e) => {
+                    self.reversal_error = Some(e);
+                    Task::none()
+                }
+            },
+            Message::PaymentDocumentsLoaded(result) => {
+                match result {
+                    Ok(documents) => self.payment_documents = documents,
+                    Err(e) => eprintln!("Ошибка загрузки счетов и квитанций: {}", e),
+                }
+                Task::none()
+            }
+            Message::GeneratePaymentDocument(payment, kind) => {
+                self.payment_error = None;
+                self.payment_notice = Some(format!("Формирование документа «{}»...", kind.title()));
+                let organization = self.organization.clone();
+                let backend = self.selected_pdf_backend();
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            // Для платежей, внесённых до появления документов, номер выписывается сейчас
+                            db::issue_payment_documents(&mut conn, payment.id)
+                                .map_err(|e| format!("Не удалось выписать документ: {}", e))?;
+                            let document = db::get_payment_document(&conn, payment.id, kind)
+                                .map_err(|e| format!("Документ не найден: {}", e))?;
+                            generate_payment_document_pdf(&document, &payment, &organization, &backend)
+                                .map_err(|e| format!("Ошибка генерации PDF: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (формирование документа) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::PaymentDocumentGenerated,
+                )
+            }
+            Message::PaymentDocumentGenerated(result) => {
+                match result {
+                    Ok(path) => {
+                        println!("DEBUG: Документ по платежу сохранён: {}", path.display());
+                        self.payment_notice = Some(format!("Документ сохранён: {}", path.display()));
+                        if let Err(e) = open::that(&path) {
+                            eprintln!("Ошибка при открытии PDF: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        self.payment_notice = None;
+                        self.payment_error = Some(e);
+                    }
+                }
+                self.load_payment_documents()
+            }
+            Message::ImportBankStatement => {
+                let Some(path) = FileDialog::new()
+                    .add_filter("Выписка (CSV, 1С)", &["csv", "txt"])
+                    .pick_file()
+                else {
+                    return Task::none();
+                };
+                self.show_bank_import = true;
+                self.bank_import_file = path.display().to_string();
+                self.bank_import_lines.clear();
+                self.bank_import_error = None;
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let bytes = std::fs::read(&path)
+                                .map_err(|e| format!("Не удалось прочитать файл выписки: {}", e))?;
+                            let transactions = bank_import::parse_statement(&bytes)?;
+
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            let balances = db::get_student_balances(&conn, None)
+                                .map_err(|e| format!("Не удалось рассчитать балансы студентов: {}", e))?;
+                            let parents = db::get_parent_names(&conn).map_err(|e| e.to_string())?;
+                            let references = db::get_bank_references(&conn).map_err(|e| e.to_string())?;
+
+                            let lines = bank_import::match_transactions(
+                                transactions,
+                                &balances,
+                                &parents,
+                                &references,
+                            );
+                            let options = balances
+                                .iter()
+                                .filter_map(EnrollmentPickListItem::from_balance)
+                                .collect();
+                            Ok((lines, options))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (разбор выписки) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::BankStatementLoaded,
+                )
+            }
+            Message::BankStatementLoaded(result) => {
+                match result {
+                    Ok((lines, options)) => {
+                        if lines.is_empty() {
+                            self.bank_import_error = Some("В выписке нет поступлений.".to_string());
+                        }
+                        self.bank_import_lines = lines;
+                        self.bank_import_options = options;
+                    }
+                    Err(e) => self.bank_import_error = Some(e),
+                }
+                Task::none()
+            }
+            Message::BankLineTargetSelected(index, target) => {
+                if let Some(line) = self.bank_import_lines.get_mut(index) {
+                    line.target = Some(target);
+                    line.include = true;
+                }
+                Task::none()
+            }
+            Message::BankLineToggled(index, include) => {
+                if let Some(line) = self.bank_import_lines.get_mut(index) {
+                    line.include = include && line.target.is_some();
+                }
+                Task::none()
+            }
+            Message::ConfirmBankImport => {
+                let items: Vec<(EnrollmentPickListItem, BankTransaction)> = self
+                    .bank_import_lines
+                    .iter()
+                    .filter(|line| line.include && line.status.is_importable())
+                    .filter_map(|line| Some((line.target.clone()?, line.transaction.clone())))
+                    .collect();
+                if items.is_empty() {
+                    self.bank_import_error = Some("Не выбрано ни одной строки для загрузки.".to_string());
+                    return Task::none();
+                }
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            let payment_ids = db::import_bank_payments(&mut conn, &items)
+                                .map_err(|e| format!("Не удалось создать платежи: {}", e))?;
+                            for payment_id in &payment_ids {
+                                db::issue_payment_documents(&mut conn, *payment_id)
+                                    .map_err(|e| format!("Не удалось выписать документы по платежу: {}", e))?;
+                            }
+                            Ok(payment_ids.len())
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (загрузка выписки) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::BankImportFinished,
+                )
+            }
+            Message::BankImportFinished(result) => match result {
+                Ok(count) => {
+                    self.show_bank_import = false;
+                    self.bank_import_lines.clear();
+                    self.payment_error = None;
+                    self.payment_notice = Some(format!("Из выписки загружено платежей: {}", count));
+                    self.update(Message::GoToPayment)
+                }
+                Err(e) => {
+                    self.bank_import_error = Some(e);
+                    Task::none()
+                }
+            },
+            Message::CloseBankImport => {
+                self.show_bank_import = false;
+                self.bank_import_lines.clear();
+                self.bank_import_error = None;
+                Task::none()
+            }
+            Message::WaitlistLoaded(result) => {
+                match result {
+                    Ok(entries) => {
+                        println!("DEBUG: Лист ожидания загружен: {} записей", entries.len());
+                        self.waitlist = entries;
+                    }
+                    Err(e) => self.waitlist_error = Some(e),
+                }
+                Task::none()
+            }
+            Message::OpenWaitlist(course) => {
+                self.waitlist_course = Some(course);
+                self.waitlist_error = None;
+                self.load_waitlist()
+            }
+            Message::CloseWaitlist => {
+                self.waitlist_course = None;
+                self.waitlist_error = None;
+                Task::none()
+            }
+            Message::AddToWaitlist => {
+                let (Some(student), Some(course)) = (&self.new_payment_student, &self.new_payment_course) else {
+                    self.new_payment_error = Some("Выберите студента и курс.".to_string());
+                    return Task::none();
+                };
+                let student_id = student.id;
+                let course_id = course.id;
+                let group_id = self.new_payment_group.as_ref().map(|g| g.id);
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::add_to_waitlist(&conn, student_id, course_id, group_id)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (запись в лист ожидания) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::WaitlistEntryAdded,
+                )
+            }
+            Message::WaitlistEntryAdded(result) => match result {
+                Ok(()) => {
+                    self.show_add_payment_modal = false;
+                    self.payment_notice = Some("Студент записан в лист ожидания курса.".to_string());
+                    self.reset_new_payment_form();
+                    self.load_waitlist()
+                }
+                Err(e) => {
+                    self.new_payment_error = Some(e);
+                    Task::none()
+                }
+            },
+            Message::CloseWaitlistEntry(entry_id) => {
+                self.waitlist_error = None;
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::close_waitlist_entry(&mut conn, entry_id)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (снятие с листа ожидания) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::WaitlistEntryClosed,
+                )
+            }
+            Message::WaitlistEntryClosed(result) => {
+                if let Err(e) = result {
+                    self.waitlist_error = Some(e);
+                }
+                self.load_waitlist()
+            }
+            Message::EnrollFromWaitlist(entry) => {
+                // Зачисление оформляется обычным платежом: открываем форму с выбранным студентом,
+                // курс и группа подставятся после загрузки списков
+                self.waitlist_course = None;
+                let go_to_payment = self.update(Message::GoToPayment);
+                self.show_add_payment_modal = false;
+                let open_form = self.update(Message::ToggleAddPaymentModal);
+                let student_item = StudentPickListItem {
+                    id: entry.student_id,
+                    name: entry.student_name.clone(),
+                };
+                self.waitlist_enrollment = Some(entry);
+                Task::batch(vec![
+                    go_to_payment,
+                    open_form,
+                    self.update(Message::NewPaymentFormStudentSelected(student_item)),
+                ])
+            }
+            Message::WaitlistOfferDaysSelected(days) => {
+                self.waitlist_offer_days = days;
+                self.save_settings();
                 Task::none()
             }
-            Message::EditingAssignmentDescriptionChanged(input) => {
-                match input {
-                    TextInputOrEditorInput::TextEditor(action) => {
-                        // Если пришло действие из TextEditor, примените его к TextEditor контенту
-                        self.editing_assignment_description_content.perform(action);
-                        Task::none()
-                        // Опционально: если вам нужно, чтобы String поле всегда отражало TextEditor, синхронизируйте их:
-                        // self.editing_assignment_description_text_input = self.editing_assignment_description_content.text();
+            Message::PayrollLoaded(result) => {
+                match result {
+                    Ok(payroll) => {
+                        println!(
+                            "DEBUG: Ведомость за {} загружена, строк: {}",
+                            payroll.month,
+                            payroll.lines.len()
+                        );
+                        self.payroll = Some(payroll);
                     }
-                    TextInputOrEditorInput::TextInput(text) => {
-                        // Если пришла строка из TextInput, обновите String поле
-                        self.editing_assignment_description_text_input = text;
-                        Task::none()
-                        // Опционально: если вам нужно, чтобы TextEditor контент отражал String поле (например, при переключении типов), синхронизируйте их:
-                        // self.editing_assignment_description_content = text_editor::Content::with_text(&text); // Осторожно: это сбрасывает историю действий TextEditor
+                    Err(e) => self.payroll_error = Some(e),
+                }
+                Task::none()
+            }
+            Message::TeacherRatesLoaded(result) => {
+                match result {
+                    Ok(rates) => {
+                        // Выбранный преподаватель остаётся выбранным, но с обновлённой ставкой
+                        if let Some(selected) = &self.rate_teacher {
+                            self.rate_teacher = rates.iter().find(|r| r.teacher_id == selected.teacher_id).cloned();
+                        }
+                        self.teacher_rates = rates;
                     }
+                    Err(e) => self.payroll_error = Some(e),
                 }
+                Task::none()
             }
-            Message::EditingAssignmentTitleChanged(title) => {
-                self.editing_assignment_title = title;
+            Message::PayrollPrevMonth => {
+                if let Some(month) = self.payroll_month.checked_sub_months(Months::new(1)) {
+                    self.payroll_month = month;
+                }
+                self.payroll_confirm_approve = false;
+                self.payroll_notice = None;
+                self.payroll_error = None;
+                self.load_payroll()
+            }
+            Message::PayrollNextMonth => {
+                if let Some(month) = self.payroll_month.checked_add_months(Months::new(1)) {
+                    self.payroll_month = month;
+                }
+                self.payroll_confirm_approve = false;
+                self.payroll_notice = None;
+                self.payroll_error = None;
+                self.load_payroll()
+            }
+            Message::RateTeacherSelected(teacher) => {
+                self.rate_type = teacher.rate_type;
+                self.rate_value = if teacher.rate_type.is_some() {
+                    teacher.rate.to_string()
+                } else {
+                    "".to_string()
+                };
+                self.rate_teacher = Some(teacher);
                 Task::none()
             }
-            Message::SaveEditedAssignment => {
-                let conn = Connection::open(PATH_TO_DB).unwrap();
-                if let Some(selected_assignment) = &self.selected_assignment_for_detail {
-                    if self.editing_assignment_title.is_empty() {
-                        self.assignment_edit_error_message =
-                            Some("Название задания не может быть пустым.".to_string());
-                        return Task::none(); // <-- Правильный возврат!
+            Message::RateTypeSelected(rate_type) => {
+                self.rate_type = Some(rate_type);
+                Task::none()
+            }
+            Message::RateValueChanged(value) => {
+                self.rate_value = value;
+                Task::none()
+            }
+            Message::SaveTeacherRate => {
+                self.payroll_error = None;
+                let (Some(teacher), Some(rate_type)) = (&self.rate_teacher, self.rate_type) else {
+                    self.payroll_error = Some("Выберите преподавателя и вид ставки.".to_string());
+                    return Task::none();
+                };
+                let rate = match self.rate_value.trim().replace(',', ".").parse::<f64>() {
+                    Ok(rate) if rate > 0.0 => rate,
+                    _ => {
+                        self.payroll_error = Some("Ставка должна быть положительным числом.".to_string());
+                        return Task::none();
                     }
+                };
+                if rate_type == RateType::RevenuePercent && rate > 100.0 {
+                    self.payroll_error = Some("Процент от выручки не может быть больше 100.".to_string());
+                    return Task::none();
+                }
+                let teacher_id = teacher.teacher_id;
 
-                    let description_to_save = if selected_assignment.assignment_type
-                        == AssignmentType::Lecture.to_string()
-                        || selected_assignment.assignment_type
-                            == AssignmentType::Practice.to_string()
-                    {
-                        self.editing_assignment_description_content.text()
-                    } else {
-                        self.editing_assignment_description_text_input.clone()
-                    };
-
-                    let updated_assignment = Assignment {
-                        id: selected_assignment.id,
-                        lesson_id: selected_assignment.lesson_id,
-                        title: self.editing_assignment_title.clone(),
-                        description: description_to_save,
-                        assignment_type: selected_assignment.assignment_type.clone(),
-                    };
-
-                    match db::update_assignment(&conn, &updated_assignment) {
-                        Ok(_) => {
-                            self.selected_assignment_for_detail = Some(updated_assignment.clone());
-                            self.assignment_edit_error_message = None;
-                            self.show_assignment_detail_modal = false;
-
-                            // Важно: Обновить список заданий, чтобы изменения отобразились.
-                            // Возвращаем Task, чтобы выполнить асинхронную операцию
-                            if self.show_assignments_modal {
-                                if let Some(lesson) = &self.current_lesson_for_assignments {
-                                    let lesson_id_clone = lesson.id;
-                                    return Task::perform(
-                                        async move {
-                                            // <-- Асинхронный блок - это Future, передаваемый в Task::perform
-                                            // Этот фьючер spawn_blocking сам по себе возвращает Result<Result<Vec<Assignment>, String>, JoinError>
-                                            let blocking_result = spawn_blocking(move || {
-                                                let conn_task = Connection::open(PATH_TO_DB)
-                                                    .map_err(|e| format!("Не удалось открыть БД для загрузки заданий: {}", e))?;
-                                                db::get_assignments_for_lesson(&conn_task, lesson_id_clone)
-                                                    .map_err(|e| format!("Ошибка загрузки заданий: {}", e))
-                                            }).await; // <-- Ожидаем завершения блокирующей задачи, чтобы получить ее Result<T, JoinError>
-
-                                            // Теперь разворачиваем внешний Result от spawn_blocking.
-                                            // Если spawn_blocking сам по себе завершился с ошибкой (например, паникой),
-                                            // преобразуем JoinError в String.
-                                            // В противном случае у нас есть внутренний Result<Vec<Assignment>, String>.
-                                            blocking_result.unwrap_or_else(|join_err| {
-                                                // Обрабатываем JoinError (например, если блокирующая задача запаниковала)
-                                                eprintln!("Блокирующая задача запаниковала или была отменена: {:?}", join_err);
-                                                Err(format!("Ошибка выполнения операции: {}", join_err))
-                                            })
-                                        },
-                                        Message::AssignmentsLoaded, // <-- Теперь это сообщение корректно ожидает Result<Vec<Assignment>, String>
-                                    );
-                                }
-                            }
-                            Task::none()
-                        }
-                        Err(e) => {
-                            self.assignment_edit_error_message =
-                                Some(format!("Ошибка сохранения задания: {}", e));
-                            Task::none()
-                        }
-                    }
-                } else {
-                    eprintln!("Попытка сохранить детали задания, но задание не выбрано.");
-                    self.assignment_edit_error_message =
-                        Some("Ошибка: Не выбрано задание для сохранения.".to_string());
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::set_teacher_rate(&conn, teacher_id, rate_type, rate)
+                                .map_err(|e| format!("Не удалось сохранить ставку: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (сохранение ставки) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::TeacherRateSaved,
+                )
+            }
+            Message::TeacherRateSaved(result) => match result {
+                Ok(()) => {
+                    self.payroll_notice = Some("Ставка сохранена.".to_string());
+                    Task::batch(vec![self.load_teacher_rates(), self.load_payroll()])
+                }
+                Err(e) => {
+                    self.payroll_error = Some(e);
                     Task::none()
                 }
+            },
+            Message::ApprovePayroll => {
+                self.payroll_error = None;
+                self.payroll_confirm_approve = true;
+                Task::none()
             }
-            Message::AssignmentsLoaded(result) => {
-                match result {
-                    Ok(assignments) => {
-                        self.lesson_assignments = assignments;
-                        self.assignment_error_message = None; // Очищаем ошибки после успешной загрузки
-                    }
-                    Err(e) => {
-                        self.assignment_error_message =
-                            Some(format!("Ошибка загрузки заданий: {}", e));
-                    }
-                }
+            Message::CancelPayrollApproval => {
+                self.payroll_confirm_approve = false;
                 Task::none()
             }
-            Message::LoadTeacherGroups(teacher_id_to_load) => {
-                println!(
-                    "DEBUG: Запущена асинхронная загрузка групп для преподавателя ID: {}",
-                    teacher_id_to_load
-                );
+            Message::ConfirmPayrollApproval => {
+                self.payroll_confirm_approve = false;
+                let Some(approver_id) = self.current_user.as_ref().map(|u| u.id) else {
+                    return Task::none();
+                };
+                let month = self.payroll_month.format("%Y-%m").to_string();
+
                 Task::perform(
                     async move {
-                        // Вызываем `spawn_blocking` напрямую из `task`
                         spawn_blocking(move || {
-                            // <-- Меняем на `task::spawn_blocking`
-                            let conn = Connection::open(PATH_TO_DB)
-                                .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
-                            db::get_teacher_groups_with_details(&conn, teacher_id_to_load)
-                                .map_err(|e| format!("Ошибка загрузки групп из БД: {}", e))
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::approve_payroll(&mut conn, &month, approver_id)
                         })
                         .await
                         .unwrap_or_else(|join_err| {
                             Err(format!(
-                                "Ошибка выполнения блокирующей задачи: {:?}",
+                                "Блокирующая задача (утверждение ведомости) завершилась ошибкой: {:?}",
                                 join_err
                             ))
                         })
                     },
-                    Message::TeacherGroupsLoaded,
+                    Message::PayrollApproved,
                 )
             }
-            Message::LoadAllGroups => {
-                println!("DEBUG: Запущена асинхронная загрузка ВСЕХ групп (для администратора).");
+            Message::PayrollApproved(result) => match result {
+                Ok(()) => {
+                    self.payroll_notice = Some("Ведомость утверждена, период закрыт.".to_string());
+                    self.load_payroll()
+                }
+                Err(e) => {
+                    self.payroll_error = Some(e);
+                    Task::none()
+                }
+            },
+            Message::ExportPayroll(report_type) => {
+                let Some(payroll) = self.payroll.clone() else {
+                    return Task::none();
+                };
+                self.payroll_error = None;
+                self.payroll_notice = Some("Формирование ведомости...".to_string());
+                let organization = self.organization.clone();
+                let backend = self.selected_pdf_backend();
+
                 Task::perform(
                     async move {
-                        spawn_blocking(move || {
-                            let conn = Connection::open(PATH_TO_DB).map_err(|e| {
-                                format!("Не удалось открыть БД для загрузки ВСЕХ групп: {}", e)
-                            })?;
-                            db::get_all_groups(&conn) // <--- ВЫЗЫВАЕМ НОВУЮ ФУНКЦИЮ
-                                .map_err(|e| format!("Ошибка загрузки ВСЕХ групп из БД: {}", e))
+                        spawn_blocking(move || match report_type {
+                            ReportType::PDF => generate_payroll_pdf(&payroll, &organization, &backend)
+                                .map_err(|e| format!("Ошибка генерации PDF: {}", e)),
+                            ReportType::Excel => generate_payroll_excel(&payroll)
+                                .map_err(|e| format!("Ошибка генерации Excel: {}", e)),
                         })
                         .await
                         .unwrap_or_else(|join_err| {
                             Err(format!(
-                                "Ошибка выполнения блокирующей задачи загрузки ВСЕХ групп: {:?}",
+                                "Блокирующая задача (формирование ведомости) завершилась ошибкой: {:?}",
                                 join_err
                             ))
                         })
                     },
-                    Message::AllGroupsLoaded, // <--- ИСПОЛЬЗУЕМ НОВОЕ СООБЩЕНИЕ
+                    Message::PayrollExported,
                 )
             }
-            Message::AllGroupsLoaded(result) => {
-                // <--- НОВЫЙ ОБРАБОТЧИК
+            Message::PayrollExported(result) => {
                 match result {
-                    Ok(groups) => {
-                        self.all_groups = groups; // Обновляем новое поле
-                        self.group_error_message = None; // Очищаем ошибку, если она была
-                        println!(
-                            "DEBUG: AllGroupsLoaded успешно. Загружено ВСЕХ групп: {} шт.",
-                            self.all_groups.len()
-                        );
+                    Ok(path) => {
+                        self.payroll_notice = Some(format!("Ведомость сохранена: {}", path.display()));
+                        if let Err(e) = open::that(&path) {
+                            eprintln!("Ошибка при открытии ведомости: {}", e);
+                        }
                     }
                     Err(e) => {
-                        self.group_error_message =
-                            Some(format!("Ошибка загрузки ВСЕХ групп: {}", e));
-                        eprintln!("Ошибка загрузки ВСЕХ групп для администратора: {}", e);
+                        self.payroll_notice = None;
+                        self.payroll_error = Some(e);
                     }
                 }
                 Task::none()
             }
-            Message::TeacherGroupsLoaded(result) => {
+            Message::TogglePermissionsModal(show) => {
+                self.show_permissions_modal = show;
+                Task::none()
+            }
+            Message::TogglePermission(role, permission, allowed) => {
+                if permission.is_locked_for(role) {
+                    return Task::none();
+                }
+                let result = Connection::open(PATH_TO_DB)
+                    .and_then(|conn| db::set_role_permission(&conn, role, permission, allowed));
+                if let Err(e) = result {
+                    self.permissions_error = Some(format!("Не удалось сохранить права доступа: {}", e));
+                    return Task::none();
+                }
+                self.reload_permissions();
+                Task::none()
+            }
+            Message::DismissAccessDenied => {
+                self.access_denied = None;
+                Task::none()
+            }
+            Message::RegistrationRequestsLoaded(result) => {
                 match result {
-                    Ok(groups) => {
-                        println!(
-                            "DEBUG: TeacherGroupsLoaded успешно. Загружено групп: {}",
-                            groups.len()
-                        );
-                        self.teacher_groups = groups; // <-- Теперь self.teacher_groups будет содержать либо группы преподавателя, либо ВСЕ группы
-                        println!(
-                            "DEBUG: Группы успешно загружены: {} шт.",
-                            self.teacher_groups.len()
-                        );
-                    }
-                    Err(e) => {
-                        eprintln!("ERROR: Не удалось загрузить группы: {}", e);
-                        self.error_message = e.to_string();
+                    Ok((requests, parents)) => {
+                        self.registration_requests = requests;
+                        self.registration_parents = parents;
                     }
+                    Err(e) => self.review_error = Some(e),
+                }
+                Task::none()
+            }
+            Message::OpenRegistrationReview(user) => {
+                self.reviewing_registration = Some(user);
+                self.review_role = None;
+                self.review_parent = None;
+                self.review_group = None;
+                self.review_reason.clear();
+                self.review_error = None;
+                Task::none()
+            }
+            Message::CloseRegistrationReview => {
+                self.reviewing_registration = None;
+                self.review_error = None;
+                Task::none()
+            }
+            Message::ReviewRoleSelected(role) => {
+                self.review_role = Some(role);
+                // Родитель и группа указываются только для студента
+                if role != Role::Student {
+                    self.review_parent = None;
+                    self.review_group = None;
                 }
                 Task::none()
             }
-            Message::OpenGroupLessonsModal(group_id, course_id) => {
-                self.show_group_lessons_modal = true;
-                self.group_lessons_modal_lessons.clear();
-                self.group_lessons_modal_past_sessions.clear();
-
-                // Ищем название группы для заголовка модального окна
-                if let Some(group_found) = self.all_groups.iter().find(|g| g.id == group_id) {
-                    self.group_lessons_modal_group_name = group_found.name.clone();
-                } else {
-                    self.group_lessons_modal_group_name = "Неизвестная группа".to_string();
+            Message::ReviewParentSelected(parent) => {
+                self.review_parent = Some(parent);
+                Task::none()
+            }
+            Message::ReviewGroupSelected(group) => {
+                self.review_group = Some(group);
+                Task::none()
+            }
+            Message::ReviewReasonChanged(value) => {
+                self.review_reason = value;
+                Task::none()
+            }
+            Message::ApproveRegistration => {
+                let (Some(applicant), Some(admin)) = (&self.reviewing_registration, &self.current_user) else {
+                    return Task::none();
+                };
+                let Some(role) = self.review_role else {
+                    self.review_error = Some("Выберите роль для нового пользователя.".to_string());
+                    return Task::none();
+                };
+                let user_id = applicant.id;
+                let admin_id = admin.id;
+                let parent_id = self.review_parent.as_ref().map(|p| p.id);
+                let group_id = self.review_group.as_ref().map(|g| g.id);
+                let reason = Some(self.review_reason.trim().to_string()).filter(|r| !r.is_empty());
+                self.review_error = None;
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::approve_registration(&mut conn, user_id, role, parent_id, group_id, reason.as_deref(), admin_id)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (подтверждение регистрации) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::RegistrationReviewed,
+                )
+            }
+            Message::RejectRegistration => {
+                let (Some(applicant), Some(admin)) = (&self.reviewing_registration, &self.current_user) else {
+                    return Task::none();
+                };
+                let reason = self.review_reason.trim().to_string();
+                if reason.is_empty() {
+                    self.review_error = Some("Укажите причину отказа — её увидит заявитель.".to_string());
+                    return Task::none();
                 }
+                let user_id = applicant.id;
+                let admin_id = admin.id;
+                self.review_error = None;
 
                 Task::perform(
                     async move {
-                        let result = spawn_blocking(move || {
-                            let conn = Connection::open(PATH_TO_DB).map_err(|e| {
-                                format!("Не удалось открыть БД для загрузки занятий группы: {}", e)
-                            })?;
-
-                            // Загружаем уроки, которые ЕЩЕ НЕ ПРОВЕДЕНЫ для этой группы
-                            let available_lessons =
-                                db::get_lessons_for_course_and_group(&conn, course_id, group_id)
-                                    .map_err(|e| {
-                                        format!("Ошибка загрузки доступных уроков: {}", e)
-                                    })?;
-
-                            // Загружаем уроки, которые УЖЕ ПРОВЕДЕНЫ для этой группы
-                            let past_sessions = db::get_past_sessions_for_group(&conn, group_id)
-                                .map_err(|e| format!("Ошибка загрузки прошедших занятий: {}", e))?;
-
-                            Ok((available_lessons, past_sessions))
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::reject_registration(&conn, user_id, &reason, admin_id)
                         })
-                        .await;
-
-                        // Обработка ошибок из spawn_blocking
-                        result.unwrap_or_else(|join_err| {
+                        .await
+                        .unwrap_or_else(|join_err| {
                             Err(format!(
-                                "Блокирующая задача завершилась ошибкой: {:?}",
+                                "Блокирующая задача (отклонение регистрации) завершилась ошибкой: {:?}",
                                 join_err
                             ))
                         })
                     },
-                    Message::GroupLessonsModalLoaded, // Отправляем результат в новое сообщение
+                    Message::RegistrationReviewed,
                 )
             }
-            Message::GroupLessonsModalLoaded(result) => {
+            Message::RegistrationReviewed(result) => {
                 match result {
-                    Ok((lessons, past_sessions)) => {
-                        self.group_lessons_modal_lessons = lessons;
-                        self.group_lessons_modal_past_sessions = past_sessions;
-                        Task::none() // Больше никаких задач не нужно
+                    Ok(()) => {
+                        self.reviewing_registration = None;
+                        self.review_error = None;
+                        // Зачисление в группу меняет список групп и свободные места
+                        Task::batch(vec![self.load_registration_requests(), self.update(Message::LoadAllGroups)])
                     }
                     Err(e) => {
-                        eprintln!("Ошибка загрузки занятий для модального окна: {}", e);
-                        self.error_message = e.to_string();
-                        self.show_group_lessons_modal = false; // Закрываем модальное окно при ошибке
+                        self.review_error = Some(e);
                         Task::none()
                     }
                 }
             }
-            Message::CloseGroupLessonsModal => {
-                self.show_group_lessons_modal = false;
-                self.group_lessons_modal_lessons.clear();
-                self.group_lessons_modal_past_sessions.clear();
-                self.group_lessons_modal_group_name.clear();
+            Message::OpenChangePassword => {
+                self.show_change_password_modal = true;
+                self.current_password_input.clear();
+                self.new_password_input.clear();
+                self.new_password_repeat_input.clear();
+                self.change_password_error = None;
+                self.change_password_notice = None;
                 Task::none()
             }
-            Message::ErrorOccurred(_) => Task::none(),
-            Message::PaymentsFetched(Ok(payments)) => {
-                self.payments = payments;
+            Message::CloseChangePassword => {
+                // Временный пароль нельзя оставить без смены
+                if !self.password_change_required {
+                    self.show_change_password_modal = false;
+                }
                 Task::none()
             }
-            Message::PaymentsFetched(Err(e)) => {
-                eprintln!("Ошибка загрузки платежей: {}", e);
-                // Тут можно показать ошибку пользователю
+            Message::CurrentPasswordInputChanged(value) => {
+                self.current_password_input = value;
                 Task::none()
             }
-            Message::ToggleAddPaymentModal => {
-                self.show_add_payment_modal = !self.show_add_payment_modal;
-                // При открытии модального окна загружаем необходимые данные
-                if self.show_add_payment_modal {
-                    self.reset_new_payment_form(); // Сбросить форму
-                    Task::batch(vec![
-                        Task::perform(
-                            async {
-                                // Используйте tokio::task::spawn_blocking для блокирующих DB-операций
-                                let conn =
-                                    Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
-                                db::get_students_not_in_any_group(&conn).map_err(|e| e.to_string())
-                            },
-                            Message::StudentsWithoutGroupFetched,
-                        ),
-                        Task::perform(
-                            async {
-                                let conn =
-                                    Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
-                                db::get_courses_with_available_seats(&conn)
-                                    .map_err(|e| e.to_string())
-                            },
-                            Message::CoursesWithSeatsFetched,
-                        ),
-                    ])
-                } else {
-                    Task::none()
-                }
-            }
-            Message::StudentsWithoutGroupFetched(Ok(students)) => {
-                self.students_without_group = students;
+            Message::NewPasswordInputChanged(value) => {
+                self.new_password_input = value;
                 Task::none()
             }
-            Message::StudentsWithoutGroupFetched(Err(e)) => {
-                eprintln!("Ошибка загрузки студентов без группы: {}", e);
+            Message::NewPasswordRepeatInputChanged(value) => {
+                self.new_password_repeat_input = value;
                 Task::none()
             }
-            Message::CoursesWithSeatsFetched(Ok(courses)) => {
-                self.courses_with_seats = courses;
+            Message::SubmitPasswordChange => {
+                let Some(user_id) = self.current_user.as_ref().map(|user| user.id) else {
+                    return Task::none();
+                };
+                if self.current_password_input.is_empty() {
+                    self.change_password_error = Some("Введите текущий пароль.".to_string());
+                    return Task::none();
+                }
+                if self.new_password_input != self.new_password_repeat_input {
+                    self.change_password_error = Some("Пароли не совпадают".to_string());
+                    return Task::none();
+                }
+                if let Err(e) = check_password_strength(&self.new_password_input) {
+                    self.change_password_error = Some(e);
+                    return Task::none();
+                }
+                if self.new_password_input == self.current_password_input {
+                    self.change_password_error = Some("Новый пароль должен отличаться от текущего.".to_string());
+                    return Task::none();
+                }
+                let current_password = self.current_password_input.clone();
+                let new_password = self.new_password_input.clone();
+                self.change_password_error = None;
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::change_password(&conn, user_id, &current_password, &new_password)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!("Блокирующая задача (смена пароля) завершилась ошибкой: {:?}", join_err))
+                        })
+                    },
+                    Message::PasswordChangeFinished,
+                )
+            }
+            Message::PasswordChangeFinished(result) => {
+                match result {
+                    Ok(()) => {
+                        self.password_change_required = false;
+                        self.show_change_password_modal = false;
+                        self.current_password_input.clear();
+                        self.new_password_input.clear();
+                        self.new_password_repeat_input.clear();
+                        self.change_password_notice = Some("Пароль изменён.".to_string());
+                    }
+                    Err(e) => self.change_password_error = Some(e),
+                }
                 Task::none()
             }
-            Message::CoursesWithSeatsFetched(Err(e)) => {
-                eprintln!("Ошибка загрузки курсов со свободными местами: {}", e);
+            Message::ResetUserPassword(user_id) => {
+                self.reset_password_error = None;
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            let name: String = conn
+                                .query_row("SELECT Name FROM Users WHERE ID = ?1", [user_id], |row| row.get(0))
+                                .map_err(|e| format!("Пользователь не найден: {}", e))?;
+                            db::reset_password(&conn, user_id).map(|temporary_password| (name, temporary_password))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!("Блокирующая задача (сброс пароля) завершилась ошибкой: {:?}", join_err))
+                        })
+                    },
+                    Message::UserPasswordReset,
+                )
+            }
+            Message::UserPasswordReset(result) => {
+                match result {
+                    Ok(reset) => self.temporary_password = Some(reset),
+                    Err(e) => self.reset_password_error = Some(e),
+                }
                 Task::none()
             }
-            Message::NewPaymentFormStudentSelected(selected_student_item) => {
-                self.new_payment_student = Some(selected_student_item);
+            Message::DismissTemporaryPassword => {
+                self.temporary_password = None;
                 Task::none()
             }
-            Message::NewPaymentFormCourseSelected(selected_course_item) => {
-                // Теперь мы получаем выбранный CoursePickListItem
-                self.new_payment_course = Some(selected_course_item.clone());
-
-                // Автоматически подтягиваем цену курса из выбранного элемента
-                if let Some(course_price_str) =
-                    selected_course_item.price_display.strip_suffix(" €")
-                {
-                    if let Ok(price) = course_price_str.parse::<f64>() {
-                        self.new_payment_amount = Some(price);
-                    }
-                } else if selected_course_item.price_display == "Цена не указана" {
-                    self.new_payment_amount = None; // Или 0.0, в зависимости от логики
-                }
-
-                // Загружаем группы для выбранного курса, используя его ID
+            Message::GenerateRecoveryCodes => {
+                let Some(user_id) = self.current_user.as_ref().map(|user| user.id) else {
+                    return Task::none();
+                };
+                self.change_password_error = None;
                 Task::perform(
                     async move {
-                        let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
-                        db::get_groups_by_course_id(&conn, selected_course_item.id)
-                            .map_err(|e| e.to_string())
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::replace_recovery_codes(&mut conn, user_id)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (выдача кодов восстановления) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
                     },
-                    Message::GroupsForCourseFetched,
+                    Message::RecoveryCodesGenerated,
                 )
             }
-            Message::GroupsForCourseFetched(Ok(groups)) => {
-                self.groups_for_selected_course = groups;
+            Message::RecoveryCodesGenerated(result) => {
+                match result {
+                    Ok(codes) => {
+                        self.recovery_codes_left = codes.len() as i64;
+                        self.recovery_codes = codes;
+                    }
+                    Err(e) => self.change_password_error = Some(e),
+                }
                 Task::none()
             }
-            Message::GroupsForCourseFetched(Err(e)) => {
-                eprintln!("Ошибка загрузки групп для курса: {}", e);
+            Message::CopyRecoveryCodes => iced::clipboard::write(self.recovery_codes.join("\n")),
+            Message::DismissRecoveryCodes => {
+                self.recovery_codes.clear();
                 Task::none()
             }
-            Message::NewPaymentFormGroupSelected(selected_group_item) => {
-                self.new_payment_group = Some(selected_group_item);
+            Message::SwitchToPasswordRecovery => {
+                self.current_screen = Screen::PasswordRecovery;
+                self.user_password.clear();
+                self.user_password_repeat.clear();
+                self.recovery_code_input.clear();
+                self.recovery_error = None;
                 Task::none()
             }
-            Message::NewPaymentFormTypeChanged(selected_type_string) => {
-                let payment_types_options = vec!["Карта".to_string(), "QR-Код".to_string()];
-
-                self.selected_payment_type_idx = payment_types_options
-                    .iter()
-                    .position(|s| s == &selected_type_string);
-
-                self.new_payment_type = selected_type_string;
-
-                Task::none() // Возвращаем пустую команду
+            Message::RecoveryCodeChanged(value) => {
+                self.recovery_code_input = value;
+                Task::none()
             }
-            Message::AddPaymentConfirmed => {
-                if let (Some(student), Some(course), Some(group), Some(amount)) = (
-                    &self.new_payment_student,
-                    &self.new_payment_course,
-                    &self.new_payment_group,
-                    self.new_payment_amount,
-                ) {
-                    let student_id = student.id;
-                    let course_id = course.id;
-                    let group_id = group.id;
-                    let payment_type = self.new_payment_type.clone();
-                    let current_date = Local::now().format("%Y-%m-%d").to_string();
-
-                    Task::perform(
-                        async move {
-                            let mut conn =
-                                Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
-
-                            // Добавление платежа
-                            db::add_payment(
-                                &conn,
-                                student_id,
-                                &current_date,
-                                amount,
-                                &payment_type,
-                                course_id,
-                                group_id,
-                            )
-                            .map_err(|e| e.to_string())?;
-
-                            // Добавление студента в группу
-                            db::add_student_to_group(&mut conn, student_id, group_id)
-                                .map_err(|e| e.to_string())?;
-
-                            Ok(())
-                        },
-                        Message::PaymentAdded,
-                    )
-                } else {
-                    eprintln!("Не все поля для нового платежа заполнены.");
-                    Task::none()
+            Message::SubmitPasswordRecovery => {
+                let email = self.user_email.trim().to_string();
+                if email.is_empty() || self.recovery_code_input.trim().is_empty() {
+                    self.recovery_error = Some("Введите email и код восстановления.".to_string());
+                    return Task::none();
                 }
-            }
-            Message::PaymentAdded(Ok(_)) => {
-                println!("Платеж успешно добавлен.");
-                self.show_add_payment_modal = false;
-                self.reset_new_payment_form();
-                self.error_message = "".to_string();
-                Task::batch(vec![
-                    Task::perform(
-                        async {
-                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
-                            db::get_all_payments_with_details(&conn).map_err(|e| e.to_string())
-                        },
-                        Message::PaymentsFetched,
-                    ),
-                    Task::perform(
-                        async {
-                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
-                            db::get_students_not_in_any_group(&conn) // Перезагружаем этот список
-                                .map_err(|e| e.to_string())
-                        },
-                        Message::StudentsWithoutGroupFetched,
-                    ),
-                    Task::perform(
-                        async {
-                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
-                            db::get_courses_with_available_seats(&conn) // Перезагружаем курсы (места)
-                                .map_err(|e| e.to_string())
-                        },
-                        Message::CoursesWithSeatsFetched,
-                    ),
-                    Task::perform(
-                        async {
-                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                if self.user_password != self.user_password_repeat {
+                    self.recovery_error = Some("Пароли не совпадают".to_string());
+                    return Task::none();
+                }
+                if let Err(e) = check_password_strength(&self.user_password) {
+                    self.recovery_error = Some(e);
+                    return Task::none();
+                }
+                let code = self.recovery_code_input.clone();
+                let new_password = self.user_password.clone();
+                self.recovery_error = None;
 
-                            db::get_all_groups(&conn).map_err(|e| e.to_string())
-                        },
-                        Message::GroupsFetched, // Это новое сообщение
-                    ),
-                ])
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::recover_password(&mut conn, &email, &code, &new_password)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (восстановление пароля) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::PasswordRecoveryFinished,
+                )
             }
-            Message::GroupsFetched(Ok(groups)) => {
-                self.all_groups = groups;
+            Message::PasswordRecoveryFinished(result) => {
+                match result {
+                    Ok(()) => {
+                        self.current_screen = Screen::Login;
+                        self.user_password.clear();
+                        self.user_password_repeat.clear();
+                        self.recovery_code_input.clear();
+                        self.error_message = "Пароль изменён, войдите с новым паролем.".to_string();
+                    }
+                    Err(e) => self.recovery_error = Some(e),
+                }
                 Task::none()
             }
-            Message::GroupsFetched(Err(e)) => {
-                eprintln!("Ошибка загрузки всех групп: {}", e);
+            Message::AuditLogLoaded(result) => {
+                match result {
+                    Ok((entries, actors)) => {
+                        self.audit_entries = entries;
+                        self.audit_actors = actors;
+                        self.audit_error = None;
+                    }
+                    Err(e) => self.audit_error = Some(e),
+                }
                 Task::none()
             }
-            Message::PaymentAdded(Err(e)) => {
-                eprintln!("Ошибка добавления платежа: {}", e);
+            Message::AuditActorSelected(actor) => {
+                self.audit_filter.actor = Some(actor);
+                self.load_audit_log()
+            }
+            Message::AuditEntitySelected(entity) => {
+                self.audit_filter.entity = Some(entity);
+                self.load_audit_log()
+            }
+            Message::AuditActionSelected(action) => {
+                self.audit_filter.action = Some(action);
+                self.load_audit_log()
+            }
+            Message::AuditDateFromChanged(value) => {
+                self.audit_filter.date_from = value;
                 Task::none()
             }
-            Message::DeletePayment(payment_id) => {
-                let conn = Connection::open(PATH_TO_DB).unwrap();
+            Message::AuditDateToChanged(value) => {
+                self.audit_filter.date_to = value;
+                Task::none()
+            }
+            Message::ApplyAuditFilter => {
+                let filter = &self.audit_filter;
+                for date in [&filter.date_from, &filter.date_to] {
+                    let date = date.trim();
+                    if !date.is_empty() && NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+                        self.audit_error = Some(format!("Неверная дата «{}», нужен формат ГГГГ-ММ-ДД", date));
+                        return Task::none();
+                    }
+                }
+                self.load_audit_log()
+            }
+            Message::ResetAuditFilter => {
+                self.audit_filter = Default::default();
+                self.load_audit_log()
+            }
+            Message::ExportAuditLog => {
+                let entries = self.audit_entries.clone();
+                self.audit_error = None;
+                self.audit_notice = Some("Формирование выгрузки...".to_string());
 
-                if let Err(err) = db::delete_payment(&conn, payment_id) {
-                    eprintln!("Ошибка удаления платежа: {:?}", err);
-                    Task::none()
-                } else {
-                    // Загружаем обновлённый список платежей асинхронно
-                    Task::perform(
-                        async {
-                            let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
-                            db::load_payments(&conn).map_err(|e| e.to_string())
-                        },
-                        |result| match result {
-                            Ok(payments) => Message::PaymentsUpdated(payments),
-                            Err(e) => {
-                                eprintln!("Ошибка загрузки платежей после удаления: {}", e);
-                                Message::NoOp
-                            }
-                        },
-                    )
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            generate_audit_excel(&entries).map_err(|e| format!("Ошибка генерации Excel: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (выгрузка журнала) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::AuditLogExported,
+                )
+            }
+            Message::AuditLogExported(result) => {
+                match result {
+                    Ok(path) => {
+                        self.audit_notice = Some(format!("Журнал выгружен: {}", path.display()));
+                        if let Err(e) = open::that(&path) {
+                            eprintln!("Ошибка при открытии выгрузки журнала: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        self.audit_notice = None;
+                        self.audit_error = Some(e);
+                    }
                 }
+                Task::none()
             }
-            Message::PaymentsUpdated(new_list) => {
-                self.payments = new_list;
+            Message::NewPaymentFormInstallmentsSelected(parts) => {
+                self.new_payment_installments = parts;
+                Task::none()
+            }
+            Message::OpenInstallmentSchedule(balance) => {
+                self.installment_parts = (balance.installments.len() as u32).max(1);
+                self.installment_error = None;
+                self.installment_balance = Some(balance);
                 Task::none()
             }
+            Message::CloseInstallmentSchedule => {
+                self.installment_balance = None;
+                self.installment_error = None;
+                Task::none()
+            }
+            Message::InstallmentPartsSelected(parts) => {
+                self.installment_parts = parts;
+                Task::none()
+            }
+            Message::CreateInstallmentPlan => {
+                let Some(balance) = self.installment_balance.clone() else {
+                    return Task::none();
+                };
+                if balance.charged < 0.01 {
+                    self.installment_error = Some("Стоимость обучения не указана — график не нужен.".to_string());
+                    return Task::none();
+                }
+                let parts = self.installment_parts;
+                let first_due = Local::now().date_naive();
+
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                            db::create_installment_plan(
+                                &mut conn,
+                                balance.enrollment_id,
+                                balance.charged,
+                                parts,
+                                first_due,
+                            )
+                            .map_err(|e| format!("Не удалось сохранить график платежей: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (график платежей) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::InstallmentPlanCreated,
+                )
+            }
+            Message::InstallmentPlanCreated(result) => match result {
+                Ok(()) => {
+                    self.installment_error = None;
+                    self.load_balances()
+                }
+                Err(e) => {
+                    self.installment_error = Some(e);
+                    Task::none()
+                }
+            },
+            Message::BalanceSettled(result) => match result {
+                Ok(()) => {
+                    self.settle_balance = None;
+                    self.settle_error = None;
+                    self.update(Message::GoToPayment)
+                }
+                Err(e) => {
+                    self.settle_error = Some(e);
+                    Task::none()
+                }
+            },
             Message::NoOp => Task::none(),
             Message::OpenConductLessonModal(lesson_id, group_id) => {
                 // Сохраняем контекст для модального окна
@@ -2906,6 +5314,10 @@ impl App {
                                 let past_session_id = db::add_past_session(&tx, group_id, lesson_id)
                                     .map_err(|e| format!("Ошибка добавления PastSession: {}", e))?; // <--- ИСПРАВЛЕНИЕ ЗДЕСЬ (и для других db:: вызовов)
 
+                                // Связываем проведённое занятие с запланированным по расписанию
+                                db::mark_planned_session_conducted(&tx, group_id, lesson_id, past_session_id)
+                                    .map_err(|e| format!("Ошибка обновления расписания: {}", e))?;
+
                                 // 2. Добавляем записи о посещаемости
                                 for student in students_to_save {
                                     let present_status = if student.present { "Present" } else { "Absent" };
@@ -3030,42 +5442,13 @@ impl App {
             Message::GenerateCertificatePdf(cert, student) => {
                 self.error_message = format!("Генерация сертификата для {}...", cert.course_title);
                 println!("DEBUG: Запущена генерация PDF для сертификата: {}", cert.id);
+                let backend = self.selected_pdf_backend();
 
                 Task::perform(
                     async move {
                         spawn_blocking(move || {
-                            // 1. Получаем путь к директории с исполняемым файлом
-                            let exe_dir = std::env::current_exe()
-                                .map_err(|e| {
-                                    format!("Не удалось получить путь к исполняемому файлу: {}", e)
-                                })?
-                                .parent()
-                                .ok_or("Не удалось получить директорию исполняемого файла")?
-                                .to_path_buf();
-
-                            // 2. Формируем абсолютные пути к HTML и PDF
-                            let assets_path = exe_dir.join("assets");
-                            let html_path =
-                                exe_dir.join("certificates").join("temp_certificate.html");
-                            let pdf_path =
-                                exe_dir.join("certificates").join("certificate_output.pdf");
-
-                            println!("DEBUG: HTML путь: {:?}", html_path);
-                            println!("DEBUG: PDF путь: {:?}", pdf_path);
-
-                            // 3. Генерируем HTML
-                            if let Err(e) =
-                                generate_certificate_html(&cert, &student, &html_path, &assets_path)
-                            {
-                                return Err(format!("Ошибка генерации HTML: {}", e));
-                            }
-
-                            // 4. Генерируем PDF из HTML
-                            if let Err(e) = generate_pdf_from_html(&html_path, &pdf_path) {
-                                return Err(format!("Ошибка конвертации в PDF: {}", e));
-                            }
-
-                            Ok(pdf_path)
+                            generate_certificate_pdf(&cert, &student, &backend)
+                                .map_err(|e| format!("Ошибка генерации PDF: {}", e))
                         })
                         .await
                         .unwrap_or_else(|join_err| {
@@ -3192,6 +5575,7 @@ impl App {
                             let from_str = from.format("%Y-%m-%d").to_string();
                             let to_str = to.format("%Y-%m-%d").to_string();
                             let output_dir = output_dir.to_path_buf();
+                            let backend = self.selected_pdf_backend();
 
                             Task::perform(
                                 async move {
@@ -3203,9 +5587,10 @@ impl App {
                                             &from_str,
                                             &to_str,
                                             &output_dir,
+                                            &backend,
                                         )
-                                        .unwrap();
-                                        Ok(path)
+                                        .map(|_| path)
+                                        .map_err(|e| format!("Ошибка генерации PDF: {}", e))
                                     })
                                     .await
                                     .unwrap_or_else(|e| {
@@ -3373,6 +5758,7 @@ impl App {
                             let from_copy = from.clone();
                             let to_copy = to.clone();
                             let output_dir = output_dir.to_path_buf();
+                            let backend = self.selected_pdf_backend();
 
                             Task::perform(
                                 async move {
@@ -3402,6 +5788,7 @@ impl App {
                                             &from_str,
                                             &to_str,
                                             &output_dir,
+                                            &backend,
                                         )
                                         .map(|_| path)
                                         .map_err(|e| format!("Ошибка генерации PDF: {}", e))
@@ -3523,10 +5910,11 @@ impl App {
                     Some(ReportType::PDF) => {
                         let groups = groups.clone();
                         let output_dir = output_dir.to_path_buf();
+                        let backend = self.selected_pdf_backend();
                         Task::perform(
                             async move {
                                 spawn_blocking(move || {
-                                    generate_group_report(&groups, &output_dir)
+                                    generate_group_report(&groups, &output_dir, &backend)
                                         .map(|_| output_dir.join("group_report.pdf"))
                                         .map_err(|e| format!("Ошибка генерации PDF: {}", e))
                                 })
@@ -3594,52 +5982,97 @@ impl App {
             }
             Message::BackupIntervalSelected(interval) => {
                 self.backup_interval = interval;
-                save_config(
-                    &self.theme.target(),
-                    self.backup_interval.as_ref().map(|i| i.value),
-                    self.backup_folder.clone(),
-                    self.max_backup_count,
-                )
-                .ok();
+                self.save_settings();
+                self.refresh_next_backup_time();
                 Task::none()
             }
-            Message::BackupNowPressed => {
-                let result = backup_database_now_with_config(
-                    self.backup_folder.clone(),
-                    self.max_backup_count,
-                );
-
-                if let Err(e) = result {
-                    println!("Ошибка резервного копирования: {}", e);
-                } else if let Some(folder) = &self.backup_folder {
-                    self.last_backup_time = get_last_backup_time(folder);
+            Message::BackupTimeChanged(value) => {
+                self.backup_time = value;
+                // Сохраняем только корректное время или пустое значение (любое время)
+                let time = self.backup_time.trim();
+                if time.is_empty() || NaiveTime::parse_from_str(time, "%H:%M").is_ok() {
+                    self.save_settings();
+                    self.refresh_next_backup_time();
                 }
-
                 Task::none()
             }
+            Message::BackupNowPressed => self.start_backup(),
+            Message::BackupSchedulerTick => {
+                self.refresh_next_backup_time();
+                let now = Local::now().naive_local();
+                let is_due = self.next_backup_time.is_some_and(|due| due <= now);
+                // После ошибки следующая попытка — не раньше чем через час
+                let recently_failed = self
+                    .backup_failed_at
+                    .is_some_and(|failed_at| now - failed_at < Duration::hours(1));
+                if is_due && !recently_failed {
+                    println!("DEBUG: запуск автоматического резервного копирования");
+                    return self.start_backup();
+                }
+                Task::none()
+            }
+            Message::BackupFinished(result) => {
+                self.backup_in_progress = false;
+                match result {
+                    Ok(path) => {
+                        self.backup_status = Some(format!(
+                            "Резервная копия создана: {}",
+                            path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+                        ));
+                        self.backup_error = None;
+                        self.backup_failed_at = None;
+                    }
+                    Err(e) => {
+                        self.backup_status = None;
+                        self.backup_error = Some(format!("Ошибка резервного копирования: {}", e));
+                        self.backup_failed_at = Some(Local::now().naive_local());
+                    }
+                }
+                self.last_backup_time = get_last_backup_time(&self.backup_dir());
+                self.refresh_next_backup_time();
+                self.update(Message::LoadBackupList)
+            }
 
             Message::SelectBackupFolder => {
                 if let Some(folder) = FileDialog::new().pick_folder() {
                     self.backup_folder = Some(folder.display().to_string());
-                    save_config(
-                        &self.theme.target(),
-                        self.backup_interval.as_ref().map(|interval| interval.value),
-                        self.backup_folder.clone(),
-                        self.max_backup_count,
-                    )
-                    .ok();
+                    self.save_settings();
+                    self.last_backup_time = get_last_backup_time(&self.backup_dir());
+                    self.refresh_next_backup_time();
                 }
                 Task::none()
             }
             Message::MaxBackupCountSelected(count_opt) => {
                 self.max_backup_count = count_opt;
-                save_config(
-                    &self.theme.target(),
-                    self.backup_interval.as_ref().map(|interval| interval.value),
-                    self.backup_folder.clone(),
-                    self.max_backup_count,
-                )
-                .ok();
+                self.save_settings();
+                Task::none()
+            }
+            Message::BackupCompressionSelected(compression) => {
+                self.backup_compression = compression;
+                self.save_settings();
+                Task::none()
+            }
+            Message::PdfBackendSelected(backend) => {
+                self.pdf_backend = backend;
+                self.save_settings();
+                Task::none()
+            }
+            Message::PaymentLockDaysSelected(days) => {
+                self.payment_lock_days = days;
+                self.save_settings();
+                Task::none()
+            }
+            Message::SiblingDiscountSelected(percent) => {
+                self.sibling_discount_percent = percent;
+                self.save_settings();
+                Task::none()
+            }
+            Message::OrganizationFieldChanged(field, value) => {
+                *self.organization.field_mut(field) = value;
+                Task::none()
+            }
+            Message::SaveOrganization => {
+                self.save_settings();
                 Task::none()
             }
             Message::OpenBackupFolder => {
@@ -3659,7 +6092,575 @@ impl App {
                 }
                 Task::none()
             }
+            Message::LoadBackupList => {
+                let folder = self.backup_dir();
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            list_backups(&folder)
+                                .map_err(|e| format!("Не удалось прочитать папку с бэкапами: {}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (список бэкапов) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::BackupListLoaded,
+                )
+            }
+            Message::BackupListLoaded(result) => {
+                match result {
+                    Ok(backups) => self.backup_files = backups,
+                    Err(e) => self.restore_error = Some(e),
+                }
+                Task::none()
+            }
+            Message::RequestRestoreBackup(backup) => {
+                self.restore_candidate = Some(backup);
+                self.restore_error = None;
+                Task::none()
+            }
+            Message::CancelRestoreBackup => {
+                self.restore_candidate = None;
+                Task::none()
+            }
+            Message::ConfirmRestoreBackup => {
+                let Some(backup) = self.restore_candidate.clone() else {
+                    return Task::none();
+                };
+                self.restore_in_progress = true;
+                let folder = self.backup_dir();
+                let max_copies = self.max_backup_count;
+                let compression = self.backup_compression.clone();
+                Task::perform(
+                    async move {
+                        spawn_blocking(move || {
+                            restore_backup(&backup.path, &folder, max_copies, compression.as_ref())
+                                .map(|()| backup.file_name)
+                        })
+                        .await
+                        .unwrap_or_else(|join_err| {
+                            Err(format!(
+                                "Блокирующая задача (восстановление БД) завершилась ошибкой: {:?}",
+                                join_err
+                            ))
+                        })
+                    },
+                    Message::BackupRestored,
+                )
+            }
+            Message::BackupRestored(result) => {
+                self.restore_in_progress = false;
+                self.restore_candidate = None;
+                match result {
+                    Ok(file_name) => {
+                        // Данные в памяти относятся к старой БД — начинаем сессию заново
+                        let _ = self.update(Message::Logout);
+                        self.current_user = None;
+                        self.backup_files.clear();
+                        self.error_message = format!(
+                            "База данных восстановлена из копии {}. Войдите в систему заново.",
+                            file_name
+                        );
+                        if let Some(folder) = &self.backup_folder {
+                            self.last_backup_time = get_last_backup_time(folder);
+                        }
+                        Task::none()
+                    }
+                    Err(e) => {
+                        self.restore_error = Some(format!("Восстановление не выполнено: {}", e));
+                        self.update(Message::LoadBackupList)
+                    }
+                }
+            }
+        }
+    }
+    // Сохраняет текущие настройки приложения в файл конфигурации
+    fn save_settings(&self) {
+        let config = Config {
+            theme_name: theme_to_str(self.theme.target()).to_string(),
+            backup_interval: self.backup_interval.as_ref().map(|i| i.value.to_string()),
+            backup_folder: self.backup_folder.clone(),
+            max_backup_count: self.max_backup_count,
+            backup_compression: self.backup_compression.as_ref().map(|c| c.value.to_string()),
+            backup_time: Some(self.backup_time.trim().to_string()).filter(|t| !t.is_empty()),
+            pdf_backend: self.pdf_backend.as_ref().map(|b| b.value.to_string()),
+            payment_lock_days: Some(self.payment_lock_days),
+            sibling_discount_percent: Some(self.sibling_discount_percent),
+            organization: Some(self.organization.clone()),
+            waitlist_offer_days: Some(self.waitlist_offer_days),
+            idle_lock_minutes: Some(self.idle_lock_minutes),
+            require_admin_two_factor: Some(self.require_admin_two_factor),
+        };
+        if let Err(e) = save_config(&config) {
+            eprintln!("Не удалось сохранить настройки: {}", e);
+        }
+    }
+    fn selected_pdf_backend(&self) -> PdfBackend {
+        self.pdf_backend.clone().unwrap_or_else(|| PDF_BACKENDS[0].clone())
+    }
+    fn backup_dir(&self) -> String {
+        self.backup_folder.clone().unwrap_or_else(|| "backup".to_string())
+    }
+    fn refresh_next_backup_time(&mut self) {
+        self.next_backup_time = self
+            .backup_interval
+            .as_ref()
+            .and_then(|interval| next_backup_due(interval, &self.backup_time, &self.backup_dir()));
+    }
+    // Бэкап в фоне: VACUUM INTO и сжатие могут занять заметное время
+    fn start_backup(&mut self) -> Task<Message> {
+        if self.backup_in_progress {
+            return Task::none();
+        }
+        self.backup_in_progress = true;
+        let folder = self.backup_dir();
+        let max_copies = self.max_backup_count;
+        let compression = self.backup_compression.clone();
+        Task::perform(
+            async move {
+                spawn_blocking(move || perform_backup(&folder, max_copies, compression.as_ref()))
+                    .await
+                    .unwrap_or_else(|join_err| {
+                        Err(format!(
+                            "Блокирующая задача (резервное копирование) завершилась ошибкой: {:?}",
+                            join_err
+                        ))
+                    })
+            },
+            Message::BackupFinished,
+        )
+    }
+    fn load_schedule(&self) -> Task<Message> {
+        let Some(group_id) = self.schedule_group.as_ref().map(|g| g.id) else {
+            return Task::none();
+        };
+        Task::perform(
+            async move {
+                spawn_blocking(move || {
+                    let conn = Connection::open(PATH_TO_DB)
+                        .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+                    let slots = db::get_schedule_slots(&conn, group_id)
+                        .map_err(|e| format!("Не удалось загрузить расписание: {}", e))?;
+                    let planned_sessions = db::get_planned_sessions_for_group(&conn, group_id)
+                        .map_err(|e| format!("Не удалось загрузить занятия: {}", e))?;
+                    Ok((slots, planned_sessions))
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (загрузка расписания) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::ScheduleLoaded,
+        )
+    }
+    // Преподаватель видит только свои группы, администратор — все
+    fn load_calendar_sessions(&self) -> Task<Message> {
+        let teacher_id = self
+            .current_user
+            .as_ref()
+            .filter(|u| u.user_type == Role::Teacher)
+            .map(|u| u.id);
+        let from = self.calendar_week_start;
+        let to = from + Duration::days(6);
+        Task::perform(
+            async move {
+                spawn_blocking(move || {
+                    let conn = Connection::open(PATH_TO_DB)
+                        .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+                    db::get_planned_sessions_between(&conn, from, to, teacher_id)
+                        .map_err(|e| format!("Не удалось загрузить календарь занятий: {}", e))
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (загрузка календаря) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::CalendarSessionsLoaded,
+        )
+    }
+    fn load_balances(&self) -> Task<Message> {
+        Task::perform(
+            async {
+                spawn_blocking(|| {
+                    let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                    db::get_student_balances(&conn, None)
+                        .map_err(|e| format!("Не удалось рассчитать балансы студентов: {}", e))
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (расчёт балансов) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::BalancesLoaded,
+        )
+    }
+    /// Загружает лист ожидания (для студента — только его записи), предварительно
+    /// закрывая просроченные предложения
+    fn load_group_enrollments(&self, group_id: i32) -> Task<Message> {
+        Task::perform(
+            async move {
+                spawn_blocking(move || {
+                    let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                    db::get_group_enrollments(&conn, group_id)
+                        .map_err(|e| format!("Не удалось загрузить зачисления группы: {}", e))
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (загрузка зачислений) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::GroupEnrollmentsLoaded,
+        )
+    }
+    fn load_waitlist(&self) -> Task<Message> {
+        let offer_days = self.waitlist_offer_days;
+        let student_id = self
+            .current_user
+            .as_ref()
+            .filter(|u| u.user_type == Role::Student)
+            .map(|u| u.id);
+        Task::perform(
+            async move {
+                spawn_blocking(move || {
+                    let mut conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                    db::expire_waitlist_offers(&mut conn, offer_days)
+                        .map_err(|e| format!("Не удалось обновить предложения листа ожидания: {}", e))?;
+                    db::get_waitlist(&conn, student_id)
+                        .map_err(|e| format!("Не удалось загрузить лист ожидания: {}", e))
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (загрузка листа ожидания) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::WaitlistLoaded,
+        )
+    }
+    fn load_payroll(&self) -> Task<Message> {
+        let month = self.payroll_month.format("%Y-%m").to_string();
+        Task::perform(
+            async move {
+                spawn_blocking(move || {
+                    let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                    db::get_payroll(&conn, &month).map_err(|e| format!("Ошибка расчёта зарплаты: {}", e))
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (расчёт зарплаты) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::PayrollLoaded,
+        )
+    }
+    // Начало сеанса после успешного входа (и проверки второго фактора, если он включён)
+    fn start_session(&mut self, user_info_data: UserInfo) -> Task<Message> {
+        self.current_user = Some(UserInfo {
+            id: user_info_data.id,
+            name: user_info_data.name,
+            email: user_info_data.email,
+            birthday: user_info_data.birthday,
+            user_type: user_info_data.user_type,
+            group_id: user_info_data.group_id,
+            avatar_data: user_info_data.avatar_data,
+            child_count: user_info_data.child_count,
+        });
+        self.error_message = "".to_string();
+        self.current_screen = Screen::Profile;
+        self.set_audit_actor(self.current_user.as_ref().map(|user| user.id));
+        self.reload_permissions();
+        self.access_denied = None;
+        self.load_own_registration_review();
+        self.load_password_state();
+        self.load_two_factor_state();
+        self.last_activity = Instant::now();
+
+        if let Some(user) = &self.current_user {
+            if user.user_type == Role::Admin {
+                println!(
+                    "DEBUG: Пользователь является АДМИНИСТРАТОРОМ. Запускаем загрузку ВСЕХ групп."
+                );
+                // !!! АДМИНИСТРАТОР: отправляем LoadAllGroups
+                return self.update(Message::LoadAllGroups);
+            } else if user.user_type == Role::Teacher {
+                println!(
+                    "DEBUG: Пользователь является ПРЕПОДАВАТЕЛЕМ. Запускаем загрузку его групп."
+                );
+                let teacher_id_for_task = user.id;
+                // !!! ПРЕПОДАВАТЕЛЬ: отправляем LoadTeacherGroups с ID
+                return self.update(Message::LoadTeacherGroups(teacher_id_for_task));
+            } else if user.user_type == Role::Student {
+                // <-- ДОБАВЛЕН ВАРИАНТ ДЛЯ СТУДЕНТА
+                println!(
+                    "DEBUG: Пользователь является СТУДЕНТОМ. Запускаем загрузку его группы."
+                );
+                // !!! СТУДЕНТ: отправляем LoadStudentGroupInfo
+                return Task::batch(vec![
+                    self.update(Message::LoadStudentGroupInfo),
+                    self.update(Message::LoadStudentProgress),
+                ]);
+            } else if user.user_type == Role::Parent {
+                println!(
+                    "DEBUG: Пользователь является РОДИТЕЛЕМ. Запускаем загрузку сводки по детям."
+                );
+                return self.update(Message::LoadStudentProgress);
+            } else {
+                // Если это другой тип пользователя, для которого нет специфичной загрузки
+                println!(
+                    "DEBUG: Пользователь {} (ID: {}) не преподаватель, не администратор, не студент и не родитель. Группы не загружаются автоматически.",
+                    user.user_type, user.id
+                );
+            }
+        }
+        Task::none()
+    }
+    // Включён ли второй фактор и не обязан ли пользователь его включить прямо сейчас
+    fn load_two_factor_state(&mut self) {
+        let Some(user) = &self.current_user else {
+            return;
+        };
+        let is_admin = user.user_type == Role::Admin;
+        let result = Connection::open(PATH_TO_DB).and_then(|conn| db::two_factor_enabled(&conn, user.id));
+        self.two_factor_enabled = result.unwrap_or_else(|e| {
+            eprintln!("Не удалось проверить двухфакторную аутентификацию: {}", e);
+            false
+        });
+        self.two_factor_setup_secret = None;
+        self.two_factor_qr = None;
+        self.two_factor_code_input.clear();
+        self.two_factor_error = None;
+        self.two_factor_setup_required = self.require_admin_two_factor && is_admin && !self.two_factor_enabled;
+        if self.two_factor_setup_required {
+            self.begin_two_factor_setup();
+        }
+    }
+    // Новый секрет и QR-код для приложения-аутентификатора; в БД секрет попадёт после проверки кода
+    fn begin_two_factor_setup(&mut self) {
+        let Some(user) = &self.current_user else {
+            return;
+        };
+        let secret = totp::generate_secret();
+        let uri = totp::provisioning_uri(&secret, &user.email, totp::ISSUER);
+        self.two_factor_qr = totp::qr_code_rgba(&uri, 6)
+            .map(|(width, height, pixels)| image::Handle::from_rgba(width, height, pixels));
+        self.two_factor_setup_secret = Some(secret);
+        self.two_factor_code_input.clear();
+        self.two_factor_error = None;
+    }
+    fn load_registration_requests(&self) -> Task<Message> {
+        Task::perform(
+            async {
+                spawn_blocking(|| {
+                    let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                    let requests = db::get_registration_requests(&conn)
+                        .map_err(|e| format!("Ошибка загрузки заявок на регистрацию: {}", e))?;
+                    let parents = db::get_all_users_for_list(&conn, Some(Role::Parent))
+                        .map_err(|e| format!("Ошибка загрузки списка родителей: {}", e))?;
+                    Ok((requests, parents))
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (загрузка заявок на регистрацию) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::RegistrationRequestsLoaded,
+        )
+    }
+    // Решение по собственной заявке показывается заявителю в профиле
+    fn load_own_registration_review(&mut self) {
+        let Some(user_id) = self.current_user.as_ref().map(|user| user.id) else {
+            return;
+        };
+        let result = Connection::open(PATH_TO_DB).and_then(|conn| db::get_registration_review(&conn, user_id));
+        self.registration_review = result.unwrap_or_else(|e| {
+            eprintln!("Не удалось загрузить решение по заявке на регистрацию: {}", e);
+            None
+        });
+    }
+    // Нужно ли сменить временный пароль и сколько осталось кодов восстановления
+    fn load_password_state(&mut self) {
+        let Some(user_id) = self.current_user.as_ref().map(|user| user.id) else {
+            return;
+        };
+        let result = Connection::open(PATH_TO_DB).and_then(|conn| {
+            Ok((db::must_change_password(&conn, user_id)?, db::count_recovery_codes(&conn, user_id)?))
+        });
+        match result {
+            Ok((must_change, codes_left)) => {
+                self.password_change_required = must_change;
+                self.show_change_password_modal = false;
+                self.recovery_codes_left = codes_left;
+            }
+            Err(e) => eprintln!("Не удалось проверить состояние пароля: {}", e),
+        }
+        self.current_password_input.clear();
+        self.new_password_input.clear();
+        self.new_password_repeat_input.clear();
+        self.change_password_error = None;
+        self.change_password_notice = None;
+    }
+    fn load_login_history(&self) -> Task<Message> {
+        let email_filter = self.login_history_email_filter.clone();
+        let failures_only = self.login_history_failures_only;
+        Task::perform(
+            async move {
+                spawn_blocking(move || {
+                    let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                    let attempts = db::get_login_history(&conn, &email_filter, failures_only)
+                        .map_err(|e| format!("Ошибка загрузки истории входов: {}", e))?;
+                    let locked = db::get_locked_accounts(&conn)
+                        .map_err(|e| format!("Ошибка загрузки заблокированных учётных записей: {}", e))?;
+                    Ok((attempts, locked))
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (загрузка истории входов) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::LoginHistoryLoaded,
+        )
+    }
+    fn load_audit_log(&self) -> Task<Message> {
+        let filter = self.audit_filter.clone();
+        Task::perform(
+            async move {
+                spawn_blocking(move || {
+                    let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                    let entries = db::get_audit_log(&conn, &filter)
+                        .map_err(|e| format!("Ошибка загрузки журнала изменений: {}", e))?;
+                    let actors = db::get_audit_actors(&conn)
+                        .map_err(|e| format!("Ошибка загрузки журнала изменений: {}", e))?;
+                    Ok((entries, actors))
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (загрузка журнала изменений) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::AuditLogLoaded,
+        )
+    }
+    // Запоминаем в БД, от чьего имени идут изменения, — их подхватывают триггеры журнала
+    fn set_audit_actor(&self, actor_id: Option<i32>) {
+        let result = Connection::open(PATH_TO_DB).and_then(|conn| audit::set_actor(&conn, actor_id));
+        if let Err(e) = result {
+            eprintln!("Не удалось записать пользователя журнала изменений: {}", e);
+        }
+    }
+    fn load_teacher_rates(&self) -> Task<Message> {
+        Task::perform(
+            async {
+                spawn_blocking(|| {
+                    let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                    db::get_teacher_rates(&conn).map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (загрузка ставок преподавателей) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::TeacherRatesLoaded,
+        )
+    }
+    fn load_payment_documents(&self) -> Task<Message> {
+        Task::perform(
+            async {
+                spawn_blocking(|| {
+                    let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                    db::get_payment_documents(&conn).map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (загрузка счетов и квитанций) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::PaymentDocumentsLoaded,
+        )
+    }
+    fn load_promo_codes(&self) -> Task<Message> {
+        Task::perform(
+            async {
+                spawn_blocking(|| {
+                    let conn = Connection::open(PATH_TO_DB).map_err(|e| e.to_string())?;
+                    db::get_promo_codes(&conn).map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(|join_err| {
+                    Err(format!(
+                        "Блокирующая задача (загрузка промокодов) завершилась ошибкой: {:?}",
+                        join_err
+                    ))
+                })
+            },
+            Message::PromoCodesLoaded,
+        )
+    }
+    /// Скидки к стоимости обучения в форме нового платежа: братья/сёстры, промокод, ручная
+    pub fn new_payment_discounts(&self) -> Vec<DiscountLine> {
+        let price = self.new_payment_amount.unwrap_or(0.0);
+        let mut lines = Vec::new();
+
+        if let Some(sibling) = &self.new_payment_sibling
+            && self.sibling_discount_percent > 0
+        {
+            lines.push(DiscountLine {
+                label: format!(
+                    "Скидка {}% (учится брат/сестра: {})",
+                    self.sibling_discount_percent, sibling
+                ),
+                amount: price * self.sibling_discount_percent as f64 / 100.0,
+            });
         }
+        if let Some(promo) = &self.new_payment_promo {
+            lines.push(DiscountLine {
+                label: format!("Промокод {} ({})", promo.code, promo.value_display()),
+                amount: promo.discount_for(price),
+            });
+        }
+        if let Ok(value) = self.new_payment_manual_discount.trim().replace(',', ".").parse::<f64>() {
+            lines.push(DiscountLine {
+                label: format!("Скидка администратора: {}", self.new_payment_manual_reason.trim()),
+                amount: value,
+            });
+        }
+
+        cap_discounts(price, lines)
     }
     fn reset_new_payment_form(&mut self) {
         self.new_payment_student = None;
@@ -3671,6 +6672,14 @@ impl App {
         self.courses_with_seats.clear();
         self.groups_for_selected_course.clear();
         self.selected_payment_type_idx = Some(0);
+        self.new_payment_installments = 1;
+        self.new_payment_sibling = None;
+        self.new_payment_promo_input.clear();
+        self.new_payment_promo = None;
+        self.new_payment_manual_discount.clear();
+        self.new_payment_manual_reason.clear();
+        self.new_payment_error = None;
+        self.waitlist_enrollment = None;
     }
     fn clear_fields(&mut self) {
         self.user_name.clear();
@@ -3685,12 +6694,6 @@ impl App {
     }
 }
 
-fn hash_password(password: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(password);
-    hasher.update(password);
-    hasher.finalize_fixed().iter().map(|b| format!("{:02x}", b)).collect()
-}
 async fn load_teacher_groups(teacher_email: String) -> Result<Vec<Group>, String> {
     let conn = Connection::open(PATH_TO_DB)
         .map_err(|e| format!("Failed to open database connection: {}", e))?;
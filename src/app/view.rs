@@ -1,11 +1,14 @@
 use super::{App, Message};
 use crate::app::state::Screen;
 use crate::screens::{
-    certificates_screen, classes_screen, courses_screen, groups_screen, login_screen, nav_menu,
-    payment_screen, profile_screen, register_screen, settings_screen, user_list_screen,
+    audit_log_screen, certificates_screen, change_password_screen, classes_screen, courses_screen, groups_screen,
+    login_history_screen, login_screen, nav_menu, password_recovery_screen, payment_screen, payroll_screen, profile_screen,
+    register_screen, settings_screen, two_factor_screen, two_factor_setup_screen,
+    student_progress_screen, user_list_screen,
 };
-use iced::widget::{Column, Container, Row};
-use iced::Length;
+use iced::widget::container::bordered_box;
+use iced::widget::{Column, Container, Row, Space, Text, button};
+use iced::{Alignment, Color, Length};
 
 impl App {
     pub fn view(&self) -> Row<Message> {
@@ -13,7 +16,12 @@ impl App {
             .spacing(20)
             .push(
                 // Левое меню (sidebar)
-                if self.current_screen != Screen::Login && self.current_screen != Screen::Register {
+                if !matches!(
+                    self.current_screen,
+                    Screen::Login | Screen::Register | Screen::PasswordRecovery | Screen::TwoFactor
+                ) && !self.password_change_required
+                    && !self.two_factor_setup_required
+                {
                     Container::new(nav_menu(self))
                         .width(Length::Fixed(210.0)) // Фиксированная ширина меню
                         .height(Length::Fill)
@@ -24,11 +32,17 @@ impl App {
                         .height(Length::Fill)
                 },
             )
-            .push(
+            .push({
                 // Основной контент
-                match &self.current_screen {
+                let screen = match &self.current_screen {
+                    // После входа по временному паролю доступна только его смена
+                    _ if self.password_change_required => change_password_screen(self),
+                    // Администратор обязан включить второй фактор, прежде чем работать дальше
+                    _ if self.two_factor_setup_required => two_factor_setup_screen(self),
                     Screen::Login => login_screen(self),
                     Screen::Register => register_screen(self),
+                    Screen::PasswordRecovery => password_recovery_screen(self),
+                    Screen::TwoFactor => two_factor_screen(self),
                     Screen::Profile => profile_screen(self),
                     Screen::Settings => settings_screen(self),
                     Screen::CoursesList => courses_screen(self),
@@ -37,9 +51,29 @@ impl App {
                     Screen::Classes => classes_screen(self),
                     Screen::Payment => payment_screen(self),
                     Screen::Certificates => certificates_screen(self),
+                    Screen::StudentProgress => student_progress_screen(self),
+                    Screen::Payroll => payroll_screen(self),
+                    Screen::AuditLog => audit_log_screen(self),
+                    Screen::LoginHistory => login_history_screen(self),
+                };
+                let mut content = Column::new().width(Length::Fill).height(Length::Fill);
+                // Отказ в доступе виден на любом экране, пока его не закроют
+                if let Some(denied) = &self.access_denied {
+                    content = content.push(
+                        Container::new(
+                            Row::new()
+                                .spacing(10)
+                                .align_y(Alignment::Center)
+                                .push(Text::new(denied).color(Color::from_rgb8(255, 0, 0)))
+                                .push(Space::new().width(Length::Fill))
+                                .push(button(Text::new("Закрыть")).on_press(Message::DismissAccessDenied)),
+                        )
+                        .padding(10)
+                        .style(bordered_box),
+                    );
                 }
-                .width(Length::Fill),
-            )
+                content.push(screen.width(Length::Fill))
+            })
             .into()
     }
 }
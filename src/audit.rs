@@ -0,0 +1,140 @@
+//! Журнал изменений данных. Каждая вставка, изменение и удаление в основных таблицах
+//! записывается триггерами SQLite в `AuditLog` вместе с состоянием строки до и после (JSON).
+//! Автор изменения берётся из однострочной таблицы `AuditContext`, куда при входе
+//! записывается текущий пользователь, — поэтому функции `db.rs` не нужно передавать ему явно.
+
+use rusqlite::{params, Connection};
+
+/// Отслеживаемые таблицы: имя, столбец с идентификатором записи и подпись для журнала.
+pub const AUDITED_TABLES: [(&str, &str, &str); 23] = [
+    ("Users", "ID", "Пользователь"),
+    ("ParentStudent", "student_id", "Связь родитель — ребёнок"),
+    ("Course", "ID", "Курс"),
+    ("Lessons", "ID", "Урок курса"),
+    ("Assignment", "id", "Задание"),
+    ("Group", "id", "Группа"),
+    ("GroupStudent", "id", "Зачисление"),
+    ("ScheduleSlot", "id", "Расписание"),
+    ("PlannedSession", "id", "Запланированное занятие"),
+    ("PastSessions", "id", "Проведённое занятие"),
+    ("Attendance", "id", "Посещаемость"),
+    ("Submission", "id", "Сданная работа"),
+    ("SubmissionFile", "id", "Файл сданной работы"),
+    ("Payment", "id", "Платёж"),
+    ("PaymentDocument", "id", "Счёт или квитанция"),
+    ("Installment", "id", "Рассрочка"),
+    ("PromoCode", "id", "Промокод"),
+    ("Certificates", "id", "Сертификат"),
+    ("TeacherRate", "teacher_id", "Ставка преподавателя"),
+    ("PayrollPeriod", "id", "Ведомость зарплаты"),
+    ("Waitlist", "id", "Лист ожидания"),
+    ("RolePermission", "rowid", "Права доступа"),
+    ("RegistrationReview", "user_id", "Заявка на регистрацию"),
+];
+
+/// Столбцы, которые не попадают в журнал: секреты и бинарные данные.
+const SKIPPED_COLUMNS: [&str; 4] = ["password", "totp_secret", "AvatarData", "data"];
+
+/// Счётчики, которые пересчитываются автоматически. В снимок строки они попадают,
+/// но их изменение само по себе не считается действием пользователя.
+const DERIVED_COLUMNS: [&str; 4] = ["seats", "student_count", "used_count", "totp_last_step"];
+
+/// Подпись таблицы для журнала; для неизвестных таблиц возвращает её имя.
+pub fn entity_label(table: &str) -> &str {
+    AUDITED_TABLES
+        .iter()
+        .find(|(name, _, _)| *name == table)
+        .map(|(_, _, label)| *label)
+        .unwrap_or(table)
+}
+
+/// Имя таблицы по подписи из журнала (для фильтра).
+pub fn entity_table(label: &str) -> Option<&'static str> {
+    AUDITED_TABLES
+        .iter()
+        .find(|(_, _, l)| *l == label)
+        .map(|(name, _, _)| *name)
+}
+
+/// Записывает, от чьего имени выполняются дальнейшие изменения (`None` — никто не вошёл).
+pub fn set_actor(conn: &Connection, actor_id: Option<i32>) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO AuditContext (id, actor_id) VALUES (1, ?1)
+         ON CONFLICT(id) DO UPDATE SET actor_id = excluded.actor_id",
+        params![actor_id],
+    )?;
+    Ok(())
+}
+
+/// Есть ли отслеживаемые таблицы без триггеров журнала (например, таблица добавлена
+/// в `AUDITED_TABLES` без изменения схемы).
+pub fn triggers_missing(conn: &Connection) -> rusqlite::Result<bool> {
+    for (table, _, _) in AUDITED_TABLES {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'trigger' AND name = ?1)",
+            [format!("audit_{table}_insert")],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Пересоздаёт триггеры журнала по текущему набору столбцов таблиц.
+/// Вызывается после миграций, чтобы новые столбцы сразу попадали в снимки.
+pub fn rebuild_triggers(conn: &Connection) -> rusqlite::Result<()> {
+    for (table, key, _) in AUDITED_TABLES {
+        let columns: Vec<String> = {
+            let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+            stmt.query_map([table], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?
+        };
+        let logged: Vec<&String> = columns
+            .iter()
+            .filter(|c| !SKIPPED_COLUMNS.contains(&c.as_str()))
+            .collect();
+        let snapshot = |alias: &str| {
+            let pairs: Vec<String> = logged
+                .iter()
+                .map(|c| format!("'{c}', {alias}.\"{c}\""))
+                .collect();
+            format!("json_object({})", pairs.join(", "))
+        };
+        let changed: Vec<String> = logged
+            .iter()
+            .filter(|c| !DERIVED_COLUMNS.contains(&c.as_str()))
+            .map(|c| format!("OLD.\"{c}\" IS NOT NEW.\"{c}\""))
+            .collect();
+
+        let insert_row = |action: &str, row: &str, before: &str, after: &str| {
+            format!(
+                "INSERT INTO AuditLog (created_at, actor_id, actor_name, action, entity, entity_id, before_json, after_json)
+                 SELECT datetime('now', 'localtime'), U.ID, U.Name, '{action}', '{table}', {row}.\"{key}\", {before}, {after}
+                 FROM (SELECT 1)
+                 LEFT JOIN AuditContext C ON C.id = 1
+                 LEFT JOIN Users U ON U.ID = C.actor_id;"
+            )
+        };
+
+        let sql = format!(
+            "DROP TRIGGER IF EXISTS \"audit_{table}_insert\";
+             DROP TRIGGER IF EXISTS \"audit_{table}_update\";
+             DROP TRIGGER IF EXISTS \"audit_{table}_delete\";
+             CREATE TRIGGER \"audit_{table}_insert\" AFTER INSERT ON \"{table}\"
+             BEGIN {insert} END;
+             CREATE TRIGGER \"audit_{table}_update\" AFTER UPDATE ON \"{table}\"
+             WHEN {changed}
+             BEGIN {update} END;
+             CREATE TRIGGER \"audit_{table}_delete\" AFTER DELETE ON \"{table}\"
+             BEGIN {delete} END;",
+            insert = insert_row("insert", "NEW", "NULL", &snapshot("NEW")),
+            update = insert_row("update", "NEW", &snapshot("OLD"), &snapshot("NEW")),
+            delete = insert_row("delete", "OLD", &snapshot("OLD"), "NULL"),
+            changed = changed.join(" OR "),
+        );
+        conn.execute_batch(&sql)?;
+    }
+    Ok(())
+}
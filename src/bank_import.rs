@@ -0,0 +1,369 @@
+//! Импорт банковской выписки (CSV или формат 1С ClientBankExchange) и сопоставление
+//! входящих переводов со студентами и их графиками платежей.
+
+use crate::app::state::{
+    BankTransaction, EnrollmentPickListItem, MatchStatus, StatementLine, StudentBalance,
+};
+use chrono::NaiveDate;
+use std::collections::{HashMap, HashSet};
+
+/// За сколько дней до и после срока взноса перевод считается оплатой этого взноса
+const DUE_DATE_WINDOW_DAYS: i64 = 10;
+
+/// Текст файла: UTF-8 (в том числе с BOM), иначе Windows-1251, как принято у банков и 1С
+fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::WINDOWS_1251.decode(bytes).0.into_owned(),
+    }
+}
+
+fn parse_date(value: &str) -> Option<String> {
+    // Отбрасываем время, если дата выгружена вместе с ним
+    let value = value.split_whitespace().next()?;
+    ["%d.%m.%Y", "%Y-%m-%d", "%d/%m/%Y", "%d.%m.%y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+fn parse_amount(value: &str) -> Option<f64> {
+    let cleaned: String = value
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, ',' | '.' | '-'))
+        .collect();
+    cleaned.replace(',', ".").parse().ok()
+}
+
+/// Разбирает файл выписки, формат определяется по содержимому
+pub fn parse_statement(bytes: &[u8]) -> Result<Vec<BankTransaction>, String> {
+    let text = decode(bytes);
+    if text.trim_start().starts_with("1CClientBankExchange") {
+        parse_client_bank_exchange(&text)
+    } else {
+        parse_csv(&text)
+    }
+}
+
+/// Формат обмена 1С: строки "Ключ=Значение", документы между СекцияДокумент и КонецДокумента.
+/// Берутся только поступления на расчётный счёт, указанный в заголовке файла.
+fn parse_client_bank_exchange(text: &str) -> Result<Vec<BankTransaction>, String> {
+    let mut own_account: Option<String> = None;
+    let mut document: Option<HashMap<String, String>> = None;
+    let mut transactions = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        let (key, value) = line.split_once('=').unwrap_or((line, ""));
+        let value = value.trim();
+
+        match (key, document.as_mut()) {
+            ("СекцияДокумент", _) => document = Some(HashMap::new()),
+            ("КонецДокумента", Some(_)) => {
+                let fields = document.take().unwrap_or_default();
+                let incoming = match (&own_account, fields.get("ПолучательСчет")) {
+                    (Some(own), Some(receiver)) => own == receiver,
+                    _ => true,
+                };
+                if !incoming {
+                    continue;
+                }
+
+                let number = fields.get("Номер").cloned().unwrap_or_default();
+                let date = fields
+                    .get("ДатаПоступило")
+                    .filter(|d| !d.is_empty())
+                    .or_else(|| fields.get("Дата"))
+                    .and_then(|d| parse_date(d))
+                    .ok_or_else(|| format!("Документ № {}: не указана дата", number))?;
+                let amount = fields
+                    .get("Сумма")
+                    .and_then(|a| parse_amount(a))
+                    .ok_or_else(|| format!("Документ № {}: не указана сумма", number))?;
+                let payer = fields
+                    .get("Плательщик1")
+                    .or_else(|| fields.get("Плательщик"))
+                    .cloned()
+                    .unwrap_or_default();
+
+                transactions.push(BankTransaction {
+                    reference: format!("1C:{}:{}:{:.2}", number, date, amount),
+                    date,
+                    amount,
+                    payer,
+                    purpose: fields.get("НазначениеПлатежа").cloned().unwrap_or_default(),
+                });
+            }
+            (_, Some(fields)) => {
+                fields.insert(key.to_string(), value.to_string());
+            }
+            ("РасчСчет", None) if own_account.is_none() && !value.is_empty() => {
+                own_account = Some(value.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(transactions)
+}
+
+// Разбивает строку CSV с учётом кавычек ("a;""b"";c")
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => fields.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// CSV из интернет-банка: ищется строка заголовка с датой и суммой, остальные столбцы
+/// (плательщик, назначение, номер документа) необязательны. Берутся только поступления.
+fn parse_csv(text: &str) -> Result<Vec<BankTransaction>, String> {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    let header_index = lines
+        .iter()
+        .position(|l| {
+            let lower = l.to_lowercase();
+            lower.contains("дата") || lower.contains("date")
+        })
+        .ok_or("В файле не найдена строка заголовка со столбцом даты")?;
+
+    let header_line = lines[header_index];
+    let delimiter = [';', '\t', ',']
+        .into_iter()
+        .max_by_key(|d| header_line.matches(*d).count())
+        .unwrap_or(';');
+    let headers: Vec<String> = split_csv_line(header_line, delimiter)
+        .iter()
+        .map(|h| h.to_lowercase())
+        .collect();
+
+    let find_column = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| headers.iter().position(|h| h.contains(key)))
+    };
+    let date_col = find_column(&["дата", "date"]).ok_or("Не найден столбец с датой")?;
+    let amount_col = find_column(&["приход", "поступлен", "кредит", "сумма", "amount"])
+        .ok_or("Не найден столбец с суммой")?;
+    let payer_col = find_column(&["плательщик", "отправитель", "контрагент", "payer", "наименование"]);
+    let purpose_col = find_column(&["назначение", "описание", "комментарий", "purpose", "description"]);
+    let number_col = find_column(&["номер документа", "номер п/п", "№", "номер"]);
+
+    let mut transactions = Vec::new();
+    for line in lines.iter().skip(header_index + 1) {
+        let fields = split_csv_line(line, delimiter);
+        let field = |col: Option<usize>| {
+            col.and_then(|c| fields.get(c)).cloned().unwrap_or_default()
+        };
+
+        // Итоговые строки и пустые суммы пропускаем, расходы тоже
+        let (Some(date), Some(amount)) = (
+            parse_date(&field(Some(date_col))),
+            parse_amount(&field(Some(amount_col))),
+        ) else {
+            continue;
+        };
+        if amount <= 0.0 {
+            continue;
+        }
+
+        let payer = field(payer_col);
+        let purpose = field(purpose_col);
+        let number = field(number_col);
+        // Без номера документа ссылка строится только из содержимого строки (не из её позиции),
+        // чтобы при загрузке пересекающейся выписки тот же перевод узнавался как уже загруженный
+        let reference = if number.is_empty() {
+            format!("CSV:{}:{:.2}:{}:{}", date, amount, payer, purpose)
+        } else {
+            format!("CSV:{}:{}:{:.2}", number, date, amount)
+        };
+
+        transactions.push(BankTransaction {
+            reference,
+            date,
+            amount,
+            payer,
+            purpose,
+        });
+    }
+
+    Ok(transactions)
+}
+
+// Слова ФИО в нижнем регистре без "ё" для сравнения
+fn name_words(name: &str) -> Vec<String> {
+    name.to_lowercase()
+        .replace('ё', "е")
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| w.len() > 1)
+        .map(str::to_string)
+        .collect()
+}
+
+// Слова совпадают с точностью до падежного окончания ("Белова" и "Белову", "София" и "Софию")
+fn same_word(a: &str, b: &str) -> bool {
+    let common = a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+    let shorter = a.chars().count().min(b.chars().count());
+    a == b || (common >= 4 && common + 2 >= shorter)
+}
+
+/// 2 — совпали фамилия и имя, 1 — только фамилия, 0 — нет совпадения
+fn name_score(person: &str, text_words: &[String]) -> u32 {
+    let words = name_words(person);
+    let contains = |word: &String| text_words.iter().any(|w| same_word(w, word));
+    let Some(surname) = words.first() else {
+        return 0;
+    };
+    if !contains(surname) {
+        return 0;
+    }
+    match words.get(1) {
+        Some(first_name) if contains(first_name) => 2,
+        _ => 1,
+    }
+}
+
+fn amounts_equal(a: f64, b: f64) -> bool {
+    (a - b).abs() < 0.01
+}
+
+/// Сопоставляет переводы с зачислениями студентов по плательщику (студент или его родитель),
+/// назначению платежа, сумме очередного взноса или долга и близости к сроку взноса.
+/// Повторы строки внутри одной выписки помечаются и не загружаются.
+pub fn match_transactions(
+    transactions: Vec<BankTransaction>,
+    balances: &[StudentBalance],
+    parents: &HashMap<i32, Vec<String>>,
+    imported_references: &HashSet<String>,
+) -> Vec<StatementLine> {
+    let mut seen_references = HashSet::new();
+    transactions
+        .into_iter()
+        .map(|transaction| {
+            if !seen_references.insert(transaction.reference.clone()) {
+                return StatementLine {
+                    transaction,
+                    status: MatchStatus::Duplicate,
+                    target: None,
+                    include: false,
+                    match_note: "совпадает с одной из строк выше; отдельный перевод внесите вручную".to_string(),
+                };
+            }
+            if imported_references.contains(&transaction.reference) {
+                return StatementLine {
+                    transaction,
+                    status: MatchStatus::AlreadyImported,
+                    target: None,
+                    include: false,
+                    match_note: "".to_string(),
+                };
+            }
+
+            let payer_words = name_words(&transaction.payer);
+            let purpose_words = name_words(&transaction.purpose);
+            let transaction_date = NaiveDate::parse_from_str(&transaction.date, "%Y-%m-%d").ok();
+
+            // (баллы, совпало ли имя, совпала ли сумма, пояснение, зачисление)
+            let mut candidates: Vec<(u32, bool, bool, String, &StudentBalance)> = Vec::new();
+            for balance in balances {
+                let mut notes = Vec::new();
+
+                let student_score = name_score(&balance.student_name, &payer_words)
+                    .max(name_score(&balance.student_name, &purpose_words));
+                let parent_score = parents
+                    .get(&balance.student_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|parent| name_score(parent, &payer_words))
+                    .max()
+                    .unwrap_or(0);
+                let name_points = student_score.max(parent_score);
+                if name_points == 0 {
+                    continue;
+                }
+                if parent_score > student_score {
+                    notes.push("плательщик — родитель");
+                } else {
+                    notes.push("совпало ФИО");
+                }
+
+                let today = transaction.date.as_str();
+                let next = balance.next_installment();
+                let amount_match = [
+                    next.map(|i| i.remaining()),
+                    Some(balance.overdue_amount(today)),
+                    Some(balance.outstanding()),
+                ]
+                .into_iter()
+                .flatten()
+                .any(|expected| amounts_equal(expected, transaction.amount));
+                if amount_match {
+                    notes.push("совпала сумма");
+                }
+
+                let near_due = match (next, transaction_date) {
+                    (Some(installment), Some(date)) => {
+                        NaiveDate::parse_from_str(&installment.due_date, "%Y-%m-%d")
+                            .is_ok_and(|due| (date - due).num_days().abs() <= DUE_DATE_WINDOW_DAYS)
+                    }
+                    _ => false,
+                };
+                if near_due {
+                    notes.push("близко к сроку взноса");
+                }
+
+                let points = name_points * 2 + if amount_match { 2 } else { 0 } + u32::from(near_due);
+                candidates.push((points, name_points == 2, amount_match, notes.join(", "), balance));
+            }
+
+            candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.0));
+            let unique_best = match candidates.as_slice() {
+                [] => None,
+                [best] => Some(best),
+                [best, second, ..] => (best.0 > second.0).then_some(best),
+            };
+
+            match unique_best {
+                Some((_, full_name, amount_match, note, balance)) => {
+                    let status = if *full_name && *amount_match {
+                        MatchStatus::Matched
+                    } else {
+                        MatchStatus::Suggested
+                    };
+                    StatementLine {
+                        transaction,
+                        status,
+                        target: EnrollmentPickListItem::from_balance(balance),
+                        include: status == MatchStatus::Matched,
+                        match_note: note.clone(),
+                    }
+                }
+                None => StatementLine {
+                    transaction,
+                    status: MatchStatus::Unmatched,
+                    target: None,
+                    include: false,
+                    match_note: if candidates.is_empty() {
+                        "".to_string()
+                    } else {
+                        format!("несколько подходящих студентов: {}", candidates.len())
+                    },
+                },
+            }
+        })
+        .collect()
+}
@@ -1,9 +1,17 @@
-use std::fs;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 use iced::Theme;
-use crate::app::state::{BackupInterval, Config, CONFIG_FILE, PATH_TO_DB};
+use rusqlite::{params, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::app::state::{BackupCompression, BackupFile, BackupInterval, Config, CONFIG_FILE, PATH_TO_DB};
+use crate::migrations::{run_migrations, schema_version, SCHEMA_VERSION};
 
 pub fn theme_from_str(name: &str) -> Option<Theme> {
     Theme::ALL
@@ -12,89 +20,254 @@ pub fn theme_from_str(name: &str) -> Option<Theme> {
         .cloned()
 }
 
-pub fn save_config(theme: &Theme, interval: Option<&str>, folder: Option<String>, max_count: Option<usize>) -> std::io::Result<()> {
-    let config = Config {
-        theme_name: theme_to_str(theme).to_string(),
-        backup_interval: interval.map(|s| s.to_string()),
-        backup_folder: folder,
-        max_backup_count: max_count,
-    };
-    let json = serde_json::to_string_pretty(&config)?;
+pub fn save_config(config: &Config) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(config)?;
     fs::write(CONFIG_FILE, json)?;
     Ok(())
 }
-pub fn backup_database_now() -> std::io::Result<()> {
-    let backup_dir = Path::new("backup");
+pub fn backup_database_now() -> Result<PathBuf, String> {
+    perform_backup("backup", None, None)
+}
+/// Время последнего бэкапа — по самой новой копии в папке
+pub fn last_backup_run(backup_dir: &str) -> Option<NaiveDateTime> {
+    let newest = list_backups(backup_dir).ok()?.into_iter().next()?;
+    NaiveDateTime::parse_from_str(&newest.modified, "%Y-%m-%d %H:%M:%S").ok()
+}
 
-    // Создаём папку, если она не существует
-    if !backup_dir.exists() {
-        fs::create_dir_all(backup_dir)?;
+/// Когда должен быть сделан следующий автоматический бэкап. `None` — автобэкап выключен.
+/// Если копий ещё нет (или срок пропущен, пока приложение было закрыто), время уже наступило.
+pub fn next_backup_due(interval: &BackupInterval, backup_time: &str, backup_dir: &str) -> Option<NaiveDateTime> {
+    let time_of_day = NaiveTime::parse_from_str(backup_time.trim(), "%H:%M").ok();
+    let now = Local::now().naive_local();
+    match last_backup_run(backup_dir) {
+        Some(last) => interval.next_run(last, time_of_day),
+        // Копий ещё нет: делаем сразу, если автобэкап включён
+        None => interval.next_run(now, None).map(|_| now),
     }
+}
 
-    // Формируем имя файла резервной копии с меткой времени
-    let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
-    let backup_filename = format!("backup_{}.db", timestamp);
-    let backup_path = backup_dir.join(backup_filename);
+/// Сопроводительный файл резервной копии (`<копия>.manifest.json`)
+#[derive(Serialize, Deserialize)]
+struct BackupManifest {
+    created_at: String,
+    file_name: String,
+    compression: String,
+    schema_version: i32,
+    size_bytes: u64,
+    /// SHA-256 файла копии в том виде, в каком он лежит на диске
+    sha256: String,
+    /// SHA-256 несжатого файла БД
+    database_sha256: String,
+    row_counts: BTreeMap<String, i64>,
+}
 
-    // Копируем БД в указанное место
-    fs::copy(PATH_TO_DB, backup_path)?;
+fn manifest_path(backup_file: &Path) -> PathBuf {
+    let mut name = backup_file.as_os_str().to_owned();
+    name.push(".manifest.json");
+    PathBuf::from(name)
+}
 
-    Ok(())
+// Файлы копий: "backup_<время>.db" с необязательным расширением сжатия
+fn is_backup_file(file_name: &str) -> bool {
+    file_name.starts_with("backup_")
+        && (file_name.ends_with(".db") || file_name.ends_with(".db.gz") || file_name.ends_with(".db.zst"))
 }
-pub fn start_backup_scheduler(
-    interval: Option<BackupInterval>,
-    folder: Option<String>,
-    max_copies: Option<usize>,
-) {
-    if let Some(interval) = interval {
-        if let Some(duration) = interval.duration() {
-            let folder = folder.unwrap_or_else(|| "backup".to_string());
-
-            std::thread::spawn(move || {
-                loop {
-                    if let Err(e) = perform_backup(&folder, max_copies) {
-                        eprintln!("Ошибка резервного копирования: {}", e);
-                    }
-                    std::thread::sleep(duration);
-                }
-            });
-        } else {
-            println!("Автоматическое резервное копирование отключено.");
+
+fn sha256_hex(reader: &mut impl Read) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn file_sha256(path: &Path) -> io::Result<String> {
+    sha256_hex(&mut BufReader::new(File::open(path)?))
+}
+
+// Поток чтения несжатой БД из файла копии, формат определяется по расширению
+fn open_backup_reader(path: &Path) -> io::Result<Box<dyn Read>> {
+    let file = BufReader::new(File::open(path)?);
+    let name = path.to_string_lossy();
+    if name.ends_with(".gz") {
+        Ok(Box::new(GzDecoder::new(file)))
+    } else if name.ends_with(".zst") {
+        Ok(Box::new(zstd::stream::read::Decoder::new(file)?))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+fn compress_file(source: &Path, target: &Path, compression: &BackupCompression) -> io::Result<()> {
+    let mut input = BufReader::new(File::open(source)?);
+    let output = BufWriter::new(File::create(target)?);
+    match compression.value {
+        "gzip" => {
+            let mut encoder = GzEncoder::new(output, flate2::Compression::default());
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?.flush()?;
+        }
+        "zstd" => {
+            let mut encoder = zstd::stream::write::Encoder::new(output, 0)?;
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?.flush()?;
+        }
+        other => {
+            return Err(io::Error::other(format!("Неизвестный формат сжатия: {}", other)));
         }
     }
+    Ok(())
+}
+
+fn table_row_counts(conn: &Connection) -> rusqlite::Result<BTreeMap<String, i64>> {
+    let tables: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )?;
+        stmt.query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut counts = BTreeMap::new();
+    for table in tables {
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\"")),
+            [],
+            |row| row.get(0),
+        )?;
+        counts.insert(table, count);
+    }
+    Ok(counts)
 }
-pub fn perform_backup(backup_dir: &str, max_copies: Option<usize>) -> std::io::Result<()> {
+
+/// Создаёт согласованную копию БД через `VACUUM INTO` (в отличие от копирования файла,
+/// SQLite сам следит, чтобы параллельная запись не попала в копию наполовину),
+/// при необходимости сжимает её, проверяет и записывает рядом манифест.
+/// Возвращает путь к созданному файлу копии.
+pub fn perform_backup(
+    backup_dir: &str,
+    max_copies: Option<usize>,
+    compression: Option<&BackupCompression>,
+) -> Result<PathBuf, String> {
     let backup_path = Path::new(backup_dir);
     if !backup_path.exists() {
-        fs::create_dir_all(backup_path)?;
+        fs::create_dir_all(backup_path)
+            .map_err(|e| format!("Не удалось создать папку для бэкапов: {}", e))?;
     }
 
-    let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
-    let backup_filename = format!("backup_{}.db", timestamp);
-    let backup_file_path = backup_path.join(backup_filename);
+    // Две копии в одну секунду (например, снимок перед восстановлением) не должны перезаписать друг друга
+    let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+    let base_name = (0..)
+        .map(|n| if n == 0 { format!("backup_{}", timestamp) } else { format!("backup_{}_{}", timestamp, n) })
+        .find(|name| {
+            ["db", "db.gz", "db.zst", "db.tmp"]
+                .iter()
+                .all(|ext| !backup_path.join(format!("{}.{}", name, ext)).exists())
+        })
+        .unwrap_or_default();
+    let db_file_path = backup_path.join(format!("{}.db", base_name));
+    let compression = compression.filter(|c| !c.extension.is_empty());
 
-    fs::copy(PATH_TO_DB, &backup_file_path)?;
+    // Несжатая копия сначала пишется во временный файл, чтобы она не попала в список копий
+    let snapshot_path = match compression {
+        Some(_) => backup_path.join(format!("{}.db.tmp", base_name)),
+        None => db_file_path.clone(),
+    };
+
+    let result = write_backup(&snapshot_path, &db_file_path, compression);
+    if compression.is_some() {
+        let _ = fs::remove_file(&snapshot_path);
+    }
+    let backup_file_path = result?;
 
-    // Очистка старых копий
+    println!("DEBUG: создана резервная копия {}", backup_file_path.display());
+
+    // Очистка старых копий вместе с их манифестами
     if let Some(max) = max_copies {
-        let mut entries: Vec<_> = fs::read_dir(backup_path)?
+        let mut entries: Vec<_> = fs::read_dir(backup_path)
+            .map_err(|e| format!("Не удалось прочитать папку для бэкапов: {}", e))?
             .filter_map(|e| e.ok())
             .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false))
-            .filter(|e| e.file_name().to_string_lossy().starts_with("backup_"))
+            .filter(|e| is_backup_file(&e.file_name().to_string_lossy()))
             .collect();
 
         entries.sort_by_key(|e| e.metadata().and_then(|m| m.modified()).unwrap_or(std::time::SystemTime::UNIX_EPOCH));
 
         while entries.len() > max {
-            if let Some(entry) = entries.first() {
-                let path = entry.path();
-                let _ = fs::remove_file(path);
-                entries.remove(0);
-            }
+            let path = entries.remove(0).path();
+            let _ = fs::remove_file(manifest_path(&path));
+            let _ = fs::remove_file(path);
         }
     }
 
-    Ok(())
+    Ok(backup_file_path)
+}
+
+fn write_backup(
+    snapshot_path: &Path,
+    db_file_path: &Path,
+    compression: Option<&BackupCompression>,
+) -> Result<PathBuf, String> {
+    let conn = Connection::open(PATH_TO_DB)
+        .map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+    conn.execute("VACUUM INTO ?1", params![snapshot_path.to_string_lossy()])
+        .map_err(|e| format!("Не удалось создать копию БД: {}", e))?;
+    drop(conn);
+
+    let schema_version = verify_backup(snapshot_path)
+        .map_err(|e| format!("Созданная копия не прошла проверку: {}", e))?;
+    let row_counts = Connection::open_with_flags(snapshot_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|conn| table_row_counts(&conn))
+        .map_err(|e| format!("Не удалось подсчитать строки в копии: {}", e))?;
+    let database_sha256 = file_sha256(snapshot_path)
+        .map_err(|e| format!("Не удалось вычислить контрольную сумму копии: {}", e))?;
+
+    let backup_file_path = match compression {
+        Some(compression) => {
+            let mut name = db_file_path.as_os_str().to_owned();
+            name.push(compression.extension);
+            let compressed_path = PathBuf::from(name);
+            compress_file(snapshot_path, &compressed_path, compression)
+                .map_err(|e| format!("Не удалось сжать копию: {}", e))?;
+
+            // Проверяем, что сжатый файл распаковывается в ту же самую БД
+            let unpacked_sha256 = open_backup_reader(&compressed_path)
+                .and_then(|mut reader| sha256_hex(&mut reader))
+                .map_err(|e| format!("Не удалось прочитать сжатую копию: {}", e))?;
+            if unpacked_sha256 != database_sha256 {
+                let _ = fs::remove_file(&compressed_path);
+                return Err("Сжатая копия не совпадает с исходной".to_string());
+            }
+            compressed_path
+        }
+        None => db_file_path.to_path_buf(),
+    };
+
+    let manifest = BackupManifest {
+        created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        file_name: backup_file_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        compression: compression.map_or("none", |c| c.value).to_string(),
+        schema_version,
+        size_bytes: fs::metadata(&backup_file_path).map(|m| m.len()).unwrap_or(0),
+        sha256: file_sha256(&backup_file_path)
+            .map_err(|e| format!("Не удалось вычислить контрольную сумму копии: {}", e))?,
+        database_sha256,
+        row_counts,
+    };
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Не удалось сформировать манифест копии: {}", e))?;
+    fs::write(manifest_path(&backup_file_path), json)
+        .map_err(|e| format!("Не удалось записать манифест копии: {}", e))?;
+
+    Ok(backup_file_path)
 }
 pub fn get_last_backup_time(backup_dir: &str) -> Option<String> {
     let path = Path::new(backup_dir);
@@ -119,10 +292,146 @@ pub fn get_last_backup_time(backup_dir: &str) -> Option<String> {
 }
 pub fn backup_database_now_with_config(
     folder: Option<String>,
-    max_copies: Option<usize>
-) -> std::io::Result<()> {
+    max_copies: Option<usize>,
+    compression: Option<&BackupCompression>,
+) -> Result<PathBuf, String> {
     let backup_folder = folder.unwrap_or_else(|| "backup".to_string());
-    perform_backup(&backup_folder, max_copies)
+    perform_backup(&backup_folder, max_copies, compression)
+}
+/// Список резервных копий в папке, от новых к старым
+pub fn list_backups(backup_dir: &str) -> std::io::Result<Vec<BackupFile>> {
+    let path = Path::new(backup_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(path)?.filter_map(Result::ok) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let meta = entry.metadata()?;
+        if !meta.is_file() || !is_backup_file(&file_name) {
+            continue;
+        }
+        let modified: DateTime<Local> = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH).into();
+        backups.push(BackupFile {
+            file_name,
+            path: entry.path(),
+            size_bytes: meta.len(),
+            modified: modified.format("%Y-%m-%d %H:%M:%S").to_string(),
+        });
+    }
+
+    // Формат даты сортируется как строка
+    backups.sort_by(|a, b| b.modified.cmp(&a.modified));
+    Ok(backups)
+}
+
+/// Проверяет файл БД: целостность SQLite и совместимость версии схемы.
+/// Возвращает версию схемы копии.
+pub fn verify_backup(path: &Path) -> Result<i32, String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Не удалось открыть резервную копию: {}", e))?;
+
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| format!("Файл не является корректной базой данных: {}", e))?;
+    if integrity != "ok" {
+        return Err(format!("Проверка целостности не пройдена: {}", integrity));
+    }
+
+    let version = schema_version(&conn)
+        .map_err(|e| format!("Не удалось прочитать версию схемы копии: {}", e))?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Копия создана более новой версией приложения (схема {}, поддерживается {})",
+            version, SCHEMA_VERSION
+        ));
+    }
+
+    // Копии без миграций (версия 0) допустимы только если это БД платформы
+    let has_users: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'Users')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Не удалось прочитать структуру копии: {}", e))?;
+    if !has_users {
+        return Err("В файле нет таблиц платформы".to_string());
+    }
+
+    Ok(version)
+}
+
+// Сверяет контрольную сумму копии с манифестом, если он есть (у старых копий манифеста нет)
+fn verify_backup_checksum(backup_file: &Path) -> Result<(), String> {
+    let Ok(json) = fs::read_to_string(manifest_path(backup_file)) else {
+        return Ok(());
+    };
+    let manifest: BackupManifest = serde_json::from_str(&json)
+        .map_err(|e| format!("Манифест копии повреждён: {}", e))?;
+    let actual = file_sha256(backup_file)
+        .map_err(|e| format!("Не удалось вычислить контрольную сумму копии: {}", e))?;
+    if actual != manifest.sha256 {
+        return Err("Контрольная сумма копии не совпадает с манифестом".to_string());
+    }
+    Ok(())
+}
+
+/// Восстанавливает БД из резервной копии.
+/// Копия распаковывается, проверяется и доводится миграциями до текущей схемы во временном файле
+/// рядом с БД, текущая БД сохраняется в папку бэкапов, после чего временный файл атомарно заменяет рабочий.
+pub fn restore_backup(
+    backup_file: &Path,
+    backup_dir: &str,
+    max_copies: Option<usize>,
+    compression: Option<&BackupCompression>,
+) -> Result<(), String> {
+    verify_backup_checksum(backup_file)?;
+
+    let temp_path = format!("{}.restore", PATH_TO_DB);
+
+    let prepare = || -> Result<(), String> {
+        let mut reader = open_backup_reader(backup_file)
+            .map_err(|e| format!("Не удалось открыть резервную копию: {}", e))?;
+        let mut output = File::create(&temp_path)
+            .map_err(|e| format!("Не удалось создать временный файл: {}", e))?;
+        io::copy(&mut reader, &mut output)
+            .and_then(|_| output.sync_all())
+            .map_err(|e| format!("Не удалось распаковать резервную копию: {}", e))?;
+        drop(output);
+
+        verify_backup(Path::new(&temp_path))?;
+
+        let mut conn = Connection::open(&temp_path)
+            .map_err(|e| format!("Не удалось открыть восстановленную БД: {}", e))?;
+        run_migrations(&mut conn)?;
+        let integrity: String = conn
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .map_err(|e| format!("Не удалось проверить восстановленную БД: {}", e))?;
+        if integrity != "ok" {
+            return Err(format!("Проверка целостности после миграции не пройдена: {}", integrity));
+        }
+        Ok(())
+    };
+    if let Err(e) = prepare() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    // Снимок текущей БД, чтобы восстановление можно было откатить
+    if let Err(e) = perform_backup(backup_dir, max_copies, compression) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("Не удалось сохранить текущую БД перед восстановлением: {}", e));
+    }
+
+    fs::rename(&temp_path, PATH_TO_DB).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("Не удалось заменить файл БД: {}", e)
+    })?;
+
+    println!("DEBUG: БД восстановлена из {}", backup_file.display());
+    Ok(())
 }
 
 pub fn load_config() -> Option<Config> {
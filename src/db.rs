@@ -1,24 +1,28 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Cursor;
-use chrono::NaiveDate;
+use chrono::{Months, NaiveDate, NaiveDateTime};
 use image::imageops::FilterType;
 use image::ImageReader;
-use rusqlite::{params, Connection, OptionalExtension, Result, Error, ffi, Transaction, params_from_iter};
+use rusqlite::{params, Connection, OptionalExtension, Result, Error, ffi, Transaction, TransactionBehavior, params_from_iter};
 use serde::de::StdError;
 use tokio::task;
-use crate::app::state::{Assignment, Certificate, Course, Group, GroupForReport, GroupStatus, LessonWithAssignments, PastSession, Payment, StudentAttendanceStatus, UserInfo, PATH_TO_DB};
+use crate::password::{generate_recovery_codes, generate_temporary_password, hash_password, hash_recovery_code, verify_password, verify_recovery_code, PasswordCheck};
+use crate::schedule::{end_time, generate_session_dates, overlaps, WEEKDAY_SHORT_NAMES};
+use crate::audit;
+use crate::totp;
+use crate::app::state::{Assignment, AuditActor, AuditEntry, AuditFilter, LockedAccount, LoginAttempt, LoginOutcome, Permission, RegistrationDecision, RegistrationRequest, RegistrationReview, Role, AssignmentType, BankTransaction, Certificate, Enrollment, EnrollmentPayment, EnrollmentPickListItem, EnrollmentStatus, EnrollmentTerms, Course, Group, GroupForReport, GroupStatus, Installment, LessonWithAssignments, PastSession, Payment, PaymentDocument, PaymentDocumentKind, Payroll, PayrollLine, PlannedSession, PromoCode, RateType, ScheduleSlot, StudentAssignment, StudentAttendanceStatus, StudentBalance, StudentProgress, Submission, SubmissionFile, TeacherRate, UserInfo, WaitlistEntry, WaitlistStatus, BANK_TRANSFER_TYPE, DEFAULT_SESSION_MINUTES, PATH_TO_DB};
 
 
 pub async fn authenticate_and_get_user_data(
     email_input: String,
-    hashed_password: String,
+    password: String,
 ) -> std::result::Result<UserInfo, String> {
     task::spawn_blocking(move || {
         let conn = Connection::open(PATH_TO_DB)
             .map_err(|e| format!("Не удалось открыть базу данных: {}", e))?;
 
         let mut stmt = conn
-            .prepare("SELECT ID, Email, Name, AvatarData, Birthday, Type, password FROM Users WHERE Email = ?1")
+            .prepare("SELECT ID, Email, Name, AvatarData, Birthday, Type, password, totp_secret IS NOT NULL FROM Users WHERE Email = ?1")
             .map_err(|e| format!("Ошибка подготовки запроса: {}", e))?;
 
         let mut rows = stmt
@@ -32,28 +36,60 @@ pub async fn authenticate_and_get_user_data(
             let name: String = row.get(2).map_err(|e| format!("Ошибка получения имени: {}", e))?;
             let avatar_data: Option<Vec<u8>> = row.get(3).map_err(|e| format!("Ошибка получения аватара: {}", e))?;
             let birthday: String = row.get(4).map_err(|e| format!("Ошибка получения дня рождения: {}", e))?;
-            let user_type: String = row.get(5).map_err(|e| format!("Ошибка получения типа пользователя: {}", e))?;
+            let user_type: Role = row.get(5).map_err(|e| format!("Ошибка получения типа пользователя: {}", e))?;
             let stored_hash: String = row.get(6).map_err(|e| format!("Ошибка получения хэша пароля: {}", e))?;
+            let two_factor: bool = row.get(7).map_err(|e| format!("Ошибка получения настроек входа: {}", e))?;
 
             // **ОБЪЯВЛЯЕМ group и child_count здесь, чтобы они были видны в конце блока**
             let group_name: Option<String>; // Используем group_name, как было в UserInfo.group
             let child_count: Option<i32>;
 
-            if stored_hash == hashed_password {
+            // Пока учётная запись заблокирована, пароль даже не проверяем
+            if let Some(locked_until) = login_locked_until(&conn, id)
+                .map_err(|e| format!("Ошибка проверки блокировки входа: {}", e))?
+            {
+                record_login_attempt(&conn, Some(id), &email_input, LoginOutcome::Locked);
+                return Err(login_locked_message(locked_until));
+            }
+
+            let password_check = verify_password(&password, &stored_hash);
+
+            if password_check != PasswordCheck::Invalid {
+                // С двухфакторной аутентификацией вход завершится только после проверки кода
+                // (verify_second_factor) — до тех пор счётчик неудач не сбрасываем
+                if !two_factor {
+                    if let Err(e) = reset_failed_logins(&conn, id) {
+                        eprintln!("Не удалось сбросить счётчик неудачных входов пользователя {}: {}", id, e);
+                    }
+                    record_login_attempt(&conn, Some(id), &email_input, LoginOutcome::Success);
+                }
+
+                // Старый хэш пересчитываем при первом успешном входе, ошибка пересчёта вход не блокирует
+                if password_check == PasswordCheck::ValidLegacy {
+                    match hash_password(&password) {
+                        Ok(new_hash) => {
+                            if let Err(e) = update_user_password_hash(&conn, id, &new_hash) {
+                                eprintln!("Не удалось обновить хэш пароля пользователя {}: {}", id, e);
+                            }
+                        }
+                        Err(e) => eprintln!("{}", e),
+                    }
+                }
+
                 // ПРИСВАИВАЕМ ЗНАЧЕНИЯ group_name и child_count
-                group_name = match user_type.as_str() {
-                    "student" => db_get_group_name_for_student(&conn, id).unwrap_or_else(|e| {
+                group_name = match user_type {
+                    Role::Student => db_get_group_name_for_student(&conn, id).unwrap_or_else(|e| {
                         eprintln!("Ошибка получения группы для студента {}: {}", id, e);
                         None
                     }),
-                    "teacher" => db_get_group_name_for_teacher(&conn, id).unwrap_or_else(|e| {
+                    Role::Teacher => db_get_group_name_for_teacher(&conn, id).unwrap_or_else(|e| {
                         eprintln!("Ошибка получения группы для учителя {}: {}", id, e);
                         None
                     }),
                     _ => None,
                 };
 
-                child_count = if user_type == "parent" {
+                child_count = if user_type == Role::Parent {
                     db_get_child_count_for_parent(&conn, id).ok()
                 } else {
                     None
@@ -73,10 +109,14 @@ pub async fn authenticate_and_get_user_data(
                 })
             } else {
                 // Если пароль неверен, никаких group_name или child_count не будет
-                Err("Неверный пароль. Попробуйте снова.".to_string())
+                record_login_attempt(&conn, Some(id), &email_input, LoginOutcome::WrongPassword);
+                let locked_until = register_failed_login(&conn, id)
+                    .map_err(|e| format!("Ошибка учёта неудачного входа: {}", e))?;
+                Err(wrong_password_message(locked_until))
             }
         } else {
             // Если пользователь не найден
+            record_login_attempt(&conn, None, &email_input, LoginOutcome::UnknownUser);
             Err("Пользователь с таким email не найден.".to_string())
         }
     })
@@ -89,7 +129,8 @@ pub fn db_get_group_name_for_student(conn: &Connection, user_id: i32) -> Result<
     // Внимание: если студент может быть в нескольких группах, этот запрос вернет только одну.
     // Если нужно все группы, тип возвращаемого значения должен быть Vec<String>.
     conn.query_row(
-        "SELECT T2.name FROM GroupStudent AS T1 INNER JOIN 'Group' AS T2 ON T1.group_id = T2.id WHERE T1.student_id = ?1",
+        "SELECT T2.name FROM GroupStudent AS T1 INNER JOIN 'Group' AS T2 ON T1.group_id = T2.id
+         WHERE T1.student_id = ?1 AND T1.status IN ('approved', 'active')",
         params![user_id],
         |row| row.get::<_, String>(0),
     )
@@ -129,6 +170,380 @@ pub fn register_user(conn: &Connection, full_name: &str, birthday: &str, email:
     Ok(())
 }
 
+pub fn update_user_password_hash(conn: &Connection, user_id: i32, password_hash: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE Users SET password = ?1 WHERE ID = ?2",
+        params![password_hash, user_id],
+    )?;
+    Ok(())
+}
+
+/// Нужно ли пользователю сменить пароль при входе (пароль был сброшен администратором)
+pub fn must_change_password(conn: &Connection, user_id: i32) -> Result<bool> {
+    conn.query_row(
+        "SELECT must_change_password FROM Users WHERE ID = ?1",
+        params![user_id],
+        |row| row.get(0),
+    )
+}
+
+/// Смена пароля самим пользователем: текущий пароль должен подойти.
+/// Снимает требование сменить временный пароль.
+pub fn change_password(conn: &Connection, user_id: i32, current_password: &str, new_password: &str) -> Result<(), String> {
+    let stored_hash: String = conn
+        .query_row("SELECT password FROM Users WHERE ID = ?1", params![user_id], |row| row.get(0))
+        .map_err(|e| format!("Ошибка получения хэша пароля: {}", e))?;
+    if verify_password(current_password, &stored_hash) == PasswordCheck::Invalid {
+        return Err("Текущий пароль введён неверно.".to_string());
+    }
+    let new_hash = hash_password(new_password)?;
+    conn.execute(
+        "UPDATE Users SET password = ?1, must_change_password = 0 WHERE ID = ?2",
+        params![new_hash, user_id],
+    )
+    .map_err(|e| format!("Ошибка сохранения нового пароля: {}", e))?;
+    Ok(())
+}
+
+/// Сброс пароля администратором. Возвращает временный пароль, который нужно передать
+/// пользователю: он действует до первого входа, после чего его обязательно сменить.
+pub fn reset_password(conn: &Connection, user_id: i32) -> Result<String, String> {
+    let temporary_password = generate_temporary_password();
+    let password_hash = hash_password(&temporary_password)?;
+    let updated = conn
+        .execute(
+            "UPDATE Users SET password = ?1, must_change_password = 1 WHERE ID = ?2",
+            params![password_hash, user_id],
+        )
+        .map_err(|e| format!("Ошибка сброса пароля: {}", e))?;
+    if updated == 0 {
+        return Err("Пользователь не найден.".to_string());
+    }
+    Ok(temporary_password)
+}
+
+/// Сколько неиспользованных кодов восстановления осталось у пользователя
+pub fn count_recovery_codes(conn: &Connection, user_id: i32) -> Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM RecoveryCode WHERE user_id = ?1 AND used_at IS NULL",
+        params![user_id],
+        |row| row.get(0),
+    )
+}
+
+/// Выдаёт новый набор кодов восстановления, старые коды перестают действовать.
+/// Коды возвращаются открытым текстом только здесь — в БД хранятся их хэши.
+pub fn replace_recovery_codes(conn: &mut Connection, user_id: i32) -> Result<Vec<String>, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let codes = replace_recovery_codes_in_tx(&tx, user_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(codes)
+}
+
+fn replace_recovery_codes_in_tx(tx: &Connection, user_id: i32) -> Result<Vec<String>, String> {
+    let codes = generate_recovery_codes();
+    tx.execute("DELETE FROM RecoveryCode WHERE user_id = ?1", params![user_id])
+        .map_err(|e| format!("Ошибка удаления старых кодов восстановления: {}", e))?;
+    for code in &codes {
+        let code_hash = hash_recovery_code(code)?;
+        tx.execute(
+            "INSERT INTO RecoveryCode (user_id, code_hash, created_at) VALUES (?1, ?2, datetime('now', 'localtime'))",
+            params![user_id, code_hash],
+        )
+        .map_err(|e| format!("Ошибка сохранения кода восстановления: {}", e))?;
+    }
+    Ok(codes)
+}
+
+// Ищет среди неиспользованных кодов восстановления пользователя тот, что подходит к `code`.
+// Хэши солёные, поэтому по хэшу код не найти — проверяем каждый.
+fn find_recovery_code(conn: &Connection, user_id: i32, code: &str) -> Result<Option<i32>> {
+    let mut stmt = conn.prepare("SELECT id, code_hash FROM RecoveryCode WHERE user_id = ?1 AND used_at IS NULL")?;
+    let codes = stmt
+        .query_map(params![user_id], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(codes
+        .into_iter()
+        .find(|(_, code_hash)| verify_recovery_code(code, code_hash))
+        .map(|(id, _)| id))
+}
+
+// Отмечает неиспользованный код восстановления пользователя как использованный; false — код не подошёл
+fn consume_recovery_code(conn: &Connection, user_id: i32, code: &str) -> Result<bool> {
+    let Some(code_id) = find_recovery_code(conn, user_id, code)? else {
+        return Ok(false);
+    };
+    let updated = conn.execute(
+        "UPDATE RecoveryCode SET used_at = datetime('now', 'localtime') WHERE id = ?1 AND used_at IS NULL",
+        params![code_id],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Включена ли у пользователя двухфакторная аутентификация
+pub fn two_factor_enabled(conn: &Connection, user_id: i32) -> Result<bool> {
+    conn.query_row(
+        "SELECT totp_secret IS NOT NULL FROM Users WHERE ID = ?1",
+        params![user_id],
+        |row| row.get(0),
+    )
+}
+
+// Проверяет код из приложения-аутентификатора. Принятый шаг запоминается,
+// поэтому один и тот же код дважды не сработает.
+fn check_totp_code(conn: &Connection, user_id: i32, code: &str) -> Result<bool> {
+    let (secret, last_step): (Option<String>, Option<i64>) = conn.query_row(
+        "SELECT totp_secret, totp_last_step FROM Users WHERE ID = ?1",
+        params![user_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let Some(step) = secret.and_then(|secret| totp::verify(&secret, code, totp::now())) else {
+        return Ok(false);
+    };
+    let step = step as i64;
+    if last_step.is_some_and(|last| step <= last) {
+        return Ok(false);
+    }
+    conn.execute("UPDATE Users SET totp_last_step = ?1 WHERE ID = ?2", params![step, user_id])?;
+    Ok(true)
+}
+
+/// Включает двухфакторную аутентификацию, если `code` подходит к новому секрету
+/// (так проверяем, что секрет действительно добавлен в приложение). Возвращает новый
+/// набор кодов восстановления — ими можно войти, если телефон потерян.
+pub fn enable_two_factor(conn: &mut Connection, user_id: i32, secret: &str, code: &str) -> Result<Vec<String>, String> {
+    let step = totp::verify(secret, code, totp::now())
+        .ok_or("Код не подходит. Проверьте, что время на телефоне точное, и введите новый код.")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE Users SET totp_secret = ?1, totp_last_step = ?2, totp_enabled_at = datetime('now', 'localtime') WHERE ID = ?3",
+        params![secret, step as i64, user_id],
+    )
+    .map_err(|e| format!("Ошибка сохранения секрета: {}", e))?;
+    let codes = replace_recovery_codes_in_tx(&tx, user_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(codes)
+}
+
+/// Отключает двухфакторную аутентификацию; нужен действующий код из приложения.
+pub fn disable_two_factor(conn: &Connection, user_id: i32, code: &str) -> Result<(), String> {
+    if !check_totp_code(conn, user_id, code).map_err(|e| format!("Ошибка проверки кода: {}", e))? {
+        return Err("Код не подходит.".to_string());
+    }
+    conn.execute(
+        "UPDATE Users SET totp_secret = NULL, totp_last_step = NULL, totp_enabled_at = NULL WHERE ID = ?1",
+        params![user_id],
+    )
+    .map_err(|e| format!("Ошибка отключения двухфакторной аутентификации: {}", e))?;
+    Ok(())
+}
+
+/// Второй шаг входа: код из приложения-аутентификатора или один из кодов восстановления.
+/// Неверный код считается неудачной попыткой входа, как и неверный пароль.
+pub fn verify_second_factor(conn: &Connection, user_id: i32, email: &str, code: &str) -> Result<(), String> {
+    if let Some(locked_until) = login_locked_until(conn, user_id).map_err(|e| e.to_string())? {
+        record_login_attempt(conn, Some(user_id), email, LoginOutcome::Locked);
+        return Err(login_locked_message(locked_until));
+    }
+
+    let accepted = check_totp_code(conn, user_id, code)
+        .and_then(|ok| if ok { Ok(true) } else { consume_recovery_code(conn, user_id, code) })
+        .map_err(|e| format!("Ошибка проверки кода: {}", e))?;
+    if !accepted {
+        record_login_attempt(conn, Some(user_id), email, LoginOutcome::WrongSecondFactor);
+        let locked_until = register_failed_login(conn, user_id)
+            .map_err(|e| format!("Ошибка учёта неудачного входа: {}", e))?;
+        return Err(match locked_until {
+            Some(until) => format!("Неверный код. {}", login_locked_message(until)),
+            None => "Неверный код. Попробуйте снова.".to_string(),
+        });
+    }
+
+    reset_failed_logins(conn, user_id).map_err(|e| e.to_string())?;
+    record_login_attempt(conn, Some(user_id), email, LoginOutcome::Success);
+    Ok(())
+}
+
+/// Восстановление доступа без администратора: по email и одному из кодов восстановления
+/// задаёт новый пароль. Использованный код больше не действует. Неверный код считается
+/// неудачной попыткой входа — перебор кодов ограничен так же, как перебор паролей.
+pub fn recover_password(conn: &mut Connection, email: &str, code: &str, new_password: &str) -> Result<(), String> {
+    // Не уточняем, что именно не подошло, чтобы по ответу нельзя было проверять адреса
+    let rejected = "Неверный email или код восстановления.".to_string();
+    let user_id: Option<i32> = conn
+        .query_row("SELECT ID FROM Users WHERE Email = ?1", params![email], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Ошибка поиска пользователя: {}", e))?;
+    let Some(user_id) = user_id else {
+        record_login_attempt(conn, None, email, LoginOutcome::RecoveryFailed);
+        return Err(rejected);
+    };
+    if let Some(locked_until) = login_locked_until(conn, user_id).map_err(|e| e.to_string())? {
+        record_login_attempt(conn, Some(user_id), email, LoginOutcome::Locked);
+        return Err(login_locked_message(locked_until));
+    }
+
+    let code_id = find_recovery_code(conn, user_id, code)
+        .map_err(|e| format!("Ошибка проверки кода восстановления: {}", e))?;
+    let Some(code_id) = code_id else {
+        record_login_attempt(conn, Some(user_id), email, LoginOutcome::RecoveryFailed);
+        register_failed_login(conn, user_id).map_err(|e| format!("Ошибка учёта неудачного входа: {}", e))?;
+        return Err(rejected);
+    };
+
+    let new_hash = hash_password(new_password)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE RecoveryCode SET used_at = datetime('now', 'localtime') WHERE id = ?1",
+        params![code_id],
+    )
+    .map_err(|e| format!("Ошибка отметки кода восстановления: {}", e))?;
+    tx.execute(
+        "UPDATE Users SET password = ?1, must_change_password = 0 WHERE ID = ?2",
+        params![new_hash, user_id],
+    )
+    .map_err(|e| format!("Ошибка сохранения нового пароля: {}", e))?;
+    reset_failed_logins(&tx, user_id).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    record_login_attempt(conn, Some(user_id), email, LoginOutcome::Recovered);
+    Ok(())
+}
+
+/// После скольких неудачных попыток подряд учётная запись блокируется
+pub const MAX_FAILED_LOGINS: i64 = 5;
+/// На сколько минут блокируется учётная запись
+pub const LOCKOUT_MINUTES: i64 = 15;
+
+// Пауза перед следующей попыткой после `failed` неудач подряд: первые две ошибки прощаются,
+// дальше пауза растёт, а с MAX_FAILED_LOGINS учётная запись блокируется на LOCKOUT_MINUTES
+fn login_delay(failed: i64) -> Option<chrono::Duration> {
+    match failed {
+        ..=2 => None,
+        3 => Some(chrono::Duration::seconds(10)),
+        4 => Some(chrono::Duration::seconds(30)),
+        _ => Some(chrono::Duration::minutes(LOCKOUT_MINUTES)),
+    }
+}
+
+// До какого времени запрещён вход; None — можно входить
+fn login_locked_until(conn: &Connection, user_id: i32) -> Result<Option<NaiveDateTime>> {
+    let locked_until: Option<String> = conn
+        .query_row(
+            "SELECT locked_until FROM LoginThrottle WHERE user_id = ?1",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    let now = chrono::Local::now().naive_local();
+    Ok(locked_until
+        .and_then(|value| NaiveDateTime::parse_from_str(&value, "%Y-%m-%d %H:%M:%S").ok())
+        .filter(|until| *until > now))
+}
+
+// Учитывает неудачную попытку и возвращает, до какого времени теперь запрещён вход
+fn register_failed_login(conn: &Connection, user_id: i32) -> Result<Option<NaiveDateTime>> {
+    let failed: i64 = conn.query_row(
+        "INSERT INTO LoginThrottle (user_id, failed_count, last_failed_at) VALUES (?1, 1, datetime('now', 'localtime'))
+         ON CONFLICT(user_id) DO UPDATE SET failed_count = failed_count + 1, last_failed_at = excluded.last_failed_at
+         RETURNING failed_count",
+        params![user_id],
+        |row| row.get(0),
+    )?;
+    let locked_until = login_delay(failed).map(|delay| chrono::Local::now().naive_local() + delay);
+    conn.execute(
+        "UPDATE LoginThrottle SET locked_until = ?1 WHERE user_id = ?2",
+        params![locked_until.map(|until| until.format("%Y-%m-%d %H:%M:%S").to_string()), user_id],
+    )?;
+    Ok(locked_until)
+}
+
+fn reset_failed_logins(conn: &Connection, user_id: i32) -> Result<()> {
+    conn.execute("DELETE FROM LoginThrottle WHERE user_id = ?1", params![user_id])?;
+    Ok(())
+}
+
+// История входов ведётся «по возможности»: сбой записи не должен мешать входу
+fn record_login_attempt(conn: &Connection, user_id: Option<i32>, email: &str, outcome: LoginOutcome) {
+    if let Err(e) = conn.execute(
+        "INSERT INTO LoginHistory (user_id, email, attempted_at, outcome) VALUES (?1, ?2, datetime('now', 'localtime'), ?3)",
+        params![user_id, email, outcome],
+    ) {
+        eprintln!("Не удалось записать попытку входа в историю: {}", e);
+    }
+}
+
+fn login_locked_message(locked_until: NaiveDateTime) -> String {
+    let millis_left = (locked_until - chrono::Local::now().naive_local()).num_milliseconds();
+    let seconds_left = ((millis_left + 999) / 1000).max(1);
+    if seconds_left > 60 {
+        format!(
+            "Слишком много неудачных попыток. Учётная запись заблокирована до {}.",
+            locked_until.format("%H:%M")
+        )
+    } else {
+        format!("Слишком много неудачных попыток. Повторите через {} с.", seconds_left)
+    }
+}
+
+fn wrong_password_message(locked_until: Option<NaiveDateTime>) -> String {
+    match locked_until {
+        Some(until) => format!("Неверный пароль. {}", login_locked_message(until)),
+        None => "Неверный пароль. Попробуйте снова.".to_string(),
+    }
+}
+
+/// История входов, новые сверху. Фильтр по части email; `failures_only` — только неудачные попытки.
+pub fn get_login_history(conn: &Connection, email_filter: &str, failures_only: bool) -> Result<Vec<LoginAttempt>> {
+    let mut stmt = conn.prepare(
+        "SELECT H.attempted_at, H.email, U.Name, H.outcome
+         FROM LoginHistory H
+         LEFT JOIN Users U ON U.ID = H.user_id
+         WHERE (?1 = '' OR H.email LIKE '%' || ?1 || '%')
+           AND (?2 = 0 OR H.outcome NOT IN (?3, ?4))
+         ORDER BY H.id DESC
+         LIMIT 500",
+    )?;
+    stmt.query_map(
+        params![email_filter.trim(), failures_only, LoginOutcome::Success, LoginOutcome::Recovered],
+        |row| {
+            Ok(LoginAttempt {
+                attempted_at: row.get(0)?,
+                email: row.get(1)?,
+                user_name: row.get(2)?,
+                outcome: row.get(3)?,
+            })
+        },
+    )?
+    .collect()
+}
+
+/// Учётные записи, вход в которые сейчас запрещён из-за неудачных попыток
+pub fn get_locked_accounts(conn: &Connection) -> Result<Vec<LockedAccount>> {
+    let mut stmt = conn.prepare(
+        "SELECT T.user_id, U.Name, U.Email, T.failed_count, T.locked_until
+         FROM LoginThrottle T
+         JOIN Users U ON U.ID = T.user_id
+         WHERE T.locked_until > datetime('now', 'localtime')
+         ORDER BY T.locked_until DESC",
+    )?;
+    stmt.query_map([], |row| {
+        Ok(LockedAccount {
+            user_id: row.get(0)?,
+            name: row.get(1)?,
+            email: row.get(2)?,
+            failed_count: row.get(3)?,
+            locked_until: row.get(4)?,
+        })
+    })?
+    .collect()
+}
+
+/// Снимает блокировку входа досрочно (администратором) и обнуляет счётчик неудач
+pub fn unlock_account(conn: &Connection, user_id: i32) -> Result<()> {
+    reset_failed_logins(conn, user_id)
+}
+
 // Обновлено для приема avatar_data как &[u8]
 pub fn update_user_avatar(conn: &Connection, email: &str, raw_image_data: &[u8]) -> Result<()> {
     // Вспомогательная функция для преобразования произвольных ошибок в rusqlite::Error::SqliteFailure
@@ -278,6 +693,8 @@ pub fn update_course(conn: &Connection, course: &Course) -> Result<()> {
             course.id
         ],
     )?;
+    // Если мест стало больше, они достаются ожидающим в листе ожидания
+    offer_waitlist_seats(conn, course.id)?;
     Ok(())
 }
 pub fn get_all_groups(conn: &Connection) -> Result<Vec<Group>> {
@@ -460,7 +877,7 @@ pub fn delete_lesson(conn: &Connection, lesson_id: i32) -> Result<()> {
     conn.execute("DELETE FROM Lessons WHERE ID = ?1", params![lesson_id])?;
     Ok(())
 }
-pub fn get_all_users_for_list(conn: &Connection, user_type_filter: Option<&str>) -> Result<Vec<UserInfo>> {
+pub fn get_all_users_for_list(conn: &Connection, user_type_filter: Option<Role>) -> Result<Vec<UserInfo>> {
     let mut query = "
         SELECT
             U.ID, U.Name, U.Email, U.Birthday, U.Type, U.AvatarData,
@@ -470,18 +887,18 @@ pub fn get_all_users_for_list(conn: &Connection, user_type_filter: Option<&str>)
             CASE WHEN U.Type = 'teacher' THEN GROUP_CONCAT(G_teacher.name, ', ') ELSE NULL END AS TeacherGroups,
             COUNT(PS.student_id) AS ChildCount
         FROM Users U
-        LEFT JOIN GroupStudent GSS ON U.ID = GSS.student_id -- For students' groups
+        LEFT JOIN GroupStudent GSS ON U.ID = GSS.student_id AND GSS.status IN ('approved', 'active') -- For students' groups
         LEFT JOIN \"Group\" GS_student ON GSS.group_id = GS_student.id -- For students' group names
         LEFT JOIN \"Group\" G_teacher ON U.ID = G_teacher.teacher_id -- For teachers' groups (new join)
         LEFT JOIN ParentStudent PS ON U.ID = PS.parent_id
     ".to_string();
 
-    let mut owned_params: Vec<String> = Vec::new();
+    let mut owned_params: Vec<Role> = Vec::new();
     let mut params_refs: Vec<&dyn rusqlite::ToSql> = Vec::new();
 
     if let Some(filter_type) = user_type_filter {
         query.push_str(" WHERE U.Type = ?1");
-        owned_params.push(filter_type.to_string());
+        owned_params.push(filter_type);
         params_refs.push(&owned_params[0]);
     }
 
@@ -493,12 +910,12 @@ pub fn get_all_users_for_list(conn: &Connection, user_type_filter: Option<&str>)
     let mut stmt = conn.prepare(&query)?;
 
     let user_iter = stmt.query_map(params_refs.as_slice(), |row| {
-        let user_type: String = row.get("Type")?;
+        let user_type: Role = row.get("Type")?;
 
         // Determine the 'group' field based on user_type
-        let group_info = if user_type == "student" {
+        let group_info = if user_type == Role::Student {
             row.get("StudentGroups").ok()
-        } else if user_type == "teacher" { // <--- Get TeacherGroups here
+        } else if user_type == Role::Teacher { // <--- Get TeacherGroups here
             row.get("TeacherGroups").ok()
         } else {
             None
@@ -509,10 +926,10 @@ pub fn get_all_users_for_list(conn: &Connection, user_type_filter: Option<&str>)
             name: row.get("Name")?,
             email: row.get("Email")?,
             birthday: row.get("Birthday")?,
-            user_type: user_type.clone(),
+            user_type,
             avatar_data: row.get("AvatarData").ok(),
             group_id: group_info, // <--- Assign the determined group_info
-            child_count: if user_type == "parent" { Some(row.get("ChildCount")?) } else { None },
+            child_count: if user_type == Role::Parent { Some(row.get("ChildCount")?) } else { None },
         })
     })?;
     user_iter.collect()
@@ -523,7 +940,7 @@ pub fn update_user(
     new_name: &str,
     new_email: &str,
     birthday: &str,
-    user_type: &str,
+    user_type: Role,
 ) -> Result<()> {
     conn.execute(
         "UPDATE Users SET Name = ?, Email = ?, Birthday = ?, Type = ? WHERE Email = ?",
@@ -563,7 +980,7 @@ pub fn get_student_group_by_user_id(conn: &Connection, user_id: i32) -> Result<O
         JOIN GroupStudent GS ON G.id = GS.group_id
         JOIN Users U ON G.teacher_id = U.ID
         JOIN Course C ON G.course_id = C.ID
-        WHERE GS.student_id = ?1
+        WHERE GS.student_id = ?1 AND GS.status IN ('approved', 'active')
     ")?;
 
     let group_opt = stmt.query_row(params![user_id], |row| {
@@ -598,7 +1015,7 @@ pub fn get_students_without_group(conn: &Connection) -> Result<Vec<UserInfo>> {
             U.AvatarData
         FROM Users U
         WHERE U.Type = 'student'
-        AND U.ID NOT IN (SELECT student_id FROM GroupStudent) -- ИСПРАВЛЕНО ЗДЕСЬ: student_id
+        AND U.ID NOT IN (SELECT student_id FROM GroupStudent WHERE status IN ('approved', 'active')) -- ИСПРАВЛЕНО ЗДЕСЬ: student_id
         ORDER BY U.Name
     ")?;
 
@@ -627,12 +1044,61 @@ pub fn insert_group(conn: &Connection, name: &str, course_id: i32, teacher_id: i
     Ok(())
 }
 
-pub fn update_group(conn: &Connection, id: i32, name: &str, course_id: i32, teacher_id: i32, status: GroupStatus) -> Result<()> {
-    conn.execute(
+/// Сохраняет группу. Если у активной группы меняется преподаватель или группа снова
+/// становится активной, её расписание заново проверяется на пересечения с другими группами —
+/// при конфликте изменения не сохраняются.
+pub fn update_group(
+    conn: &mut Connection,
+    id: i32,
+    name: &str,
+    course_id: i32,
+    teacher_id: i32,
+    status: GroupStatus,
+) -> Result<(), String> {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Не удалось начать транзакцию: {}", e))?;
+
+    let (old_course_id, old_teacher_id, old_status): (Option<i32>, Option<i32>, GroupStatus) = tx
+        .query_row(
+            "SELECT course_id, teacher_id, status FROM \"Group\" WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| format!("Группа не найдена: {}", e))?;
+
+    let schedule_affected =
+        status == GroupStatus::Active && (old_status != GroupStatus::Active || old_teacher_id != Some(teacher_id));
+    if schedule_affected {
+        let mut conflicts = Vec::new();
+        for slot in get_schedule_slots(&tx, id).map_err(|e| format!("Не удалось загрузить расписание: {}", e))? {
+            let slot_conflicts = find_schedule_conflicts(&tx, id, Some(teacher_id), &slot)
+                .map_err(|e| format!("Не удалось проверить расписание: {}", e))?;
+            for conflict in slot_conflicts {
+                // Пересечение двух слотов самой группы находится дважды — с каждой стороны
+                let line = format!("{} {}: {}", WEEKDAY_SHORT_NAMES[(slot.weekday - 1) as usize], slot.start_time, conflict);
+                if !conflicts.contains(&line) {
+                    conflicts.push(line);
+                }
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(format!("Группа не сохранена, расписание пересекается:\n{}", conflicts.join("\n")));
+        }
+    }
+
+    tx.execute(
         "UPDATE \"Group\" SET name = ?, course_id = ?, teacher_id = ?, status = ? WHERE id = ?",
         params![name, course_id, teacher_id, status, id],
-    )?;
-    Ok(())
+    )
+    .map_err(|e| format!("Ошибка обновления группы: {}", e))?;
+
+    // Неактивная группа (или группа, переведённая на другой курс) освобождает места курса
+    offer_waitlist_seats(&tx, course_id).map_err(|e| e.to_string())?;
+    if let Some(old_course_id) = old_course_id.filter(|old| *old != course_id) {
+        offer_waitlist_seats(&tx, old_course_id).map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| format!("Ошибка обновления группы: {}", e))
 }
 
 pub fn delete_group(conn: &Connection, id: i32) -> Result<()> {
@@ -640,117 +1106,694 @@ pub fn delete_group(conn: &Connection, id: i32) -> Result<()> {
     Ok(())
 }
 
-pub fn add_student_to_group(conn: &mut Connection, student_id: i32, group_id: i32) -> Result<()> {
-    println!("DEBUG DB: Добавление студента ID: {} в группу ID: {}", student_id, group_id);
-
-    let tx = conn.transaction()?;
-
-    // 1. Добавляем студента в GroupStudent
-    tx.execute(
-        "INSERT INTO GroupStudent (student_id, group_id) VALUES (?1, ?2)",
-        params![student_id, group_id],
-    )?;
+/// Зачисляет студента в группу с первой оплатой одной транзакцией: применение промокода,
+/// зачисление, платёж, счёт и квитанция, скидка и график рассрочки. Если какой-то шаг
+/// не удался (например, исчерпан лимит промокода), не сохраняется ничего.
+/// Возвращает ID зачисления.
+pub fn enroll_with_payment(
+    conn: &mut Connection,
+    student_id: i32,
+    group_id: i32,
+    payment: &EnrollmentPayment,
+) -> Result<i32, String> {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let date = payment.date.format("%Y-%m-%d").to_string();
+
+    // Промокод могли исчерпать или удалить, пока открыта форма; применение учитываем первым
+    if let Some(promo_id) = payment.promo_code_id {
+        let promo = get_promo_codes(&tx)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|p| p.id == promo_id)
+            .ok_or("Промокод был удалён")?;
+        promo.check(&date)?;
+        tx.execute("UPDATE PromoCode SET used_count = used_count + 1 WHERE id = ?1", params![promo_id])
+            .map_err(|e| format!("Ошибка учёта применения промокода: {}", e))?;
+    }
 
-    // 2. Пересчитываем количество студентов в группе
-    let new_student_count: i32 = tx.query_row(
-        "SELECT COUNT(*) FROM GroupStudent WHERE group_id = ?1",
-        params![group_id],
-        |row| row.get(0),
+    let enrollment_id = enroll_student_in_tx(
+        &tx,
+        student_id,
+        group_id,
+        EnrollmentStatus::Active,
+        EnrollmentTerms::Standard,
+        None,
     )?;
+    let payment_id = add_payment(
+        &tx,
+        student_id,
+        &date,
+        payment.first_payment,
+        &payment.payment_type,
+        payment.course_id,
+        group_id,
+    )
+    .map_err(|e| format!("Ошибка добавления платежа: {}", e))?;
+    issue_payment_documents_in_tx(&tx, payment_id as i32)
+        .map_err(|e| format!("Ошибка выписки документов: {}", e))?;
+
+    if payment.discount > 0.0 {
+        apply_enrollment_discount(
+            &tx,
+            enrollment_id,
+            payment_id,
+            payment.discount,
+            &payment.discount_note,
+            payment.promo_code_id,
+        )?;
+    }
 
-    // 3. Обновляем student_count в таблице "Group"
-    // ИСПРАВЛЕНО: Добавлены кавычки вокруг "Group"
-    tx.execute(
-        "UPDATE \"Group\" SET student_count = ?1 WHERE id = ?2",
-        params![new_student_count, group_id],
-    )?;
+    // График оставшихся платежей
+    if payment.installments > 1 {
+        create_installment_plan_in_tx(&tx, enrollment_id, payment.total, payment.installments, payment.date)
+            .map_err(|e| format!("Не удалось сохранить график платежей: {}", e))?;
+    }
 
-    tx.commit()?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(enrollment_id)
+}
 
-    println!("DEBUG DB: student_count для группы ID {} обновлен до {}", group_id, new_student_count);
-    Ok(())
+/// Создаёт зачисление студента в группу на выбранном этапе. Уже поданная заявка
+/// (или одобренное зачисление) в эту группу не дублируется, а переводится на новый этап.
+/// Возвращает ID зачисления (строки GroupStudent).
+pub fn enroll_student(
+    conn: &mut Connection,
+    student_id: i32,
+    group_id: i32,
+    status: EnrollmentStatus,
+    terms: EnrollmentTerms,
+    payment_due: Option<&str>,
+) -> Result<i32, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let enrollment_id = enroll_student_in_tx(&tx, student_id, group_id, status, terms, payment_due)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(enrollment_id)
 }
 
-pub fn remove_student_from_group(conn: &mut Connection, student_id: i32, group_id: i32) -> Result<()> {
-    println!("DEBUG DB: Удаление студента ID: {} из группы ID: {}", student_id, group_id);
+/// Зачисление внутри уже открытой транзакции — для операций, где оно лишь один из шагов
+fn enroll_student_in_tx(
+    tx: &Connection,
+    student_id: i32,
+    group_id: i32,
+    status: EnrollmentStatus,
+    terms: EnrollmentTerms,
+    payment_due: Option<&str>,
+) -> Result<i32, String> {
+    println!(
+        "DEBUG DB: Зачисление студента ID: {} в группу ID: {} ({})",
+        student_id, group_id, status.as_str()
+    );
+
+    let course_id: Option<i32> = tx
+        .query_row("SELECT course_id FROM \"Group\" WHERE id = ?1", params![group_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .flatten();
+    let existing: Option<(i32, EnrollmentStatus)> = tx
+        .query_row(
+            "SELECT id, status FROM GroupStudent
+             WHERE student_id = ?1 AND group_id = ?2 AND status IN ('applied', 'approved', 'active')
+             ORDER BY id DESC LIMIT 1",
+            params![student_id, group_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
 
-    // Начало транзакции для атомарности операций
-    let tx = conn.transaction()?;
+    if let Some((_, current)) = existing
+        && (current == status || !current.next_statuses().contains(&status))
+    {
+        return Err(format!("Студент уже числится в группе: {}.", current));
+    }
 
-    // 1. Удаляем студента из GroupStudent
-    tx.execute(
-        "DELETE FROM GroupStudent WHERE student_id = ?1 AND group_id = ?2",
-        params![student_id, group_id],
-    )?;
+    // Место на курсе проверяем, только если зачисление начинает его занимать
+    let takes_seat = status.holds_seat() && !existing.is_some_and(|(_, current)| current.holds_seat());
+    if takes_seat && let Some(course_id) = course_id {
+        ensure_seat_available(tx, student_id, course_id)?;
+    }
+
+    // 1. Заявка переходит на новый этап, иначе — новое зачисление с ценой курса на сегодня
+    let enrollment_id = match existing {
+        Some((enrollment_id, _)) => {
+            tx.execute(
+                "UPDATE GroupStudent
+                 SET status = ?1, terms = ?2, payment_due = ?3, status_changed_at = datetime('now', 'localtime')
+                 WHERE id = ?4",
+                params![status, terms, payment_due, enrollment_id],
+            )
+            .map_err(|e| e.to_string())?;
+            enrollment_id
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO GroupStudent (student_id, group_id, price, enrolled_at, status, terms, payment_due, status_changed_at)
+                 VALUES (?1, ?2,
+                         (SELECT C.price FROM \"Group\" G JOIN Course C ON C.ID = G.course_id WHERE G.id = ?2),
+                         date('now', 'localtime'), ?3, ?4, ?5, datetime('now', 'localtime'))",
+                params![student_id, group_id, status, terms, payment_due],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.last_insert_rowid() as i32
+        }
+    };
+
+    if takes_seat {
+        // 2. Пересчитываем количество студентов в группе
+        refresh_group_student_count(tx, group_id).map_err(|e| e.to_string())?;
+
+        // 3. Запись студента в листе ожидания этого курса выполнена
+        tx.execute(
+            "UPDATE Waitlist SET status = ?3, closed_at = datetime('now', 'localtime')
+             WHERE student_id = ?1
+               AND status IN ('waiting', 'offered')
+               AND course_id = (SELECT course_id FROM \"Group\" WHERE id = ?2)",
+            params![student_id, group_id, WaitlistStatus::Enrolled],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(enrollment_id)
+}
 
-    // 2. Пересчитываем количество студентов в группе
-    let new_student_count: i32 = tx.query_row(
-        "SELECT COUNT(*) FROM GroupStudent WHERE group_id = ?1",
+/// Пересчитывает student_count группы по зачислениям, занимающим место
+fn refresh_group_student_count(conn: &Connection, group_id: i32) -> Result<i32> {
+    let new_student_count: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM GroupStudent WHERE group_id = ?1 AND status IN ('approved', 'active')",
         params![group_id],
         |row| row.get(0),
     )?;
-    // 3. Обновляем student_count в таблице "Group"
-    // ИСПРАВЛЕНО: Добавлены кавычки вокруг "Group"
-    tx.execute(
+    // Обновление student_count запускает триггер пересчёта свободных мест курса
+    conn.execute(
         "UPDATE \"Group\" SET student_count = ?1 WHERE id = ?2",
         params![new_student_count, group_id],
     )?;
+    println!("DEBUG DB: student_count для группы ID {} обновлен до {}", group_id, new_student_count);
+    Ok(new_student_count)
+}
 
-    // Коммитим транзакцию
-    tx.commit()?;
+/// Отчисляет студента из группы: зачисление остаётся в истории со статусом «отчислен»,
+/// график будущих платежей удаляется
+pub fn remove_student_from_group(conn: &mut Connection, student_id: i32, group_id: i32) -> Result<(), String> {
+    println!("DEBUG DB: Отчисление студента ID: {} из группы ID: {}", student_id, group_id);
+
+    let enrollment_id: Option<i32> = conn
+        .query_row(
+            "SELECT id FROM GroupStudent
+             WHERE student_id = ?1 AND group_id = ?2 AND status IN ('approved', 'active')
+             ORDER BY id DESC LIMIT 1",
+            params![student_id, group_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match enrollment_id {
+        Some(enrollment_id) => set_enrollment_status(conn, enrollment_id, EnrollmentStatus::Dropped),
+        None => Ok(()),
+    }
+}
 
-    println!("DEBUG DB: student_count для группы ID {} обновлен до {}", group_id, new_student_count);
+/// Переводит зачисление на следующий этап жизненного цикла. Завершение, отчисление
+/// и перевод фиксируют дату окончания и освобождают место для листа ожидания.
+pub fn set_enrollment_status(conn: &mut Connection, enrollment_id: i32, status: EnrollmentStatus) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (student_id, group_id, current, course_id): (i32, i32, EnrollmentStatus, Option<i32>) = tx
+        .query_row(
+            "SELECT GS.student_id, GS.group_id, GS.status, G.course_id
+             FROM GroupStudent GS
+             JOIN \"Group\" G ON G.id = GS.group_id
+             WHERE GS.id = ?1",
+            params![enrollment_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| format!("Зачисление не найдено: {}", e))?;
+
+    if !current.next_statuses().contains(&status) {
+        return Err(format!("Нельзя перевести зачисление из этапа «{}» в «{}».", current, status));
+    }
+    if status.holds_seat() && !current.holds_seat() && let Some(course_id) = course_id {
+        ensure_seat_available(&tx, student_id, course_id)?;
+    }
+
+    let ends = !status.holds_seat() && status != EnrollmentStatus::Applied;
+    tx.execute(
+        "UPDATE GroupStudent
+         SET status = ?1,
+             status_changed_at = datetime('now', 'localtime'),
+             ended_at = CASE WHEN ?2 THEN date('now', 'localtime') ELSE ended_at END
+         WHERE id = ?3",
+        params![status, ends, enrollment_id],
+    )
+    .map_err(|e| e.to_string())?;
+    if status == EnrollmentStatus::Dropped {
+        tx.execute("DELETE FROM Installment WHERE group_student_id = ?1", params![enrollment_id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if status.holds_seat() != current.holds_seat() {
+        refresh_group_student_count(&tx, group_id).map_err(|e| e.to_string())?;
+        if status.holds_seat() {
+            tx.execute(
+                "UPDATE Waitlist SET status = ?3, closed_at = datetime('now', 'localtime')
+                 WHERE student_id = ?1 AND course_id = ?2 AND status IN ('waiting', 'offered')",
+                params![student_id, course_id, WaitlistStatus::Enrolled],
+            )
+            .map_err(|e| e.to_string())?;
+        } else if let Some(course_id) = course_id {
+            // Освободившееся место предлагаем следующему в листе ожидания курса
+            offer_waitlist_seats(&tx, course_id).map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    println!(
+        "DEBUG DB: зачисление #{} переведено из «{}» в «{}»",
+        enrollment_id, current, status
+    );
     Ok(())
 }
 
-
-pub fn get_students_in_group(conn: &Connection, group_id: i32) -> Result<Vec<UserInfo>> {
-    println!("DEBUG DB: Загрузка студентов для group_id: {}", group_id);
-    let mut stmt = conn.prepare("
+// Зачисления по условию `filter` (параметр ?1) с группами, связанными переводом
+fn query_enrollments(conn: &Connection, filter: &str, id: i32) -> Result<Vec<Enrollment>> {
+    let mut stmt = conn.prepare(&format!("
         SELECT
-            U.ID,
-            U.Name,
-            U.Email,
-            U.Birthday,
-            U.Type,
-            U.AvatarData
-        FROM Users U
-        JOIN GroupStudent GS ON U.ID = GS.student_id 
-        WHERE GS.group_id = ?1
-        ORDER BY U.Name
-    ")?;
-
-    let students_iter = stmt.query_map(params![group_id], |row| {
-        Ok(UserInfo {
-            id: row.get("ID")?,
-            name: row.get("Name")?,
-            email: row.get("Email")?,
-            birthday: row.get("Birthday")?,
-            user_type: row.get("Type")?,
-            avatar_data: row.get("AvatarData")?,
-            group_id: None,
-            child_count: None,
+            GS.id,
+            U.Name AS student_name,
+            G.name AS group_name,
+            GS.status,
+            GS.terms,
+            GS.payment_due,
+            GS.enrolled_at,
+            GS.status_changed_at,
+            GS.ended_at,
+            (SELECT PG.name FROM GroupStudent Prev JOIN \"Group\" PG ON PG.id = Prev.group_id
+             WHERE Prev.id = GS.transferred_from_id) AS transferred_from,
+            (SELECT NG.name FROM GroupStudent Next JOIN \"Group\" NG ON NG.id = Next.group_id
+             WHERE Next.transferred_from_id = GS.id) AS transferred_to,
+            COALESCE(GS.price, 0) AS charged,
+            GS.carried_over
+                + COALESCE((SELECT SUM(P.amount) FROM Payment P WHERE P.enrollment_id = GS.id), 0) AS paid
+        FROM GroupStudent GS
+        JOIN Users U ON U.ID = GS.student_id
+        JOIN \"Group\" G ON G.id = GS.group_id
+        WHERE {}
+        ORDER BY CASE GS.status
+                     WHEN 'applied' THEN 0
+                     WHEN 'approved' THEN 1
+                     WHEN 'active' THEN 2
+                     ELSE 3
+                 END,
+                 U.Name, GS.id DESC
+    ", filter))?;
+
+    let enrollments = stmt.query_map(params![id], |row| {
+        Ok(Enrollment {
+            id: row.get("id")?,
+            student_name: row.get("student_name")?,
+            group_name: row.get("group_name")?,
+            status: row.get("status")?,
+            terms: row.get("terms")?,
+            payment_due: row.get("payment_due")?,
+            enrolled_at: row.get("enrolled_at")?,
+            status_changed_at: row.get("status_changed_at")?,
+            ended_at: row.get("ended_at")?,
+            transferred_from: row.get("transferred_from")?,
+            transferred_to: row.get("transferred_to")?,
+            charged: row.get("charged")?,
+            paid: row.get("paid")?,
         })
     })?;
+    enrollments.collect()
+}
 
-    let students: Vec<UserInfo> = students_iter.collect::<Result<Vec<_>, Error>>()?;
-    println!("DEBUG DB: Загружено студентов в группе: {} шт.", students.len());
-    Ok(students)
+/// Все зачисления группы, включая заявки и историю: сначала действующие, затем завершённые
+pub fn get_group_enrollments(conn: &Connection, group_id: i32) -> Result<Vec<Enrollment>> {
+    query_enrollments(conn, "GS.group_id = ?1", group_id)
 }
-pub fn get_user_id_by_email(conn: &Connection, email: &str) -> Option<i32> {
-    let mut stmt = conn.prepare("SELECT ID FROM Users WHERE Email = ?1").ok()?;
-    let mut rows = stmt.query([email]).ok()?;
-    rows.next().ok().flatten().map(|row| row.get(0).ok()).flatten()
+
+/// История зачислений студента во все группы
+pub fn get_student_enrollments(conn: &Connection, student_id: i32) -> Result<Vec<Enrollment>> {
+    query_enrollments(conn, "GS.student_id = ?1", student_id)
 }
-pub fn get_children_for_parent(conn: &Connection, parent_email: &str) -> Result<Vec<UserInfo>, Error> {
-    let mut stmt = conn.prepare("
-        SELECT
-            U.ID, U.Name, U.Email, U.Birthday, U.Type, U.AvatarData,
-            GROUP_CONCAT(G.name, ', ') AS StudentGroups
-        FROM Users U
-        JOIN ParentStudent PS ON U.ID = PS.student_id
-        JOIN Users P ON PS.parent_id = P.ID
-        LEFT JOIN GroupStudent GS ON U.ID = GS.student_id
+
+/// Переводит студента в другую группу того же или другого курса одной транзакцией.
+/// Прежнее зачисление закрывается со статусом «переведён», новое ссылается на него
+/// и получает внесённую оплату. При переводе внутри курса сохраняются цена со скидкой
+/// и график платежей, а посещённые занятия засчитываются в новой группе.
+/// Возвращает ID нового зачисления.
+pub fn transfer_student(conn: &mut Connection, enrollment_id: i32, target_group_id: i32) -> Result<i32, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (student_id, group_id, status, course_id, paid): (i32, i32, EnrollmentStatus, Option<i32>, f64) = tx
+        .query_row(
+            "SELECT GS.student_id, GS.group_id, GS.status, G.course_id,
+                    GS.carried_over
+                        + COALESCE((SELECT SUM(P.amount) FROM Payment P WHERE P.enrollment_id = GS.id), 0)
+             FROM GroupStudent GS
+             JOIN \"Group\" G ON G.id = GS.group_id
+             WHERE GS.id = ?1",
+            params![enrollment_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .map_err(|e| format!("Зачисление не найдено: {}", e))?;
+    if !status.holds_seat() {
+        return Err(format!("Перевести можно только обучающегося студента, а не «{}».", status));
+    }
+    if group_id == target_group_id {
+        return Err("Студент уже в этой группе.".to_string());
+    }
+
+    let (target_course_id, target_status, already_there): (Option<i32>, GroupStatus, bool) = tx
+        .query_row(
+            "SELECT G.course_id, G.status,
+                    EXISTS(SELECT 1 FROM GroupStudent GS
+                           WHERE GS.group_id = G.id AND GS.student_id = ?2 AND GS.status IN ('approved', 'active'))
+             FROM \"Group\" G WHERE G.id = ?1",
+            params![target_group_id, student_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| format!("Группа не найдена: {}", e))?;
+    if target_status != GroupStatus::Active {
+        return Err("Перевести можно только в активную группу.".to_string());
+    }
+    if already_there {
+        return Err("Студент уже учится в выбранной группе.".to_string());
+    }
+    let same_course = course_id.is_some() && course_id == target_course_id;
+    if !same_course && let Some(target_course_id) = target_course_id {
+        ensure_seat_available(&tx, student_id, target_course_id)?;
+    }
+
+    // 1. Прежнее зачисление остаётся в истории
+    tx.execute(
+        "UPDATE GroupStudent
+         SET status = ?1, status_changed_at = datetime('now', 'localtime'), ended_at = date('now', 'localtime')
+         WHERE id = ?2",
+        params![EnrollmentStatus::Transferred, enrollment_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // 2. Новое зачисление: внутри курса — прежняя цена со скидкой, иначе цена нового курса
+    tx.execute(
+        "INSERT INTO GroupStudent (student_id, group_id, price, enrolled_at, discount, discount_note,
+                                   status, terms, payment_due, status_changed_at, transferred_from_id, carried_over)
+         SELECT Old.student_id, ?2,
+                CASE WHEN ?3 THEN Old.price
+                     ELSE (SELECT C.price FROM \"Group\" G JOIN Course C ON C.ID = G.course_id WHERE G.id = ?2) END,
+                date('now', 'localtime'),
+                CASE WHEN ?3 THEN Old.discount ELSE 0 END,
+                CASE WHEN ?3 THEN Old.discount_note END,
+                ?5, Old.terms, Old.payment_due, datetime('now', 'localtime'), Old.id, ?4
+         FROM GroupStudent Old
+         WHERE Old.id = ?1",
+        params![enrollment_id, target_group_id, same_course, paid, status],
+    )
+    .map_err(|e| e.to_string())?;
+    let new_enrollment_id = tx.last_insert_rowid() as i32;
+
+    // 3. График платежей действует только в пределах того же курса
+    if same_course {
+        tx.execute(
+            "UPDATE Installment SET group_student_id = ?2 WHERE group_student_id = ?1",
+            params![enrollment_id, new_enrollment_id],
+        )
+    } else {
+        tx.execute("DELETE FROM Installment WHERE group_student_id = ?1", params![enrollment_id])
+    }
+    .map_err(|e| e.to_string())?;
+
+    // 4. Места в обеих группах и лист ожидания курсов
+    refresh_group_student_count(&tx, group_id).map_err(|e| e.to_string())?;
+    refresh_group_student_count(&tx, target_group_id).map_err(|e| e.to_string())?;
+    if !same_course {
+        tx.execute(
+            "UPDATE Waitlist SET status = ?3, closed_at = datetime('now', 'localtime')
+             WHERE student_id = ?1 AND course_id = ?2 AND status IN ('waiting', 'offered')",
+            params![student_id, target_course_id, WaitlistStatus::Enrolled],
+        )
+        .map_err(|e| e.to_string())?;
+        if let Some(course_id) = course_id {
+            offer_waitlist_seats(&tx, course_id).map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    println!(
+        "DEBUG DB: студент ID {} переведён из группы {} в группу {} (зачисление #{} → #{}), перенесено {:.2}",
+        student_id, group_id, target_group_id, enrollment_id, new_enrollment_id, paid
+    );
+    Ok(new_enrollment_id)
+}
+
+/// Предлагает свободные места курса следующим по очереди студентам из листа ожидания.
+/// Действующее предложение держит место, поэтому из свободных мест вычитается.
+pub fn offer_waitlist_seats(conn: &Connection, course_id: i32) -> Result<usize> {
+    let free_seats: i32 = conn
+        .query_row(
+            "SELECT COALESCE(C.seats, 0) - (SELECT COUNT(*) FROM Waitlist W
+                                            WHERE W.course_id = C.ID AND W.status = 'offered')
+             FROM Course C
+             WHERE C.ID = ?1",
+            params![course_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or(0);
+    if free_seats <= 0 {
+        return Ok(0);
+    }
+
+    let offered = conn.execute(
+        "UPDATE Waitlist SET status = ?3, offered_at = datetime('now', 'localtime')
+         WHERE id IN (SELECT id FROM Waitlist
+                      WHERE course_id = ?1 AND status = 'waiting'
+                      ORDER BY created_at, id
+                      LIMIT ?2)",
+        params![course_id, free_seats, WaitlistStatus::Offered],
+    )?;
+    if offered > 0 {
+        println!("DEBUG DB: курс ID {}: предложено мест из листа ожидания: {}", course_id, offered);
+    }
+    Ok(offered)
+}
+
+/// Проверяет, можно ли зачислить студента на курс: свободное место не должно быть
+/// обещано другому студенту из листа ожидания
+pub fn ensure_seat_available(conn: &Connection, student_id: i32, course_id: i32) -> Result<(), String> {
+    let (free_seats, has_offer): (i32, bool) = conn
+        .query_row(
+            "SELECT
+                COALESCE(C.seats, 0) - (SELECT COUNT(*) FROM Waitlist W
+                                        WHERE W.course_id = C.ID AND W.status = 'offered'),
+                EXISTS(SELECT 1 FROM Waitlist W
+                       WHERE W.course_id = C.ID AND W.student_id = ?2 AND W.status = 'offered')
+             FROM Course C
+             WHERE C.ID = ?1",
+            params![course_id, student_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Курс не найден: {}", e))?;
+
+    if has_offer || free_seats > 0 {
+        Ok(())
+    } else {
+        Err("На курсе нет свободных мест — запишите студента в лист ожидания.".to_string())
+    }
+}
+
+pub fn add_to_waitlist(conn: &Connection, student_id: i32, course_id: i32, group_id: Option<i32>) -> Result<(), String> {
+    let (in_queue, enrolled): (bool, bool) = conn
+        .query_row(
+            "SELECT
+                EXISTS(SELECT 1 FROM Waitlist
+                       WHERE student_id = ?1 AND course_id = ?2 AND status IN ('waiting', 'offered')),
+                EXISTS(SELECT 1 FROM GroupStudent GS
+                       JOIN \"Group\" G ON G.id = GS.group_id
+                       WHERE GS.student_id = ?1 AND G.course_id = ?2 AND GS.status IN ('approved', 'active'))",
+            params![student_id, course_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    if in_queue {
+        return Err("Студент уже в листе ожидания этого курса.".to_string());
+    }
+    if enrolled {
+        return Err("Студент уже учится на этом курсе.".to_string());
+    }
+
+    conn.execute(
+        "INSERT INTO Waitlist (student_id, course_id, group_id, status, created_at)
+         VALUES (?1, ?2, ?3, ?4, datetime('now', 'localtime'))",
+        params![student_id, course_id, group_id, WaitlistStatus::Waiting],
+    )
+    .map_err(|e| format!("Не удалось записать в лист ожидания: {}", e))?;
+    println!("DEBUG DB: студент ID {} записан в лист ожидания курса ID {}", student_id, course_id);
+
+    // Место могло освободиться, пока заполнялась форма
+    offer_waitlist_seats(conn, course_id).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Снимает запись с очереди: от предложенного места студент отказывается,
+/// ожидающий просто выходит из очереди. Освободившееся место уходит следующему.
+pub fn close_waitlist_entry(conn: &mut Connection, entry_id: i32) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (status, course_id): (WaitlistStatus, i32) = tx
+        .query_row(
+            "SELECT status, course_id FROM Waitlist WHERE id = ?1",
+            params![entry_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Запись листа ожидания не найдена: {}", e))?;
+
+    let new_status = match status {
+        WaitlistStatus::Offered => WaitlistStatus::Declined,
+        WaitlistStatus::Waiting => WaitlistStatus::Cancelled,
+        _ => return Err("Запись уже не в очереди.".to_string()),
+    };
+    tx.execute(
+        "UPDATE Waitlist SET status = ?2, closed_at = datetime('now', 'localtime') WHERE id = ?1",
+        params![entry_id, new_status],
+    )
+    .map_err(|e| e.to_string())?;
+    offer_waitlist_seats(&tx, course_id).map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Закрывает предложения, на которые не ответили за `offer_days` дней,
+/// и передаёт места следующим в очереди
+pub fn expire_waitlist_offers(conn: &mut Connection, offer_days: u32) -> Result<usize> {
+    let tx = conn.transaction()?;
+    let modifier = format!("-{} days", offer_days);
+
+    let course_ids: Vec<i32> = {
+        let mut stmt = tx.prepare(
+            "SELECT DISTINCT course_id FROM Waitlist
+             WHERE status = 'offered' AND offered_at <= datetime('now', 'localtime', ?1)",
+        )?;
+        stmt.query_map(params![modifier], |row| row.get(0))?
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let expired = tx.execute(
+        "UPDATE Waitlist SET status = ?2, closed_at = datetime('now', 'localtime')
+         WHERE status = 'offered' AND offered_at <= datetime('now', 'localtime', ?1)",
+        params![modifier, WaitlistStatus::Expired],
+    )?;
+    for course_id in course_ids {
+        offer_waitlist_seats(&tx, course_id)?;
+    }
+
+    tx.commit()?;
+    if expired > 0 {
+        println!("DEBUG DB: истекло предложений из листа ожидания: {}", expired);
+    }
+    Ok(expired)
+}
+
+/// Лист ожидания всех курсов или одного студента: сначала предложения, затем очередь, затем история
+pub fn get_waitlist(conn: &Connection, student_id: Option<i32>) -> Result<Vec<WaitlistEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT
+            W.id,
+            W.student_id,
+            COALESCE(U.Name, '') AS student_name,
+            W.course_id,
+            COALESCE(C.title, '') AS course_title,
+            W.group_id,
+            G.name AS group_name,
+            W.status,
+            W.created_at,
+            W.offered_at,
+            W.closed_at,
+            CASE WHEN W.status = 'waiting' THEN
+                (SELECT COUNT(*) FROM Waitlist W2
+                 WHERE W2.course_id = W.course_id AND W2.status = 'waiting'
+                   AND (W2.created_at < W.created_at OR (W2.created_at = W.created_at AND W2.id <= W.id)))
+            END AS position
+         FROM Waitlist W
+         JOIN Users U ON U.ID = W.student_id
+         JOIN Course C ON C.ID = W.course_id
+         LEFT JOIN \"Group\" G ON G.id = W.group_id
+         WHERE ?1 IS NULL OR W.student_id = ?1
+         ORDER BY
+            W.course_id,
+            CASE W.status WHEN 'offered' THEN 0 WHEN 'waiting' THEN 1 ELSE 2 END,
+            CASE WHEN W.status IN ('offered', 'waiting') THEN W.created_at ELSE '' END,
+            W.closed_at DESC,
+            W.id",
+    )?;
+    let entries = stmt.query_map(params![student_id], |row| {
+        Ok(WaitlistEntry {
+            id: row.get("id")?,
+            student_id: row.get("student_id")?,
+            student_name: row.get("student_name")?,
+            course_id: row.get("course_id")?,
+            course_title: row.get("course_title")?,
+            group_id: row.get("group_id")?,
+            group_name: row.get("group_name")?,
+            status: row.get("status")?,
+            created_at: row.get("created_at")?,
+            offered_at: row.get("offered_at")?,
+            closed_at: row.get("closed_at")?,
+            position: row.get("position")?,
+        })
+    })?;
+    entries.collect()
+}
+
+
+pub fn get_students_in_group(conn: &Connection, group_id: i32) -> Result<Vec<UserInfo>> {
+    println!("DEBUG DB: Загрузка студентов для group_id: {}", group_id);
+    let mut stmt = conn.prepare("
+        SELECT
+            U.ID,
+            U.Name,
+            U.Email,
+            U.Birthday,
+            U.Type,
+            U.AvatarData
+        FROM Users U
+        JOIN GroupStudent GS ON U.ID = GS.student_id 
+        WHERE GS.group_id = ?1 AND GS.status IN ('approved', 'active')
+        ORDER BY U.Name
+    ")?;
+
+    let students_iter = stmt.query_map(params![group_id], |row| {
+        Ok(UserInfo {
+            id: row.get("ID")?,
+            name: row.get("Name")?,
+            email: row.get("Email")?,
+            birthday: row.get("Birthday")?,
+            user_type: row.get("Type")?,
+            avatar_data: row.get("AvatarData")?,
+            group_id: None,
+            child_count: None,
+        })
+    })?;
+
+    let students: Vec<UserInfo> = students_iter.collect::<Result<Vec<_>, Error>>()?;
+    println!("DEBUG DB: Загружено студентов в группе: {} шт.", students.len());
+    Ok(students)
+}
+pub fn get_user_id_by_email(conn: &Connection, email: &str) -> Option<i32> {
+    let mut stmt = conn.prepare("SELECT ID FROM Users WHERE Email = ?1").ok()?;
+    let mut rows = stmt.query([email]).ok()?;
+    rows.next().ok().flatten().map(|row| row.get(0).ok()).flatten()
+}
+pub fn get_children_for_parent(conn: &Connection, parent_email: &str) -> Result<Vec<UserInfo>, Error> {
+    let mut stmt = conn.prepare("
+        SELECT
+            U.ID, U.Name, U.Email, U.Birthday, U.Type, U.AvatarData,
+            GROUP_CONCAT(G.name, ', ') AS StudentGroups
+        FROM Users U
+        JOIN ParentStudent PS ON U.ID = PS.student_id
+        JOIN Users P ON PS.parent_id = P.ID
+        LEFT JOIN GroupStudent GS ON U.ID = GS.student_id AND GS.status IN ('approved', 'active')
         LEFT JOIN \"Group\" G ON GS.group_id = G.id
         WHERE P.email = ?1
         GROUP BY U.ID, U.Name, U.Email, U.Birthday, U.Type, U.AvatarData
@@ -788,7 +1831,7 @@ pub fn get_unassigned_children(conn: &Connection) -> Result<Vec<UserInfo>, Error
             U.ID, U.Name, U.Email, U.Birthday, U.Type, U.AvatarData,
             GROUP_CONCAT(G.name, ', ') AS StudentGroups
         FROM Users U
-        LEFT JOIN GroupStudent GS ON U.ID = GS.student_id
+        LEFT JOIN GroupStudent GS ON U.ID = GS.student_id AND GS.status IN ('approved', 'active')
         LEFT JOIN \"Group\" G ON GS.group_id = G.id
         WHERE U.Type = 'student'
           AND U.ID NOT IN (SELECT student_id FROM ParentStudent)
@@ -860,113 +1903,553 @@ pub fn update_assignment(conn: &Connection, assignment: &Assignment) -> Result<(
     }
     Ok(())
 }
-pub fn get_groups_for_teacher(conn: &Connection, teacher_id: i32) -> Result<Vec<Group>> {
+// --- Сданные работы по заданиям ---
+
+const SUBMISSION_SELECT: &str = "
+    SELECT
+        S.id, A.title AS assignment_title, L.number AS lesson_number,
+        U.Name AS student_name, S.text, S.submitted_at,
+        S.score, S.comment, S.graded_at
+    FROM Submission S
+    JOIN Assignment A ON S.assignment_id = A.id
+    JOIN Lessons L ON A.lesson_id = L.ID
+    JOIN Users U ON S.student_id = U.ID
+";
+
+fn submission_from_row(row: &rusqlite::Row) -> Result<Submission> {
+    Ok(Submission {
+        id: row.get("id")?,
+        assignment_title: row.get("assignment_title")?,
+        lesson_number: row.get("lesson_number")?,
+        student_name: row.get("student_name")?,
+        text: row.get("text")?,
+        submitted_at: row.get("submitted_at")?,
+        score: row.get("score")?,
+        comment: row.get("comment")?,
+        graded_at: row.get("graded_at")?,
+        files: Vec::new(), // Заполняется отдельным запросом
+    })
+}
+
+pub fn get_submission_files(conn: &Connection, submission_id: i32) -> Result<Vec<SubmissionFile>> {
     let mut stmt = conn.prepare(
-        "
-        SELECT
-            G.id,
-            G.name,
-            G.course_id,       -- Включаем ID курса
-            C.title AS course_title, -- Название курса
-            G.teacher_id,      -- Включаем ID учителя
-            U_teacher.name AS teacher_name, -- Имя учителя
-            COUNT(GS.student_id) AS student_count,
-            G.status
-        FROM \"Group\" G
-        LEFT JOIN Course C ON G.course_id = C.id
-        LEFT JOIN Users U_teacher ON G.teacher_id = U_teacher.id
-        LEFT JOIN GroupStudent GS ON G.id = GS.group_id
-        WHERE G.teacher_id = ?1
-        GROUP BY G.id, G.name, G.course_id, C.title, G.teacher_id, U_teacher.name
-        ORDER BY G.name
-        "
+        "SELECT id, file_name FROM SubmissionFile WHERE submission_id = ?1 ORDER BY id"
     )?;
-
-    let groups = stmt.query_map(params![teacher_id], |row| {
-        Ok(Group {
-            id: row.get("id")?,
-            name: row.get("name")?,
-            course_id: row.get("course_id")?,       // Получаем ID курса
-            course_name: row.get("course_title")?, // Получаем название курса
-            teacher_id: row.get("teacher_id")?,      // Получаем ID учителя
-            teacher_name: row.get("teacher_name")?, // Получаем имя учителя
-            student_count: row.get("student_count")?,
-            status: row.get("status")?,
+    let files_iter = stmt.query_map(params![submission_id], |row| {
+        Ok(SubmissionFile {
+            id: row.get(0)?,
+            file_name: row.get(1)?,
         })
-    })?.collect::<Result<Vec<_>>>()?;
-
-    println!("DEBUG: Количество загруженных групп для учителя (ID: {}) в get_groups_for_teacher: {}", teacher_id, groups.len());
-    for group in &groups {
-        println!("DEBUG: Загруженная группа для учителя (с именами): {:?}", group);
-    }
+    })?;
+    files_iter.collect()
+}
 
-    Ok(groups)
+/// Возвращает имя и содержимое прикреплённого файла
+pub fn get_submission_file_data(conn: &Connection, file_id: i32) -> Result<(String, Vec<u8>)> {
+    conn.query_row(
+        "SELECT file_name, data FROM SubmissionFile WHERE id = ?1",
+        params![file_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
 }
-pub fn get_past_sessions_for_group(conn: &Connection, group_id: i32) -> Result<Vec<PastSession>> {
-    // 1. Загружаем основные данные прошедших сессий
-    let mut stmt_sessions = conn.prepare("
-        SELECT
-            PS.id,
-            PS.group_id,
-            PS.date,
-            PS.lesson_id,
-            L.title AS lesson_title,
-            L.number AS lesson_number
-        FROM PastSessions PS
-        JOIN Lessons L ON PS.lesson_id = L.ID
-        WHERE PS.group_id = ?1
-        ORDER BY PS.date DESC
+
+/// Задания курса, по которым сдаются работы (всё, кроме лекций), вместе с работами студента
+pub fn get_student_assignments(conn: &Connection, student_id: i32, course_id: i32) -> Result<Vec<StudentAssignment>> {
+    let mut stmt = conn.prepare("
+        SELECT A.id, A.lesson_id, A.title, A.description, A.type, L.number, L.title AS lesson_title
+        FROM Assignment A
+        JOIN Lessons L ON A.lesson_id = L.ID
+        WHERE L.course_id = ?1 AND A.type != ?2
+        ORDER BY L.number, A.id
     ")?;
+    let assignments = stmt
+        .query_map(params![course_id, AssignmentType::Lecture.to_string()], |row| {
+            Ok(StudentAssignment {
+                assignment: Assignment {
+                    id: row.get(0)?,
+                    lesson_id: row.get(1)?,
+                    title: row.get(2)?,
+                    description: row.get(3)?,
+                    assignment_type: row.get(4)?,
+                },
+                lesson_number: row.get(5)?,
+                lesson_title: row.get(6)?,
+                submission: None,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut submission_stmt = conn.prepare(&format!(
+        "{} WHERE S.assignment_id = ?1 AND S.student_id = ?2",
+        SUBMISSION_SELECT
+    ))?;
+
+    let mut result = Vec::with_capacity(assignments.len());
+    for mut item in assignments {
+        let submission = submission_stmt
+            .query_row(params![item.assignment.id, student_id], submission_from_row)
+            .optional()?;
+        item.submission = match submission {
+            Some(mut submission) => {
+                submission.files = get_submission_files(conn, submission.id)?;
+                Some(submission)
+            }
+            None => None,
+        };
+        result.push(item);
+    }
+    Ok(result)
+}
 
-    let sessions_iter = stmt_sessions.query_map(params![group_id], |row| {
-        Ok(PastSession {
-            id: row.get("id")?,
-            group_id: row.get("group_id")?,
-            date: row.get("date")?,
-            lesson_id: row.get("lesson_id")?,
-            lesson_title: row.get("lesson_title")?,
-            lesson_number: row.get("lesson_number")?,
-            attendance_records: Vec::new(), // Инициализируем пустым, заполним позже
-        })
-    })?;
+/// Сохраняет работу студента. Повторная сдача заменяет текст и добавляет новые файлы,
+/// пока работа не проверена. Возвращает `false`, если работа уже оценена и изменять её нельзя.
+pub fn submit_assignment(
+    conn: &mut Connection,
+    assignment_id: i32,
+    student_id: i32,
+    group_id: i32,
+    text: &str,
+    files: &[(String, Vec<u8>)],
+) -> Result<bool> {
+    let tx = conn.transaction()?;
+    let submitted_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+
+    let changed = tx.execute(
+        "INSERT INTO Submission (assignment_id, student_id, group_id, text, submitted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(assignment_id, student_id) DO UPDATE
+         SET text = excluded.text, submitted_at = excluded.submitted_at, group_id = excluded.group_id
+         WHERE Submission.score IS NULL",
+        params![assignment_id, student_id, group_id, text, submitted_at],
+    )?;
+    if changed == 0 {
+        return Ok(false);
+    }
 
-    let mut past_sessions_map: HashMap<i32, PastSession> = sessions_iter
-        .map(|res| res.map(|session| (session.id, session)))
-        .collect::<Result<HashMap<i32, PastSession>, Error>>()?;
+    let submission_id: i32 = tx.query_row(
+        "SELECT id FROM Submission WHERE assignment_id = ?1 AND student_id = ?2",
+        params![assignment_id, student_id],
+        |row| row.get(0),
+    )?;
 
-    // 2. Загружаем данные о посещаемости для всех этих прошедших сессий
-    // Используем `IN` для фильтрации по ID сессий
-    if past_sessions_map.is_empty() {
-        return Ok(Vec::new()); // Если нет прошедших сессий, сразу возвращаем пустой вектор
+    for (file_name, data) in files {
+        tx.execute(
+            "INSERT INTO SubmissionFile (submission_id, file_name, data) VALUES (?1, ?2, ?3)",
+            params![submission_id, file_name, data],
+        )?;
     }
 
-    let session_ids: Vec<i32> = past_sessions_map.keys().cloned().collect();
-    let query_placeholders = session_ids.iter().map(|_| "?").collect::<Vec<&str>>().join(",");
+    tx.commit()?;
+    Ok(true)
+}
 
-    let query_attendance = format!("
-        SELECT
-            A.id,
-            A.lesson_id AS past_session_id, -- lesson_id в Attendance фактически ссылается на PastSession.id
-            A.student_id,
-            U.Name AS student_name,
-            A.present AS present_status
-        FROM Attendance A
-        JOIN Users U ON A.student_id = U.ID
-        WHERE A.lesson_id IN ({}) -- Используем PastSession.id, т.к. в схеме Attendance.lesson_id ссылается на PastSessions.id
-        ORDER BY A.lesson_id, U.Name
-    ", query_placeholders);
+/// Все работы студентов группы, сначала непроверенные
+pub fn get_submissions_for_group(conn: &Connection, group_id: i32) -> Result<Vec<Submission>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE S.group_id = ?1 ORDER BY S.score IS NOT NULL, S.submitted_at DESC",
+        SUBMISSION_SELECT
+    ))?;
+    let submissions = stmt
+        .query_map(params![group_id], submission_from_row)?
+        .collect::<Result<Vec<_>>>()?;
+
+    submissions
+        .into_iter()
+        .map(|mut submission| {
+            submission.files = get_submission_files(conn, submission.id)?;
+            Ok(submission)
+        })
+        .collect()
+}
 
-    let mut stmt_attendance = conn.prepare(&query_attendance)?;
+pub fn grade_submission(conn: &Connection, submission_id: i32, score: i32, comment: &str, teacher_id: i32) -> Result<()> {
+    let graded_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+    conn.execute(
+        "UPDATE Submission SET score = ?1, comment = ?2, graded_at = ?3, graded_by = ?4 WHERE id = ?5",
+        params![score, comment, graded_at, teacher_id, submission_id],
+    )?;
+    Ok(())
+}
 
-    // Преобразуем Vec<i32> в Vec<rusqlite::params::Value> для использования в query_map
-    let attendance_iter = stmt_attendance.query_map(params_from_iter(session_ids.iter()), |row| {
-        Ok((
-            row.get("past_session_id")?, // ID PastSession, к которой относится эта запись
-            StudentAttendanceStatus {
-                student_id: row.get("student_id")?,
-                student_name: row.get("student_name")?,
-                present_status: row.get("present_status")?,
-            }
+/// Средний балл (0..=100) каждого студента группы по всем заданиям курса, по которым сдаются работы.
+/// Несданные и непроверенные работы считаются за 0. `None`, если таких заданий в курсе нет.
+pub fn get_student_assignment_scores(
+    conn: &Connection,
+    group_id: i32,
+    course_id: i32,
+) -> Result<Option<HashMap<i32, f64>>> {
+    let gradable_count: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM Assignment A JOIN Lessons L ON A.lesson_id = L.ID
+         WHERE L.course_id = ?1 AND A.type != ?2",
+        params![course_id, AssignmentType::Lecture.to_string()],
+        |row| row.get(0),
+    )?;
+    if gradable_count == 0 {
+        return Ok(None);
+    }
+
+    let mut stmt = conn.prepare("
+        SELECT S.student_id, COALESCE(SUM(S.score), 0) AS total_score
+        FROM Submission S
+        JOIN Assignment A ON S.assignment_id = A.id
+        JOIN Lessons L ON A.lesson_id = L.ID
+        WHERE S.group_id = ?1 AND L.course_id = ?2 AND A.type != ?3
+        GROUP BY S.student_id
+    ")?;
+    let iter = stmt.query_map(
+        params![group_id, course_id, AssignmentType::Lecture.to_string()],
+        |row| Ok((row.get::<_, i32>(0)?, row.get::<_, i64>(1)?)),
+    )?;
+
+    let mut scores = HashMap::new();
+    for result in iter {
+        let (student_id, total_score) = result?;
+        scores.insert(student_id, total_score as f64 / gradable_count as f64);
+    }
+    Ok(Some(scores))
+}
+
+// --- Расписание групп ---
+
+pub fn get_schedule_slots(conn: &Connection, group_id: i32) -> Result<Vec<ScheduleSlot>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, weekday, start_time, duration_minutes, room FROM ScheduleSlot
+         WHERE group_id = ?1 ORDER BY weekday, start_time"
+    )?;
+    let slots_iter = stmt.query_map(params![group_id], |row| {
+        Ok(ScheduleSlot {
+            id: row.get(0)?,
+            weekday: row.get(1)?,
+            start_time: row.get(2)?,
+            duration_minutes: row.get(3)?,
+            room: row.get(4)?,
+        })
+    })?;
+    slots_iter.collect()
+}
+
+/// Ищет пересечения слота с расписанием: другие занятия той же группы в это время,
+/// а у других активных групп — тот же преподаватель (`teacher_id` группы слота) или та же
+/// аудитория. Сам слот (по `slot.id`, у ещё не добавленного — 0) не учитывается.
+/// Возвращает описания найденных конфликтов (пустой список — конфликтов нет).
+pub fn find_schedule_conflicts(
+    conn: &Connection,
+    group_id: i32,
+    teacher_id: Option<i32>,
+    slot: &ScheduleSlot,
+) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("
+        SELECT S.group_id, S.start_time, S.duration_minutes, S.room, G.name, G.teacher_id
+        FROM ScheduleSlot S
+        JOIN \"Group\" G ON S.group_id = G.id
+        WHERE S.weekday = ?1 AND S.id <> ?2 AND (S.group_id = ?3 OR G.status = ?4)
+    ")?;
+    let rows = stmt
+        .query_map(params![slot.weekday, slot.id, group_id, GroupStatus::Active], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i32>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<i32>>(5)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let room = slot.room.trim();
+    let mut conflicts = Vec::new();
+    for (other_group_id, other_start, other_duration, other_room, other_group, other_teacher) in rows {
+        if !overlaps(&slot.start_time, slot.duration_minutes, &other_start, other_duration) {
+            continue;
+        }
+        let time = format!("{}–{}", other_start, end_time(&other_start, other_duration));
+        if other_group_id == group_id {
+            conflicts.push(format!("У группы уже есть занятие в {}", time));
+            continue;
+        }
+        if teacher_id.is_some() && teacher_id == other_teacher {
+            conflicts.push(format!("Преподаватель уже ведёт группу «{}» в {}", other_group, time));
+        }
+        if !room.is_empty() && room.to_lowercase() == other_room.trim().to_lowercase() {
+            conflicts.push(format!("Аудитория «{}» занята группой «{}» в {}", room, other_group, time));
+        }
+    }
+    Ok(conflicts)
+}
+
+/// Добавляет слот, если он не пересекается с расписанием группы и других групп.
+/// Проверка и вставка идут в одной транзакции (с блокировкой на запись с самого начала),
+/// чтобы два одновременных добавления не заняли одно и то же время.
+pub fn add_schedule_slot(
+    conn: &mut Connection,
+    group_id: i32,
+    weekday: u32,
+    start_time: &str,
+    duration_minutes: i32,
+    room: &str,
+) -> Result<(), String> {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Не удалось начать транзакцию: {}", e))?;
+
+    let teacher_id: Option<i32> = tx
+        .query_row("SELECT teacher_id FROM \"Group\" WHERE id = ?1", params![group_id], |row| row.get(0))
+        .map_err(|e| format!("Группа не найдена: {}", e))?;
+    let slot = ScheduleSlot {
+        id: 0,
+        weekday,
+        start_time: start_time.to_string(),
+        duration_minutes,
+        room: room.to_string(),
+    };
+    let conflicts = find_schedule_conflicts(&tx, group_id, teacher_id, &slot)
+        .map_err(|e| format!("Не удалось проверить расписание: {}", e))?;
+    if !conflicts.is_empty() {
+        return Err(format!("Слот не добавлен, найдены пересечения:\n{}", conflicts.join("\n")));
+    }
+
+    tx.execute(
+        "INSERT INTO ScheduleSlot (group_id, weekday, start_time, duration_minutes, room) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![group_id, weekday, start_time, duration_minutes, room.trim()],
+    )
+    .map_err(|e| format!("Не удалось добавить слот расписания: {}", e))?;
+    tx.commit().map_err(|e| format!("Не удалось добавить слот расписания: {}", e))
+}
+
+pub fn delete_schedule_slot(conn: &Connection, slot_id: i32) -> Result<()> {
+    conn.execute("DELETE FROM ScheduleSlot WHERE id = ?1", params![slot_id])?;
+    Ok(())
+}
+
+/// Пересоздаёт непроведённые занятия группы: ещё не пройденные уроки курса по порядку
+/// раскладываются по слотам расписания начиная с даты `from`. Возвращает число занятий.
+pub fn generate_planned_sessions(conn: &mut Connection, group_id: i32, from: NaiveDate) -> Result<usize> {
+    let tx = conn.transaction()?;
+
+    let slots = get_schedule_slots(&tx, group_id)?;
+
+    let lesson_ids: Vec<i32> = {
+        let mut stmt = tx.prepare("
+            SELECT L.ID FROM Lessons L
+            JOIN \"Group\" G ON L.course_id = G.course_id
+            WHERE G.id = ?1
+              AND L.ID NOT IN (SELECT lesson_id FROM PastSessions WHERE group_id = ?1)
+            ORDER BY L.number
+        ")?;
+        stmt.query_map(params![group_id], |row| row.get(0))?
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    tx.execute(
+        "DELETE FROM PlannedSession WHERE group_id = ?1 AND past_session_id IS NULL",
+        params![group_id],
+    )?;
+
+    let dates = generate_session_dates(&slots, from, lesson_ids.len());
+    for (lesson_id, (date, slot)) in lesson_ids.iter().zip(dates.iter()) {
+        tx.execute(
+            "INSERT INTO PlannedSession (group_id, lesson_id, date, start_time, duration_minutes, room)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                group_id,
+                lesson_id,
+                date.format("%Y-%m-%d").to_string(),
+                slot.start_time,
+                slot.duration_minutes,
+                slot.room
+            ],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(dates.len())
+}
+
+const PLANNED_SESSION_SELECT: &str = "
+    SELECT
+        G.name AS group_name, U.Name AS teacher_name,
+        L.number AS lesson_number, L.title AS lesson_title,
+        PS.date, PS.start_time, PS.duration_minutes, PS.room,
+        PS.past_session_id IS NOT NULL AS conducted
+    FROM PlannedSession PS
+    JOIN \"Group\" G ON PS.group_id = G.id
+    JOIN Lessons L ON PS.lesson_id = L.ID
+    LEFT JOIN Users U ON G.teacher_id = U.ID
+";
+
+fn planned_session_from_row(row: &rusqlite::Row) -> Result<PlannedSession> {
+    Ok(PlannedSession {
+        group_name: row.get("group_name")?,
+        teacher_name: row.get("teacher_name")?,
+        lesson_number: row.get("lesson_number")?,
+        lesson_title: row.get("lesson_title")?,
+        date: row.get("date")?,
+        start_time: row.get("start_time")?,
+        duration_minutes: row.get("duration_minutes")?,
+        room: row.get("room")?,
+        conducted: row.get("conducted")?,
+    })
+}
+
+pub fn get_planned_sessions_for_group(conn: &Connection, group_id: i32) -> Result<Vec<PlannedSession>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE PS.group_id = ?1 ORDER BY PS.date, PS.start_time",
+        PLANNED_SESSION_SELECT
+    ))?;
+    let sessions_iter = stmt.query_map(params![group_id], planned_session_from_row)?;
+    sessions_iter.collect()
+}
+
+/// Занятия всех групп (или только групп преподавателя) в диапазоне дат включительно
+pub fn get_planned_sessions_between(
+    conn: &Connection,
+    from: NaiveDate,
+    to: NaiveDate,
+    teacher_id: Option<i32>,
+) -> Result<Vec<PlannedSession>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE PS.date BETWEEN ?1 AND ?2 AND (?3 IS NULL OR G.teacher_id = ?3)
+         ORDER BY PS.date, PS.start_time",
+        PLANNED_SESSION_SELECT
+    ))?;
+    let sessions_iter = stmt.query_map(
+        params![
+            from.format("%Y-%m-%d").to_string(),
+            to.format("%Y-%m-%d").to_string(),
+            teacher_id
+        ],
+        planned_session_from_row,
+    )?;
+    sessions_iter.collect()
+}
+
+/// Отмечает ближайшее запланированное занятие по уроку как проведённое
+pub fn mark_planned_session_conducted(
+    tx: &Transaction,
+    group_id: i32,
+    lesson_id: i32,
+    past_session_id: i32,
+) -> Result<()> {
+    tx.execute(
+        "UPDATE PlannedSession SET past_session_id = ?3
+         WHERE id = (
+             SELECT id FROM PlannedSession
+             WHERE group_id = ?1 AND lesson_id = ?2 AND past_session_id IS NULL
+             ORDER BY date LIMIT 1
+         )",
+        params![group_id, lesson_id, past_session_id],
+    )?;
+    Ok(())
+}
+
+pub fn get_groups_for_teacher(conn: &Connection, teacher_id: i32) -> Result<Vec<Group>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT
+            G.id,
+            G.name,
+            G.course_id,       -- Включаем ID курса
+            C.title AS course_title, -- Название курса
+            G.teacher_id,      -- Включаем ID учителя
+            U_teacher.name AS teacher_name, -- Имя учителя
+            COUNT(GS.student_id) AS student_count,
+            G.status
+        FROM \"Group\" G
+        LEFT JOIN Course C ON G.course_id = C.id
+        LEFT JOIN Users U_teacher ON G.teacher_id = U_teacher.id
+        LEFT JOIN GroupStudent GS ON G.id = GS.group_id AND GS.status IN ('approved', 'active')
+        WHERE G.teacher_id = ?1
+        GROUP BY G.id, G.name, G.course_id, C.title, G.teacher_id, U_teacher.name
+        ORDER BY G.name
+        "
+    )?;
+
+    let groups = stmt.query_map(params![teacher_id], |row| {
+        Ok(Group {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            course_id: row.get("course_id")?,       // Получаем ID курса
+            course_name: row.get("course_title")?, // Получаем название курса
+            teacher_id: row.get("teacher_id")?,      // Получаем ID учителя
+            teacher_name: row.get("teacher_name")?, // Получаем имя учителя
+            student_count: row.get("student_count")?,
+            status: row.get("status")?,
+        })
+    })?.collect::<Result<Vec<_>>>()?;
+
+    println!("DEBUG: Количество загруженных групп для учителя (ID: {}) в get_groups_for_teacher: {}", teacher_id, groups.len());
+    for group in &groups {
+        println!("DEBUG: Загруженная группа для учителя (с именами): {:?}", group);
+    }
+
+    Ok(groups)
+}
+pub fn get_past_sessions_for_group(conn: &Connection, group_id: i32) -> Result<Vec<PastSession>> {
+    // 1. Загружаем основные данные прошедших сессий
+    let mut stmt_sessions = conn.prepare("
+        SELECT
+            PS.id,
+            PS.group_id,
+            PS.date,
+            PS.lesson_id,
+            L.title AS lesson_title,
+            L.number AS lesson_number
+        FROM PastSessions PS
+        JOIN Lessons L ON PS.lesson_id = L.ID
+        WHERE PS.group_id = ?1
+        ORDER BY PS.date DESC
+    ")?;
+
+    let sessions_iter = stmt_sessions.query_map(params![group_id], |row| {
+        Ok(PastSession {
+            id: row.get("id")?,
+            group_id: row.get("group_id")?,
+            date: row.get("date")?,
+            lesson_id: row.get("lesson_id")?,
+            lesson_title: row.get("lesson_title")?,
+            lesson_number: row.get("lesson_number")?,
+            attendance_records: Vec::new(), // Инициализируем пустым, заполним позже
+        })
+    })?;
+
+    let mut past_sessions_map: HashMap<i32, PastSession> = sessions_iter
+        .map(|res| res.map(|session| (session.id, session)))
+        .collect::<Result<HashMap<i32, PastSession>, Error>>()?;
+
+    // 2. Загружаем данные о посещаемости для всех этих прошедших сессий
+    // Используем `IN` для фильтрации по ID сессий
+    if past_sessions_map.is_empty() {
+        return Ok(Vec::new()); // Если нет прошедших сессий, сразу возвращаем пустой вектор
+    }
+
+    let session_ids: Vec<i32> = past_sessions_map.keys().cloned().collect();
+    let query_placeholders = session_ids.iter().map(|_| "?").collect::<Vec<&str>>().join(",");
+
+    let query_attendance = format!("
+        SELECT
+            A.id,
+            A.lesson_id AS past_session_id, -- lesson_id в Attendance фактически ссылается на PastSession.id
+            A.student_id,
+            U.Name AS student_name,
+            A.present AS present_status
+        FROM Attendance A
+        JOIN Users U ON A.student_id = U.ID
+        WHERE A.lesson_id IN ({}) -- Используем PastSession.id, т.к. в схеме Attendance.lesson_id ссылается на PastSessions.id
+        ORDER BY A.lesson_id, U.Name
+    ", query_placeholders);
+
+    let mut stmt_attendance = conn.prepare(&query_attendance)?;
+
+    // Преобразуем Vec<i32> в Vec<rusqlite::params::Value> для использования в query_map
+    let attendance_iter = stmt_attendance.query_map(params_from_iter(session_ids.iter()), |row| {
+        Ok((
+            row.get("past_session_id")?, // ID PastSession, к которой относится эта запись
+            StudentAttendanceStatus {
+                student_id: row.get("student_id")?,
+                student_name: row.get("student_name")?,
+                present_status: row.get("present_status")?,
+            }
         ))
     })?;
 
@@ -986,161 +2469,800 @@ pub fn get_past_sessions_for_group(conn: &Connection, group_id: i32) -> Result<V
 
     Ok(final_sessions)
 }
-pub fn add_past_session(conn: &Connection, group_id: i32, lesson_id: i32) -> Result<i32> {
-    let now: chrono::DateTime<chrono::Local> = chrono::Local::now();
-    let date_str = now.format("%Y-%m-%d %H:%M:%S").to_string(); // Формат ГГГГ-ММ-ДД ЧЧ:ММ:СС
+pub fn add_past_session(conn: &Connection, group_id: i32, lesson_id: i32) -> Result<i32> {
+    let now: chrono::DateTime<chrono::Local> = chrono::Local::now();
+    let date_str = now.format("%Y-%m-%d %H:%M:%S").to_string(); // Формат ГГГГ-ММ-ДД ЧЧ:ММ:СС
+
+    // Запоминаем, кто из преподавателей провёл занятие: по нему считается зарплата,
+    // даже если группу позже передадут другому преподавателю
+    conn.execute(
+        "INSERT INTO PastSessions (group_id, date, lesson_id, teacher_id)
+         VALUES (?1, ?2, ?3, (SELECT teacher_id FROM \"Group\" WHERE id = ?1))",
+        params![group_id, date_str, lesson_id],
+    )?;
+    Ok(conn.last_insert_rowid() as i32) // Возвращаем ID
+}
+pub fn get_all_payments_with_details(conn: &Connection) -> Result<Vec<Payment>> {
+    let mut stmt = conn.prepare("
+        SELECT
+            P.id,
+            P.student_id,
+            P.date,
+            P.amount,
+            P.type,
+            P.course_id,
+            P.group_id,
+            U.Name AS student_name,
+            C.title AS course_title,
+            G.name AS group_name,
+            P.reverses_payment_id,
+            P.reason,
+            O.Name AS operator_name,
+            P.created_at,
+            P.discount,
+            P.discount_note
+        FROM Payment P
+        JOIN Users U ON P.student_id = U.ID
+        JOIN Course C ON P.course_id = C.ID
+        JOIN \"Group\" G ON P.group_id = G.id
+        LEFT JOIN Users O ON P.operator_id = O.ID
+        ORDER BY P.date DESC, P.id DESC
+    ")?;
+
+    let payments_iter = stmt.query_map(params![], |row| {
+        Ok(Payment {
+            id: row.get("id")?,
+            student_id: row.get("student_id")?,
+            date: row.get("date")?,
+            amount: row.get("amount")?,
+            payment_type: row.get("type")?,
+            course_id: row.get("course_id")?,
+            group_id: row.get("group_id")?,
+            student_name: row.get("student_name")?,
+            course_title: row.get("course_title")?,
+            group_name: row.get("group_name")?,
+            reverses_payment_id: row.get("reverses_payment_id")?,
+            reason: row.get("reason")?,
+            operator_name: row.get("operator_name")?,
+            created_at: row.get("created_at")?,
+            discount: row.get("discount")?,
+            discount_note: row.get("discount_note")?,
+        })
+    })?;
+
+    payments_iter.collect::<Result<Vec<_>, Error>>()
+}
+
+// получить студентов, которые не состоят ни в одной группе
+pub fn get_students_not_in_any_group(conn: &Connection) -> Result<Vec<UserInfo>> {
+    let mut stmt = conn.prepare("
+        SELECT
+            U.ID, U.Name, U.Email, U.Birthday, U.Type, U.AvatarData
+        FROM Users U
+        WHERE U.Type = 'student'
+          AND U.ID NOT IN (SELECT student_id FROM GroupStudent WHERE status IN ('approved', 'active'))
+        ORDER BY U.Name
+    ")?;
+
+    let users_iter = stmt.query_map(params![], |row| {
+        Ok(UserInfo {
+            id: row.get("ID")?,
+            name: row.get("Name")?,
+            email: row.get("Email")?,
+            birthday: row.get("Birthday")?,
+            user_type: row.get("Type")?,
+            avatar_data: row.get("AvatarData").ok(),
+            group_id: None,
+            child_count: None,
+        })
+    })?;
+
+    users_iter.collect::<Result<Vec<_>, Error>>()
+}
+
+// получить курсы со свободными местами
+pub fn get_courses_with_available_seats(conn: &Connection) -> Result<Vec<Course>> {
+    let mut stmt = conn.prepare("
+        SELECT
+            ID,
+            title,
+            description,
+            level,
+            total_seats,
+            seats, -- <--- Просто выбираем существующий столбец seats
+            price
+        FROM Course
+        ORDER BY title
+    ")?;
+
+    let courses_iter = stmt.query_map(params![], |row| {
+        Ok(Course {
+            id: row.get("ID")?,
+            title: row.get("title")?,
+            description: row.get("description")?,
+            level: row.get("level")?,
+            total_seats: row.get("total_seats")?,
+            seats: row.get("seats")?, // <--- Получаем значение из БД
+            price: row.get("price")?,
+            lesson_count: 0,
+        })
+    })?;
+    courses_iter.collect::<Result<Vec<_>>>()
+}
+
+// получить группы по course_id
+pub fn get_groups_by_course_id(conn: &Connection, course_id: i32) -> Result<Vec<Group>> {
+    let mut stmt = conn.prepare("
+        SELECT
+            G.id,
+            G.course_id,
+            G.teacher_id,
+            G.name,
+            G.student_count,
+            U.Name AS teacher_name,
+            G.status
+        FROM \"Group\" G
+        LEFT JOIN Users U ON G.teacher_id = U.ID
+        WHERE G.course_id = ?1
+        ORDER BY G.name
+    ")?;
+
+    let groups_iter = stmt.query_map(params![course_id], |row| {
+        Ok(Group {
+            id: row.get("id")?,
+            course_id: row.get("course_id")?,
+            teacher_id: row.get("teacher_id")?,
+            name: row.get("name")?,
+            student_count: row.get("student_count")?,
+            course_name: None, // Не получаем здесь название курса
+            teacher_name: row.get("teacher_name").ok(),
+            status: row.get("status")?,
+        })
+    })?;
+    groups_iter.collect::<Result<Vec<_>>>()
+}
+
+// добавить платеж
+pub fn add_payment(
+    conn: &Connection,
+    student_id: i32,
+    date: &str,
+    amount: f64,
+    payment_type: &str,
+    course_id: i32,
+    group_id: i32,
+) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO Payment (student_id, date, amount, type, course_id, group_id, created_at, enrollment_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now', 'localtime'),
+                 (SELECT id FROM GroupStudent WHERE student_id = ?1 AND group_id = ?6 ORDER BY id DESC LIMIT 1))",
+        params![student_id, date, amount, payment_type, course_id, group_id],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Фиксирует скидку при зачислении: уменьшает начисление в GroupStudent
+/// и отмечает скидку (и промокод) в первом платеже
+fn apply_enrollment_discount(
+    tx: &Connection,
+    enrollment_id: i32,
+    payment_id: i64,
+    discount: f64,
+    note: &str,
+    promo_code_id: Option<i32>,
+) -> Result<(), String> {
+    tx.execute(
+        "UPDATE GroupStudent
+         SET discount = ?1, discount_note = ?2, price = MAX(COALESCE(price, 0) - ?1, 0)
+         WHERE id = ?3",
+        params![discount, note, enrollment_id],
+    )
+    .map_err(|e| format!("Ошибка записи скидки: {}", e))?;
+    tx.execute(
+        "UPDATE Payment SET discount = ?1, discount_note = ?2, promo_code_id = ?3 WHERE id = ?4",
+        params![discount, note, promo_code_id, payment_id],
+    )
+    .map_err(|e| format!("Ошибка записи скидки: {}", e))?;
+
+    println!("DEBUG DB: скидка {:.2} для зачисления {} ({})", discount, enrollment_id, note);
+    Ok(())
+}
+
+/// Имя брата/сестры (общий родитель в ParentStudent), который уже учится в какой-либо группе
+pub fn find_enrolled_sibling(conn: &Connection, student_id: i32) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT U.Name
+         FROM ParentStudent PS
+         JOIN ParentStudent Sib ON Sib.parent_id = PS.parent_id AND Sib.student_id <> PS.student_id
+         JOIN GroupStudent GS ON GS.student_id = Sib.student_id AND GS.status IN ('approved', 'active')
+         JOIN Users U ON U.ID = Sib.student_id
+         WHERE PS.student_id = ?1
+         LIMIT 1",
+        params![student_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+fn map_promo_code(row: &rusqlite::Row) -> Result<PromoCode> {
+    Ok(PromoCode {
+        id: row.get("id")?,
+        code: row.get("code")?,
+        is_percent: row.get("is_percent")?,
+        value: row.get("value")?,
+        valid_from: row.get("valid_from")?,
+        valid_to: row.get("valid_to")?,
+        max_uses: row.get("max_uses")?,
+        used_count: row.get("used_count")?,
+    })
+}
+
+pub fn get_promo_codes(conn: &Connection) -> Result<Vec<PromoCode>> {
+    let mut stmt = conn.prepare("SELECT * FROM PromoCode ORDER BY code")?;
+    let codes = stmt.query_map([], map_promo_code)?;
+    codes.collect()
+}
+
+/// Поиск промокода без учёта регистра
+pub fn find_promo_code(conn: &Connection, code: &str) -> Result<Option<PromoCode>> {
+    conn.query_row(
+        "SELECT * FROM PromoCode WHERE code = ?1",
+        params![code.trim()],
+        map_promo_code,
+    )
+    .optional()
+}
+
+pub fn add_promo_code(conn: &Connection, promo: &PromoCode) -> Result<()> {
+    conn.execute(
+        "INSERT INTO PromoCode (code, is_percent, value, valid_from, valid_to, max_uses)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            promo.code.trim(),
+            promo.is_percent,
+            promo.value,
+            promo.valid_from,
+            promo.valid_to,
+            promo.max_uses
+        ],
+    )?;
+    Ok(())
+}
+
+/// Удаляет промокод; уже оформленные скидки сохраняют своё примечание
+pub fn delete_promo_code(conn: &Connection, promo_id: i32) -> Result<()> {
+    conn.execute(
+        "UPDATE Payment SET promo_code_id = NULL WHERE promo_code_id = ?1",
+        params![promo_id],
+    )?;
+    conn.execute("DELETE FROM PromoCode WHERE id = ?1", params![promo_id])?;
+    Ok(())
+}
+/// Баланс студентов по зачислениям: начислено (цена курса при зачислении), оплачено и остаток.
+/// Заявки не начисляются, отчисленные видны, только пока по ним есть оплата.
+/// Оплата переведённого студента учитывается в его новом зачислении.
+/// `student_id = None` — по всем студентам, должники первыми.
+pub fn get_student_balances(conn: &Connection, student_id: Option<i32>) -> Result<Vec<StudentBalance>> {
+    let mut stmt = conn.prepare("
+        SELECT
+            GS.id AS enrollment_id,
+            GS.student_id,
+            U.Name AS student_name,
+            GS.group_id,
+            G.name AS group_name,
+            G.course_id,
+            COALESCE(C.title, '—') AS course_title,
+            CASE WHEN GS.status = 'dropped' THEN 0 ELSE COALESCE(GS.price, 0) END AS charged,
+            GS.carried_over + COALESCE((
+                SELECT SUM(P.amount)
+                FROM Payment P
+                WHERE P.enrollment_id = GS.id
+            ), 0) AS paid
+        FROM GroupStudent GS
+        JOIN Users U ON GS.student_id = U.ID
+        JOIN \"Group\" G ON GS.group_id = G.id
+        LEFT JOIN Course C ON G.course_id = C.ID
+        WHERE (?1 IS NULL OR GS.student_id = ?1)
+          AND GS.status NOT IN ('applied', 'transferred')
+          AND NOT (GS.status = 'dropped' AND paid = 0)
+        ORDER BY (charged - paid) DESC, U.Name
+    ")?;
+
+    let balances = stmt.query_map(params![student_id], |row| {
+        Ok(StudentBalance {
+            enrollment_id: row.get("enrollment_id")?,
+            student_id: row.get("student_id")?,
+            student_name: row.get("student_name")?,
+            group_id: row.get("group_id")?,
+            group_name: row.get("group_name")?,
+            course_id: row.get("course_id")?,
+            course_title: row.get("course_title")?,
+            charged: row.get("charged")?,
+            paid: row.get("paid")?,
+            installments: Vec::new(),
+        })
+    })?;
+
+    let mut balances = balances.collect::<Result<Vec<_>>>()?;
+    for balance in &mut balances {
+        balance.installments = get_installments(conn, balance.enrollment_id, balance.paid)?;
+    }
+    Ok(balances)
+}
+
+/// Платежи по графику; внесённая сумма `paid` распределяется по взносам по порядку,
+/// так что частичная оплата закрывает ближайший неоплаченный взнос
+fn get_installments(conn: &Connection, enrollment_id: i32, paid: f64) -> Result<Vec<Installment>> {
+    let mut stmt = conn.prepare(
+        "SELECT number, due_date, amount FROM Installment WHERE group_student_id = ?1 ORDER BY number",
+    )?;
+    let mut remaining = paid;
+    let installments = stmt
+        .query_map(params![enrollment_id], |row| {
+            Ok(Installment {
+                number: row.get("number")?,
+                due_date: row.get("due_date")?,
+                amount: row.get("amount")?,
+                paid: 0.0,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .map(|mut installment| {
+            installment.paid = remaining.clamp(0.0, installment.amount);
+            remaining -= installment.paid;
+            installment
+        })
+        .collect();
+    Ok(installments)
+}
+
+/// Создаёт (или заменяет) график из `parts` ежемесячных платежей, первый — в `first_due`.
+/// Сумма делится поровну, копейки от округления добавляются к последнему платежу.
+pub fn create_installment_plan(
+    conn: &mut Connection,
+    enrollment_id: i32,
+    total: f64,
+    parts: u32,
+    first_due: NaiveDate,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    create_installment_plan_in_tx(&tx, enrollment_id, total, parts, first_due)?;
+    tx.commit()
+}
+
+fn create_installment_plan_in_tx(
+    tx: &Connection,
+    enrollment_id: i32,
+    total: f64,
+    parts: u32,
+    first_due: NaiveDate,
+) -> Result<()> {
+    let parts = parts.max(1);
+    let part_amount = (total / parts as f64 * 100.0).round() / 100.0;
+
+    tx.execute(
+        "DELETE FROM Installment WHERE group_student_id = ?1",
+        params![enrollment_id],
+    )?;
+    for index in 0..parts {
+        let amount = if index + 1 == parts {
+            ((total - part_amount * (parts - 1) as f64) * 100.0).round() / 100.0
+        } else {
+            part_amount
+        };
+        let due_date = first_due
+            .checked_add_months(Months::new(index))
+            .unwrap_or(first_due)
+            .format("%Y-%m-%d")
+            .to_string();
+        tx.execute(
+            "INSERT INTO Installment (group_student_id, number, due_date, amount) VALUES (?1, ?2, ?3, ?4)",
+            params![enrollment_id, index + 1, due_date, amount],
+        )?;
+    }
+
+    println!(
+        "DEBUG DB: график платежей для зачисления {}: {} частей на {:.2}",
+        enrollment_id, parts, total
+    );
+    Ok(())
+}
+/// Встречная запись к платежу (возврат или корректировка): та же группа и курс,
+/// сумма со знаком, причина, оператор и время оформления
+pub fn add_payment_reversal(
+    conn: &Connection,
+    original_id: i32,
+    amount: f64,
+    payment_type: &str,
+    reason: &str,
+    operator_id: Option<i32>,
+) -> Result<()> {
+    // Если студента с тех пор перевели, встречная запись относится к его текущему зачислению:
+    // перенесённая при переводе сумма уже учитывает исходный платёж
+    let enrollment_id: Option<i32> = conn.query_row(
+        "WITH RECURSIVE Forward(id) AS (
+             SELECT enrollment_id FROM Payment WHERE id = ?1
+             UNION
+             SELECT Next.id FROM GroupStudent Next JOIN Forward ON Next.transferred_from_id = Forward.id
+         )
+         SELECT MAX(id) FROM Forward",
+        params![original_id],
+        |row| row.get(0),
+    )?;
+
+    let inserted = conn.execute(
+        "INSERT INTO Payment (student_id, date, amount, type, course_id, group_id,
+                              reverses_payment_id, reason, operator_id, created_at, enrollment_id)
+         SELECT student_id, date('now', 'localtime'), ?2, ?3, course_id, group_id,
+                id, ?4, ?5, datetime('now', 'localtime'), ?6
+         FROM Payment
+         WHERE id = ?1",
+        params![original_id, amount, payment_type, reason, operator_id, enrollment_id],
+    )?;
+    if inserted == 0 {
+        return Err(Error::QueryReturnedNoRows);
+    }
+    println!(
+        "DEBUG DB: {} к платежу #{} на сумму {:.2}",
+        payment_type, original_id, amount
+    );
+    Ok(())
+}
+
+/// Удаляет платёж, если он моложе `lock_days` дней и у него нет связанных возвратов/корректировок.
+/// Более старые платежи исправляются только встречными записями. Выписанные по платежу
+/// счёт и квитанция остаются в базе аннулированными, чтобы их номера не выдавались повторно.
+pub fn delete_payment(conn: &mut Connection, payment_id: i32, lock_days: u32) -> Result<(), String> {
+    let (date, linked): (String, i32) = conn
+        .query_row(
+            "SELECT COALESCE(date(created_at), date),
+                    (SELECT COUNT(*) FROM Payment R WHERE R.reverses_payment_id = P.id)
+             FROM Payment P WHERE id = ?1",
+            params![payment_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Платёж #{} не найден: {}", payment_id, e))?;
+
+    if linked > 0 {
+        return Err(format!(
+            "К платежу #{} оформлены возвраты или корректировки — удалить его нельзя.",
+            payment_id
+        ));
+    }
+
+    let payment_date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Некорректная дата платежа #{}: {}", payment_id, e))?;
+    let age_days = (chrono::Local::now().date_naive() - payment_date).num_days();
+    if age_days >= lock_days as i64 {
+        return Err(format!(
+            "Платёж #{} старше {} дн. — удаление запрещено, оформите возврат или корректировку.",
+            payment_id, lock_days
+        ));
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE PaymentDocument SET cancelled_at = datetime('now', 'localtime')
+         WHERE payment_id = ?1 AND cancelled_at IS NULL",
+        params![payment_id],
+    )
+    .map_err(|e| format!("Ошибка аннулирования документов платежа: {}", e))?;
+    tx.execute("DELETE FROM Payment WHERE id = ?1", params![payment_id])
+        .map_err(|e| format!("Ошибка удаления платежа: {}", e))?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Имена родителей каждого студента (ключ — ID студента)
+pub fn get_parent_names(conn: &Connection) -> Result<HashMap<i32, Vec<String>>> {
+    let mut stmt = conn.prepare(
+        "SELECT PS.student_id, U.Name
+         FROM ParentStudent PS
+         JOIN Users U ON U.ID = PS.parent_id",
+    )?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))?;
+
+    let mut parents: HashMap<i32, Vec<String>> = HashMap::new();
+    for row in rows {
+        let (student_id, name) = row?;
+        parents.entry(student_id).or_default().push(name);
+    }
+    Ok(parents)
+}
+
+/// Ссылки на строки выписок, по которым уже созданы платежи
+pub fn get_bank_references(conn: &Connection) -> Result<HashSet<String>> {
+    let mut stmt = conn.prepare("SELECT bank_reference FROM Payment WHERE bank_reference IS NOT NULL")?;
+    let references = stmt.query_map([], |row| row.get(0))?;
+    references.collect()
+}
+
+/// Создаёт платежи по подтверждённым строкам выписки одной транзакцией
+/// и возвращает ID новых платежей
+pub fn import_bank_payments(
+    conn: &mut Connection,
+    items: &[(EnrollmentPickListItem, BankTransaction)],
+) -> Result<Vec<i32>> {
+    let tx = conn.transaction()?;
+    let mut payment_ids = Vec::new();
+
+    for (enrollment, transaction) in items {
+        tx.execute(
+            "INSERT INTO Payment (student_id, date, amount, type, course_id, group_id,
+                                  created_at, bank_reference, enrollment_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now', 'localtime'), ?7,
+                     (SELECT id FROM GroupStudent WHERE student_id = ?1 AND group_id = ?6 ORDER BY id DESC LIMIT 1))",
+            params![
+                enrollment.student_id,
+                transaction.date,
+                transaction.amount,
+                BANK_TRANSFER_TYPE,
+                enrollment.course_id,
+                enrollment.group_id,
+                transaction.reference
+            ],
+        )?;
+        payment_ids.push(tx.last_insert_rowid() as i32);
+    }
+
+    tx.commit()?;
+    println!("DEBUG DB: из выписки загружено платежей: {}", payment_ids.len());
+    Ok(payment_ids)
+}
+
+/// Выписывает счёт и квитанцию по платежу, если они ещё не выписаны.
+/// Номер — следующий по порядку для вида документа в текущем году
+/// (аннулированные документы тоже учитываются, их номера не переиспользуются).
+pub fn issue_payment_documents(conn: &mut Connection, payment_id: i32) -> Result<()> {
+    let tx = conn.transaction()?;
+    issue_payment_documents_in_tx(&tx, payment_id)?;
+    tx.commit()
+}
 
-    conn.execute(
-        "INSERT INTO PastSessions (group_id, date, lesson_id) VALUES (?1, ?2, ?3)",
-        params![group_id, date_str, lesson_id],
-    )?;
-    Ok(conn.last_insert_rowid() as i32) // Возвращаем ID
+fn issue_payment_documents_in_tx(tx: &Connection, payment_id: i32) -> Result<()> {
+    for kind in PaymentDocumentKind::ALL {
+        tx.execute(
+            "INSERT OR IGNORE INTO PaymentDocument (payment_id, kind, year, number, issued_at)
+             SELECT ?1, ?2, CAST(strftime('%Y', 'now', 'localtime') AS INTEGER),
+                    COALESCE(MAX(number), 0) + 1, datetime('now', 'localtime')
+             FROM PaymentDocument
+             WHERE kind = ?2 AND year = CAST(strftime('%Y', 'now', 'localtime') AS INTEGER)",
+            params![payment_id, kind],
+        )?;
+    }
+    Ok(())
 }
-pub fn get_all_payments_with_details(conn: &Connection) -> Result<Vec<Payment>> {
-    let mut stmt = conn.prepare("
-        SELECT
-            P.id,
-            P.student_id,
-            P.date,
-            P.amount,
-            P.type,
-            P.course_id,
-            P.group_id,
-            U.Name AS student_name,
-            C.title AS course_title,
-            G.name AS group_name
-        FROM Payment P
-        JOIN Users U ON P.student_id = U.ID
-        JOIN Course C ON P.course_id = C.ID
-        JOIN \"Group\" G ON P.group_id = G.id
-        ORDER BY P.date DESC
-    ")?;
 
-    let payments_iter = stmt.query_map(params![], |row| {
-        Ok(Payment {
-            id: row.get("id")?,
-            student_id: row.get("student_id")?,
-            date: row.get("date")?,
-            amount: row.get("amount")?,
-            payment_type: row.get("type")?,
-            course_id: row.get("course_id")?,
-            group_id: row.get("group_id")?,
-            student_name: row.get("student_name")?,
-            course_title: row.get("course_title")?,
-            group_name: row.get("group_name")?,
-        })
-    })?;
+fn map_payment_document(row: &rusqlite::Row) -> Result<PaymentDocument> {
+    Ok(PaymentDocument {
+        payment_id: row.get("payment_id")?,
+        kind: row.get("kind")?,
+        year: row.get("year")?,
+        number: row.get("number")?,
+        issued_at: row.get("issued_at")?,
+    })
+}
 
-    payments_iter.collect::<Result<Vec<_>, Error>>()
+pub fn get_payment_documents(conn: &Connection) -> Result<Vec<PaymentDocument>> {
+    let mut stmt = conn.prepare("SELECT * FROM PaymentDocument WHERE cancelled_at IS NULL ORDER BY year, kind, number")?;
+    let documents = stmt.query_map([], map_payment_document)?;
+    documents.collect()
 }
 
-// получить студентов, которые не состоят ни в одной группе
-pub fn get_students_not_in_any_group(conn: &Connection) -> Result<Vec<UserInfo>> {
-    let mut stmt = conn.prepare("
-        SELECT
-            U.ID, U.Name, U.Email, U.Birthday, U.Type, U.AvatarData
-        FROM Users U
-        WHERE U.Type = 'student' AND U.ID NOT IN (SELECT student_id FROM GroupStudent)
-        ORDER BY U.Name
-    ")?;
+pub fn get_payment_document(
+    conn: &Connection,
+    payment_id: i32,
+    kind: PaymentDocumentKind,
+) -> Result<PaymentDocument> {
+    conn.query_row(
+        "SELECT * FROM PaymentDocument WHERE payment_id = ?1 AND kind = ?2 AND cancelled_at IS NULL",
+        params![payment_id, kind],
+        map_payment_document,
+    )
+}
 
-    let users_iter = stmt.query_map(params![], |row| {
-        Ok(UserInfo {
-            id: row.get("ID")?,
-            name: row.get("Name")?,
-            email: row.get("Email")?,
-            birthday: row.get("Birthday")?,
-            user_type: row.get("Type")?,
-            avatar_data: row.get("AvatarData").ok(),
-            group_id: None,
-            child_count: None,
+/// Ставки всех преподавателей, включая тех, кому ставка ещё не задана
+pub fn get_teacher_rates(conn: &Connection) -> Result<Vec<TeacherRate>> {
+    let mut stmt = conn.prepare(
+        "SELECT U.ID, U.Name, R.rate_type, COALESCE(R.rate, 0)
+         FROM Users U
+         LEFT JOIN TeacherRate R ON R.teacher_id = U.ID
+         WHERE U.Type = 'teacher'
+         ORDER BY U.Name",
+    )?;
+    let rates = stmt.query_map([], |row| {
+        Ok(TeacherRate {
+            teacher_id: row.get(0)?,
+            teacher_name: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            rate_type: row.get(2)?,
+            rate: row.get(3)?,
         })
     })?;
+    rates.collect()
+}
 
-    users_iter.collect::<Result<Vec<_>, Error>>()
+pub fn set_teacher_rate(conn: &Connection, teacher_id: i32, rate_type: RateType, rate: f64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO TeacherRate (teacher_id, rate_type, rate) VALUES (?1, ?2, ?3)
+         ON CONFLICT(teacher_id) DO UPDATE SET rate_type = excluded.rate_type, rate = excluded.rate",
+        params![teacher_id, rate_type, rate],
+    )?;
+    println!("DEBUG DB: ставка преподавателя {} обновлена: {} {}", teacher_id, rate_type.as_str(), rate);
+    Ok(())
 }
 
-// получить курсы со свободными местами
-pub fn get_courses_with_available_seats(conn: &Connection) -> Result<Vec<Course>> {
-    let mut stmt = conn.prepare("
-        SELECT
-            ID,
-            title,
-            description,
-            level,
-            total_seats,
-            seats, -- <--- Просто выбираем существующий столбец seats
-            price
-        FROM Course
-        ORDER BY title
-    ")?;
+/// Расчёт зарплаты за месяц "ГГГГ-ММ" по текущим ставкам.
+/// Занятия относятся к преподавателю, который их провёл (PastSessions.teacher_id),
+/// студенто-часы — по отметкам присутствия, выручка — по оплатам групп преподавателя.
+/// Оплата засчитывается тому, кто вёл группу на дату платежа, то есть провёл последнее
+/// занятие не позже этой даты; истории назначений нет, поэтому оплаты до первого
+/// занятия группы идут её текущему преподавателю.
+fn calculate_payroll_lines(conn: &Connection, month: &str) -> Result<Vec<PayrollLine>> {
+    let mut stmt = conn.prepare(
+        "SELECT
+            U.ID,
+            U.Name,
+            R.rate_type,
+            COALESCE(R.rate, 0),
+            (SELECT COUNT(*) FROM PastSessions PS
+             WHERE PS.teacher_id = U.ID AND substr(PS.date, 1, 7) = ?1) AS sessions,
+            (SELECT COALESCE(SUM(COALESCE(
+                        (SELECT PL.duration_minutes FROM PlannedSession PL
+                         WHERE PL.past_session_id = PS.id LIMIT 1), ?2)), 0) / 60.0
+             FROM Attendance A
+             JOIN PastSessions PS ON PS.id = A.lesson_id
+             WHERE PS.teacher_id = U.ID AND A.present = 'Present'
+               AND substr(PS.date, 1, 7) = ?1) AS student_hours,
+            (SELECT COALESCE(SUM(P.amount), 0) FROM Payment P
+             JOIN \"Group\" G ON G.id = P.group_id
+             WHERE COALESCE(
+                       (SELECT PS.teacher_id FROM PastSessions PS
+                        WHERE PS.group_id = P.group_id AND PS.teacher_id IS NOT NULL
+                          AND date(PS.date) <= P.date
+                        ORDER BY PS.date DESC, PS.id DESC LIMIT 1),
+                       G.teacher_id) = U.ID
+               AND substr(P.date, 1, 7) = ?1) AS revenue
+         FROM Users U
+         LEFT JOIN TeacherRate R ON R.teacher_id = U.ID
+         WHERE U.Type = 'teacher'
+         ORDER BY U.Name",
+    )?;
+    let lines = stmt.query_map(params![month, DEFAULT_SESSION_MINUTES], |row| {
+        let mut line = PayrollLine {
+            teacher_id: row.get(0)?,
+            teacher_name: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            rate_type: row.get(2)?,
+            rate: row.get(3)?,
+            sessions: row.get(4)?,
+            student_hours: row.get(5)?,
+            revenue: row.get(6)?,
+            amount: 0.0,
+        };
+        line.accrue();
+        Ok(line)
+    })?;
 
-    let courses_iter = stmt.query_map(params![], |row| {
-        Ok(Course {
-            id: row.get("ID")?,
-            title: row.get("title")?,
-            description: row.get("description")?,
-            level: row.get("level")?,
-            total_seats: row.get("total_seats")?,
-            seats: row.get("seats")?, // <--- Получаем значение из БД
-            price: row.get("price")?,
-            lesson_count: 0,
+    // Преподаватели без занятий и оплат за месяц в ведомость не попадают
+    lines
+        .filter(|line| {
+            line.as_ref()
+                .map_or(true, |line| line.sessions > 0 || line.revenue != 0.0)
         })
-    })?;
-    courses_iter.collect::<Result<Vec<_>>>()
+        .collect()
 }
 
-// получить группы по course_id
-pub fn get_groups_by_course_id(conn: &Connection, course_id: i32) -> Result<Vec<Group>> {
-    let mut stmt = conn.prepare("
-        SELECT
-            G.id,
-            G.course_id,
-            G.teacher_id,
-            G.name,
-            G.student_count,
-            U.Name AS teacher_name,
-            G.status
-        FROM \"Group\" G
-        LEFT JOIN Users U ON G.teacher_id = U.ID
-        WHERE G.course_id = ?1
-        ORDER BY G.name
-    ")?;
+/// Ведомость за месяц "ГГГГ-ММ": сохранённая, если период утверждён, иначе расчётная
+pub fn get_payroll(conn: &Connection, month: &str) -> Result<Payroll> {
+    let period: Option<(i32, String, Option<String>)> = conn
+        .query_row(
+            "SELECT PP.id, PP.approved_at, U.Name
+             FROM PayrollPeriod PP
+             LEFT JOIN Users U ON U.ID = PP.approved_by
+             WHERE PP.month = ?1",
+            params![month],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
 
-    let groups_iter = stmt.query_map(params![course_id], |row| {
-        Ok(Group {
-            id: row.get("id")?,
-            course_id: row.get("course_id")?,
-            teacher_id: row.get("teacher_id")?,
-            name: row.get("name")?,
-            student_count: row.get("student_count")?,
-            course_name: None, // Не получаем здесь название курса
-            teacher_name: row.get("teacher_name").ok(),
-            status: row.get("status")?,
-        })
-    })?;
-    groups_iter.collect::<Result<Vec<_>>>()
-}
+    let Some((period_id, approved_at, approved_by)) = period else {
+        return Ok(Payroll {
+            month: month.to_string(),
+            lines: calculate_payroll_lines(conn, month)?,
+            approved_at: None,
+            approved_by: None,
+        });
+    };
 
-// добавить платеж
-pub fn add_payment(
-    conn: &Connection,
-    student_id: i32,
-    date: &str,
-    amount: f64,
-    payment_type: &str,
-    course_id: i32,
-    group_id: i32,
-) -> Result<()> {
-    conn.execute(
-        "INSERT INTO Payment (student_id, date, amount, type, course_id, group_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![student_id, date, amount, payment_type, course_id, group_id],
+    let mut stmt = conn.prepare(
+        "SELECT teacher_id, teacher_name, rate_type, rate, sessions, student_hours, revenue, amount
+         FROM PayrollLine
+         WHERE period_id = ?1
+         ORDER BY teacher_name",
     )?;
-    Ok(())
+    let lines = stmt
+        .query_map(params![period_id], |row| {
+            Ok(PayrollLine {
+                teacher_id: row.get(0)?,
+                teacher_name: row.get(1)?,
+                rate_type: row.get(2)?,
+                rate: row.get(3)?,
+                sessions: row.get(4)?,
+                student_hours: row.get(5)?,
+                revenue: row.get(6)?,
+                amount: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Payroll {
+        month: month.to_string(),
+        lines,
+        approved_at: Some(approved_at),
+        approved_by,
+    })
 }
-pub fn delete_payment(conn: &Connection, course_id: i32) -> Result<()> {
-    let mut stmt = conn.prepare("DELETE FROM Payment WHERE id = ?")?;
-    stmt.execute([course_id])?;
+
+/// Утверждает ведомость за завершившийся месяц: фиксирует расчёт в PayrollLine
+/// и закрывает период от пересчёта
+pub fn approve_payroll(conn: &mut Connection, month: &str, approver_id: i32) -> Result<(), String> {
+    let current_month = chrono::Local::now().format("%Y-%m").to_string();
+    if month >= current_month.as_str() {
+        return Err("Утвердить можно только завершившийся месяц.".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let already_approved: bool = tx
+        .query_row("SELECT EXISTS(SELECT 1 FROM PayrollPeriod WHERE month = ?1)", params![month], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if already_approved {
+        return Err(format!("Ведомость за {} уже утверждена.", month));
+    }
+
+    let lines = calculate_payroll_lines(&tx, month)
+        .map_err(|e| format!("Ошибка расчёта зарплаты: {}", e))?;
+    if lines.is_empty() {
+        return Err(format!("За {} нет проведённых занятий — утверждать нечего.", month));
+    }
+    let without_rate: Vec<&str> = lines
+        .iter()
+        .filter(|line| line.rate_type.is_none())
+        .map(|line| line.teacher_name.as_str())
+        .collect();
+    if !without_rate.is_empty() {
+        return Err(format!("Не задана ставка: {}", without_rate.join(", ")));
+    }
+
+    tx.execute(
+        "INSERT INTO PayrollPeriod (month, approved_at, approved_by)
+         VALUES (?1, datetime('now', 'localtime'), ?2)",
+        params![month, approver_id],
+    )
+    .map_err(|e| format!("Ошибка закрытия периода: {}", e))?;
+    let period_id = tx.last_insert_rowid();
+
+    for line in &lines {
+        tx.execute(
+            "INSERT INTO PayrollLine (period_id, teacher_id, teacher_name, rate_type, rate,
+                                      sessions, student_hours, revenue, amount)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                period_id,
+                line.teacher_id,
+                line.teacher_name,
+                line.rate_type,
+                line.rate,
+                line.sessions,
+                line.student_hours,
+                line.revenue,
+                line.amount
+            ],
+        )
+        .map_err(|e| format!("Ошибка сохранения ведомости: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    println!("DEBUG DB: ведомость за {} утверждена, строк: {}", month, lines.len());
     Ok(())
 }
 pub fn load_payments(conn: &Connection) -> Result<Vec<Payment>> {
@@ -1155,11 +3277,19 @@ pub fn load_payments(conn: &Connection) -> Result<Vec<Payment>> {
             g.name AS group_name,
             p.date,
             p.amount,
-            p.type
+            p.type,
+            p.reverses_payment_id,
+            p.reason,
+            o.name AS operator_name,
+            p.created_at,
+            p.discount,
+            p.discount_note
         FROM Payment p
         JOIN Users u ON p.student_id = u.id
         JOIN Course c ON p.course_id = c.id
         JOIN \"Group\" g ON p.group_id = g.id
+        LEFT JOIN Users o ON p.operator_id = o.id
+        ORDER BY p.date DESC, p.id DESC
     ")?;
 
 
@@ -1175,6 +3305,12 @@ pub fn load_payments(conn: &Connection) -> Result<Vec<Payment>> {
             date: row.get(7)?,
             amount: row.get(8)?,
             payment_type: row.get(9)?,
+            reverses_payment_id: row.get(10)?,
+            reason: row.get(11)?,
+            operator_name: row.get(12)?,
+            created_at: row.get(13)?,
+            discount: row.get(14)?,
+            discount_note: row.get(15)?,
         })
     })?
         .filter_map(Result::ok)
@@ -1229,6 +3365,37 @@ pub fn get_student_attendance_counts(
     Ok(attendance_counts)
 }
 
+/// Посещённые занятия курса, отмеченные студентам группы в прежних группах того же курса,
+/// из которых они были переведены (по цепочке переводов)
+pub fn get_transferred_attendance_counts(conn: &Connection, group_id: i32) -> Result<HashMap<i32, i32>> {
+    let mut stmt = conn.prepare("
+        WITH RECURSIVE Chain(student_id, group_id, prev_id) AS (
+            SELECT GS.student_id, GS.group_id, GS.transferred_from_id
+            FROM GroupStudent GS
+            WHERE GS.group_id = ?1 AND GS.status IN ('approved', 'active')
+            UNION
+            SELECT Prev.student_id, Prev.group_id, Prev.transferred_from_id
+            FROM GroupStudent Prev
+            JOIN Chain ON Prev.id = Chain.prev_id
+            JOIN \"Group\" PG ON PG.id = Prev.group_id
+            WHERE PG.course_id = (SELECT course_id FROM \"Group\" WHERE id = ?1)
+        )
+        SELECT A.student_id, COUNT(DISTINCT PS.lesson_id) AS attended_lessons_count
+        FROM Chain
+        JOIN Attendance A ON A.student_id = Chain.student_id AND A.group_id = Chain.group_id
+        JOIN PastSessions PS ON PS.id = A.lesson_id
+        WHERE Chain.group_id <> ?1 AND A.present = 'Present'
+        GROUP BY A.student_id
+    ")?;
+
+    let counts = stmt
+        .query_map(params![group_id], |row| {
+            Ok((row.get("student_id")?, row.get("attended_lessons_count")?))
+        })?
+        .collect::<Result<HashMap<_, _>>>()?;
+    Ok(counts)
+}
+
 /// Добавляет запись о сертификате.
 /// Используем `&Transaction` для атомарности, если вызывается внутри транзакции.
 pub fn add_certificate(
@@ -1279,13 +3446,13 @@ pub fn check_course_completion_and_issue_certificates(
         let mut students_in_group_stmt = tx.prepare("
             SELECT U.ID, U.Name FROM Users U
             JOIN GroupStudent GS ON U.ID = GS.student_id
-            WHERE GS.group_id = ?1
+            WHERE GS.group_id = ?1 AND GS.status IN ('approved', 'active')
         ")?;
         let students_iter = students_in_group_stmt.query_map(params![group_id], |row| {
             Ok(UserInfo {
                 id: row.get("ID")?,
                 name: row.get("Name")?,
-                user_type: "".to_string(),
+                user_type: Role::Student,
                 email: "".to_string(),
                 avatar_data: None,
                 group_id: None,
@@ -1300,18 +3467,32 @@ pub fn check_course_completion_and_issue_certificates(
         // Получаем данные о посещаемости для всех студентов в этой группе
         // Передаем `tx` напрямую
         let student_attendance_counts = get_student_attendance_counts(tx, group_id)?;
+        let transferred_attendance_counts = get_transferred_attendance_counts(tx, group_id)?;
         println!("DEBUG DB: Собраны данные посещаемости для {} студентов.", student_attendance_counts.len());
 
+        // Баллы за сданные работы (None — в курсе нет заданий, по которым сдаются работы)
+        let student_assignment_scores = get_student_assignment_scores(tx, group_id, course_id)?;
+
 
         let now: chrono::DateTime<chrono::Local> = chrono::Local::now();
         let issue_date_str = now.format("%Y-%m-%d").to_string();
 
         for student in students_in_group {
-            let attended_lessons = student_attendance_counts.get(&student.id).copied().unwrap_or(0);
+            // Занятия, посещённые до перевода из другой группы курса, тоже засчитываются
+            let attended_lessons = student_attendance_counts.get(&student.id).copied().unwrap_or(0)
+                + transferred_attendance_counts.get(&student.id).copied().unwrap_or(0);
             println!("DEBUG DB: Студент {}: {} из {} уроков посетил.", student.name, attended_lessons, total_lessons_in_course);
 
             let grade = if total_lessons_in_course > 0 {
-                let percentage = (attended_lessons as f64 / total_lessons_in_course as f64) * 100.0;
+                let attendance_percentage = (attended_lessons as f64 / total_lessons_in_course as f64) * 100.0;
+                // Итог — среднее посещаемости и среднего балла за работы, если работы в курсе есть
+                let percentage = match &student_assignment_scores {
+                    Some(scores) => {
+                        let assignment_score = scores.get(&student.id).copied().unwrap_or(0.0);
+                        (attendance_percentage + assignment_score) / 2.0
+                    }
+                    None => attendance_percentage,
+                };
                 if percentage >= 100.0 {
                     "Отлично".to_string()
                 } else if percentage >= 75.0 {
@@ -1345,6 +3526,15 @@ pub fn check_course_completion_and_issue_certificates(
             params![group_id],
         )?;
         println!("DEBUG DB: Статус группы {} успешно обновлен на 'Неактивна'.", group_id);
+
+        // Обучение студентов группы завершено
+        tx.execute(
+            "UPDATE GroupStudent
+             SET status = ?2, status_changed_at = datetime('now', 'localtime'), ended_at = date('now', 'localtime')
+             WHERE group_id = ?1 AND status IN ('approved', 'active')",
+            params![group_id, EnrollmentStatus::Completed],
+        )?;
+        refresh_group_student_count(tx, group_id)?;
         
     } else {
         println!("DEBUG DB: Группа {} еще не завершила все уроки курса {}.", group_id, course_id);
@@ -1419,6 +3609,52 @@ pub fn get_certificates_for_student(conn: &Connection, student_id: i32) -> Resul
 
     certificates_iter.collect()
 }
+/// Собирает сводку обучения студента: текущая группа, проведённые занятия с отметками и сертификаты.
+pub fn get_student_progress(conn: &Connection, student: UserInfo) -> Result<StudentProgress> {
+    let group = get_student_group_by_user_id(conn, student.id)?;
+
+    let (total_lessons, past_sessions, attended_lessons, carried_attendance) = match &group {
+        Some(group) => {
+            let total_lessons = match group.course_id {
+                Some(course_id) => get_total_lessons_for_course(conn, course_id)?,
+                None => 0,
+            };
+            let past_sessions = get_past_sessions_for_group(conn, group.id)?;
+            let attended_lessons = get_student_attendance_counts(conn, group.id)?
+                .get(&student.id)
+                .copied()
+                .unwrap_or(0);
+            let carried_attendance = get_transferred_attendance_counts(conn, group.id)?
+                .get(&student.id)
+                .copied()
+                .unwrap_or(0);
+            (total_lessons, past_sessions, attended_lessons, carried_attendance)
+        }
+        None => (0, Vec::new(), 0, 0),
+    };
+
+    let assignments = match group.as_ref().and_then(|g| g.course_id) {
+        Some(course_id) => get_student_assignments(conn, student.id, course_id)?,
+        None => Vec::new(),
+    };
+
+    let certificates = get_certificates_for_student(conn, student.id)?;
+    let balances = get_student_balances(conn, Some(student.id))?;
+    let enrollments = get_student_enrollments(conn, student.id)?;
+
+    Ok(StudentProgress {
+        student,
+        group,
+        total_lessons,
+        past_sessions,
+        attended_lessons,
+        assignments,
+        certificates,
+        balances,
+        carried_attendance,
+        enrollments,
+    })
+}
 pub fn get_payments_between(
     conn: &Connection,
     start: NaiveDate,
@@ -1430,7 +3666,9 @@ pub fn get_payments_between(
             u.name AS student_name,
             c.title AS course_title,
             p.type AS payment_type,
-            p.amount
+            p.amount,
+            p.discount,
+            p.discount_note
         FROM Payment p
         JOIN Users u ON p.student_id = u.ID
         JOIN Course c ON p.course_id = c.ID
@@ -1455,6 +3693,12 @@ pub fn get_payments_between(
             amount: row.get(4)?,
             group_id: 0,
             group_name: "".to_string(),
+            reverses_payment_id: None,
+            reason: None,
+            operator_name: None,
+            created_at: None,
+            discount: row.get(5)?,
+            discount_note: row.get(6)?,
         })
     })?;
 
@@ -1531,7 +3775,7 @@ pub fn get_all_groups_for_report(conn: &Connection) -> Result<Vec<GroupForReport
         let mut stmt = conn.prepare(
             "SELECT u.Name FROM GroupStudent gs
              JOIN Users u ON gs.student_id = u.ID
-             WHERE gs.group_id = ?1"
+             WHERE gs.group_id = ?1 AND gs.status IN ('approved', 'active', 'completed')"
         )?;
 
         let student_names = stmt
@@ -1545,3 +3789,221 @@ pub fn get_all_groups_for_report(conn: &Connection) -> Result<Vec<GroupForReport
     Ok(groups)
 }
 
+/// Сколько последних записей журнала показываем и выгружаем за раз
+const AUDIT_LOG_LIMIT: i64 = 1000;
+
+/// Записи журнала изменений по фильтру, новые сверху
+pub fn get_audit_log(conn: &Connection, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+    let date_from = Some(filter.date_from.trim()).filter(|d| !d.is_empty());
+    let date_to = Some(filter.date_to.trim()).filter(|d| !d.is_empty());
+    let entity = filter.entity.and_then(audit::entity_table);
+
+    let mut stmt = conn.prepare(
+        "SELECT created_at, actor_name, action, entity, entity_id, before_json, after_json
+         FROM AuditLog
+         WHERE (?1 IS NULL OR actor_id = ?1)
+           AND (?2 IS NULL OR entity = ?2)
+           AND (?3 IS NULL OR action = ?3)
+           AND (?4 IS NULL OR date(created_at) >= ?4)
+           AND (?5 IS NULL OR date(created_at) <= ?5)
+         ORDER BY id DESC
+         LIMIT ?6",
+    )?;
+    let entries = stmt.query_map(
+        params![filter.actor.as_ref().map(|a| a.id), entity, filter.action, date_from, date_to, AUDIT_LOG_LIMIT],
+        |row| {
+            Ok(AuditEntry {
+                created_at: row.get("created_at")?,
+                actor_name: row.get("actor_name")?,
+                action: row.get("action")?,
+                entity: row.get("entity")?,
+                entity_id: row.get("entity_id")?,
+                before_json: row.get("before_json")?,
+                after_json: row.get("after_json")?,
+            })
+        },
+    )?;
+    entries.collect()
+}
+
+/// Пользователи, от имени которых есть записи в журнале
+pub fn get_audit_actors(conn: &Connection) -> Result<Vec<AuditActor>> {
+    let mut stmt = conn.prepare(
+        "SELECT A.actor_id, COALESCE(U.Name, MAX(A.actor_name)) AS name, U.Email
+         FROM AuditLog A
+         LEFT JOIN Users U ON U.ID = A.actor_id
+         WHERE A.actor_id IS NOT NULL
+         GROUP BY A.actor_id
+         ORDER BY name",
+    )?;
+    let actors = stmt.query_map([], |row| {
+        Ok(AuditActor {
+            id: row.get(0)?,
+            name: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            email: row.get(2)?,
+        })
+    })?;
+    actors.collect()
+}
+
+/// Матрица прав: разрешённые пары роль — действие
+pub fn get_role_permissions(conn: &Connection) -> Result<HashSet<(Role, Permission)>> {
+    let mut stmt = conn.prepare("SELECT role, permission FROM RolePermission")?;
+    let pairs = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    pairs.collect()
+}
+
+/// Разрешает или запрещает роли действие
+pub fn set_role_permission(conn: &Connection, role: Role, permission: Permission, allowed: bool) -> Result<()> {
+    if allowed {
+        conn.execute(
+            "INSERT OR IGNORE INTO RolePermission (role, permission) VALUES (?1, ?2)",
+            params![role, permission],
+        )?;
+    } else {
+        conn.execute(
+            "DELETE FROM RolePermission WHERE role = ?1 AND permission = ?2",
+            params![role, permission],
+        )?;
+    }
+    Ok(())
+}
+
+fn map_registration_review(row: &rusqlite::Row) -> Result<Option<RegistrationReview>> {
+    let Some(decision) = row.get("decision")? else {
+        return Ok(None);
+    };
+    Ok(Some(RegistrationReview {
+        decision,
+        role: row.get("role")?,
+        reason: row.get("reason")?,
+        decided_at: row.get("decided_at")?,
+        decided_by: row.get("decided_by_name")?,
+    }))
+}
+
+/// Очередь заявок на регистрацию: неподтверждённые учётные записи, сначала ещё не рассмотренные
+pub fn get_registration_requests(conn: &Connection) -> Result<Vec<RegistrationRequest>> {
+    let mut stmt = conn.prepare(
+        "SELECT U.ID, U.Name, U.Email, U.Birthday, U.Type,
+                R.decision, R.role, R.reason, R.decided_at, D.Name AS decided_by_name
+         FROM Users U
+         LEFT JOIN RegistrationReview R ON R.user_id = U.ID
+         LEFT JOIN Users D ON D.ID = R.decided_by
+         WHERE U.Type = 'unconfirmed'
+         ORDER BY R.decision IS NOT NULL, U.ID",
+    )?;
+    let requests = stmt.query_map([], |row| {
+        Ok(RegistrationRequest {
+            user: UserInfo {
+                id: row.get("ID")?,
+                name: row.get("Name")?,
+                email: row.get("Email")?,
+                birthday: row.get("Birthday")?,
+                user_type: row.get("Type")?,
+                avatar_data: None,
+                group_id: None,
+                child_count: None,
+            },
+            review: map_registration_review(row)?,
+        })
+    })?;
+    requests.collect()
+}
+
+/// Решение по заявке пользователя, если её уже рассматривали
+pub fn get_registration_review(conn: &Connection, user_id: i32) -> Result<Option<RegistrationReview>> {
+    conn.query_row(
+        "SELECT R.decision, R.role, R.reason, R.decided_at, D.Name AS decided_by_name
+         FROM RegistrationReview R
+         LEFT JOIN Users D ON D.ID = R.decided_by
+         WHERE R.user_id = ?1",
+        params![user_id],
+        map_registration_review,
+    )
+    .optional()
+    .map(Option::flatten)
+}
+
+fn save_registration_review(
+    conn: &Connection,
+    user_id: i32,
+    decision: RegistrationDecision,
+    role: Option<Role>,
+    reason: Option<&str>,
+    decided_by: i32,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO RegistrationReview (user_id, decision, role, reason, decided_at, decided_by)
+         VALUES (?1, ?2, ?3, ?4, datetime('now', 'localtime'), ?5)
+         ON CONFLICT(user_id) DO UPDATE SET
+            decision = excluded.decision, role = excluded.role, reason = excluded.reason,
+            decided_at = excluded.decided_at, decided_by = excluded.decided_by",
+        params![user_id, decision, role, reason, decided_by],
+    )?;
+    Ok(())
+}
+
+/// Подтверждает регистрацию с выбранной ролью. Студента при этом можно сразу привязать
+/// к родителю и зачислить в группу (место резервируется, обучение начнётся после оплаты).
+pub fn approve_registration(
+    conn: &mut Connection,
+    user_id: i32,
+    role: Role,
+    parent_id: Option<i32>,
+    group_id: Option<i32>,
+    reason: Option<&str>,
+    decided_by: i32,
+) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let updated = tx
+        .execute(
+            "UPDATE Users SET Type = ?1 WHERE ID = ?2 AND Type = ?3",
+            params![role, user_id, Role::Unconfirmed],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Учётная запись уже подтверждена или удалена.".to_string());
+    }
+    save_registration_review(&tx, user_id, RegistrationDecision::Approved, Some(role), reason, decided_by)
+        .map_err(|e| e.to_string())?;
+
+    if role == Role::Student {
+        if let Some(parent_id) = parent_id {
+            tx.execute(
+                "INSERT OR IGNORE INTO ParentStudent (parent_id, student_id) VALUES (?1, ?2)",
+                params![parent_id, user_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        if let Some(group_id) = group_id {
+            enroll_student_in_tx(
+                &tx,
+                user_id,
+                group_id,
+                EnrollmentStatus::Approved,
+                EnrollmentTerms::Standard,
+                None,
+            )?;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Отклоняет заявку на регистрацию; учётная запись остаётся неподтверждённой и видит причину
+pub fn reject_registration(conn: &Connection, user_id: i32, reason: &str, decided_by: i32) -> Result<(), String> {
+    let pending: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM Users WHERE ID = ?1 AND Type = ?2)",
+            params![user_id, Role::Unconfirmed],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !pending {
+        return Err("Учётная запись уже подтверждена или удалена.".to_string());
+    }
+    save_registration_review(conn, user_id, RegistrationDecision::Rejected, None, Some(reason), decided_by)
+        .map_err(|e| e.to_string())
+}
@@ -1,5 +1,11 @@
-use crate::app::state::{Certificate, GroupForReport, Payment, UserInfo};
-use chrono::NaiveDate;
+use crate::amount_words::amount_in_words;
+use crate::app::state::{
+    Certificate, GroupForReport, Organization, Payment, PaymentDocument, PaymentDocumentKind,
+    AuditEntry, Payroll, PdfBackend, UserInfo,
+};
+use crate::audit;
+use crate::pdf_native;
+use chrono::{Local, NaiveDate};
 use headless_chrome::{Browser, LaunchOptionsBuilder};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -22,6 +28,183 @@ fn get_reports_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
     Ok(reports_dir)
 }
 
+// Папка assets рядом с исполняемым файлом, иначе в текущей директории (запуск через cargo run)
+fn get_assets_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let exe_dir = std::env::current_exe()?
+        .parent()
+        .ok_or("Не удалось получить директорию исполняемого файла")?
+        .to_path_buf();
+
+    let assets_dir = exe_dir.join("assets");
+    if assets_dir.exists() {
+        Ok(assets_dir)
+    } else {
+        Ok(PathBuf::from("assets"))
+    }
+}
+
+/// Формирует PDF сертификата выбранным способом и возвращает путь к файлу
+pub fn generate_certificate_pdf(
+    cert: &Certificate,
+    student: &UserInfo,
+    backend: &PdfBackend,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let exe_dir = std::env::current_exe()?
+        .parent()
+        .ok_or("Не удалось получить директорию исполняемого файла")?
+        .to_path_buf();
+
+    let certificates_dir = exe_dir.join("certificates");
+    fs::create_dir_all(&certificates_dir)?;
+    let pdf_path = certificates_dir.join("certificate_output.pdf");
+    let assets_dir = get_assets_dir()?;
+
+    println!("DEBUG: PDF путь: {:?}", pdf_path);
+
+    if backend.is_native() {
+        pdf_native::generate_certificate_pdf(cert, student, &assets_dir, &pdf_path)?;
+    } else {
+        let html_path = certificates_dir.join("temp_certificate.html");
+        println!("DEBUG: HTML путь: {:?}", html_path);
+        generate_certificate_html(cert, student, &html_path, &assets_dir)
+            .map_err(|e| format!("Ошибка генерации HTML: {}", e))?;
+        generate_pdf_from_html(&html_path, &pdf_path)
+            .map_err(|e| format!("Ошибка конвертации в PDF: {}", e))?;
+    }
+
+    Ok(pdf_path)
+}
+
+/// Формирует PDF счёта или квитанции по платежу и возвращает путь к файлу.
+/// Документы складываются в папку receipts рядом с сертификатами.
+pub fn generate_payment_document_pdf(
+    document: &PaymentDocument,
+    payment: &Payment,
+    organization: &Organization,
+    backend: &PdfBackend,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let exe_dir = std::env::current_exe()?
+        .parent()
+        .ok_or("Не удалось получить директорию исполняемого файла")?
+        .to_path_buf();
+
+    let receipts_dir = exe_dir.join("receipts");
+    fs::create_dir_all(&receipts_dir)?;
+    let file_stem = format!(
+        "{}_{}_{:04}",
+        document.kind.as_str(),
+        document.year,
+        document.number
+    );
+    let pdf_path = receipts_dir.join(format!("{}.pdf", file_stem));
+
+    if backend.is_native() {
+        pdf_native::generate_payment_document_pdf(document, payment, organization, &get_assets_dir()?, &pdf_path)?;
+    } else {
+        let html_path = receipts_dir.join(format!("{}.html", file_stem));
+        generate_payment_document_html(document, payment, organization, &html_path)
+            .map_err(|e| format!("Ошибка генерации HTML: {}", e))?;
+        generate_pdf_from_html(&html_path, &pdf_path)
+            .map_err(|e| format!("Ошибка конвертации в PDF: {}", e))?;
+    }
+
+    Ok(pdf_path)
+}
+
+pub fn generate_payment_document_html(
+    document: &PaymentDocument,
+    payment: &Payment,
+    organization: &Organization,
+    output_path: &Path,
+) -> std::io::Result<()> {
+    let format_date = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(|d| d.format("%d.%m.%Y").to_string())
+            .unwrap_or_else(|_| date.to_string())
+    };
+
+    let requisites: String = organization
+        .requisites()
+        .iter()
+        .map(|(label, value)| format!("<div><b>{}:</b> {}</div>", label, value))
+        .collect();
+
+    let (payer_label, total_label, signature) = match document.kind {
+        PaymentDocumentKind::Invoice => ("Плательщик", "Всего к оплате", "Руководитель"),
+        PaymentDocumentKind::Receipt => ("Принято от", "Получено", "Принял"),
+    };
+    let receipt_lines = if document.kind == PaymentDocumentKind::Receipt {
+        format!(
+            "<div><b>Дата оплаты:</b> {}</div><div><b>Способ оплаты:</b> {}</div>",
+            format_date(&payment.date),
+            payment.payment_type
+        )
+    } else {
+        String::new()
+    };
+    let discount_line = match payment.discount_note.as_deref() {
+        Some(note) if payment.discount > 0.0 => format!("<div><b>Скидка:</b> {}</div>", note),
+        _ => String::new(),
+    };
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="ru">
+<head>
+<meta charset="UTF-8">
+<title>{title}</title>
+<style>
+  body {{ font-family: "DejaVu Sans", Arial, sans-serif; color: #3c3836; margin: 40px; font-size: 14px; }}
+  .org {{ font-size: 17px; font-weight: bold; margin-bottom: 6px; }}
+  h1 {{ font-size: 22px; border-bottom: 2px solid #3c3836; padding-bottom: 6px; margin-top: 24px; }}
+  table {{ width: 100%; border-collapse: collapse; margin: 16px 0; }}
+  th {{ background: #ebdbb2; text-align: left; }}
+  th, td {{ border: 1px solid #a89984; padding: 6px; }}
+  .words {{ font-weight: bold; margin-top: 4px; }}
+  .signature {{ margin-top: 60px; }}
+</style>
+</head>
+<body>
+  <div class="org">{org_name}</div>
+  {requisites}
+  <h1>{title} № {number} от {issue_date}</h1>
+  <div><b>{payer_label}:</b> {student}</div>
+  {receipt_lines}
+  <div><b>Основание:</b> платёж № {payment_id}</div>
+  <table>
+    <tr><th>№</th><th>Наименование</th><th>Кол-во</th><th>Цена</th><th>Скидка</th><th>Сумма</th></tr>
+    <tr><td>1</td><td>Обучение по курсу «{course}», группа {group}</td><td>1</td><td>{price:.2} ₽</td><td>{discount:.2} ₽</td><td>{amount:.2} ₽</td></tr>
+  </table>
+  <div><b>Итого:</b> {amount:.2} ₽</div>
+  {discount_line}
+  <p>{total_label}:</p>
+  <div class="words">{amount_words}</div>
+  <div class="signature">{signature} ____________________</div>
+</body>
+</html>"#,
+        title = document.kind.title(),
+        org_name = organization.name,
+        requisites = requisites,
+        number = document.display_number(),
+        issue_date = format_date(document.issue_date()),
+        payer_label = payer_label,
+        student = payment.student_name,
+        receipt_lines = receipt_lines,
+        payment_id = payment.id,
+        course = payment.course_title,
+        group = payment.group_name,
+        price = payment.amount + payment.discount,
+        discount = payment.discount,
+        amount = payment.amount,
+        discount_line = discount_line,
+        total_label = total_label,
+        amount_words = amount_in_words(payment.amount),
+        signature = signature,
+    );
+
+    fs::write(output_path, html)
+}
+
 pub fn generate_certificate_html(
     cert: &Certificate,
     student: &UserInfo,
@@ -240,6 +423,18 @@ pub fn generate_pdf_from_html(
 
     Ok(())
 }
+/// Скидка по платежу для отчётов: сумма и перечень применённых скидок
+pub fn discount_cell(payment: &Payment) -> String {
+    if payment.discount > 0.0 {
+        format!(
+            "{:.2} ₽ ({})",
+            payment.discount,
+            payment.discount_note.as_deref().unwrap_or("")
+        )
+    } else {
+        String::new()
+    }
+}
 pub fn generate_payment_report_html(
     payments: &[Payment],
     from: &str,
@@ -272,8 +467,13 @@ pub fn generate_payment_report_html(
             .unwrap_or_else(|_| p.date.clone());
 
         table_rows.push_str(&format!(
-            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.2} ₽</td></tr>",
-            formatted_date, p.student_name, p.course_title, p.payment_type, p.amount
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.2} ₽</td></tr>",
+            formatted_date,
+            p.student_name,
+            p.course_title,
+            p.payment_type,
+            discount_cell(p),
+            p.amount
         ));
     }
 
@@ -457,7 +657,7 @@ pub fn generate_payment_report_html(
 
   <table>
     <thead>
-      <tr><th>Дата</th><th>Студент</th><th>Курс</th><th>Тип</th><th>Сумма</th></tr>
+      <tr><th>Дата</th><th>Студент</th><th>Курс</th><th>Тип</th><th>Скидка</th><th>Сумма</th></tr>
     </thead>
     <tbody>
       {table_rows}
@@ -545,6 +745,7 @@ pub fn generate_payment_report(
     from: &str,
     to: &str,
     output_dir: &Path,
+    backend: &PdfBackend,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use chrono::NaiveDate;
 
@@ -566,11 +767,15 @@ pub fn generate_payment_report(
         return Ok(());
     }
 
-    let html_path = output_dir.join("payment_report.html");
     let pdf_path = output_dir.join("payment_report.pdf");
 
-    generate_payment_report_html(&filtered, from, to, &html_path)?;
-    generate_pdf_from_html(&html_path, &pdf_path)?;
+    if backend.is_native() {
+        pdf_native::generate_payment_report_pdf(&filtered, from, to, &get_assets_dir()?, &pdf_path)?;
+    } else {
+        let html_path = output_dir.join("payment_report.html");
+        generate_payment_report_html(&filtered, from, to, &html_path)?;
+        generate_pdf_from_html(&html_path, &pdf_path)?;
+    }
 
     println!("PDF-отчёт сгенерирован: {:?}", pdf_path);
     Ok(())
@@ -602,7 +807,7 @@ pub fn generate_payment_excel_report(
         .set_value(format!("Период: {} — {}", from, to));
 
     // Заголовки таблицы
-    let headers = ["Дата", "Студент", "Курс", "Тип", "Сумма"];
+    let headers = ["Дата", "Студент", "Курс", "Тип", "Скидка", "Сумма"];
     for (i, header) in headers.iter().enumerate() {
         let col_letter = col_to_letter(i + 1);
         let cell_address = format!("{}4", col_letter);
@@ -622,6 +827,7 @@ pub fn generate_payment_excel_report(
             payment.student_name.clone(),
             payment.course_title.clone(),
             payment.payment_type.clone(),
+            discount_cell(payment),
             payment.amount.to_string(),
         ];
 
@@ -946,17 +1152,22 @@ pub fn generate_certificate_report(
     from: &str,
     to: &str,
     output_dir: &Path,
+    backend: &PdfBackend,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if certificates.is_empty() {
         println!("Нет сертификатов за указанный период");
         return Ok(());
     }
 
-    let html_path = output_dir.join("certificate_report.html");
     let pdf_path = output_dir.join("certificate_report.pdf");
 
-    generate_certificate_report_html(certificates, from, to, &html_path)?;
-    generate_pdf_from_html(&html_path, &pdf_path)?;
+    if backend.is_native() {
+        pdf_native::generate_certificate_report_pdf(certificates, from, to, &get_assets_dir()?, &pdf_path)?;
+    } else {
+        let html_path = output_dir.join("certificate_report.html");
+        generate_certificate_report_html(certificates, from, to, &html_path)?;
+        generate_pdf_from_html(&html_path, &pdf_path)?;
+    }
 
     println!("PDF-отчёт по сертификатам сгенерирован: {:?}", pdf_path);
     Ok(())
@@ -1306,17 +1517,22 @@ pub fn generate_group_report_html(
 pub fn generate_group_report(
     groups: &[GroupForReport],
     output_dir: &Path,
+    backend: &PdfBackend,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if groups.is_empty() {
         println!("Нет групп за указанный период");
         return Ok(());
     }
 
-    let html_path = output_dir.join("group_report.html");
     let pdf_path = output_dir.join("group_report.pdf");
 
-    generate_group_report_html(groups, &html_path)?;
-    generate_pdf_from_html(&html_path, &pdf_path)?;
+    if backend.is_native() {
+        pdf_native::generate_group_report_pdf(groups, &get_assets_dir()?, &pdf_path)?;
+    } else {
+        let html_path = output_dir.join("group_report.html");
+        generate_group_report_html(groups, &html_path)?;
+        generate_pdf_from_html(&html_path, &pdf_path)?;
+    }
 
     println!("PDF-отчёт по группам сгенерирован: {:?}", pdf_path);
     Ok(())
@@ -1391,3 +1607,212 @@ pub fn generate_group_excel_report(
     println!("Excel отчёт по группам сгенерирован: {:?}", output_path);
     Ok(())
 }
+
+/// Формирует PDF расчётной ведомости за месяц в папке reports и возвращает путь к файлу
+pub fn generate_payroll_pdf(
+    payroll: &Payroll,
+    organization: &Organization,
+    backend: &PdfBackend,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let reports_dir = get_reports_dir()?;
+    let pdf_path = reports_dir.join(format!("payroll_{}.pdf", payroll.month));
+
+    if backend.is_native() {
+        pdf_native::generate_payroll_pdf(payroll, organization, &get_assets_dir()?, &pdf_path)?;
+    } else {
+        let html_path = reports_dir.join(format!("payroll_{}.html", payroll.month));
+        generate_payroll_html(payroll, organization, &html_path)
+            .map_err(|e| format!("Ошибка генерации HTML: {}", e))?;
+        generate_pdf_from_html(&html_path, &pdf_path)
+            .map_err(|e| format!("Ошибка конвертации в PDF: {}", e))?;
+    }
+
+    println!("PDF-ведомость по зарплате сгенерирована: {:?}", pdf_path);
+    Ok(pdf_path)
+}
+
+pub fn generate_payroll_html(
+    payroll: &Payroll,
+    organization: &Organization,
+    output_path: &Path,
+) -> std::io::Result<()> {
+    let status = match &payroll.approved_at {
+        Some(approved_at) => format!(
+            "утверждена {} {}",
+            NaiveDate::parse_from_str(approved_at.get(..10).unwrap_or(approved_at), "%Y-%m-%d")
+                .map(|d| d.format("%d.%m.%Y").to_string())
+                .unwrap_or_else(|_| approved_at.clone()),
+            payroll.approved_by.as_deref().unwrap_or("")
+        ),
+        None => "предварительный расчёт, не утверждена".to_string(),
+    };
+
+    let rows: String = payroll
+        .lines
+        .iter()
+        .map(|line| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.1}</td><td>{:.2} ₽</td><td>{:.2} ₽</td></tr>",
+                line.teacher_name,
+                line.rate_type
+                    .map_or("не задана".to_string(), |rate_type| rate_type.format_rate(line.rate)),
+                line.sessions,
+                line.student_hours,
+                line.revenue,
+                line.amount
+            )
+        })
+        .collect();
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="ru">
+<head>
+<meta charset="UTF-8">
+<title>Расчётная ведомость</title>
+<style>
+  body {{ font-family: "DejaVu Sans", Arial, sans-serif; color: #3c3836; margin: 40px; font-size: 14px; }}
+  .org {{ font-size: 17px; font-weight: bold; margin-bottom: 6px; }}
+  h1 {{ font-size: 22px; border-bottom: 2px solid #3c3836; padding-bottom: 6px; }}
+  table {{ width: 100%; border-collapse: collapse; margin: 16px 0; }}
+  th {{ background: #ebdbb2; text-align: left; }}
+  th, td {{ border: 1px solid #a89984; padding: 6px; }}
+  .words {{ font-weight: bold; margin-top: 4px; }}
+  .signature {{ margin-top: 60px; }}
+</style>
+</head>
+<body>
+  <div class="org">{org_name}</div>
+  <h1>Расчётная ведомость по оплате труда преподавателей</h1>
+  <div><b>Период:</b> {period}</div>
+  <div><b>Статус:</b> {status}</div>
+  <table>
+    <tr><th>Преподаватель</th><th>Ставка</th><th>Занятий</th><th>Студ.-часов</th><th>Выручка</th><th>Начислено</th></tr>
+    {rows}
+  </table>
+  <div><b>Итого к выплате:</b> {total:.2} ₽</div>
+  <div class="words">{total_words}</div>
+  <div class="signature">Руководитель ____________________</div>
+</body>
+</html>"#,
+        org_name = organization.name,
+        period = payroll.period_title(),
+        status = status,
+        rows = rows,
+        total = payroll.total(),
+        total_words = amount_in_words(payroll.total()),
+    );
+
+    fs::write(output_path, html)
+}
+
+/// Выгружает расчётную ведомость за месяц в Excel и возвращает путь к файлу
+pub fn generate_payroll_excel(payroll: &Payroll) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let output_path = get_reports_dir()?.join(format!("payroll_{}.xlsx", payroll.month));
+    let mut book = new_file();
+    let sheet = book.sheet_by_name_mut("Sheet1")?;
+
+    sheet.cell_mut("A1").set_value("Расчётная ведомость по оплате труда преподавателей");
+    sheet
+        .cell_mut("A2")
+        .set_value(format!("Период: {}", payroll.period_title()));
+    if let Some(approved_at) = &payroll.approved_at {
+        sheet.cell_mut("A3").set_value(format!(
+            "Утверждена: {} {}",
+            approved_at,
+            payroll.approved_by.as_deref().unwrap_or("")
+        ));
+    }
+
+    let headers = ["Преподаватель", "Ставка", "Занятий", "Студ.-часов", "Выручка", "Начислено"];
+    for (i, header) in headers.iter().enumerate() {
+        let cell_address = format!("{}5", col_to_letter(i + 1));
+        sheet.cell_mut(cell_address.as_str()).set_value(*header);
+    }
+
+    let mut row = 6;
+    for line in &payroll.lines {
+        sheet
+            .cell_mut(format!("A{}", row).as_str())
+            .set_value(&line.teacher_name);
+        sheet.cell_mut(format!("B{}", row).as_str()).set_value(
+            line.rate_type
+                .map_or("не задана".to_string(), |rate_type| rate_type.format_rate(line.rate)),
+        );
+        sheet
+            .cell_mut(format!("C{}", row).as_str())
+            .set_value_number(line.sessions);
+        sheet
+            .cell_mut(format!("D{}", row).as_str())
+            .set_value_number(line.student_hours);
+        sheet
+            .cell_mut(format!("E{}", row).as_str())
+            .set_value_number(line.revenue);
+        sheet
+            .cell_mut(format!("F{}", row).as_str())
+            .set_value_number(line.amount);
+        row += 1;
+    }
+
+    sheet.cell_mut(format!("E{}", row).as_str()).set_value("Итого");
+    sheet
+        .cell_mut(format!("F{}", row).as_str())
+        .set_value_number(payroll.total());
+
+    writer::xlsx::write(&book, &output_path)?;
+    println!("Excel-ведомость по зарплате сгенерирована: {:?}", output_path);
+    Ok(output_path)
+}
+
+pub fn generate_audit_excel(entries: &[AuditEntry]) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let output_path = get_reports_dir()?.join(format!(
+        "audit_log_{}.xlsx",
+        Local::now().format("%Y-%m-%d_%H-%M-%S")
+    ));
+    let mut book = new_file();
+    let sheet = book.sheet_by_name_mut("Sheet1")?;
+
+    sheet.cell_mut("A1").set_value("Журнал изменений данных");
+    sheet
+        .cell_mut("A2")
+        .set_value(format!("Записей: {}", entries.len()));
+
+    let headers = ["Время", "Пользователь", "Действие", "Объект", "ID", "Изменения", "До", "После"];
+    for (i, header) in headers.iter().enumerate() {
+        let cell_address = format!("{}4", col_to_letter(i + 1));
+        sheet.cell_mut(cell_address.as_str()).set_value(*header);
+    }
+
+    for (row, entry) in (5..).zip(entries) {
+        sheet
+            .cell_mut(format!("A{}", row).as_str())
+            .set_value(&entry.created_at);
+        sheet
+            .cell_mut(format!("B{}", row).as_str())
+            .set_value(entry.actor_name.as_deref().unwrap_or("без входа"));
+        sheet
+            .cell_mut(format!("C{}", row).as_str())
+            .set_value(entry.action.to_string());
+        sheet
+            .cell_mut(format!("D{}", row).as_str())
+            .set_value(audit::entity_label(&entry.entity));
+        if let Some(entity_id) = entry.entity_id {
+            sheet
+                .cell_mut(format!("E{}", row).as_str())
+                .set_value_number(entity_id as f64);
+        }
+        sheet
+            .cell_mut(format!("F{}", row).as_str())
+            .set_value(entry.change_lines().join("; "));
+        sheet
+            .cell_mut(format!("G{}", row).as_str())
+            .set_value(entry.before_json.as_deref().unwrap_or(""));
+        sheet
+            .cell_mut(format!("H{}", row).as_str())
+            .set_value(entry.after_json.as_deref().unwrap_or(""));
+    }
+
+    writer::xlsx::write(&book, &output_path)?;
+    println!("Excel-выгрузка журнала изменений сгенерирована: {:?}", output_path);
+    Ok(output_path)
+}
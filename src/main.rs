@@ -1,20 +1,43 @@
+mod amount_words;
 mod app;
+mod audit;
+mod bank_import;
 mod db;
 mod doc_gen;
 pub mod config;
+mod migrations;
+mod password;
+mod pdf_native;
+mod schedule;
 mod screens;
+mod totp;
 
-use iced::{window, Size};
-use app::App;
+use iced::{window, Size, Task};
+use app::{App, Message};
+use app::state::PATH_TO_DB;
 
 fn main() -> iced::Result {
+    // Схема БД должна быть актуальной до того, как App::default начнёт к ней обращаться
+    if let Err(e) = migrations::migrate_database(PATH_TO_DB) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    // Прошлый сеанс мог завершиться без выхода из учётной записи — до входа изменения ничьи
+    if let Err(e) = rusqlite::Connection::open(PATH_TO_DB).and_then(|conn| audit::set_actor(&conn, None)) {
+        eprintln!("Не удалось сбросить пользователя журнала изменений: {}", e);
+    }
+
     let window_settings = window::Settings {
         size: Size::new(1400.0, 800.0),
         min_size: Some(Size::new(1400.0, 800.0)),
         ..Default::default()
     };
 
-    iced::application(App::default, App::update, App::view)
+    // Сразу после запуска проверяем, не пропущен ли автоматический бэкап, пока приложение было закрыто
+    let boot = || (App::default(), Task::done(Message::BackupSchedulerTick));
+
+    iced::application(boot, App::update, App::view)
+        .subscription(App::subscription)
         .title("Platform")
         .theme(|app: &App| app.theme.value().clone())
         .window(window_settings)
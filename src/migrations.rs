@@ -0,0 +1,516 @@
+use rusqlite::Connection;
+
+use crate::audit;
+
+/// Упорядоченный список миграций схемы БД.
+/// Номер версии миграции = её позиция в списке + 1, применённая версия хранится в `PRAGMA user_version`.
+/// Уже выпущенные миграции не редактируются — любые изменения схемы добавляются новой записью в конец.
+const MIGRATIONS: &[&str] = &[
+    // 1: исходная схема платформы
+    r#"
+    CREATE TABLE IF NOT EXISTS "Users" (
+        "ID"	INTEGER NOT NULL UNIQUE,
+        "Name"	TEXT,
+        "Type"	TEXT,
+        "Birthday"	TEXT,
+        "Email"	TEXT UNIQUE,
+        "password"	TEXT NOT NULL,
+        "AvatarData"	BLOB,
+        PRIMARY KEY("ID" AUTOINCREMENT)
+    );
+    CREATE TABLE IF NOT EXISTS "Course" (
+        "ID"	INTEGER NOT NULL UNIQUE,
+        "title"	TEXT,
+        "description"	TEXT,
+        "level"	TEXT,
+        "total_seats"	INTEGER,
+        "seats"	INTEGER,
+        "price"	INTEGER,
+        PRIMARY KEY("ID" AUTOINCREMENT)
+    );
+    CREATE TABLE IF NOT EXISTS "ParentStudent" (
+        "parent_id"	INTEGER NOT NULL,
+        "student_id"	INTEGER NOT NULL,
+        PRIMARY KEY("parent_id","student_id"),
+        FOREIGN KEY("parent_id") REFERENCES "Users"("ID") ON DELETE CASCADE,
+        FOREIGN KEY("student_id") REFERENCES "Users"("ID") ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS "Group" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "course_id"	INTEGER,
+        "teacher_id"	INTEGER,
+        "name"	TEXT,
+        "student_count"	INTEGER NOT NULL DEFAULT 0,
+        "status"	TEXT,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("course_id") REFERENCES "Course"("ID") ON DELETE CASCADE,
+        FOREIGN KEY("teacher_id") REFERENCES "Users"("ID") ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS "GroupStudent" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "group_id"	INTEGER NOT NULL,
+        "student_id"	INTEGER NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("group_id") REFERENCES "Group"("id") ON DELETE CASCADE,
+        FOREIGN KEY("student_id") REFERENCES "Users"("ID") ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS "Lessons" (
+        "ID"	INTEGER NOT NULL UNIQUE,
+        "course_id"	INTEGER NOT NULL,
+        "number"	INTEGER NOT NULL,
+        "title"	TEXT NOT NULL,
+        PRIMARY KEY("ID" AUTOINCREMENT),
+        FOREIGN KEY("course_id") REFERENCES "Course"("ID") ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS "Assignment" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "lesson_id"	INTEGER NOT NULL,
+        "title"	TEXT NOT NULL,
+        "description"	TEXT NOT NULL,
+        "type"	TEXT NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT)
+    );
+    CREATE TABLE IF NOT EXISTS "PastSessions" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "group_id"	INTEGER NOT NULL,
+        "date"	TEXT NOT NULL,
+        "lesson_id"	INTEGER NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("group_id") REFERENCES "Group"("id") ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS "Attendance" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "group_id"	INTEGER NOT NULL,
+        "lesson_id"	INTEGER NOT NULL,
+        "student_id"	INTEGER NOT NULL,
+        "present"	TEXT NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("group_id") REFERENCES "Group"("id") ON DELETE CASCADE,
+        FOREIGN KEY("lesson_id") REFERENCES "PastSessions"("id"),
+        FOREIGN KEY("student_id") REFERENCES "Users"("ID") ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS "Certificates" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "student_id"	INTEGER NOT NULL,
+        "course_id"	INTEGER NOT NULL,
+        "issue_date"	TEXT NOT NULL,
+        "grade"	TEXT NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("course_id") REFERENCES "Course"("ID") ON DELETE CASCADE,
+        FOREIGN KEY("student_id") REFERENCES "Users"("ID") ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS "Payment" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "student_id"	INTEGER NOT NULL,
+        "date"	TEXT NOT NULL,
+        "amount"	REAL NOT NULL,
+        "type"	TEXT NOT NULL,
+        "course_id"	INTEGER NOT NULL,
+        "group_id"	INTEGER NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("course_id") REFERENCES "Course"("ID") ON DELETE CASCADE,
+        FOREIGN KEY("group_id") REFERENCES "Group"("id") ON DELETE CASCADE,
+        FOREIGN KEY("student_id") REFERENCES "Users"("ID") ON DELETE CASCADE
+    );
+    CREATE TRIGGER IF NOT EXISTS update_course_seats_after_group_student_count_update
+    AFTER UPDATE ON "Group"
+    FOR EACH ROW
+    WHEN OLD.student_count != NEW.student_count OR OLD.status != NEW.status
+    BEGIN
+        UPDATE Course
+        SET seats = total_seats - (
+            SELECT COALESCE(SUM(G.student_count), 0)
+            FROM "Group" G
+            WHERE G.course_id = OLD.course_id
+              AND G.status = 'Активна'
+        )
+        WHERE ID = OLD.course_id;
+    END;
+    "#,
+    // 2: сданные студентами работы по практическим заданиям и прикреплённые к ним файлы
+    r#"
+    CREATE TABLE IF NOT EXISTS "Submission" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "assignment_id"	INTEGER NOT NULL,
+        "student_id"	INTEGER NOT NULL,
+        "group_id"	INTEGER NOT NULL,
+        "text"	TEXT NOT NULL DEFAULT '',
+        "submitted_at"	TEXT NOT NULL,
+        "score"	INTEGER,
+        "comment"	TEXT,
+        "graded_at"	TEXT,
+        "graded_by"	INTEGER,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        UNIQUE("assignment_id","student_id"),
+        FOREIGN KEY("assignment_id") REFERENCES "Assignment"("id") ON DELETE CASCADE,
+        FOREIGN KEY("student_id") REFERENCES "Users"("ID") ON DELETE CASCADE,
+        FOREIGN KEY("group_id") REFERENCES "Group"("id") ON DELETE CASCADE,
+        FOREIGN KEY("graded_by") REFERENCES "Users"("ID") ON DELETE SET NULL
+    );
+    CREATE TABLE IF NOT EXISTS "SubmissionFile" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "submission_id"	INTEGER NOT NULL,
+        "file_name"	TEXT NOT NULL,
+        "data"	BLOB NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("submission_id") REFERENCES "Submission"("id") ON DELETE CASCADE
+    );
+    "#,
+    // 3: еженедельное расписание групп и запланированные по нему занятия
+    r#"
+    CREATE TABLE IF NOT EXISTS "ScheduleSlot" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "group_id"	INTEGER NOT NULL,
+        "weekday"	INTEGER NOT NULL,
+        "start_time"	TEXT NOT NULL,
+        "duration_minutes"	INTEGER NOT NULL,
+        "room"	TEXT NOT NULL DEFAULT '',
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("group_id") REFERENCES "Group"("id") ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS "PlannedSession" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "group_id"	INTEGER NOT NULL,
+        "lesson_id"	INTEGER NOT NULL,
+        "date"	TEXT NOT NULL,
+        "start_time"	TEXT NOT NULL,
+        "duration_minutes"	INTEGER NOT NULL,
+        "room"	TEXT NOT NULL DEFAULT '',
+        "past_session_id"	INTEGER,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("group_id") REFERENCES "Group"("id") ON DELETE CASCADE,
+        FOREIGN KEY("lesson_id") REFERENCES "Lessons"("ID") ON DELETE CASCADE,
+        FOREIGN KEY("past_session_id") REFERENCES "PastSessions"("id") ON DELETE SET NULL
+    );
+    CREATE INDEX IF NOT EXISTS "idx_planned_session_date" ON "PlannedSession" ("date");
+    "#,
+    // 4: стоимость обучения, зафиксированная при зачислении студента в группу
+    r#"
+    ALTER TABLE "GroupStudent" ADD COLUMN "price" REAL;
+    ALTER TABLE "GroupStudent" ADD COLUMN "enrolled_at" TEXT;
+    UPDATE "GroupStudent" SET "price" = (
+        SELECT C.price
+        FROM "Group" G
+        JOIN "Course" C ON C.ID = G.course_id
+        WHERE G.id = "GroupStudent".group_id
+    );
+    "#,
+    // 5: график платежей (рассрочка) по зачислению студента в группу
+    r#"
+    CREATE TABLE IF NOT EXISTS "Installment" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "group_student_id"	INTEGER NOT NULL,
+        "number"	INTEGER NOT NULL,
+        "due_date"	TEXT NOT NULL,
+        "amount"	REAL NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        UNIQUE("group_student_id","number"),
+        FOREIGN KEY("group_student_id") REFERENCES "GroupStudent"("id") ON DELETE CASCADE
+    );
+    "#,
+    // 6: возвраты и корректировки — встречные записи Payment со ссылкой на исходный платёж
+    r#"
+    ALTER TABLE "Payment" ADD COLUMN "reverses_payment_id" INTEGER REFERENCES "Payment"("id");
+    ALTER TABLE "Payment" ADD COLUMN "reason" TEXT;
+    ALTER TABLE "Payment" ADD COLUMN "operator_id" INTEGER REFERENCES "Users"("ID") ON DELETE SET NULL;
+    ALTER TABLE "Payment" ADD COLUMN "created_at" TEXT;
+    CREATE INDEX IF NOT EXISTS "idx_payment_reverses" ON "Payment" ("reverses_payment_id");
+    "#,
+    // 7: скидки — промокоды, скидка при зачислении и её отражение в платеже
+    r#"
+    CREATE TABLE IF NOT EXISTS "PromoCode" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "code"	TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        "is_percent"	INTEGER NOT NULL DEFAULT 1,
+        "value"	REAL NOT NULL,
+        "valid_from"	TEXT,
+        "valid_to"	TEXT,
+        "max_uses"	INTEGER,
+        "used_count"	INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY("id" AUTOINCREMENT)
+    );
+    ALTER TABLE "GroupStudent" ADD COLUMN "discount" REAL NOT NULL DEFAULT 0;
+    ALTER TABLE "GroupStudent" ADD COLUMN "discount_note" TEXT;
+    ALTER TABLE "Payment" ADD COLUMN "discount" REAL NOT NULL DEFAULT 0;
+    ALTER TABLE "Payment" ADD COLUMN "discount_note" TEXT;
+    ALTER TABLE "Payment" ADD COLUMN "promo_code_id" INTEGER REFERENCES "PromoCode"("id") ON DELETE SET NULL;
+    "#,
+    // 8: нумерованные счета и квитанции по платежам (сквозная нумерация в пределах года)
+    r#"
+    CREATE TABLE IF NOT EXISTS "PaymentDocument" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "payment_id"	INTEGER NOT NULL,
+        "kind"	TEXT NOT NULL,
+        "year"	INTEGER NOT NULL,
+        "number"	INTEGER NOT NULL,
+        "issued_at"	TEXT NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        UNIQUE("payment_id","kind"),
+        UNIQUE("kind","year","number"),
+        FOREIGN KEY("payment_id") REFERENCES "Payment"("id") ON DELETE CASCADE
+    );
+    "#,
+    // 9: ссылка на строку банковской выписки, из которой создан платёж
+    r#"
+    ALTER TABLE "Payment" ADD COLUMN "bank_reference" TEXT;
+    CREATE UNIQUE INDEX IF NOT EXISTS "idx_payment_bank_reference" ON "Payment" ("bank_reference");
+    "#,
+    // 10: зарплата преподавателей — ставки, проводивший занятие преподаватель и утверждённые ведомости
+    r#"
+    ALTER TABLE "PastSessions" ADD COLUMN "teacher_id" INTEGER REFERENCES "Users"("ID") ON DELETE SET NULL;
+    UPDATE "PastSessions" SET "teacher_id" = (
+        SELECT G.teacher_id FROM "Group" G WHERE G.id = "PastSessions".group_id
+    );
+    CREATE TABLE IF NOT EXISTS "TeacherRate" (
+        "teacher_id"	INTEGER NOT NULL UNIQUE,
+        "rate_type"	TEXT NOT NULL,
+        "rate"	REAL NOT NULL,
+        PRIMARY KEY("teacher_id"),
+        FOREIGN KEY("teacher_id") REFERENCES "Users"("ID") ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS "PayrollPeriod" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "month"	TEXT NOT NULL UNIQUE,
+        "approved_at"	TEXT NOT NULL,
+        "approved_by"	INTEGER,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("approved_by") REFERENCES "Users"("ID") ON DELETE SET NULL
+    );
+    CREATE TABLE IF NOT EXISTS "PayrollLine" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "period_id"	INTEGER NOT NULL,
+        "teacher_id"	INTEGER NOT NULL,
+        "teacher_name"	TEXT NOT NULL,
+        "rate_type"	TEXT,
+        "rate"	REAL NOT NULL DEFAULT 0,
+        "sessions"	INTEGER NOT NULL,
+        "student_hours"	REAL NOT NULL,
+        "revenue"	REAL NOT NULL,
+        "amount"	REAL NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        UNIQUE("period_id","teacher_id"),
+        FOREIGN KEY("period_id") REFERENCES "PayrollPeriod"("id") ON DELETE CASCADE
+    );
+    "#,
+    // 11: лист ожидания на курсы без свободных мест и предложения освободившихся мест
+    r#"
+    CREATE TABLE IF NOT EXISTS "Waitlist" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "student_id"	INTEGER NOT NULL,
+        "course_id"	INTEGER NOT NULL,
+        "group_id"	INTEGER,
+        "status"	TEXT NOT NULL DEFAULT 'waiting',
+        "created_at"	TEXT NOT NULL,
+        "offered_at"	TEXT,
+        "closed_at"	TEXT,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("student_id") REFERENCES "Users"("ID") ON DELETE CASCADE,
+        FOREIGN KEY("course_id") REFERENCES "Course"("ID") ON DELETE CASCADE,
+        FOREIGN KEY("group_id") REFERENCES "Group"("id") ON DELETE SET NULL
+    );
+    CREATE INDEX IF NOT EXISTS "idx_waitlist_course_status" ON "Waitlist" ("course_id", "status");
+    "#,
+    // 12: жизненный цикл зачисления (заявка → обучение → завершение/отчисление/перевод) и ссылка платежа на зачисление
+    r#"
+    ALTER TABLE "GroupStudent" ADD COLUMN "status" TEXT NOT NULL DEFAULT 'active';
+    ALTER TABLE "GroupStudent" ADD COLUMN "terms" TEXT NOT NULL DEFAULT 'standard';
+    ALTER TABLE "GroupStudent" ADD COLUMN "payment_due" TEXT;
+    ALTER TABLE "GroupStudent" ADD COLUMN "status_changed_at" TEXT;
+    ALTER TABLE "GroupStudent" ADD COLUMN "ended_at" TEXT;
+    UPDATE "GroupStudent" SET "status_changed_at" = "enrolled_at";
+    ALTER TABLE "Payment" ADD COLUMN "enrollment_id" INTEGER REFERENCES "GroupStudent"("id") ON DELETE SET NULL;
+    UPDATE "Payment" SET "enrollment_id" = (
+        SELECT GS.id FROM "GroupStudent" GS
+        WHERE GS.student_id = "Payment".student_id AND GS.group_id = "Payment".group_id
+        ORDER BY GS.id DESC LIMIT 1
+    );
+    CREATE INDEX IF NOT EXISTS "idx_group_student_status" ON "GroupStudent" ("group_id", "status");
+    CREATE INDEX IF NOT EXISTS "idx_payment_enrollment" ON "Payment" ("enrollment_id");
+    "#,
+    // 13: перевод студента между группами — ссылка на прежнее зачисление и перенесённая оплата
+    r#"
+    ALTER TABLE "GroupStudent" ADD COLUMN "transferred_from_id" INTEGER REFERENCES "GroupStudent"("id") ON DELETE SET NULL;
+    ALTER TABLE "GroupStudent" ADD COLUMN "carried_over" REAL NOT NULL DEFAULT 0;
+    "#,
+    // 14: журнал изменений данных и текущий пользователь, от имени которого они выполняются
+    r#"
+    CREATE TABLE IF NOT EXISTS "AuditLog" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "created_at"	TEXT NOT NULL,
+        "actor_id"	INTEGER,
+        "actor_name"	TEXT,
+        "action"	TEXT NOT NULL,
+        "entity"	TEXT NOT NULL,
+        "entity_id"	INTEGER,
+        "before_json"	TEXT,
+        "after_json"	TEXT,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("actor_id") REFERENCES "Users"("ID") ON DELETE SET NULL
+    );
+    CREATE INDEX IF NOT EXISTS "idx_audit_log_created" ON "AuditLog" ("created_at");
+    CREATE INDEX IF NOT EXISTS "idx_audit_log_entity" ON "AuditLog" ("entity", "entity_id");
+    CREATE TABLE IF NOT EXISTS "AuditContext" (
+        "id"	INTEGER NOT NULL PRIMARY KEY CHECK ("id" = 1),
+        "actor_id"	INTEGER,
+        FOREIGN KEY("actor_id") REFERENCES "Users"("ID") ON DELETE SET NULL
+    );
+    INSERT OR IGNORE INTO "AuditContext" ("id", "actor_id") VALUES (1, NULL);
+    "#,
+    // 15: матрица прав — какие действия разрешены каждой роли (по умолчанию как в меню до её появления);
+    // роли, введённые раньше вручную, приводятся к допустимым значениям, неизвестные — к «не подтверждён»
+    r#"
+    UPDATE "Users" SET "Type" = lower(trim("Type"));
+    UPDATE "Users" SET "Type" = 'unconfirmed'
+        WHERE "Type" IS NULL OR "Type" NOT IN ('admin', 'teacher', 'student', 'parent', 'unconfirmed');
+    CREATE TABLE IF NOT EXISTS "RolePermission" (
+        "role"	TEXT NOT NULL,
+        "permission"	TEXT NOT NULL,
+        PRIMARY KEY("role","permission")
+    );
+    INSERT OR IGNORE INTO "RolePermission" ("role", "permission") VALUES
+        ('admin', 'manage_users'),
+        ('admin', 'manage_courses'),
+        ('admin', 'manage_groups'),
+        ('admin', 'manage_waitlist'),
+        ('admin', 'manage_payments'),
+        ('admin', 'manage_discounts'),
+        ('admin', 'manage_payroll'),
+        ('admin', 'manage_settings'),
+        ('admin', 'manage_backups'),
+        ('admin', 'manage_permissions'),
+        ('teacher', 'conduct_lessons'),
+        ('teacher', 'grade_submissions'),
+        ('student', 'submit_assignments'),
+        ('student', 'respond_to_waitlist');
+    "#,
+    // 16: решения администратора по заявкам на регистрацию (роль при подтверждении или причина отказа)
+    r#"
+    CREATE TABLE IF NOT EXISTS "RegistrationReview" (
+        "user_id"	INTEGER NOT NULL UNIQUE,
+        "decision"	TEXT NOT NULL,
+        "role"	TEXT,
+        "reason"	TEXT,
+        "decided_at"	TEXT NOT NULL,
+        "decided_by"	INTEGER,
+        PRIMARY KEY("user_id"),
+        FOREIGN KEY("user_id") REFERENCES "Users"("ID") ON DELETE CASCADE,
+        FOREIGN KEY("decided_by") REFERENCES "Users"("ID") ON DELETE SET NULL
+    );
+    "#,
+    // 17: обязательная смена временного пароля и одноразовые коды восстановления доступа
+    r#"
+    ALTER TABLE "Users" ADD COLUMN "must_change_password" INTEGER NOT NULL DEFAULT 0;
+    CREATE TABLE IF NOT EXISTS "RecoveryCode" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "user_id"	INTEGER NOT NULL,
+        "code_hash"	TEXT NOT NULL,
+        "created_at"	TEXT NOT NULL,
+        "used_at"	TEXT,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("user_id") REFERENCES "Users"("ID") ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS "idx_recovery_code_hash" ON "RecoveryCode" ("code_hash");
+    "#,
+    // 18: неудачные попытки входа с временной блокировкой учётной записи и история входов
+    r#"
+    CREATE TABLE IF NOT EXISTS "LoginThrottle" (
+        "user_id"	INTEGER NOT NULL UNIQUE,
+        "failed_count"	INTEGER NOT NULL DEFAULT 0,
+        "last_failed_at"	TEXT,
+        "locked_until"	TEXT,
+        PRIMARY KEY("user_id"),
+        FOREIGN KEY("user_id") REFERENCES "Users"("ID") ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS "LoginHistory" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "user_id"	INTEGER,
+        "email"	TEXT NOT NULL,
+        "attempted_at"	TEXT NOT NULL,
+        "outcome"	TEXT NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        FOREIGN KEY("user_id") REFERENCES "Users"("ID") ON DELETE SET NULL
+    );
+    CREATE INDEX IF NOT EXISTS "idx_login_history_attempted" ON "LoginHistory" ("attempted_at");
+    "#,
+    // 19: двухфакторная аутентификация — секрет TOTP и последний принятый шаг (против повторного ввода кода)
+    r#"
+    ALTER TABLE "Users" ADD COLUMN "totp_secret" TEXT;
+    ALTER TABLE "Users" ADD COLUMN "totp_last_step" INTEGER;
+    ALTER TABLE "Users" ADD COLUMN "totp_enabled_at" TEXT;
+    "#,
+    // 20: выписанные счета и квитанции не удаляются вместе с платежом, а аннулируются —
+    // иначе номер удалённого документа достался бы следующему платежу
+    r#"
+    CREATE TABLE "PaymentDocument_new" (
+        "id"	INTEGER NOT NULL UNIQUE,
+        "payment_id"	INTEGER NOT NULL,
+        "kind"	TEXT NOT NULL,
+        "year"	INTEGER NOT NULL,
+        "number"	INTEGER NOT NULL,
+        "issued_at"	TEXT NOT NULL,
+        "cancelled_at"	TEXT,
+        PRIMARY KEY("id" AUTOINCREMENT),
+        UNIQUE("payment_id","kind"),
+        UNIQUE("kind","year","number")
+    );
+    INSERT INTO "PaymentDocument_new" ("id", "payment_id", "kind", "year", "number", "issued_at")
+        SELECT "id", "payment_id", "kind", "year", "number", "issued_at" FROM "PaymentDocument";
+    DROP TABLE "PaymentDocument";
+    ALTER TABLE "PaymentDocument_new" RENAME TO "PaymentDocument";
+    "#,
+    // 21: коды восстановления хэшируются Argon2id с солью, поиск по хэшу больше невозможен.
+    // Старые хэши SHA-256 удаляются — новые коды выдаются в профиле
+    r#"
+    DELETE FROM "RecoveryCode" WHERE "code_hash" NOT LIKE '$%';
+    DROP INDEX IF EXISTS "idx_recovery_code_hash";
+    CREATE INDEX IF NOT EXISTS "idx_recovery_code_user" ON "RecoveryCode" ("user_id");
+    "#,
+];
+
+/// Версия схемы, которую ожидает текущая сборка приложения.
+pub const SCHEMA_VERSION: i32 = MIGRATIONS.len() as i32;
+
+/// Возвращает версию схемы, записанную в БД.
+pub fn schema_version(conn: &Connection) -> rusqlite::Result<i32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Применяет к БД все миграции, которые ещё не были применены.
+/// Каждая миграция выполняется в своей транзакции вместе с обновлением `user_version`.
+pub fn run_migrations(conn: &mut Connection) -> Result<(), String> {
+    let current = schema_version(conn).map_err(|e| format!("Не удалось прочитать версию схемы БД: {}", e))?;
+
+    if current > SCHEMA_VERSION {
+        return Err(format!(
+            "Версия схемы БД ({}) новее, чем поддерживает приложение ({})",
+            current, SCHEMA_VERSION
+        ));
+    }
+
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = index as i32 + 1;
+        let apply = |conn: &mut Connection| -> rusqlite::Result<()> {
+            let tx = conn.transaction()?;
+            tx.execute_batch(sql)?;
+            tx.pragma_update(None, "user_version", version)?;
+            tx.commit()
+        };
+        apply(conn).map_err(|e| format!("Ошибка миграции БД до версии {}: {}", version, e))?;
+        println!("DEBUG: применена миграция БД до версии {}", version);
+    }
+
+    // Триггеры журнала строятся по текущим столбцам таблиц, поэтому после любой миграции их пересоздаём
+    let triggers_missing = audit::triggers_missing(conn)
+        .map_err(|e| format!("Не удалось проверить триггеры журнала изменений: {}", e))?;
+    if current < SCHEMA_VERSION || triggers_missing {
+        audit::rebuild_triggers(conn).map_err(|e| format!("Не удалось обновить триггеры журнала изменений: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Открывает (или создаёт) файл БД и приводит его схему к актуальной версии.
+pub fn migrate_database(path: &str) -> Result<(), String> {
+    let mut conn = Connection::open(path).map_err(|e| format!("Не удалось открыть БД: {}", e))?;
+    run_migrations(&mut conn)
+}
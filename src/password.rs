@@ -0,0 +1,138 @@
+use argon2::Argon2;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use sha2::digest::FixedOutput;
+use sha2::{Digest, Sha256};
+
+/// Результат проверки пароля по хэшу из БД.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordCheck {
+    /// Пароль верный, хэш в актуальном формате.
+    Valid,
+    /// Пароль верный, но хэш старого формата — его нужно пересчитать.
+    ValidLegacy,
+    Invalid,
+}
+
+/// Минимальная длина пароля.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Сколько кодов восстановления выдаётся за раз.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+// Символы для временных паролей и кодов: без похожих друг на друга 0/O, 1/l/I
+const TEMPORARY_PASSWORD_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnpqrstuvwxyz23456789";
+const RECOVERY_CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Проверяет пароль на соответствие требованиям: длина, заглавная буква и цифра.
+/// Возвращает текст первой нарушенной части требований.
+pub fn check_password_strength(password: &str) -> Result<(), String> {
+    if password.trim().is_empty() {
+        return Err("Пароль не может быть пустым".to_string());
+    }
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!("Пароль должен содержать минимум {} символов", MIN_PASSWORD_LENGTH));
+    }
+    if !password.chars().any(|c| c.is_ascii_uppercase()) {
+        return Err("Пароль должен содержать хотя бы одну заглавную букву".to_string());
+    }
+    if !password.chars().any(|c| c.is_ascii_digit()) {
+        return Err("Пароль должен содержать хотя бы одну цифру".to_string());
+    }
+    Ok(())
+}
+
+/// Одноразовый пароль, который администратор выдаёт при сбросе. Всегда проходит
+/// `check_password_strength`, чтобы им можно было войти и сразу сменить.
+pub fn generate_temporary_password() -> String {
+    loop {
+        let password = random_string(TEMPORARY_PASSWORD_CHARS, 12);
+        if check_password_strength(&password).is_ok() {
+            return password;
+        }
+    }
+}
+
+/// Новый набор кодов восстановления в виде `XXXX-XXXX`.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code = random_string(RECOVERY_CODE_CHARS, 8);
+            format!("{}-{}", &code[..4], &code[4..])
+        })
+        .collect()
+}
+
+/// Хэш кода восстановления для хранения в БД — Argon2id, как у паролей: в коде всего около
+/// 40 бит случайности, и быстрый хэш из копии БД перебирается за минуты.
+/// Регистр, пробелы и дефисы при вводе не важны.
+pub fn hash_recovery_code(code: &str) -> Result<String, String> {
+    hash_password(&normalize_recovery_code(code))
+}
+
+/// Проверяет введённый код восстановления по хэшу из БД
+pub fn verify_recovery_code(code: &str, stored_hash: &str) -> bool {
+    // Старые несолёные хэши кодов не принимаются: миграция их удаляет
+    stored_hash.starts_with('$')
+        && verify_password(&normalize_recovery_code(code), stored_hash) == PasswordCheck::Valid
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn random_string(alphabet: &[u8], length: usize) -> String {
+    (0..length)
+        .map(|_| alphabet[OsRng.next_u32() as usize % alphabet.len()] as char)
+        .collect()
+}
+
+/// Хэширует пароль через Argon2id со случайной солью. Результат — строка в формате PHC
+/// (`$argon2id$v=19$m=...,t=...,p=...$соль$хэш`), которую и нужно хранить в `Users.password`.
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Не удалось вычислить хэш пароля: {}", e))
+}
+
+/// Проверяет пароль по сохранённому хэшу. Понимает как PHC-строки Argon2,
+/// так и старые несолёные хэши SHA-256, которые были в БД до перехода на Argon2.
+pub fn verify_password(password: &str, stored_hash: &str) -> PasswordCheck {
+    if stored_hash.starts_with('$') {
+        let Ok(parsed) = PasswordHash::new(stored_hash) else {
+            return PasswordCheck::Invalid;
+        };
+        // verify_password сравнивает хэши за постоянное время
+        return match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+            Ok(()) => PasswordCheck::Valid,
+            Err(_) => PasswordCheck::Invalid,
+        };
+    }
+
+    if constant_time_eq(legacy_hash(password).as_bytes(), stored_hash.as_bytes()) {
+        PasswordCheck::ValidLegacy
+    } else {
+        PasswordCheck::Invalid
+    }
+}
+
+// Старая схема: SHA-256 от пароля, записанного дважды, в hex
+fn legacy_hash(password: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(password);
+    hasher.update(password);
+    hasher.finalize_fixed().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Сравнение без раннего выхода, чтобы время не зависело от позиции первого расхождения
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,814 @@
+//! Встроенный генератор PDF: сертификаты и отчёты без Chrome и без доступа в интернет.
+//! Шрифты DejaVu и изображения берутся из папки assets, диаграммы рисуются векторно.
+
+use crate::amount_words::amount_in_words;
+use crate::app::state::{
+    Certificate, GroupForReport, Organization, Payment, PaymentDocument, PaymentDocumentKind, Payroll,
+    UserInfo,
+};
+use crate::doc_gen::discount_cell;
+use chrono::NaiveDate;
+use image::codecs::jpeg::JpegEncoder;
+use image::{ExtendedColorType, GenericImageView};
+use printpdf::path::{PaintMode, WindingOrder};
+use printpdf::{
+    Color, ColorBits, ColorSpace, Image, ImageFilter, ImageTransform, ImageXObject, IndirectFontRef, Line, Mm,
+    PdfDocument, PdfDocumentReference, PdfLayerReference, Point, Polygon, Px, Rect, Rgb,
+};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+// Размеры страницы A4 и поля, мм
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 15.0;
+
+// Цвета Gruvbox, как в HTML-шаблонах
+const DARK_BG: u32 = 0x282828;
+const DARK_CARD: u32 = 0x3c3836;
+const BLUE: u32 = 0x458588;
+const LIGHT_FG: u32 = 0xebdbb2;
+const DARK_FG: u32 = 0x3c3836;
+const YELLOW: u32 = 0xfabd2f;
+const DARK_YELLOW: u32 = 0xd79921;
+const AQUA_BLUE: u32 = 0x83a598;
+const GREEN: u32 = 0xb8bb26;
+const RED: u32 = 0xfb4934;
+const GRAY: u32 = 0x928374;
+const LIGHT_GRAY: u32 = 0xa89984;
+const TABLE_HEADER_BG: u32 = 0xebdbb2;
+const TABLE_STRIPE_BG: u32 = 0xf9f5d7;
+const WHITE: u32 = 0xffffff;
+
+// Палитра секторов круговых диаграмм (первые пять — как в Chart.js-отчётах)
+const CHART_COLORS: [u32; 8] = [
+    0xfabd2f, 0x83a598, 0xfb4934, 0xb8bb26, 0xd3869b, 0xfe8019, 0x8ec07c, 0x928374,
+];
+
+// Таблицы отчётов
+const TABLE_FONT_SIZE: f32 = 9.0;
+const ROW_HEIGHT: f32 = 7.0;
+const CELL_PADDING: f32 = 2.0;
+
+// Перевод пунктов в миллиметры
+const PT_TO_MM: f32 = 0.352_778;
+
+fn color(hex: u32) -> Color {
+    Color::Rgb(Rgb::new(
+        ((hex >> 16) & 0xff) as f32 / 255.0,
+        ((hex >> 8) & 0xff) as f32 / 255.0,
+        (hex & 0xff) as f32 / 255.0,
+        None,
+    ))
+}
+
+fn format_date(date: &str) -> String {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|d| d.format("%d.%m.%Y").to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
+
+/// Шрифт, встроенный в документ, вместе с исходными данными для измерения ширины текста
+struct Font {
+    reference: IndirectFontRef,
+    data: Vec<u8>,
+}
+
+impl Font {
+    fn load(doc: &PdfDocumentReference, path: &Path) -> Result<Self, Box<dyn Error>> {
+        let data = fs::read(path)
+            .map_err(|e| format!("Не удалось прочитать шрифт {}: {}", path.display(), e))?;
+        let reference = doc.add_external_font(data.as_slice())?;
+        Ok(Self { reference, data })
+    }
+
+    /// Ширина строки в миллиметрах при заданном кегле
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        let Ok(face) = ttf_parser::Face::parse(&self.data, 0) else {
+            // Грубая оценка, если метрики недоступны
+            return text.chars().count() as f32 * size * 0.55 * PT_TO_MM;
+        };
+        let units_per_em = face.units_per_em() as f32;
+        let advance: f32 = text
+            .chars()
+            .map(|c| {
+                face.glyph_index(c)
+                    .and_then(|glyph| face.glyph_hor_advance(glyph))
+                    .unwrap_or(0) as f32
+            })
+            .sum();
+        advance / units_per_em * size * PT_TO_MM
+    }
+
+    /// Обрезает строку с многоточием, чтобы она поместилась в ширину
+    fn fit(&self, text: &str, size: f32, max_width: f32) -> String {
+        if self.text_width(text, size) <= max_width {
+            return text.to_string();
+        }
+        let mut fitted: String = text.to_string();
+        while !fitted.is_empty() && self.text_width(&format!("{}…", fitted), size) > max_width {
+            fitted.pop();
+        }
+        format!("{}…", fitted.trim_end())
+    }
+
+    /// Разбивает текст на строки по словам
+    fn wrap(&self, text: &str, size: f32, max_width: f32) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut current = String::new();
+        for word in text.split_whitespace() {
+            let candidate = if current.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", current, word)
+            };
+            if self.text_width(&candidate, size) > max_width && !current.is_empty() {
+                lines.push(std::mem::replace(&mut current, word.to_string()));
+            } else {
+                current = candidate;
+            }
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+        lines
+    }
+}
+
+/// Рисует закрашенный прямоугольник по левому нижнему углу и размерам
+fn fill_rect(layer: &PdfLayerReference, x: f32, y: f32, width: f32, height: f32, fill: u32) {
+    layer.set_fill_color(color(fill));
+    layer.add_rect(
+        Rect::new(Mm(x), Mm(y), Mm(x + width), Mm(y + height)).with_mode(PaintMode::Fill),
+    );
+}
+
+/// Рисует рамку прямоугольника
+fn stroke_rect(layer: &PdfLayerReference, x: f32, y: f32, width: f32, height: f32, stroke: u32, thickness: f32) {
+    layer.set_outline_color(color(stroke));
+    layer.set_outline_thickness(thickness);
+    layer.add_rect(
+        Rect::new(Mm(x), Mm(y), Mm(x + width), Mm(y + height)).with_mode(PaintMode::Stroke),
+    );
+}
+
+fn horizontal_line(layer: &PdfLayerReference, x1: f32, x2: f32, y: f32, stroke: u32, thickness: f32) {
+    layer.set_outline_color(color(stroke));
+    layer.set_outline_thickness(thickness);
+    layer.add_line(Line {
+        points: vec![
+            (Point::new(Mm(x1), Mm(y)), false),
+            (Point::new(Mm(x2), Mm(y)), false),
+        ],
+        is_closed: false,
+    });
+}
+
+/// Вставляет PNG из assets, прозрачность смешивается с цветом фона.
+/// `opacity` дополнительно ослабляет изображение (как opacity у печати в HTML).
+fn draw_image(
+    layer: &PdfLayerReference,
+    path: &Path,
+    x: f32,
+    y: f32,
+    width: f32,
+    background: u32,
+    opacity: f32,
+) -> Result<(), Box<dyn Error>> {
+    let img = image::open(path)
+        .map_err(|e| format!("Не удалось открыть изображение {}: {}", path.display(), e))?;
+    let (px_width, px_height) = img.dimensions();
+    let rgba = img.to_rgba8();
+
+    let bg = [
+        ((background >> 16) & 0xff) as f32,
+        ((background >> 8) & 0xff) as f32,
+        (background & 0xff) as f32,
+    ];
+    let mut image_data = Vec::with_capacity((px_width * px_height * 3) as usize);
+    for pixel in rgba.pixels() {
+        let alpha = pixel[3] as f32 / 255.0 * opacity;
+        for channel in 0..3 {
+            image_data.push((pixel[channel] as f32 * alpha + bg[channel] * (1.0 - alpha)).round() as u8);
+        }
+    }
+
+    // Сжимаем в JPEG (DCTDecode), иначе несжатые пиксели занимают мегабайты
+    let mut jpeg_data = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg_data, 90).encode(
+        &image_data,
+        px_width,
+        px_height,
+        ExtendedColorType::Rgb8,
+    )?;
+
+    let xobject = ImageXObject {
+        width: Px(px_width as usize),
+        height: Px(px_height as usize),
+        color_space: ColorSpace::Rgb,
+        bits_per_component: ColorBits::Bit8,
+        interpolate: true,
+        image_data: jpeg_data,
+        image_filter: Some(ImageFilter::DCT),
+        smask: None,
+        clipping_bbox: None,
+    };
+
+    // При dpi 300 естественная ширина изображения — px / 300 дюйма
+    let dpi = 300.0;
+    let natural_width = px_width as f32 / dpi * 25.4;
+    let scale = width / natural_width;
+
+    Image::from(xobject).add_to_layer(
+        layer.clone(),
+        ImageTransform {
+            translate_x: Some(Mm(x)),
+            translate_y: Some(Mm(y)),
+            scale_x: Some(scale),
+            scale_y: Some(scale),
+            dpi: Some(dpi),
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
+/// Многостраничный отчёт: заголовки, таблицы и круговые диаграммы сверху вниз
+struct ReportDocument {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: Font,
+    bold: Font,
+    y: f32, // Текущая позиция курсора (мм от нижнего края страницы)
+}
+
+impl ReportDocument {
+    fn new(title: &str, assets_dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let (doc, page, layer) =
+            PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Слой 1");
+        let fonts_dir = assets_dir.join("fonts");
+        let regular = Font::load(&doc, &fonts_dir.join("DejaVuSans-Regular.ttf"))?;
+        let bold = Font::load(&doc, &fonts_dir.join("DejaVuSans-Bold.ttf"))?;
+        let layer = doc.get_page(page).get_layer(layer);
+        Ok(Self {
+            doc,
+            layer,
+            regular,
+            bold,
+            y: PAGE_HEIGHT - MARGIN,
+        })
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Слой 1");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Переходит на новую страницу, если до нижнего поля осталось меньше `height` мм
+    fn ensure_space(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn text(&self, text: &str, size: f32, x: f32, y: f32, bold: bool, fill: u32) {
+        let font = if bold { &self.bold } else { &self.regular };
+        self.layer.set_fill_color(color(fill));
+        self.layer.use_text(text, size, Mm(x), Mm(y), &font.reference);
+    }
+
+    fn heading(&mut self, text: &str, size: f32) {
+        let height = size * PT_TO_MM * 1.8;
+        self.ensure_space(height);
+        self.y -= size * PT_TO_MM * 1.2;
+        self.text(text, size, MARGIN, self.y, true, DARK_FG);
+        self.y -= height - size * PT_TO_MM * 1.2;
+    }
+
+    /// Строка вида "Подпись: значение"
+    fn labeled_line(&mut self, label: &str, value: &str) {
+        let size = 11.0;
+        self.ensure_space(7.0);
+        self.y -= 5.0;
+        self.text(label, size, MARGIN, self.y, true, DARK_FG);
+        let label_width = self.bold.text_width(label, size);
+        self.text(value, size, MARGIN + label_width + 1.5, self.y, false, DARK_FG);
+        self.y -= 2.0;
+    }
+
+    fn paragraph(&mut self, text: &str, size: f32, bold: bool) {
+        let font = if bold { &self.bold } else { &self.regular };
+        let lines = font.wrap(text, size, PAGE_WIDTH - 2.0 * MARGIN);
+        let line_height = size * PT_TO_MM * 1.5;
+        for line in lines {
+            self.ensure_space(line_height);
+            self.y -= line_height;
+            self.text(&line, size, MARGIN, self.y + line_height * 0.3, bold, DARK_FG);
+        }
+    }
+
+    fn spacing(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn table_header(&self, columns: &[(&str, f32)]) {
+        let total_width: f32 = columns.iter().map(|(_, w)| w).sum();
+        fill_rect(&self.layer, MARGIN, self.y - ROW_HEIGHT, total_width, ROW_HEIGHT, TABLE_HEADER_BG);
+        let mut x = MARGIN;
+        for (title, width) in columns {
+            let cell = self.bold.fit(title, TABLE_FONT_SIZE, width - 2.0 * CELL_PADDING);
+            self.text(&cell, TABLE_FONT_SIZE, x + CELL_PADDING, self.y - ROW_HEIGHT + 2.3, true, DARK_FG);
+            x += width;
+        }
+    }
+
+    /// Таблица с повтором шапки на каждой новой странице.
+    /// `columns` — заголовки и ширина столбцов в мм.
+    fn table(&mut self, columns: &[(&str, f32)], rows: &[Vec<String>]) {
+        let total_width: f32 = columns.iter().map(|(_, w)| w).sum();
+        self.ensure_space(ROW_HEIGHT * 2.0);
+        self.table_header(columns);
+        self.y -= ROW_HEIGHT;
+
+        for (index, row) in rows.iter().enumerate() {
+            if self.y - ROW_HEIGHT < MARGIN {
+                self.new_page();
+                self.table_header(columns);
+                self.y -= ROW_HEIGHT;
+            }
+            if index % 2 == 1 {
+                fill_rect(&self.layer, MARGIN, self.y - ROW_HEIGHT, total_width, ROW_HEIGHT, TABLE_STRIPE_BG);
+            }
+            let mut x = MARGIN;
+            for ((_, width), value) in columns.iter().zip(row) {
+                let cell = self.regular.fit(value, TABLE_FONT_SIZE, width - 2.0 * CELL_PADDING);
+                self.text(&cell, TABLE_FONT_SIZE, x + CELL_PADDING, self.y - ROW_HEIGHT + 2.3, false, DARK_FG);
+                x += width;
+            }
+            horizontal_line(&self.layer, MARGIN, MARGIN + total_width, self.y - ROW_HEIGHT, TABLE_HEADER_BG, 0.5);
+            self.y -= ROW_HEIGHT;
+        }
+    }
+
+    /// Круговая диаграмма с легендой справа. `format_value` задаёт подпись значения в легенде.
+    fn pie_chart(&mut self, title: &str, data: &[(String, f64)], format_value: impl Fn(f64) -> String) {
+        let total: f64 = data.iter().map(|(_, value)| value).sum();
+        if total <= 0.0 {
+            return;
+        }
+
+        let radius: f32 = 32.0;
+        let legend_row = 6.0;
+        let chart_height = (2.0 * radius).max(data.len() as f32 * legend_row) + 14.0;
+        self.ensure_space(chart_height);
+
+        self.y -= 8.0;
+        self.text(title, 13.0, MARGIN, self.y, true, DARK_FG);
+        self.y -= 4.0;
+
+        let center_x = MARGIN + radius + 2.0;
+        let center_y = self.y - radius;
+        let mut start_angle = PI / 2.0; // Первый сектор начинается сверху, как в Chart.js
+
+        self.layer.set_outline_color(color(WHITE));
+        self.layer.set_outline_thickness(1.5);
+        for (index, (_, value)) in data.iter().enumerate() {
+            let sweep = (*value / total) as f32 * 2.0 * PI;
+            // Сектор — многоугольник из центра и точек дуги с шагом не больше 2 градусов
+            let steps = ((sweep / (PI / 90.0)).ceil() as usize).max(1);
+            let mut points = Vec::with_capacity(steps + 2);
+            if sweep < 2.0 * PI - 0.001 {
+                points.push((Point::new(Mm(center_x), Mm(center_y)), false));
+            }
+            for step in 0..=steps {
+                let angle = start_angle - sweep * step as f32 / steps as f32;
+                points.push((
+                    Point::new(
+                        Mm(center_x + radius * angle.cos()),
+                        Mm(center_y + radius * angle.sin()),
+                    ),
+                    false,
+                ));
+            }
+            self.layer.set_fill_color(color(CHART_COLORS[index % CHART_COLORS.len()]));
+            self.layer.add_polygon(Polygon {
+                rings: vec![points],
+                mode: PaintMode::FillStroke,
+                winding_order: WindingOrder::NonZero,
+            });
+            start_angle -= sweep;
+        }
+
+        // Легенда: цвет, подпись, значение и доля
+        let legend_x = center_x + radius + 12.0;
+        let legend_width = PAGE_WIDTH - MARGIN - legend_x - 6.0;
+        let mut legend_y = self.y - 4.0;
+        for (index, (label, value)) in data.iter().enumerate() {
+            fill_rect(&self.layer, legend_x, legend_y - 0.5, 4.0, 4.0, CHART_COLORS[index % CHART_COLORS.len()]);
+            let caption = format!(
+                "{} — {} ({:.1}%)",
+                label,
+                format_value(*value),
+                value / total * 100.0
+            );
+            let caption = self.regular.fit(&caption, 10.0, legend_width);
+            self.text(&caption, 10.0, legend_x + 6.0, legend_y, false, DARK_FG);
+            legend_y -= legend_row;
+        }
+
+        self.y -= chart_height - 12.0;
+    }
+
+    fn save(self, output_pdf: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = output_pdf.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(output_pdf)?);
+        self.doc.save(&mut writer)?;
+        Ok(())
+    }
+}
+
+/// Сертификат об окончании курса в оформлении HTML-шаблона
+pub fn generate_certificate_pdf(
+    cert: &Certificate,
+    student: &UserInfo,
+    assets_dir: &Path,
+    output_pdf: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut report = ReportDocument::new("Сертификат", assets_dir)?;
+    let center = |font: &Font, text: &str, size: f32| (PAGE_WIDTH - font.text_width(text, size)) / 2.0;
+
+    // Фон, рамка и карточка сертификата
+    fill_rect(&report.layer, 0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT, DARK_BG);
+    let card_x = 20.0;
+    let card_y = 40.0;
+    let card_width = PAGE_WIDTH - 2.0 * card_x;
+    let card_height = PAGE_HEIGHT - 2.0 * card_y;
+    fill_rect(&report.layer, card_x, card_y, card_width, card_height, DARK_CARD);
+    stroke_rect(&report.layer, card_x, card_y, card_width, card_height, BLUE, 6.0);
+
+    let text_width = card_width - 20.0;
+    let decorative_line = |report: &ReportDocument, y: f32| {
+        fill_rect(&report.layer, (PAGE_WIDTH - 16.0) / 2.0, y, 16.0, 1.1, DARK_YELLOW);
+    };
+
+    report.y = card_y + card_height - 30.0;
+    report.text("СЕРТИФИКАТ", 36.0, center(&report.bold, "СЕРТИФИКАТ", 36.0), report.y, true, YELLOW);
+    report.y -= 12.0;
+    decorative_line(&report, report.y);
+    report.y -= 16.0;
+
+    let intro = "Настоящим подтверждается, что";
+    report.text(intro, 14.0, center(&report.regular, intro, 14.0), report.y, false, LIGHT_FG);
+    report.y -= 16.0;
+
+    for line in report.bold.wrap(&student.name, 26.0, text_width) {
+        report.text(&line, 26.0, center(&report.bold, &line, 26.0), report.y, true, AQUA_BLUE);
+        report.y -= 12.0;
+    }
+    report.y -= 4.0;
+
+    let completed = "успешно завершил(а) курс";
+    report.text(completed, 14.0, center(&report.regular, completed, 14.0), report.y, false, LIGHT_FG);
+    report.y -= 14.0;
+
+    for line in report.bold.wrap(&cert.course_title, 22.0, text_width) {
+        report.text(&line, 22.0, center(&report.bold, &line, 22.0), report.y, true, GREEN);
+        report.y -= 10.0;
+    }
+    report.y -= 4.0;
+
+    let grade = format!("С оценкой: {}", cert.grade);
+    report.text(&grade, 16.0, center(&report.regular, &grade, 16.0), report.y, false, RED);
+    report.y -= 10.0;
+    decorative_line(&report, report.y);
+
+    // Подпись директора слева, печать справа
+    let footer_y = card_y + 45.0;
+    let signature_x = card_x + 15.0;
+    let signature_width = card_width * 0.4;
+    horizontal_line(&report.layer, signature_x, signature_x + signature_width, footer_y, GRAY, 0.8);
+    report.text("Подпись директора", 13.0, signature_x, footer_y - 6.0, true, LIGHT_FG);
+    draw_image(
+        &report.layer,
+        &assets_dir.join("images/signature.png"),
+        signature_x,
+        footer_y + 1.0,
+        40.0,
+        DARK_CARD,
+        1.0,
+    )?;
+    let seal_width = 32.0;
+    draw_image(
+        &report.layer,
+        &assets_dir.join("images/seal.png"),
+        card_x + card_width - 15.0 - seal_width,
+        footer_y - 12.0,
+        seal_width,
+        DARK_CARD,
+        0.5,
+    )?;
+
+    let issue_date = format!("Дата выдачи: {}", cert.issue_date);
+    report.text(&issue_date, 12.0, center(&report.regular, &issue_date, 12.0), card_y + 15.0, false, LIGHT_GRAY);
+
+    report.save(output_pdf)
+}
+
+/// Счёт на оплату или квитанция: реквизиты организации, позиция по курсу и группе, сумма прописью
+pub fn generate_payment_document_pdf(
+    document: &PaymentDocument,
+    payment: &Payment,
+    organization: &Organization,
+    assets_dir: &Path,
+    output_pdf: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let title = document.kind.title();
+    let mut report = ReportDocument::new(title, assets_dir)?;
+
+    if !organization.name.trim().is_empty() {
+        report.paragraph(&organization.name, 13.0, true);
+    }
+    for (label, value) in organization.requisites() {
+        report.labeled_line(&format!("{}:", label), value);
+    }
+    report.spacing(6.0);
+
+    report.heading(
+        &format!(
+            "{} № {} от {}",
+            title,
+            document.display_number(),
+            format_date(document.issue_date())
+        ),
+        16.0,
+    );
+    horizontal_line(&report.layer, MARGIN, PAGE_WIDTH - MARGIN, report.y + 2.0, DARK_FG, 0.8);
+    report.spacing(2.0);
+
+    let payer_label = match document.kind {
+        PaymentDocumentKind::Invoice => "Плательщик:",
+        PaymentDocumentKind::Receipt => "Принято от:",
+    };
+    report.labeled_line(payer_label, &payment.student_name);
+    if document.kind == PaymentDocumentKind::Receipt {
+        report.labeled_line("Дата оплаты:", &format_date(&payment.date));
+        report.labeled_line("Способ оплаты:", &payment.payment_type);
+    }
+    report.labeled_line("Основание:", &format!("платёж № {}", payment.id));
+    report.spacing(4.0);
+
+    let price = payment.amount + payment.discount;
+    let rows = vec![vec![
+        "1".to_string(),
+        format!("Обучение по курсу «{}», группа {}", payment.course_title, payment.group_name),
+        "1".to_string(),
+        format!("{:.2} ₽", price),
+        format!("{:.2} ₽", payment.discount),
+        format!("{:.2} ₽", payment.amount),
+    ]];
+    report.table(
+        &[
+            ("№", 9.0),
+            ("Наименование", 81.0),
+            ("Кол-во", 15.0),
+            ("Цена", 25.0),
+            ("Скидка", 25.0),
+            ("Сумма", 25.0),
+        ],
+        &rows,
+    );
+    report.spacing(2.0);
+    report.labeled_line("Итого:", &format!("{:.2} ₽", payment.amount));
+    if let Some(note) = payment.discount_note.as_deref().filter(|_| payment.discount > 0.0) {
+        report.labeled_line("Скидка:", note);
+    }
+    report.spacing(3.0);
+
+    let total_line = match document.kind {
+        PaymentDocumentKind::Invoice => "Всего к оплате:",
+        PaymentDocumentKind::Receipt => "Получено:",
+    };
+    report.paragraph(total_line, 11.0, false);
+    report.paragraph(&amount_in_words(payment.amount), 11.0, true);
+    report.spacing(18.0);
+
+    let signature = match document.kind {
+        PaymentDocumentKind::Invoice => "Руководитель",
+        PaymentDocumentKind::Receipt => "Принял",
+    };
+    report.text(signature, 11.0, MARGIN, report.y, false, DARK_FG);
+    horizontal_line(&report.layer, MARGIN + 30.0, MARGIN + 90.0, report.y - 1.0, DARK_FG, 0.5);
+
+    report.save(output_pdf)
+}
+
+pub fn generate_payment_report_pdf(
+    payments: &[Payment],
+    from: &str,
+    to: &str,
+    assets_dir: &Path,
+    output_pdf: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut report = ReportDocument::new("Отчёт по платежам", assets_dir)?;
+    report.heading("Отчёт по платежам", 20.0);
+    report.labeled_line("Период:", &format!("{} — {}", format_date(from), format_date(to)));
+    report.spacing(4.0);
+
+    let rows: Vec<Vec<String>> = payments
+        .iter()
+        .map(|p| {
+            vec![
+                format_date(&p.date),
+                p.student_name.clone(),
+                p.course_title.clone(),
+                p.payment_type.clone(),
+                discount_cell(p),
+                format!("{:.2} ₽", p.amount),
+            ]
+        })
+        .collect();
+    report.table(
+        &[
+            ("Дата", 22.0),
+            ("Студент", 40.0),
+            ("Курс", 40.0),
+            ("Тип", 25.0),
+            ("Скидка", 30.0),
+            ("Сумма", 23.0),
+        ],
+        &rows,
+    );
+
+    let total: f64 = payments.iter().map(|p| p.amount).sum();
+    let total_discount: f64 = payments.iter().map(|p| p.discount).sum();
+    report.spacing(2.0);
+    report.labeled_line("Итого:", &format!("{:.2} ₽", total));
+    if total_discount > 0.0 {
+        report.labeled_line("Скидки:", &format!("{:.2} ₽", total_discount));
+    }
+
+    let mut course_sums: BTreeMap<String, f64> = BTreeMap::new();
+    for p in payments {
+        *course_sums.entry(p.course_title.clone()).or_insert(0.0) += p.amount;
+    }
+    let chart_data: Vec<(String, f64)> = course_sums.into_iter().collect();
+    report.pie_chart("Суммы по курсам", &chart_data, |value| format!("{:.2} ₽", value));
+
+    report.save(output_pdf)
+}
+
+pub fn generate_certificate_report_pdf(
+    certificates: &[Certificate],
+    from: &str,
+    to: &str,
+    assets_dir: &Path,
+    output_pdf: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut report = ReportDocument::new("Отчёт по сертификатам", assets_dir)?;
+    report.heading("Отчёт по сертификатам", 20.0);
+    report.labeled_line("Период:", &format!("{} — {}", format_date(from), format_date(to)));
+    report.spacing(4.0);
+
+    let rows: Vec<Vec<String>> = certificates
+        .iter()
+        .map(|c| {
+            vec![
+                c.id.to_string(),
+                c.student_name.clone(),
+                c.course_title.clone(),
+                format_date(&c.issue_date),
+                c.grade.clone(),
+            ]
+        })
+        .collect();
+    report.table(
+        &[("ID", 14.0), ("Студент", 55.0), ("Курс", 55.0), ("Дата выдачи", 28.0), ("Оценка", 28.0)],
+        &rows,
+    );
+
+    let mut grade_counts: BTreeMap<String, f64> = BTreeMap::new();
+    for c in certificates {
+        *grade_counts.entry(c.grade.clone()).or_insert(0.0) += 1.0;
+    }
+    let chart_data: Vec<(String, f64)> = grade_counts.into_iter().collect();
+    report.spacing(4.0);
+    report.pie_chart("Сертификаты по оценкам", &chart_data, |value| format!("{}", value as usize));
+
+    report.save(output_pdf)
+}
+
+pub fn generate_group_report_pdf(
+    groups: &[GroupForReport],
+    assets_dir: &Path,
+    output_pdf: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut report = ReportDocument::new("Отчёт по группам", assets_dir)?;
+    report.heading("Отчёт по группам", 20.0);
+
+    let mut status_counts: BTreeMap<String, f64> = BTreeMap::new();
+    let mut course_counts: BTreeMap<String, f64> = BTreeMap::new();
+
+    for g in groups {
+        let course_name = g.course_name.clone().unwrap_or_else(|| "—".to_string());
+        *status_counts.entry(g.status.to_string()).or_insert(0.0) += 1.0;
+        *course_counts.entry(course_name.clone()).or_insert(0.0) += 1.0;
+
+        report.spacing(3.0);
+        report.paragraph(&format!("Группа: {}", g.name), 12.0, true);
+        report.paragraph(
+            &format!(
+                "Преподаватель: {} | Статус: {} | Курс: {} | Студентов: {}",
+                g.teacher_name.clone().unwrap_or_else(|| "—".to_string()),
+                g.status,
+                course_name,
+                g.student_count
+            ),
+            10.0,
+            false,
+        );
+        if g.students.is_empty() {
+            report.paragraph("Нет студентов в группе", 10.0, false);
+        } else {
+            for student in &g.students {
+                report.paragraph(&format!("• {}", student), 10.0, false);
+            }
+        }
+    }
+
+    report.spacing(4.0);
+    let status_data: Vec<(String, f64)> = status_counts.into_iter().collect();
+    report.pie_chart("Группы по статусу", &status_data, |value| format!("{}", value as usize));
+    let course_data: Vec<(String, f64)> = course_counts.into_iter().collect();
+    report.pie_chart("Группы по курсам", &course_data, |value| format!("{}", value as usize));
+
+    report.save(output_pdf)
+}
+
+pub fn generate_payroll_pdf(
+    payroll: &Payroll,
+    organization: &Organization,
+    assets_dir: &Path,
+    output_pdf: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut report = ReportDocument::new("Расчётная ведомость", assets_dir)?;
+    if !organization.name.trim().is_empty() {
+        report.paragraph(&organization.name, 12.0, true);
+    }
+    report.heading("Расчётная ведомость по оплате труда преподавателей", 18.0);
+    report.labeled_line("Период:", &payroll.period_title());
+    let status = match (&payroll.approved_at, &payroll.approved_by) {
+        (Some(approved_at), approved_by) => format!(
+            "утверждена {} {}",
+            format_date(approved_at.get(..10).unwrap_or(approved_at)),
+            approved_by.as_deref().unwrap_or("")
+        ),
+        (None, _) => "предварительный расчёт, не утверждена".to_string(),
+    };
+    report.labeled_line("Статус:", &status);
+    report.spacing(4.0);
+
+    let rows: Vec<Vec<String>> = payroll
+        .lines
+        .iter()
+        .map(|line| {
+            vec![
+                line.teacher_name.clone(),
+                line.rate_type
+                    .map_or("не задана".to_string(), |rate_type| rate_type.format_rate(line.rate)),
+                line.sessions.to_string(),
+                format!("{:.1}", line.student_hours),
+                format!("{:.2} ₽", line.revenue),
+                format!("{:.2} ₽", line.amount),
+            ]
+        })
+        .collect();
+    report.table(
+        &[
+            ("Преподаватель", 42.0),
+            ("Ставка", 44.0),
+            ("Занятий", 18.0),
+            ("Студ.-часов", 22.0),
+            ("Выручка", 27.0),
+            ("Начислено", 27.0),
+        ],
+        &rows,
+    );
+
+    report.spacing(2.0);
+    report.labeled_line("Итого к выплате:", &format!("{:.2} ₽", payroll.total()));
+    report.paragraph(&amount_in_words(payroll.total()), 10.0, false);
+
+    report.ensure_space(20.0);
+    report.spacing(15.0);
+    report.text("Руководитель", 11.0, MARGIN, report.y, false, DARK_FG);
+    horizontal_line(&report.layer, MARGIN + 30.0, MARGIN + 90.0, report.y - 1.0, DARK_FG, 0.5);
+
+    report.save(output_pdf)
+}
@@ -0,0 +1,86 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Timelike};
+
+use crate::app::state::ScheduleSlot;
+
+pub const WEEKDAY_NAMES: [&str; 7] = [
+    "Понедельник",
+    "Вторник",
+    "Среда",
+    "Четверг",
+    "Пятница",
+    "Суббота",
+    "Воскресенье",
+];
+
+pub const WEEKDAY_SHORT_NAMES: [&str; 7] = ["Пн", "Вт", "Ср", "Чт", "Пт", "Сб", "Вс"];
+
+/// Разбирает время начала занятия в формате "ЧЧ:ММ"
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+/// Время окончания занятия в формате "ЧЧ:ММ"
+pub fn end_time(start_time: &str, duration_minutes: i32) -> String {
+    parse_time(start_time)
+        .map(|start| {
+            (start + Duration::minutes(duration_minutes as i64))
+                .format("%H:%M")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// Заканчивается ли занятие не позже полуночи того же дня
+pub fn fits_in_day(start_time: NaiveTime, duration_minutes: i32) -> bool {
+    (start_time.num_seconds_from_midnight() / 60) as i64 + duration_minutes as i64 <= 24 * 60
+}
+
+/// Пересекаются ли два занятия в один и тот же день.
+/// Считаем в минутах от полуночи: сложение с `NaiveTime` заворачивается через 24:00.
+pub fn overlaps(start_a: &str, duration_a: i32, start_b: &str, duration_b: i32) -> bool {
+    let (Some(a), Some(b)) = (parse_time(start_a), parse_time(start_b)) else {
+        return false;
+    };
+    let a = (a.num_seconds_from_midnight() / 60) as i64;
+    let b = (b.num_seconds_from_midnight() / 60) as i64;
+    let a_end = a + duration_a as i64;
+    let b_end = b + duration_b as i64;
+    a < b_end && b < a_end
+}
+
+/// Понедельник недели, в которую попадает дата
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Раскладывает `count` занятий по еженедельным слотам, начиная с даты `from` (включительно).
+/// Возвращает дату и слот для каждого занятия по порядку.
+pub fn generate_session_dates(
+    slots: &[ScheduleSlot],
+    from: NaiveDate,
+    count: usize,
+) -> Vec<(NaiveDate, &ScheduleSlot)> {
+    let mut result = Vec::with_capacity(count);
+    if slots.is_empty() {
+        return result;
+    }
+
+    let mut sorted_slots: Vec<&ScheduleSlot> = slots.iter().collect();
+    sorted_slots.sort_by(|a, b| (a.weekday, &a.start_time).cmp(&(b.weekday, &b.start_time)));
+
+    let mut date = from;
+    while result.len() < count {
+        let weekday = date.weekday().number_from_monday();
+        for slot in sorted_slots.iter().filter(|s| s.weekday == weekday) {
+            if result.len() == count {
+                break;
+            }
+            result.push((date, *slot));
+        }
+        match date.succ_opt() {
+            Some(next) => date = next,
+            None => break,
+        }
+    }
+    result
+}
@@ -0,0 +1,143 @@
+use crate::app::state::{AuditAction, AuditEntry};
+use crate::app::update::icon_button_content;
+use crate::app::{App, Message};
+use crate::audit::{AUDITED_TABLES, entity_label};
+use iced::widget::container::bordered_box;
+use iced::widget::{Button, Column, Container, Row, Scrollable, Space, Text, TextInput, pick_list, text};
+use iced::{Alignment, Color, Length};
+use iced_font_awesome::fa_icon_solid;
+
+// Одна запись журнала: заголовок и построчный список изменённых полей
+fn entry_view<'a>(app: &'a App, entry: &'a AuditEntry) -> Container<'a, Message> {
+    let action_color = match entry.action {
+        AuditAction::Insert => Color::from_rgb8(0, 150, 0),
+        AuditAction::Update => Color::from_rgb8(0, 90, 200),
+        AuditAction::Delete => Color::from_rgb8(255, 0, 0),
+    };
+    let object = match entry.entity_id {
+        Some(entity_id) => format!("{} #{}", entity_label(&entry.entity), entity_id),
+        None => entity_label(&entry.entity).to_string(),
+    };
+    let actor = match &entry.actor_name {
+        Some(name) => Text::new(name),
+        None => Text::new("без входа").color(Color::from_rgb8(150, 150, 150)),
+    };
+
+    let mut column = Column::new().spacing(4).push(
+        Row::new()
+            .spacing(15)
+            .align_y(Alignment::Center)
+            .push(Text::new(&entry.created_at).width(Length::Fixed(160.0)))
+            .push(actor.width(Length::Fixed(200.0)))
+            .push(Text::new(entry.action.to_string()).color(action_color).width(Length::Fixed(100.0)))
+            .push(Text::new(object)),
+    );
+
+    let lines = entry.change_lines();
+    if lines.is_empty() {
+        column = column.push(Text::new("Без изменений значимых полей").size(13).color(Color::from_rgb8(150, 150, 150)));
+    }
+    for line in lines {
+        column = column.push(Text::new(line).size(13));
+    }
+
+    Container::new(column)
+        .padding(8)
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fill)
+}
+
+pub fn audit_log_screen(app: &App) -> Container<'_, Message> {
+    let mut main_column = Column::new().spacing(20).padding(20);
+
+    main_column = main_column.push(
+        Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(Text::new("Журнал изменений").size(30))
+            .push(Space::new().width(Length::Fill))
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("file-excel").style(move |_| text::base(app.theme.target())),
+                    "Выгрузить в Excel",
+                ))
+                .on_press_maybe((!app.audit_entries.is_empty()).then_some(Message::ExportAuditLog)),
+            ),
+    );
+
+    let entity_labels: Vec<&'static str> = AUDITED_TABLES.iter().map(|(_, _, label)| *label).collect();
+    let filter = &app.audit_filter;
+    main_column = main_column.push(
+        Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(
+                pick_list(app.audit_actors.clone(), filter.actor.clone(), Message::AuditActorSelected)
+                    .placeholder("Пользователь")
+                    .width(Length::Fixed(300.0)),
+            )
+            .push(
+                pick_list(entity_labels, filter.entity, Message::AuditEntitySelected)
+                    .placeholder("Объект")
+                    .width(Length::Fixed(220.0)),
+            )
+            .push(
+                pick_list(&AuditAction::ALL[..], filter.action, Message::AuditActionSelected)
+                    .placeholder("Действие")
+                    .width(Length::Fixed(140.0)),
+            )
+            .push(
+                TextInput::new("С (ГГГГ-ММ-ДД)", &filter.date_from)
+                    .on_input(Message::AuditDateFromChanged)
+                    .on_submit(Message::ApplyAuditFilter)
+                    .width(Length::Fixed(140.0)),
+            )
+            .push(
+                TextInput::new("По (ГГГГ-ММ-ДД)", &filter.date_to)
+                    .on_input(Message::AuditDateToChanged)
+                    .on_submit(Message::ApplyAuditFilter)
+                    .width(Length::Fixed(140.0)),
+            )
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("filter").style(move |_| text::base(app.theme.target())),
+                    "Применить",
+                ))
+                .on_press(Message::ApplyAuditFilter),
+            )
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("xmark").style(move |_| text::base(app.theme.target())),
+                    "Сбросить",
+                ))
+                .on_press(Message::ResetAuditFilter),
+            ),
+    );
+
+    if let Some(notice) = &app.audit_notice {
+        main_column = main_column.push(Text::new(notice).color(Color::from_rgb8(0, 150, 0)));
+    }
+    if let Some(error) = &app.audit_error {
+        main_column = main_column.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    let mut entries_column = Column::new().spacing(8);
+    if app.audit_entries.is_empty() {
+        entries_column = entries_column.push(
+            Text::new("Нет записей, подходящих под фильтр.").color(Color::from_rgb8(150, 150, 150)),
+        );
+    }
+    for entry in &app.audit_entries {
+        entries_column = entries_column.push(entry_view(app, entry));
+    }
+
+    main_column = main_column.push(
+        Scrollable::new(entries_column)
+            .width(Length::Fill)
+            .height(Length::Fill),
+    );
+
+    Container::new(main_column)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+}
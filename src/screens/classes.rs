@@ -1,7 +1,10 @@
 use iced::{Color, Alignment, Length, Theme, Element, Renderer};
-use iced::widget::{Column, Container, Row, Text, Button, PickList, Scrollable, text, Stack, Space, Checkbox};
+use iced::widget::{Column, Container, Row, Text, Button, PickList, Scrollable, text, Stack, Space, Checkbox, mouse_area, text_input};
 use iced::widget::container::{background, bordered_box};
+use iced_font_awesome::fa_icon_solid;
 use crate::app::{App, Message};
+use crate::app::update::icon_button_content;
+use crate::screens::timetable::week_calendar_modal;
 
 pub fn classes_screen(app: &App) -> Container<Message> {
     let mut main_column = Column::new()
@@ -28,6 +31,14 @@ pub fn classes_screen(app: &App) -> Container<Message> {
                 .align_y(Alignment::Center)
                 .push(Text::new("Мои группы:"))
                 .push(group_picklist)
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    Button::new(icon_button_content(
+                        fa_icon_solid("calendar-week").style(move |_| text::base(app.theme.target())),
+                        "Календарь"
+                    ))
+                        .on_press(Message::OpenWeekCalendar)
+                )
                 .width(Length::Fill)
         );
     }
@@ -36,8 +47,21 @@ pub fn classes_screen(app: &App) -> Container<Message> {
     // --- Список уроков с заданиями для выбранной группы ---
     if let Some(selected_group) = &app.selected_group_for_classes {
         main_column = main_column.push(
-            Text::new(format!("Занятия для группы: {}", selected_group.name))
-                .size(24)
+            Row::new()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(
+                    Text::new(format!("Занятия для группы: {}", selected_group.name))
+                        .size(24)
+                )
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    Button::new(icon_button_content(
+                        fa_icon_solid("clipboard-check").style(move |_| text::base(app.theme.target())),
+                        "Работы студентов"
+                    ))
+                        .on_press(Message::OpenGroupSubmissionsModal)
+                )
         );
 
         let mut lessons_with_assignments_list_column = Column::new()
@@ -211,7 +235,133 @@ pub fn classes_screen(app: &App) -> Container<Message> {
         );
     }
 
+    if app.show_group_submissions_modal {
+        ui_stack = ui_stack.push(group_submissions_modal(app));
+    }
+
+    if app.show_week_calendar_modal {
+        ui_stack = ui_stack.push(week_calendar_modal(app));
+    }
+
     Container::new(ui_stack)
         .center_x(Length::Fill)
         .center_y(Length::Fill)
+}
+
+// Модальное окно проверки сданных работ выбранной группы
+fn group_submissions_modal(app: &App) -> Element<'_, Message> {
+    let mut submissions_column = Column::new().spacing(10);
+
+    if app.group_submissions.is_empty() {
+        submissions_column = submissions_column.push(Text::new("Студенты группы ещё не сдавали работы."));
+    }
+
+    for submission in &app.group_submissions {
+        let status = match submission.score {
+            Some(score) => format!("Оценка: {} из 100", score),
+            None => "Не проверена".to_string(),
+        };
+
+        let mut card = Column::new()
+            .spacing(5)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(Text::new(format!("{} — урок {}: {}", submission.student_name, submission.lesson_number, submission.assignment_title)).size(18))
+                    .push(Space::new().width(Length::Fill))
+                    .push(Text::new(status))
+            )
+            .push(Text::new(format!("Сдано: {}", submission.submitted_at)).size(14));
+
+        if !submission.text.is_empty() {
+            card = card.push(Text::new(&submission.text).size(14));
+        }
+
+        for file in &submission.files {
+            card = card.push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("file").style(move |_| text::base(app.theme.target())),
+                    &file.file_name
+                ))
+                    .on_press(Message::OpenSubmissionFile(file.id))
+            );
+        }
+
+        if let Some(comment) = submission.comment.as_ref().filter(|c| !c.is_empty()) {
+            card = card.push(Text::new(format!("Комментарий: {}", comment)).size(14));
+        }
+
+        if app.grading_submission_id == Some(submission.id) {
+            card = card.push(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(
+                        text_input("Балл 0–100", &app.grading_score)
+                            .on_input(Message::GradingScoreChanged)
+                            .width(Length::Fixed(120.0))
+                    )
+                    .push(
+                        text_input("Комментарий", &app.grading_comment)
+                            .on_input(Message::GradingCommentChanged)
+                            .width(Length::Fill)
+                    )
+                    .push(
+                        Button::new(icon_button_content(
+                            fa_icon_solid("floppy-disk").style(move |_| text::base(app.theme.target())),
+                            "Сохранить"
+                        ))
+                            .on_press(Message::SaveSubmissionGrade)
+                    )
+            );
+        } else {
+            card = card.push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("marker").style(move |_| text::base(app.theme.target())),
+                    if submission.score.is_some() { "Изменить оценку" } else { "Оценить" }
+                ))
+                    .on_press(Message::StartGradingSubmission(submission.clone()))
+            );
+        }
+
+        submissions_column = submissions_column.push(
+            Container::new(card)
+                .padding(10)
+                .width(Length::Fill)
+                .style(move |_| bordered_box(app.theme.target()))
+        );
+    }
+
+    let mut modal = Column::new()
+        .spacing(15)
+        .padding(20)
+        .push(Text::new("Работы студентов").size(24));
+
+    if let Some(error) = &app.grading_error {
+        modal = modal.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    modal = modal
+        .push(Scrollable::new(submissions_column).height(Length::Fixed(450.0)))
+        .push(
+            Button::new(icon_button_content(
+                fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+                "Закрыть"
+            ))
+                .on_press(Message::CloseGroupSubmissionsModal)
+        );
+
+    Container::new(
+        mouse_area(
+            Container::new(modal)
+                .style(move |_| bordered_box(app.theme.target()))
+                .width(Length::Fixed(800.0))
+        )
+    )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(move |_| background(Color { r: 0.0, g: 0.0, b: 0.0, a: 0.7 }))
+        .into()
 }
\ No newline at end of file
@@ -8,12 +8,17 @@ use iced::widget::{button, row, text, PickList, rule, Space, TextEditor};
 use iced_font_awesome::fa_icon_solid;
 use rusqlite::Connection;
 use crate::app::{App, Message};
-use crate::app::state::{AssignmentType, Course, Level, TextInputOrEditorInput, PATH_TO_DB};
+use crate::app::state::{AssignmentType, Course, Level, TextInputOrEditorInput, WaitlistEntry, WaitlistStatus, PATH_TO_DB};
 use crate::app::update::icon_button_content;
 // Импортируем Lesson
 use crate::db;
 
 fn headrbar(course: Course, app: &App) -> Row<Message> { // Передаем тему
+    let in_queue = app
+        .waitlist
+        .iter()
+        .filter(|w| w.course_id == course.id && w.status.is_active())
+        .count();
     row![
         row![
             button(icon_button_content(
@@ -24,6 +29,10 @@ fn headrbar(course: Course, app: &App) -> Row<Message> { // Передаем т
                 fa_icon_solid("person-chalkboard").style(move |_| text::base(&app.theme.target())),
                 "Занятия"
             )).on_press(Message::ShowLessonsModal(course.clone())),
+            button(row![
+                fa_icon_solid("hourglass-half").style(move |_| text::base(app.theme.target())),
+                text(format!("Лист ожидания ({})", in_queue)),
+            ].spacing(5).align_y(Alignment::Center)).on_press(Message::OpenWaitlist(course.clone())),
         ].spacing(10),
         Space::new().width(Length::Fill),
         text(format!("{}", course.title)).size(26),
@@ -341,6 +350,10 @@ pub fn courses_screen(app: &App) -> Container<Message> {
         }
     }
 
+    if let Some(course) = &app.waitlist_course {
+        ui_stack = ui_stack.push(waitlist_modal(app, course));
+    }
+
     if app.show_add_course_modal {
         let is_editing = app.editing_course.is_some();
         let modal_title_text = if is_editing { "Редактировать курс" } else { "Новый курс" };
@@ -429,4 +442,94 @@ pub fn courses_screen(app: &App) -> Container<Message> {
     }
     Container::new(ui_stack)
         .center_x(Length::Fill).center_y(Length::Fill) // Центрируем Stack
-}
\ No newline at end of file
+}
+/// Модальное окно листа ожидания курса: очередь, предложенные места и история
+fn waitlist_modal<'a>(app: &'a App, course: &'a Course) -> Container<'a, Message> {
+    let mut entries_column = Column::new().spacing(5);
+    let entries: Vec<&WaitlistEntry> = app.waitlist.iter().filter(|w| w.course_id == course.id).collect();
+
+    if entries.is_empty() {
+        entries_column = entries_column.push(Text::new("В листе ожидания никого нет."));
+    }
+
+    for entry in entries {
+        let mut info = Column::new()
+            .spacing(2)
+            .push(Text::new(&entry.student_name).size(18))
+            .push(Text::new(format!(
+                "Записан: {}{}",
+                entry.created_at,
+                entry.group_name.as_ref().map_or(String::new(), |g| format!(", желаемая группа: {}", g))
+            )).size(14));
+
+        let status_text = match (entry.status, entry.position) {
+            (WaitlistStatus::Waiting, Some(position)) => format!("{}-й в очереди", position),
+            (WaitlistStatus::Offered, _) => format!(
+                "Предложено место до {}",
+                entry
+                    .offer_deadline(app.waitlist_offer_days)
+                    .map_or(String::new(), |d| d.format("%d.%m.%Y %H:%M").to_string())
+            ),
+            (status, _) => format!("{} {}", status, entry.closed_at.as_deref().unwrap_or("")),
+        };
+        let status_color = match entry.status {
+            WaitlistStatus::Offered | WaitlistStatus::Enrolled => Color::from_rgb8(0, 150, 0),
+            WaitlistStatus::Waiting => app.theme.target().palette().text,
+            _ => Color::from_rgb8(150, 150, 150),
+        };
+        info = info.push(Text::new(status_text).size(14).color(status_color));
+
+        let mut entry_row = Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(info)
+            .push(Space::new().width(Length::Fill));
+        if entry.status == WaitlistStatus::Offered {
+            entry_row = entry_row.push(button(icon_button_content(
+                fa_icon_solid("user-check").style(move |_| text::base(app.theme.target())),
+                "Зачислить"
+            )).on_press(Message::EnrollFromWaitlist(entry.clone())));
+        }
+        if entry.status.is_active() {
+            entry_row = entry_row.push(button(icon_button_content(
+                fa_icon_solid("xmark").style(move |_| text::base(app.theme.target())),
+                if entry.status == WaitlistStatus::Offered { "Отказ" } else { "Снять" }
+            )).on_press(Message::CloseWaitlistEntry(entry.id)));
+        }
+
+        entries_column = entries_column.push(
+            Container::new(entry_row)
+                .padding(5)
+                .width(Length::Fill)
+                .style(move |_| bordered_box(app.theme.target()))
+        );
+    }
+
+    let mut modal_col = Column::new()
+        .spacing(15)
+        .push(Text::new(format!("Лист ожидания: {}", course.title)).size(24))
+        .push(Text::new(format!(
+            "Свободных мест: {}. Освободившееся место предлагается первому в очереди, на ответ — {} дн.",
+            course.seats.unwrap_or(0),
+            app.waitlist_offer_days
+        )))
+        .push(Scrollable::new(entries_column).height(Length::Fill));
+
+    if let Some(error) = &app.waitlist_error {
+        modal_col = modal_col.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+    modal_col = modal_col.push(button(icon_button_content(
+        fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+        "Закрыть"
+    )).on_press(Message::CloseWaitlist));
+
+    let modal_container = Container::new(modal_col)
+        .style(move |_| bordered_box(app.theme.target()))
+        .padding(20)
+        .height(Length::Fixed(600.0))
+        .width(Length::Fixed(800.0));
+
+    Container::new(Container::new(modal_container).center(Length::Fill))
+        .width(Length::Fill).height(Length::Fill)
+        .style(move |_| background(Color { r: 0.0, g: 0.0, b: 0.0, a: 0.7 }))
+}
@@ -1,5 +1,7 @@
-use crate::app::state::{Course, DEFAULT_AVATAR, Group, GroupStatus, ReportType, UserInfo};
+use crate::app::state::{Course, DEFAULT_AVATAR, EnrollmentStatus, EnrollmentTerms, Group, GroupStatus, ReportType, UserInfo};
+use chrono::Local;
 use crate::app::update::icon_button_content;
+use crate::screens::timetable::{schedule_modal, week_calendar_modal};
 use crate::app::{App, Message};
 use iced::widget::container::{background, bordered_box};
 use iced::widget::image::Handle;
@@ -31,6 +33,11 @@ fn headerbar<'a>(group: &Group, app: &'a App) -> Row<'a, Message> {
                 group.id,
                 group.course_id.unwrap_or(0)
             )),
+            button(icon_button_content(
+                fa_icon_solid("calendar-days").style(move |_| text::base(app.theme.target())),
+                "Расписание"
+            ))
+            .on_press(Message::OpenScheduleModal(group.clone())),
         ]
         .spacing(10),
         Space::new().width(Length::Fill),
@@ -109,6 +116,13 @@ pub fn groups_screen(app: &App) -> Container<Message> {
                     "Отчёт",
                 ))
                 .on_press(Message::ToggleGroupReportModal),
+            )
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("calendar-week").style(move |_| text::base(app.theme.target())),
+                    "Календарь",
+                ))
+                .on_press(Message::OpenWeekCalendar),
             ),
     );
 
@@ -225,8 +239,11 @@ pub fn groups_screen(app: &App) -> Container<Message> {
             let scrollable_students = Scrollable::new(Container::new(students_list_col).padding(5))
                 .height(Length::FillPortion(1));
 
-            // Логика добавления студента
-            let add_student_row = Row::new()
+            let scrollable_enrollments = Scrollable::new(Container::new(enrollments_column(app)).padding(5))
+                .height(Length::FillPortion(1));
+
+            // Логика добавления студента: зачисление без оплаты (обычное, пробное, с отсрочкой) или заявка
+            let mut add_student_row = Row::new()
                 .spacing(10)
                 .align_y(Alignment::Center)
                 .push(Text::new("Добавить студента:").size(18))
@@ -238,15 +255,33 @@ pub fn groups_screen(app: &App) -> Container<Message> {
                     )
                     .placeholder("Выберите студента"),
                 )
+                .push(pick_list(
+                    &EnrollmentTerms::ALL[..],
+                    Some(app.new_enrollment_terms),
+                    Message::NewEnrollmentTermsSelected,
+                ));
+            if app.new_enrollment_terms.needs_due_date() {
+                add_student_row = add_student_row.push(
+                    TextInput::new("Оплатить до (ГГГГ-ММ-ДД)", &app.new_enrollment_due)
+                        .on_input(Message::NewEnrollmentDueChanged)
+                        .width(Length::Fixed(200.0)),
+                );
+            }
+            add_student_row = add_student_row
                 .push(
                     button(icon_button_content(
                         fa_icon_solid("plus").style(move |_| text::base(&app.theme.target())),
-                        "Добавить",
+                        "Зачислить",
                     ))
-                    .on_press(Message::AddStudentToGroup(
-                        app.selected_student_to_add.as_ref().map_or(0, |s| s.id), // ID выбранного студента
-                        current_group_id, // ID текущей группы
+                    .on_press(Message::EnrollStudent(EnrollmentStatus::Active))
+                    .width(Length::Shrink),
+                )
+                .push(
+                    button(icon_button_content(
+                        fa_icon_solid("file-signature").style(move |_| text::base(app.theme.target())),
+                        "Заявка",
                     ))
+                    .on_press(Message::EnrollStudent(EnrollmentStatus::Applied))
                     .width(Length::Shrink),
                 );
 
@@ -255,6 +290,8 @@ pub fn groups_screen(app: &App) -> Container<Message> {
                 .align_x(Alignment::Start)
                 .push(Text::new(modal_title_text).size(22))
                 .push(scrollable_students)
+                .push(Text::new("Зачисления и заявки").size(18))
+                .push(scrollable_enrollments)
                 .push(rule::horizontal(10.0))
                 .push(add_student_row) // Добавляем строку для добавления студента
                 .push(
@@ -272,8 +309,8 @@ pub fn groups_screen(app: &App) -> Container<Message> {
             let modal_container = Container::new(modal_content)
                 .style(move |_| bordered_box(&app.theme.target()))
                 .padding(20)
-                .height(Length::Fixed(500.0))
-                .width(Length::Fixed(600.0));
+                .height(Length::Fixed(750.0))
+                .width(Length::Fixed(1000.0));
 
             let modal_overlay = Container::new(mouse_area(
                 Container::new(modal_container).center(Length::Fill),
@@ -661,7 +698,112 @@ pub fn groups_screen(app: &App) -> Container<Message> {
         ui_stack = ui_stack.push(modal_overlay);
     }
 
+    if app.show_schedule_modal {
+        ui_stack = ui_stack.push(schedule_modal(app));
+    }
+
+    if app.show_week_calendar_modal {
+        ui_stack = ui_stack.push(week_calendar_modal(app));
+    }
+
     Container::new(ui_stack)
         .center_x(Length::Fill)
         .center_y(Length::Fill)
 }
+
+// Зачисления группы: этап, условия оплаты, даты и доступные переходы
+fn enrollments_column(app: &App) -> Column<'_, Message> {
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    let mut column = Column::new().spacing(5);
+
+    if app.group_enrollments.is_empty() {
+        return column.push(Text::new("Зачислений пока нет.").size(16));
+    }
+
+    for enrollment in &app.group_enrollments {
+        let mut terms = enrollment.terms.to_string();
+        if let Some(due) = &enrollment.payment_due {
+            terms = format!("{}, оплатить до {}", terms, due);
+        }
+        let mut dates = format!("с {}", enrollment.enrolled_at.as_deref().unwrap_or("—"));
+        if let Some(from) = &enrollment.transferred_from {
+            dates = format!("{} (переведён из «{}»)", dates, from);
+        }
+        if let Some(ended_at) = &enrollment.ended_at {
+            dates = format!("{} по {}", dates, ended_at);
+            if let Some(to) = &enrollment.transferred_to {
+                dates = format!("{}, переведён в «{}»", dates, to);
+            }
+        } else if let Some(changed_at) = &enrollment.status_changed_at {
+            dates = format!("{}, этап с {}", dates, changed_at);
+        }
+
+        let status_color = if enrollment.status.holds_seat() {
+            app.theme.target().palette().text
+        } else {
+            Color::from_rgb8(150, 150, 150)
+        };
+        let payment_text = Text::new(format!("оплачено {:.2} из {:.2} ₽", enrollment.paid, enrollment.charged))
+            .size(14)
+            .color(if enrollment.is_payment_overdue(&today) {
+                Color::from_rgb8(255, 0, 0)
+            } else {
+                status_color
+            });
+
+        let mut enrollment_row = Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(
+                Column::new()
+                    .spacing(2)
+                    .push(Text::new(format!("{} — {}", enrollment.student_name, enrollment.status)).color(status_color))
+                    .push(Text::new(format!("{}; {}", terms, dates)).size(14).color(status_color))
+                    .push(payment_text),
+            )
+            .push(Space::new().width(Length::Fill));
+        for next in enrollment.status.next_statuses() {
+            enrollment_row = enrollment_row.push(
+                button(text(next.action_label())).on_press(Message::ChangeEnrollmentStatus(enrollment.id, *next)),
+            );
+        }
+        if enrollment.status.holds_seat() && app.transfer_enrollment != Some(enrollment.id) {
+            enrollment_row = enrollment_row.push(
+                button(text(EnrollmentStatus::Transferred.action_label()))
+                    .on_press(Message::StartTransfer(enrollment.id)),
+            );
+        }
+
+        let mut enrollment_column = Column::new().spacing(5).push(enrollment_row);
+        if app.transfer_enrollment == Some(enrollment.id) {
+            // Переводить можно в любую другую активную группу, в том числе другого курса
+            let target_groups: Vec<Group> = app
+                .all_groups
+                .iter()
+                .filter(|g| g.status == GroupStatus::Active && Some(g.id) != app.current_manage_students_group_id)
+                .cloned()
+                .collect();
+            enrollment_column = enrollment_column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(Text::new("Перевести в группу:"))
+                    .push(
+                        pick_list(target_groups, app.transfer_target.clone(), Message::TransferTargetSelected)
+                            .placeholder("Выберите группу")
+                            .width(Length::Fill),
+                    )
+                    .push(button(text("Перевести")).on_press(Message::ConfirmTransfer))
+                    .push(button(text("Отмена")).on_press(Message::CancelTransfer)),
+            );
+        }
+
+        column = column.push(
+            Container::new(enrollment_column)
+                .padding(5)
+                .style(move |_| bordered_box(app.theme.target()))
+                .width(Length::Fill),
+        );
+    }
+    column
+}
@@ -23,6 +23,9 @@ pub fn login_screen(app: &App) -> Container<Message> {
             "Войти"
         )).on_press(Message::LoginPressed).padding(10),
         text(&app.error_message).size(20),
+        button(text("Забыли пароль?").size(14))
+            .style(button::text)
+            .on_press(Message::SwitchToPasswordRecovery),
         Space::new().height(Length::Fill),
         button(icon_button_content(
             fa_icon_solid("id-card").style(move |_| text::base(&app.theme.target())),
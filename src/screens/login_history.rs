@@ -0,0 +1,121 @@
+use crate::app::state::{LockedAccount, LoginAttempt};
+use crate::app::update::icon_button_content;
+use crate::app::{App, Message};
+use crate::db::{LOCKOUT_MINUTES, MAX_FAILED_LOGINS};
+use iced::widget::container::bordered_box;
+use iced::widget::{Button, Column, Container, Row, Scrollable, Space, Text, TextInput, checkbox, text};
+use iced::{Alignment, Color, Length};
+use iced_font_awesome::fa_icon_solid;
+
+// Строка истории: время, email, пользователь и результат попытки
+fn attempt_view(attempt: &LoginAttempt) -> Row<'_, Message> {
+    let outcome_color = if attempt.outcome.is_failure() {
+        Color::from_rgb8(255, 0, 0)
+    } else {
+        Color::from_rgb8(0, 150, 0)
+    };
+    let user = match &attempt.user_name {
+        Some(name) => Text::new(name),
+        None => Text::new("нет такого пользователя").color(Color::from_rgb8(150, 150, 150)),
+    };
+
+    Row::new()
+        .spacing(15)
+        .align_y(Alignment::Center)
+        .push(Text::new(&attempt.attempted_at).width(Length::Fixed(160.0)))
+        .push(Text::new(&attempt.email).width(Length::Fixed(240.0)))
+        .push(user.width(Length::Fixed(260.0)))
+        .push(Text::new(attempt.outcome.to_string()).color(outcome_color))
+}
+
+fn locked_account_view<'a>(app: &'a App, account: &'a LockedAccount) -> Container<'a, Message> {
+    Container::new(
+        Row::new()
+            .spacing(15)
+            .align_y(Alignment::Center)
+            .push(Text::new(format!("{} ({})", account.name, account.email)).width(Length::Fill))
+            .push(Text::new(format!(
+                "неудачных попыток: {}, заблокирован до {}",
+                account.failed_count, account.locked_until
+            )))
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("unlock").style(move |_| text::base(app.theme.target())),
+                    "Разблокировать",
+                ))
+                .on_press(Message::UnlockAccount(account.user_id)),
+            ),
+    )
+    .padding(8)
+    .style(move |_| bordered_box(app.theme.target()))
+    .width(Length::Fill)
+}
+
+pub fn login_history_screen(app: &App) -> Container<'_, Message> {
+    let mut main_column = Column::new().spacing(20).padding(20);
+
+    main_column = main_column.push(Text::new("История входов").size(30)).push(
+        Text::new(format!(
+            "После {} неудачных попыток подряд вход блокируется на {} мин; до этого каждая следующая попытка возможна всё позже.",
+            MAX_FAILED_LOGINS, LOCKOUT_MINUTES
+        ))
+        .size(14)
+        .color(Color::from_rgb8(150, 150, 150)),
+    );
+
+    if let Some(error) = &app.login_history_error {
+        main_column = main_column.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    if !app.locked_accounts.is_empty() {
+        let mut locked_column = Column::new().spacing(8).push(Text::new("Заблокированные учётные записи").size(22));
+        for account in &app.locked_accounts {
+            locked_column = locked_column.push(locked_account_view(app, account));
+        }
+        main_column = main_column.push(locked_column);
+    }
+
+    main_column = main_column.push(
+        Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(
+                TextInput::new("Email", &app.login_history_email_filter)
+                    .on_input(Message::LoginHistoryEmailChanged)
+                    .on_submit(Message::ApplyLoginHistoryFilter)
+                    .width(Length::Fixed(260.0)),
+            )
+            .push(
+                checkbox(app.login_history_failures_only)
+                    .label("Только неудачные")
+                    .on_toggle(Message::LoginHistoryFailuresOnlyToggled),
+            )
+            .push(Space::new().width(Length::Fill))
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("rotate").style(move |_| text::base(app.theme.target())),
+                    "Обновить",
+                ))
+                .on_press(Message::ApplyLoginHistoryFilter),
+            ),
+    );
+
+    let mut attempts_column = Column::new().spacing(6);
+    if app.login_history.is_empty() {
+        attempts_column = attempts_column
+            .push(Text::new("Нет попыток входа, подходящих под фильтр.").color(Color::from_rgb8(150, 150, 150)));
+    }
+    for attempt in &app.login_history {
+        attempts_column = attempts_column.push(attempt_view(attempt));
+    }
+
+    main_column = main_column.push(
+        Scrollable::new(attempts_column)
+            .width(Length::Fill)
+            .height(Length::Fill),
+    );
+
+    Container::new(main_column)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+}
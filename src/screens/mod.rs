@@ -9,6 +9,13 @@ pub mod groups;
 pub mod classes;
 pub mod payment;
 pub mod certificates;
+pub mod student_progress;
+pub mod timetable;
+pub mod payroll;
+pub mod audit_log;
+pub mod password;
+pub mod login_history;
+pub mod two_factor;
 
 pub use login::login_screen;
 pub use register::register_screen;
@@ -20,4 +27,10 @@ pub use user_list::user_list_screen;
 pub use groups::groups_screen;
 pub use classes::classes_screen;
 pub use payment::payment_screen;
-pub use certificates::certificates_screen;
\ No newline at end of file
+pub use certificates::certificates_screen;
+pub use student_progress::student_progress_screen;
+pub use payroll::payroll_screen;
+pub use audit_log::audit_log_screen;
+pub use password::{change_password_screen, password_recovery_screen};
+pub use login_history::login_history_screen;
+pub use two_factor::{two_factor_screen, two_factor_setup_screen};
\ No newline at end of file
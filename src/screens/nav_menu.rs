@@ -3,6 +3,7 @@ use iced::widget::{text, Space, Container};
 use iced_font_awesome::{fa_icon_solid};
 use crate::app::{Message, App};
 use crate::app::update::icon_button_content;
+use crate::app::state::Role;
 
 pub fn nav_menu(app: &App) -> Container<Message> {
     let content_for_admin = column![
@@ -18,6 +19,18 @@ pub fn nav_menu(app: &App) -> Container<Message> {
             fa_icon_solid("file-invoice-dollar").style(move |_| text::base(&app.theme.target())),
             "Платежи"
         )).on_press(Message::GoToPayment).width(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("money-check-dollar").style(move |_| text::base(app.theme.target())),
+            "Зарплата"
+        )).on_press(Message::GoToPayroll).width(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("clock-rotate-left").style(move |_| text::base(app.theme.target())),
+            "Журнал"
+        )).on_press(Message::GoToAuditLog).width(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("door-open").style(move |_| text::base(app.theme.target())),
+            "История входов"
+        )).on_press(Message::GoToLoginHistory).width(Length::Fill),
         button(icon_button_content(
             fa_icon_solid("user").style(move |_| text::base(&app.theme.target())),
             "Пользователи"
@@ -80,26 +93,79 @@ pub fn nav_menu(app: &App) -> Container<Message> {
     ]
         .spacing(10);
     
-    match app.current_user.as_ref().unwrap().user_type.as_str() {
-        "admin" => {
+    let content_for_student = column![
+        button(icon_button_content(
+            fa_icon_solid("address-card").style(move |_| text::base(app.theme.target())),
+            "Профиль"
+        )).on_press(Message::GoToProfile).width(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("book-open").style(move |_| text::base(app.theme.target())),
+            "Обучение"
+        )).on_press(Message::GoToStudentProgress).width(Length::Fill),
+        Space::new().height(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("gear").style(move |_| text::base(app.theme.target())),
+            "Настройки"
+        )).on_press(Message::GoToSettings).width(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("arrow-right-from-bracket").style(move |_| text::base(app.theme.target())),
+            "Выход"
+        )).on_press(Message::Logout).width(Length::Fill),
+    ]
+        .spacing(10);
+
+    let content_for_parent = column![
+        button(icon_button_content(
+            fa_icon_solid("address-card").style(move |_| text::base(app.theme.target())),
+            "Профиль"
+        )).on_press(Message::GoToProfile).width(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("children").style(move |_| text::base(app.theme.target())),
+            "Дети"
+        )).on_press(Message::GoToStudentProgress).width(Length::Fill),
+        Space::new().height(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("gear").style(move |_| text::base(app.theme.target())),
+            "Настройки"
+        )).on_press(Message::GoToSettings).width(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("arrow-right-from-bracket").style(move |_| text::base(app.theme.target())),
+            "Выход"
+        )).on_press(Message::Logout).width(Length::Fill),
+    ]
+        .spacing(10);
+
+    match app.current_user.as_ref().unwrap().user_type {
+        Role::Admin => {
             Container::new(content_for_admin)
                 .width(Length::Fill)
                 .height(Length::Fill)
                 .padding(10)
         }
-        "unconfirmed" => {
+        Role::Unconfirmed => {
             Container::new(content_for_unconfirmed)
                 .width(Length::Fill)
                 .height(Length::Fill)
                 .padding(10)
         }
-        "teacher" => {
+        Role::Teacher => {
             Container::new(content_for_teacher)
                 .width(Length::Fill)
                 .height(Length::Fill)
                 .padding(10)
         }
-        _ => {panic!()}
+        Role::Student => {
+            Container::new(content_for_student)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(10)
+        }
+        Role::Parent => {
+            Container::new(content_for_parent)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(10)
+        }
     }
     
 }
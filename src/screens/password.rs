@@ -0,0 +1,146 @@
+use crate::app::update::icon_button_content;
+use crate::app::{App, Message};
+use crate::password::MIN_PASSWORD_LENGTH;
+use iced::widget::{Column, Row, Space, Text, button, column, text, text_input};
+use iced::{Center, Color, Length, widget::Container};
+use iced_font_awesome::fa_icon_solid;
+
+/// Поля смены пароля. Кнопка «Отмена» есть, только если смена не обязательна.
+pub fn change_password_form(app: &App) -> Column<'_, Message> {
+    let mut form = Column::new()
+        .spacing(10)
+        .push(
+            text_input("Текущий пароль", &app.current_password_input)
+                .on_input(Message::CurrentPasswordInputChanged)
+                .secure(true)
+                .padding(10),
+        )
+        .push(
+            text_input("Новый пароль", &app.new_password_input)
+                .on_input(Message::NewPasswordInputChanged)
+                .secure(true)
+                .padding(10),
+        )
+        .push(
+            text_input("Повторите новый пароль", &app.new_password_repeat_input)
+                .on_input(Message::NewPasswordRepeatInputChanged)
+                .on_submit(Message::SubmitPasswordChange)
+                .secure(true)
+                .padding(10),
+        )
+        .push(
+            Text::new(format!(
+                "Не короче {} символов, хотя бы одна заглавная латинская буква и одна цифра.",
+                MIN_PASSWORD_LENGTH
+            ))
+            .size(13)
+            .color(Color::from_rgb8(150, 150, 150)),
+        );
+
+    let mut buttons = Row::new().spacing(10).push(
+        button(icon_button_content(
+            fa_icon_solid("key").style(move |_| text::base(app.theme.target())),
+            "Сменить пароль",
+        ))
+        .on_press(Message::SubmitPasswordChange),
+    );
+    if !app.password_change_required {
+        buttons = buttons.push(
+            button(icon_button_content(
+                fa_icon_solid("ban").style(move |_| text::base(app.theme.target())),
+                "Отмена",
+            ))
+            .on_press(Message::CloseChangePassword),
+        );
+    }
+    form = form.push(buttons);
+
+    if let Some(error) = &app.change_password_error {
+        form = form.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+    form
+}
+
+/// Экран после входа по временному паролю: пока пароль не сменён, остальное приложение недоступно.
+pub fn change_password_screen(app: &App) -> Container<'_, Message> {
+    let content = column![
+        Space::new().height(Length::Fill),
+        text("Смена временного пароля").size(30),
+        text("Пароль был сброшен администратором. Задайте новый пароль, чтобы продолжить."),
+        Container::new(change_password_form(app)).width(Length::Fixed(400.0)),
+        Space::new().height(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("right-from-bracket").style(move |_| text::base(app.theme.target())),
+            "Выйти"
+        ))
+        .on_press(Message::Logout)
+        .padding(10),
+    ]
+    .spacing(15)
+    .width(Length::Fill)
+    .align_x(Center);
+
+    Container::new(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(40)
+}
+
+/// Восстановление доступа по коду восстановления — для случая, когда сбросить пароль некому.
+pub fn password_recovery_screen(app: &App) -> Container<'_, Message> {
+    let mut content = column![
+        Space::new().height(Length::Fill),
+        text("Восстановление пароля").size(30),
+        text("Введите один из кодов восстановления, выданных в профиле.").size(14),
+        text_input("Почта", &app.user_email)
+            .on_input(Message::EmailChanged)
+            .padding(10)
+            .size(18)
+            .width(Length::Fixed(350.0)),
+        text_input("Код восстановления", &app.recovery_code_input)
+            .on_input(Message::RecoveryCodeChanged)
+            .padding(10)
+            .size(18)
+            .width(Length::Fixed(350.0)),
+        text_input("Новый пароль", &app.user_password)
+            .on_input(Message::PasswordChanged)
+            .padding(10)
+            .size(18)
+            .secure(true)
+            .width(Length::Fixed(350.0)),
+        text_input("Повторите пароль", &app.user_password_repeat)
+            .on_input(Message::PasswordRepeatChanged)
+            .on_submit(Message::SubmitPasswordRecovery)
+            .padding(10)
+            .size(18)
+            .secure(true)
+            .width(Length::Fixed(350.0)),
+        button(icon_button_content(
+            fa_icon_solid("unlock").style(move |_| text::base(app.theme.target())),
+            "Сменить пароль"
+        ))
+        .on_press(Message::SubmitPasswordRecovery)
+        .padding(10),
+    ]
+    .spacing(15)
+    .width(Length::Fill)
+    .align_x(Center);
+
+    if let Some(error) = &app.recovery_error {
+        content = content.push(text(error).size(18).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    content = content.push(Space::new().height(Length::Fill)).push(
+        button(icon_button_content(
+            fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+            "Назад ко входу",
+        ))
+        .on_press(Message::SwitchToLogin)
+        .padding(10),
+    );
+
+    Container::new(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(40)
+}
@@ -1,16 +1,18 @@
 use crate::app::state::{
-    CoursePickListItem, DatePickerOpen, GroupPickListItem, ReportType, StudentPickListItem,
+    CoursePickListItem, DatePickerOpen, GroupPickListItem, INSTALLMENT_OPTIONS, PAYMENT_TYPES,
+    MatchStatus, PaymentDocumentKind, ReportType, ReversalKind, StudentPickListItem, WaitlistStatus,
 };
 use crate::app::update::icon_button_content;
 use crate::app::{App, Message};
 use iced::widget::container::{background, bordered_box};
-use iced::widget::{PickList, button, row, text};
+use iced::widget::{PickList, TextInput, button, checkbox, row, text};
 use iced::{
     Alignment, Color, Element, Length,
     widget::{
         Button, Column, Container, Row, Scrollable, Space, Stack, Text, mouse_area, pick_list,
     },
 };
+use chrono::Local;
 use iced_aw::date_picker;
 use iced_font_awesome::fa_icon_solid;
 
@@ -29,26 +31,106 @@ pub fn payment_screen(app: &App) -> Container<Message> {
     .on_press(Message::ToggleReportModal)
     .padding(10);
 
+    let balances_button = Button::new(icon_button_content(
+        fa_icon_solid(if app.show_balances { "receipt" } else { "scale-balanced" })
+            .style(move |_| text::base(app.theme.target())),
+        if app.show_balances { "Платежи" } else { "Балансы" },
+    ))
+    .on_press(Message::ToggleBalances)
+    .padding(10);
+
+    let promo_button = Button::new(icon_button_content(
+        fa_icon_solid("ticket").style(move |_| text::base(app.theme.target())),
+        "Промокоды",
+    ))
+    .on_press(Message::TogglePromoCodes)
+    .padding(10);
+
+    let import_button = Button::new(icon_button_content(
+        fa_icon_solid("file-import").style(move |_| text::base(app.theme.target())),
+        "Импорт выписки",
+    ))
+    .on_press(Message::ImportBankStatement)
+    .padding(10);
+
     let header_section = Column::new()
         .spacing(15)
-        .push(Text::new("Список платежей").size(30))
-        .push(row![add_button, report_button,].spacing(10))
+        .push(Text::new(if app.show_balances { "Балансы студентов" } else { "Список платежей" }).size(30))
+        .push(row![add_button, report_button, balances_button, promo_button, import_button].spacing(10))
         .push(Space::new().height(10));
 
     let mut payment_cards = Column::new().spacing(15);
 
+    if let Some(error) = &app.payment_error {
+        payment_cards = payment_cards.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+    if let Some(notice) = &app.payment_notice {
+        payment_cards = payment_cards.push(Text::new(notice).color(Color::from_rgb8(0, 150, 0)));
+    }
+
     for payment in &app.payments {
-        let header = Row::new()
-            .push(Text::new(format!("Платёж #{}", payment.id)).size(20))
+        let title = match payment.reverses_payment_id {
+            Some(original_id) => format!(
+                "{} #{} к платежу #{}",
+                payment.payment_type, payment.id, original_id
+            ),
+            None => format!("Платёж #{}", payment.id),
+        };
+
+        let mut header = Row::new()
+            .push(Text::new(title).size(20))
             .push(Space::new().width(Length::Fill))
-            .push(
-                button(fa_icon_solid("xmark").style(move |_| text::base(&app.theme.target())))
-                    .on_press(Message::DeletePayment(payment.id)),
-            )
             .width(Length::Fill)
+            .align_y(Alignment::Center)
             .spacing(10);
 
-        let info = Column::new()
+        // Возвраты и корректировки оформляются только к исходным платежам
+        if !payment.is_reversal() {
+            header = header
+                .push(
+                    button(icon_button_content(
+                        fa_icon_solid("file-invoice").style(move |_| text::base(app.theme.target())),
+                        "Счёт",
+                    ))
+                    .on_press(Message::GeneratePaymentDocument(
+                        payment.clone(),
+                        PaymentDocumentKind::Invoice,
+                    )),
+                )
+                .push(
+                    button(icon_button_content(
+                        fa_icon_solid("receipt").style(move |_| text::base(app.theme.target())),
+                        "Квитанция",
+                    ))
+                    .on_press(Message::GeneratePaymentDocument(
+                        payment.clone(),
+                        PaymentDocumentKind::Receipt,
+                    )),
+                )
+                .push(
+                    button(icon_button_content(
+                        fa_icon_solid("rotate-left").style(move |_| text::base(app.theme.target())),
+                        "Возврат",
+                    ))
+                    .on_press(Message::OpenPaymentReversal(payment.clone(), ReversalKind::Refund)),
+                )
+                .push(
+                    button(icon_button_content(
+                        fa_icon_solid("pen").style(move |_| text::base(app.theme.target())),
+                        "Корректировка",
+                    ))
+                    .on_press(Message::OpenPaymentReversal(
+                        payment.clone(),
+                        ReversalKind::Correction,
+                    )),
+                );
+        }
+        header = header.push(
+            button(fa_icon_solid("xmark").style(move |_| text::base(&app.theme.target())))
+                .on_press(Message::DeletePayment(payment.id)),
+        );
+
+        let mut info = Column::new()
             .spacing(5)
             .push(Text::new(format!("Студент: {}", payment.student_name)))
             .push(Text::new(format!("Курс: {}", payment.course_title)))
@@ -57,6 +139,43 @@ pub fn payment_screen(app: &App) -> Container<Message> {
             .push(Text::new(format!("Сумма: {:.2} ₽", payment.amount)))
             .push(Text::new(format!("Тип: {}", payment.payment_type)));
 
+        if payment.discount > 0.0 {
+            info = info.push(Text::new(format!(
+                "Скидка: {:.2} ₽ ({})",
+                payment.discount,
+                payment.discount_note.as_deref().unwrap_or("")
+            )));
+        }
+
+        let documents: Vec<String> = app
+            .payment_documents
+            .iter()
+            .filter(|d| d.payment_id == payment.id)
+            .map(|d| format!("{} № {} от {}", d.kind.title(), d.display_number(), d.issue_date()))
+            .collect();
+        if !documents.is_empty() {
+            info = info.push(Text::new(format!("Документы: {}", documents.join("; "))));
+        }
+
+        if payment.is_reversal() {
+            if let Some(reason) = &payment.reason {
+                info = info.push(Text::new(format!("Причина: {}", reason)));
+            }
+            info = info.push(Text::new(format!(
+                "Оформил: {}, {}",
+                payment.operator_name.as_deref().unwrap_or("неизвестно"),
+                payment.created_at.as_deref().unwrap_or(&payment.date)
+            )));
+        } else {
+            let net = payment.net_amount(&app.payments);
+            if (net - payment.amount).abs() > 0.005 {
+                info = info.push(
+                    Text::new(format!("С учётом возвратов и корректировок: {:.2} ₽", net))
+                        .color(Color::from_rgb8(255, 0, 0)),
+                );
+            }
+        }
+
         let payment_card = Container::new(
             Column::new()
                 .push(
@@ -73,7 +192,11 @@ pub fn payment_screen(app: &App) -> Container<Message> {
         payment_cards = payment_cards.push(payment_card);
     }
 
-    let scrollable_list: Element<_> = Scrollable::new(payment_cards).height(Length::Fill).into();
+    let scrollable_list: Element<_> = if app.show_balances {
+        balances_list(app)
+    } else {
+        Scrollable::new(payment_cards).height(Length::Fill).into()
+    };
 
     let base_ui = Container::new(
         Column::new()
@@ -280,12 +403,85 @@ pub fn payment_screen(app: &App) -> Container<Message> {
         )
         .placeholder("Выберите тип платежа");
 
-        let amount = app.new_payment_amount.unwrap_or(0.0);
-        let amount_text = Text::new(format!("Сумма: {:.2} ₽", amount));
+        let installments_pick_list = pick_list(
+            &INSTALLMENT_OPTIONS[..],
+            Some(app.new_payment_installments),
+            Message::NewPaymentFormInstallmentsSelected,
+        )
+        .placeholder("Число платежей");
+
+        let discounts = app.new_payment_discounts();
+        let discount: f64 = discounts.iter().map(|d| d.amount).sum();
+        let amount = (app.new_payment_amount.unwrap_or(0.0) - discount).max(0.0);
+        let parts = app.new_payment_installments.max(1);
+        let amount_text = if parts > 1 {
+            Text::new(format!(
+                "Стоимость: {:.2} ₽, сейчас: {:.2} ₽ (1 из {}, далее ежемесячно)",
+                amount,
+                (amount / parts as f64 * 100.0).round() / 100.0,
+                parts
+            ))
+        } else {
+            Text::new(format!("Сумма: {:.2} ₽", amount))
+        };
+
+        let promo_row: Element<Message> = match &app.new_payment_promo {
+            Some(promo) => Row::new()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(Text::new(format!("Промокод {} ({})", promo.code, promo.value_display())))
+                .push(
+                    Button::new(fa_icon_solid("xmark").style(move |_| text::base(app.theme.target())))
+                        .on_press(Message::ClearPromoCode),
+                )
+                .into(),
+            None => Row::new()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(
+                    TextInput::new("Промокод", &app.new_payment_promo_input)
+                        .on_input(Message::NewPaymentPromoInputChanged)
+                        .on_submit(Message::ApplyPromoCode),
+                )
+                .push(
+                    Button::new(icon_button_content(
+                        fa_icon_solid("ticket").style(move |_| text::base(app.theme.target())),
+                        "Применить",
+                    ))
+                    .on_press(Message::ApplyPromoCode),
+                )
+                .into(),
+        };
+
+        let manual_discount_row = Row::new()
+            .spacing(10)
+            .push(
+                TextInput::new("Скидка, ₽", &app.new_payment_manual_discount)
+                    .on_input(Message::NewPaymentManualDiscountChanged)
+                    .width(Length::Fixed(120.0)),
+            )
+            .push(
+                TextInput::new("Причина скидки", &app.new_payment_manual_reason)
+                    .on_input(Message::NewPaymentManualReasonChanged),
+            );
+
+        let mut discounts_column = Column::new().spacing(3);
+        for line in &discounts {
+            discounts_column = discounts_column.push(
+                Text::new(format!("{}: −{:.2} ₽", line.label, line.amount))
+                    .color(Color::from_rgb8(0, 150, 0)),
+            );
+        }
+        if let (Some(sibling), 0) = (&app.new_payment_sibling, app.sibling_discount_percent) {
+            discounts_column = discounts_column.push(Text::new(format!(
+                "Учится брат/сестра ({}), но скидка для братьев и сестёр отключена в настройках",
+                sibling
+            )));
+        }
         let date_text = Text::new(format!("Дата: {}", chrono::Local::now().format("%Y-%m-%d")));
 
         // 1. Содержимое формы (Column)
-        let modal_form_content = Column::new()
+        let mut modal_form_content = Column::new()
             .spacing(10)
             .padding(20)
             .align_x(Alignment::Center)
@@ -294,8 +490,58 @@ pub fn payment_screen(app: &App) -> Container<Message> {
             .push(course_pick_list)
             .push(group_pick_list_widget)
             .push(type_pick_list)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(Text::new("Оплата частями:"))
+                    .push(installments_pick_list),
+            )
+            .push(promo_row)
+            .push(manual_discount_row)
+            .push(discounts_column)
             .push(amount_text)
-            .push(date_text)
+            .push(date_text);
+
+        // Места курса, не занятые студентами и не обещанные листу ожидания
+        let seat_state = app.new_payment_course.as_ref().and_then(|course| {
+            let seats = app.courses_with_seats.iter().find(|c| c.id == course.id)?.seats?;
+            let student_id = app.new_payment_student.as_ref().map(|s| s.id);
+            let offers = app
+                .waitlist
+                .iter()
+                .filter(|w| w.course_id == course.id && w.status == WaitlistStatus::Offered);
+            let has_offer = offers.clone().any(|w| Some(w.student_id) == student_id);
+            Some((seats - offers.count() as i32, has_offer))
+        });
+        let course_is_full = matches!(seat_state, Some((free, false)) if free <= 0);
+
+        if let Some((_, true)) = seat_state {
+            modal_form_content = modal_form_content.push(
+                Text::new("Студенту предложено место из листа ожидания")
+                    .color(Color::from_rgb8(0, 150, 0)),
+            );
+        } else if course_is_full {
+            modal_form_content = modal_form_content
+                .push(
+                    Text::new("Свободных мест на курсе нет — студента можно записать в лист ожидания")
+                        .color(Color::from_rgb8(255, 0, 0)),
+                )
+                .push(
+                    Button::new(icon_button_content(
+                        fa_icon_solid("hourglass-half").style(move |_| text::base(app.theme.target())),
+                        "В лист ожидания",
+                    ))
+                    .on_press(Message::AddToWaitlist),
+                );
+        }
+
+        if let Some(error) = &app.new_payment_error {
+            modal_form_content =
+                modal_form_content.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+        }
+
+        modal_form_content = modal_form_content
             .push(
                 Row::new()
                     .spacing(10)
@@ -304,7 +550,7 @@ pub fn payment_screen(app: &App) -> Container<Message> {
                             fa_icon_solid("plus").style(move |_| text::base(&app.theme.target())),
                             "Добавить",
                         ))
-                        .on_press(Message::AddPaymentConfirmed),
+                        .on_press_maybe((!course_is_full).then_some(Message::AddPaymentConfirmed)),
                     )
                     .push(
                         Button::new(icon_button_content(
@@ -338,8 +584,658 @@ pub fn payment_screen(app: &App) -> Container<Message> {
         ui_stack = ui_stack.push(modal_overlay_element);
     }
 
+    if app.installment_balance.is_some() {
+        ui_stack = ui_stack.push(installment_schedule_modal(app));
+    }
+
+    if app.settle_balance.is_some() {
+        ui_stack = ui_stack.push(settle_balance_modal(app));
+    }
+
+    if app.reversal_payment.is_some() {
+        ui_stack = ui_stack.push(payment_reversal_modal(app));
+    }
+
+    if app.show_promo_codes {
+        ui_stack = ui_stack.push(promo_codes_modal(app));
+    }
+
+    if app.show_bank_import {
+        ui_stack = ui_stack.push(bank_import_modal(app));
+    }
+
     Container::new(ui_stack)
         .width(Length::Fill)
         .height(Length::Fill)
         .into()
 }
+
+// Начислено / оплачено / остаток по каждой группе студента, должники выделены
+fn balances_list(app: &App) -> Element<'_, Message> {
+    let debtors: Vec<_> = app.balances.iter().filter(|b| b.is_debtor()).collect();
+    let total_debt: f64 = debtors.iter().map(|b| b.debt_amount()).sum();
+
+    let summary = Row::new()
+        .spacing(20)
+        .align_y(Alignment::Center)
+        .push(Text::new(format!(
+            "Должников: {}, общая задолженность: {:.2} ₽",
+            debtors.len(),
+            total_debt
+        )))
+        .push(Space::new().width(Length::Fill))
+        .push(
+            checkbox(app.show_debtors_only)
+                .label("Только должники")
+                .on_toggle(Message::DebtorsOnlyToggled),
+        );
+
+    let mut balance_cards = Column::new().spacing(10);
+    let visible = app
+        .balances
+        .iter()
+        .filter(|b| !app.show_debtors_only || b.is_debtor());
+
+    let today = Local::now().format("%Y-%m-%d").to_string();
+
+    for balance in visible {
+        let outstanding = balance.outstanding();
+        let overdue = balance.overdue_amount(&today);
+        let status = if balance.is_debtor() {
+            Text::new(format!("Долг: {:.2} ₽", balance.debt_amount())).color(Color::from_rgb8(255, 0, 0))
+        } else if outstanding >= 0.01 {
+            Text::new(format!("Остаток по графику: {:.2} ₽", outstanding))
+        } else if outstanding <= -0.01 {
+            Text::new(format!("Переплата: {:.2} ₽", -outstanding)).color(Color::from_rgb8(0, 150, 0))
+        } else {
+            Text::new("Оплачено полностью").color(Color::from_rgb8(0, 150, 0))
+        };
+
+        let mut amounts = Column::new()
+            .spacing(5)
+            .align_x(Alignment::End)
+            .push(Text::new(format!(
+                "Начислено: {:.2} ₽, оплачено: {:.2} ₽",
+                balance.charged, balance.paid
+            )))
+            .push(status.size(18));
+
+        if let Some(next) = balance.next_installment().filter(|_| overdue < 0.01) {
+            amounts = amounts.push(Text::new(format!(
+                "Следующий платёж: {:.2} ₽ до {}",
+                next.remaining(),
+                next.due_date
+            )));
+        }
+
+        let mut card_row = Row::new()
+            .spacing(20)
+            .align_y(Alignment::Center)
+            .push(
+                Column::new()
+                    .spacing(5)
+                    .push(Text::new(&balance.student_name).size(20))
+                    .push(Text::new(format!(
+                        "Курс: {}, группа: {}",
+                        balance.course_title, balance.group_name
+                    ))),
+            )
+            .push(Space::new().width(Length::Fill))
+            .push(amounts)
+            .push(
+                button(icon_button_content(
+                    fa_icon_solid("calendar-days").style(move |_| text::base(app.theme.target())),
+                    "График",
+                ))
+                .on_press(Message::OpenInstallmentSchedule(balance.clone())),
+            );
+
+        if outstanding >= 0.01 {
+            card_row = card_row.push(
+                button(icon_button_content(
+                    fa_icon_solid("money-bill").style(move |_| text::base(app.theme.target())),
+                    "Внести оплату",
+                ))
+                .on_press(Message::OpenSettleBalance(balance.clone())),
+            );
+        }
+
+        let is_debtor = balance.is_debtor();
+        balance_cards = balance_cards.push(
+            Container::new(card_row)
+                .style(move |_| {
+                    let style = bordered_box(app.theme.target());
+                    if is_debtor {
+                        style.border(iced::Border {
+                            color: Color::from_rgb8(255, 0, 0),
+                            width: 2.0,
+                            radius: style.border.radius,
+                        })
+                    } else {
+                        style
+                    }
+                })
+                .width(Length::Fill)
+                .padding(10),
+        );
+    }
+
+    if app.balances.is_empty() {
+        balance_cards = balance_cards.push(Text::new("Нет студентов, зачисленных в группы."));
+    }
+
+    Column::new()
+        .spacing(10)
+        .push(summary)
+        .push(Scrollable::new(balance_cards).height(Length::Fill))
+        .into()
+}
+
+// График платежей по зачислению: взносы, их оплата и просрочка; можно пересоздать график
+fn installment_schedule_modal(app: &App) -> Element<'_, Message> {
+    let Some(balance) = &app.installment_balance else {
+        return Space::new().into();
+    };
+    let today = Local::now().format("%Y-%m-%d").to_string();
+
+    let mut schedule = Column::new().spacing(5);
+    if balance.installments.is_empty() {
+        schedule = schedule.push(Text::new("Оплата без рассрочки: график не составлен."));
+    } else {
+        for installment in &balance.installments {
+            let status = if installment.is_paid() {
+                Text::new("оплачен").color(Color::from_rgb8(0, 150, 0))
+            } else if installment.is_overdue(&today) {
+                Text::new(format!("просрочен, осталось {:.2} ₽", installment.remaining()))
+                    .color(Color::from_rgb8(255, 0, 0))
+            } else if installment.paid >= 0.01 {
+                Text::new(format!("оплачен частично, осталось {:.2} ₽", installment.remaining()))
+            } else {
+                Text::new("ожидается")
+            };
+            schedule = schedule.push(
+                Row::new()
+                    .spacing(15)
+                    .push(Text::new(format!("№{}", installment.number)).width(Length::Fixed(40.0)))
+                    .push(Text::new(&installment.due_date).width(Length::Fixed(110.0)))
+                    .push(Text::new(format!("{:.2} ₽", installment.amount)).width(Length::Fixed(120.0)))
+                    .push(status),
+            );
+        }
+    }
+
+    let plan_row = Row::new()
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .push(Text::new("Разбить на:"))
+        .push(pick_list(
+            &INSTALLMENT_OPTIONS[..],
+            Some(app.installment_parts),
+            Message::InstallmentPartsSelected,
+        ))
+        .push(Text::new("ежемесячных платежей с сегодняшнего дня"))
+        .push(
+            button(icon_button_content(
+                fa_icon_solid("arrows-rotate").style(move |_| text::base(app.theme.target())),
+                if balance.installments.is_empty() { "Составить график" } else { "Пересоставить" },
+            ))
+            .on_press(Message::CreateInstallmentPlan),
+        );
+
+    let mut modal = Column::new()
+        .spacing(15)
+        .padding(20)
+        .push(Text::new("График платежей").size(24))
+        .push(Text::new(format!(
+            "{} — {} ({})",
+            balance.student_name, balance.course_title, balance.group_name
+        )))
+        .push(Text::new(format!(
+            "Начислено: {:.2} ₽, оплачено: {:.2} ₽, остаток: {:.2} ₽",
+            balance.charged,
+            balance.paid,
+            balance.outstanding().max(0.0)
+        )))
+        .push(Scrollable::new(schedule).height(Length::Fixed(250.0)))
+        .push(plan_row);
+
+    if let Some(error) = &app.installment_error {
+        modal = modal.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    modal = modal.push(
+        Button::new(icon_button_content(
+            fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+            "Закрыть",
+        ))
+        .on_press(Message::CloseInstallmentSchedule),
+    );
+
+    let modal_container = Container::new(modal)
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fixed(750.0));
+
+    Container::new(mouse_area(modal_container))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        })
+        .into()
+}
+
+// Доплата по задолженности уже зачисленного студента
+fn settle_balance_modal(app: &App) -> Element<'_, Message> {
+    let Some(balance) = &app.settle_balance else {
+        return Space::new().into();
+    };
+
+    let payment_types: Vec<String> = PAYMENT_TYPES.iter().map(|t| t.to_string()).collect();
+
+    let mut modal = Column::new()
+        .spacing(10)
+        .padding(20)
+        .push(Text::new("Внести оплату").size(24))
+        .push(Text::new(format!("Студент: {}", balance.student_name)))
+        .push(Text::new(format!(
+            "Курс: {}, группа: {}",
+            balance.course_title, balance.group_name
+        )))
+        .push(Text::new(format!(
+            "Остаток к оплате: {:.2} ₽, из них просрочено: {:.2} ₽",
+            balance.outstanding().max(0.0),
+            balance.debt_amount()
+        )))
+        .push(
+            TextInput::new("Сумма, ₽", &app.settle_amount)
+                .on_input(Message::SettleAmountChanged),
+        )
+        .push(
+            pick_list(
+                payment_types,
+                app.settle_payment_type.clone(),
+                Message::SettlePaymentTypeSelected,
+            )
+            .placeholder("Выберите тип платежа"),
+        );
+
+    if let Some(error) = &app.settle_error {
+        modal = modal.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    modal = modal.push(
+        Row::new()
+            .spacing(10)
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("plus").style(move |_| text::base(app.theme.target())),
+                    "Внести",
+                ))
+                .on_press(Message::ConfirmSettleBalance),
+            )
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+                    "Отмена",
+                ))
+                .on_press(Message::CloseSettleBalance),
+            ),
+    );
+
+    let modal_container = Container::new(modal)
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fixed(500.0));
+
+    Container::new(mouse_area(modal_container))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        })
+        .into()
+}
+
+/// Модальное окно возврата или корректировки платежа
+fn payment_reversal_modal(app: &App) -> Element<'_, Message> {
+    let Some(payment) = &app.reversal_payment else {
+        return Space::new().into();
+    };
+
+    let (title, amount_hint, confirm_label) = match app.reversal_kind {
+        ReversalKind::Refund => ("Возврат платежа", "Сумма возврата, ₽", "Оформить возврат"),
+        ReversalKind::Correction => (
+            "Корректировка платежа",
+            "Изменение суммы, ₽ (например, -500 или 300)",
+            "Сохранить корректировку",
+        ),
+    };
+
+    let mut modal = Column::new()
+        .spacing(10)
+        .padding(20)
+        .push(Text::new(format!("{} #{}", title, payment.id)).size(24))
+        .push(Text::new(format!("Студент: {}", payment.student_name)))
+        .push(Text::new(format!(
+            "Курс: {}, группа: {}",
+            payment.course_title, payment.group_name
+        )))
+        .push(Text::new(format!(
+            "Платёж от {}: {:.2} ₽, с учётом прежних изменений: {:.2} ₽",
+            payment.date,
+            payment.amount,
+            payment.net_amount(&app.payments)
+        )))
+        .push(TextInput::new(amount_hint, &app.reversal_amount).on_input(Message::ReversalAmountChanged))
+        .push(TextInput::new("Причина", &app.reversal_reason).on_input(Message::ReversalReasonChanged));
+
+    if let Some(error) = &app.reversal_error {
+        modal = modal.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    modal = modal.push(
+        Row::new()
+            .spacing(10)
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("check").style(move |_| text::base(app.theme.target())),
+                    confirm_label,
+                ))
+                .on_press(Message::ConfirmPaymentReversal),
+            )
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+                    "Отмена",
+                ))
+                .on_press(Message::ClosePaymentReversal),
+            ),
+    );
+
+    let modal_container = Container::new(modal)
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fixed(550.0));
+
+    Container::new(mouse_area(modal_container))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        })
+        .into()
+}
+
+/// Модальное окно управления промокодами
+fn promo_codes_modal(app: &App) -> Element<'_, Message> {
+    let today = Local::now().format("%Y-%m-%d").to_string();
+
+    let mut codes_column = Column::new().spacing(5);
+    if app.promo_codes.is_empty() {
+        codes_column = codes_column.push(Text::new("Промокодов пока нет."));
+    }
+    for promo in &app.promo_codes {
+        let period = match (&promo.valid_from, &promo.valid_to) {
+            (None, None) => "бессрочно".to_string(),
+            (from, to) => format!(
+                "с {} по {}",
+                from.as_deref().unwrap_or("…"),
+                to.as_deref().unwrap_or("…")
+            ),
+        };
+        let uses = match promo.max_uses {
+            Some(max) => format!("{} из {}", promo.used_count, max),
+            None => format!("{}", promo.used_count),
+        };
+        let status = match promo.check(&today) {
+            Ok(()) => Text::new("действует").color(Color::from_rgb8(0, 150, 0)),
+            Err(_) => Text::new("не действует").color(Color::from_rgb8(255, 0, 0)),
+        };
+
+        codes_column = codes_column.push(
+            Row::new()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(Text::new(format!(
+                    "{} — {}, {}, применений: {}",
+                    promo.code,
+                    promo.value_display(),
+                    period,
+                    uses
+                )))
+                .push(Space::new().width(Length::Fill))
+                .push(status)
+                .push(
+                    button(fa_icon_solid("trash").style(move |_| text::base(app.theme.target())))
+                        .on_press(Message::DeletePromoCode(promo.id)),
+                ),
+        );
+    }
+
+    let form = Column::new()
+        .spacing(10)
+        .push(Text::new("Новый промокод").size(18))
+        .push(
+            Row::new()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(TextInput::new("Код", &app.new_promo_code).on_input(Message::NewPromoCodeChanged))
+                .push(
+                    TextInput::new(
+                        if app.new_promo_is_percent { "Скидка, %" } else { "Скидка, ₽" },
+                        &app.new_promo_value,
+                    )
+                    .on_input(Message::NewPromoValueChanged)
+                    .width(Length::Fixed(120.0)),
+                )
+                .push(
+                    checkbox(app.new_promo_is_percent)
+                        .label("В процентах")
+                        .on_toggle(Message::NewPromoIsPercentToggled),
+                ),
+        )
+        .push(
+            Row::new()
+                .spacing(10)
+                .push(
+                    TextInput::new("Действует с (ГГГГ-ММ-ДД)", &app.new_promo_valid_from)
+                        .on_input(Message::NewPromoValidFromChanged),
+                )
+                .push(
+                    TextInput::new("по (ГГГГ-ММ-ДД)", &app.new_promo_valid_to)
+                        .on_input(Message::NewPromoValidToChanged),
+                )
+                .push(
+                    TextInput::new("Лимит применений", &app.new_promo_max_uses)
+                        .on_input(Message::NewPromoMaxUsesChanged),
+                ),
+        );
+
+    let mut modal = Column::new()
+        .spacing(15)
+        .padding(20)
+        .push(Text::new("Промокоды").size(24))
+        .push(Scrollable::new(codes_column).height(Length::Fixed(250.0)))
+        .push(form);
+
+    if let Some(error) = &app.promo_error {
+        modal = modal.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    modal = modal.push(
+        Row::new()
+            .spacing(10)
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("plus").style(move |_| text::base(app.theme.target())),
+                    "Добавить",
+                ))
+                .on_press(Message::CreatePromoCode),
+            )
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+                    "Закрыть",
+                ))
+                .on_press(Message::TogglePromoCodes),
+            ),
+    );
+
+    let modal_container = Container::new(modal)
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fixed(750.0));
+
+    Container::new(mouse_area(modal_container))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        })
+        .into()
+}
+
+/// Модальное окно разбора банковской выписки: автоматические совпадения и ручной выбор студента
+fn bank_import_modal(app: &App) -> Element<'_, Message> {
+    let selected: Vec<_> = app
+        .bank_import_lines
+        .iter()
+        .filter(|line| line.include && line.target.is_some())
+        .collect();
+    let selected_total: f64 = selected.iter().map(|line| line.transaction.amount).sum();
+
+    let mut lines_column = Column::new().spacing(8);
+    for (index, line) in app.bank_import_lines.iter().enumerate() {
+        let transaction = &line.transaction;
+        let status_color = match line.status {
+            MatchStatus::Matched => Color::from_rgb8(0, 150, 0),
+            MatchStatus::Suggested => Color::from_rgb8(200, 130, 0),
+            MatchStatus::Unmatched => Color::from_rgb8(255, 0, 0),
+            MatchStatus::AlreadyImported | MatchStatus::Duplicate => Color::from_rgb8(128, 128, 128),
+        };
+        let status = if line.match_note.is_empty() {
+            line.status.label().to_string()
+        } else {
+            format!("{} ({})", line.status.label(), line.match_note)
+        };
+
+        let mut details = Column::new()
+            .spacing(3)
+            .width(Length::Fill)
+            .push(Text::new(format!(
+                "{} — {:.2} ₽ — {}",
+                transaction.date,
+                transaction.amount,
+                if transaction.payer.is_empty() { "плательщик не указан" } else { &transaction.payer }
+            )))
+            .push(Text::new(status).size(13).color(status_color));
+        if !transaction.purpose.is_empty() {
+            details = details.push(Text::new(&transaction.purpose).size(13));
+        }
+
+        let mut line_row = Row::new().spacing(10).align_y(Alignment::Center).push(details);
+        if line.status.is_importable() {
+            line_row = line_row
+                .push(
+                    pick_list(
+                        app.bank_import_options.clone(),
+                        line.target.clone(),
+                        move |target| Message::BankLineTargetSelected(index, target),
+                    )
+                    .placeholder("Выберите студента")
+                    .width(Length::Fixed(330.0)),
+                )
+                .push(
+                    checkbox(line.include)
+                        .label("Загрузить")
+                        .on_toggle(move |include| Message::BankLineToggled(index, include)),
+                );
+        }
+
+        lines_column = lines_column.push(
+            Container::new(line_row)
+                .style(move |_| bordered_box(app.theme.target()))
+                .padding(8)
+                .width(Length::Fill),
+        );
+    }
+
+    let mut modal = Column::new()
+        .spacing(15)
+        .padding(20)
+        .push(Text::new("Импорт банковской выписки").size(24))
+        .push(Text::new(format!("Файл: {}", app.bank_import_file)).size(13))
+        .push(Scrollable::new(lines_column).height(Length::Fixed(430.0)))
+        .push(Text::new(format!(
+            "К загрузке: {} на сумму {:.2} ₽",
+            selected.len(),
+            selected_total
+        )));
+
+    if let Some(error) = &app.bank_import_error {
+        modal = modal.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    modal = modal.push(
+        Row::new()
+            .spacing(10)
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("check").style(move |_| text::base(app.theme.target())),
+                    "Создать платежи",
+                ))
+                .on_press_maybe((!selected.is_empty()).then_some(Message::ConfirmBankImport)),
+            )
+            .push(
+                Button::new(icon_button_content(
+                    fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+                    "Отмена",
+                ))
+                .on_press(Message::CloseBankImport),
+            ),
+    );
+
+    let modal_container = Container::new(modal)
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fixed(1050.0));
+
+    Container::new(mouse_area(modal_container))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        })
+        .into()
+}
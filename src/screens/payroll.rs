@@ -0,0 +1,220 @@
+use crate::app::state::{RateType, ReportType};
+use crate::app::update::icon_button_content;
+use crate::app::{App, Message};
+use iced::widget::container::bordered_box;
+use iced::widget::{
+    Button, Column, Container, Row, Scrollable, Space, Text, TextInput, button, pick_list, text,
+};
+use iced::{Alignment, Color, Length};
+use iced_font_awesome::fa_icon_solid;
+
+// Ячейка таблицы ведомости фиксированной ширины
+fn cell<'a>(value: String, width: f32) -> Text<'a> {
+    Text::new(value).width(Length::Fixed(width))
+}
+
+pub fn payroll_screen(app: &App) -> Container<'_, Message> {
+    let mut main_column = Column::new().spacing(20).padding(20);
+
+    let period_title = app
+        .payroll
+        .as_ref()
+        .map_or(app.payroll_month.format("%m.%Y").to_string(), |p| p.period_title());
+
+    main_column = main_column.push(
+        Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(Text::new("Зарплата преподавателей").size(30))
+            .push(Space::new().width(Length::Fill))
+            .push(
+                button(fa_icon_solid("chevron-left").style(move |_| text::base(app.theme.target())))
+                    .on_press(Message::PayrollPrevMonth),
+            )
+            .push(Text::new(period_title).size(20))
+            .push(
+                button(fa_icon_solid("chevron-right").style(move |_| text::base(app.theme.target())))
+                    .on_press(Message::PayrollNextMonth),
+            ),
+    );
+
+    // Ведомость за выбранный месяц
+    let mut payroll_column = Column::new().spacing(8);
+
+    if let Some(payroll) = &app.payroll {
+        let status = match &payroll.approved_at {
+            Some(approved_at) => Text::new(format!(
+                "Ведомость утверждена {} ({}), период закрыт",
+                approved_at,
+                payroll.approved_by.as_deref().unwrap_or("—")
+            ))
+            .color(Color::from_rgb8(0, 150, 0)),
+            None => Text::new("Предварительный расчёт по текущим ставкам"),
+        };
+        payroll_column = payroll_column.push(status);
+
+        if payroll.lines.is_empty() {
+            payroll_column = payroll_column.push(
+                Text::new("За этот месяц нет проведённых занятий и оплат.")
+                    .color(Color::from_rgb8(150, 150, 150)),
+            );
+        } else {
+            payroll_column = payroll_column.push(
+                Row::new()
+                    .spacing(10)
+                    .push(cell("Преподаватель".to_string(), 220.0))
+                    .push(cell("Ставка".to_string(), 220.0))
+                    .push(cell("Занятий".to_string(), 80.0))
+                    .push(cell("Студ.-часов".to_string(), 100.0))
+                    .push(cell("Выручка".to_string(), 120.0))
+                    .push(cell("Начислено".to_string(), 120.0)),
+            );
+
+            for line in &payroll.lines {
+                let rate = match line.rate_type {
+                    Some(rate_type) => Text::new(rate_type.format_rate(line.rate)),
+                    None => Text::new("ставка не задана").color(Color::from_rgb8(255, 0, 0)),
+                };
+                payroll_column = payroll_column.push(
+                    Container::new(
+                        Row::new()
+                            .spacing(10)
+                            .padding(5)
+                            .push(cell(line.teacher_name.clone(), 220.0))
+                            .push(rate.width(Length::Fixed(220.0)))
+                            .push(cell(line.sessions.to_string(), 80.0))
+                            .push(cell(format!("{:.1}", line.student_hours), 100.0))
+                            .push(cell(format!("{:.2} ₽", line.revenue), 120.0))
+                            .push(cell(format!("{:.2} ₽", line.amount), 120.0)),
+                    )
+                    .style(move |_| bordered_box(app.theme.target()))
+                    .width(Length::Fill),
+                );
+            }
+
+            payroll_column = payroll_column
+                .push(Text::new(format!("Итого к выплате: {:.2} ₽", payroll.total())).size(20));
+        }
+
+        let mut actions = Row::new().spacing(10).align_y(Alignment::Center);
+        if !payroll.is_locked() && !payroll.lines.is_empty() {
+            if app.payroll_confirm_approve {
+                actions = actions
+                    .push(Text::new("Утвердить ведомость? Пересчитать её будет нельзя."))
+                    .push(
+                        Button::new(icon_button_content(
+                            fa_icon_solid("lock").style(move |_| text::base(app.theme.target())),
+                            "Утвердить",
+                        ))
+                        .on_press(Message::ConfirmPayrollApproval),
+                    )
+                    .push(
+                        Button::new(icon_button_content(
+                            fa_icon_solid("xmark").style(move |_| text::base(app.theme.target())),
+                            "Отмена",
+                        ))
+                        .on_press(Message::CancelPayrollApproval),
+                    );
+            } else {
+                actions = actions.push(
+                    Button::new(icon_button_content(
+                        fa_icon_solid("lock").style(move |_| text::base(app.theme.target())),
+                        "Утвердить и закрыть период",
+                    ))
+                    .on_press(Message::ApprovePayroll),
+                );
+            }
+        }
+        if !payroll.lines.is_empty() {
+            actions = actions
+                .push(
+                    Button::new(icon_button_content(
+                        fa_icon_solid("file-pdf").style(move |_| text::base(app.theme.target())),
+                        "Ведомость PDF",
+                    ))
+                    .on_press(Message::ExportPayroll(ReportType::PDF)),
+                )
+                .push(
+                    Button::new(icon_button_content(
+                        fa_icon_solid("file-excel").style(move |_| text::base(app.theme.target())),
+                        "Ведомость Excel",
+                    ))
+                    .on_press(Message::ExportPayroll(ReportType::Excel)),
+                );
+        }
+        payroll_column = payroll_column.push(actions);
+    } else {
+        payroll_column = payroll_column.push(Text::new("Расчёт зарплаты..."));
+    }
+
+    if let Some(notice) = &app.payroll_notice {
+        payroll_column = payroll_column.push(Text::new(notice).color(Color::from_rgb8(0, 150, 0)));
+    }
+    if let Some(error) = &app.payroll_error {
+        payroll_column = payroll_column.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    // Ставки преподавателей
+    let mut rates_column = Column::new()
+        .spacing(8)
+        .push(Text::new("Ставки преподавателей").size(22))
+        .push(
+            Row::new()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(
+                    pick_list(
+                        app.teacher_rates.clone(),
+                        app.rate_teacher.clone(),
+                        Message::RateTeacherSelected,
+                    )
+                    .placeholder("Преподаватель")
+                    .width(Length::Fixed(250.0)),
+                )
+                .push(
+                    pick_list(&RateType::ALL[..], app.rate_type, Message::RateTypeSelected)
+                        .placeholder("Вид ставки"),
+                )
+                .push(
+                    TextInput::new(
+                        match app.rate_type {
+                            Some(RateType::RevenuePercent) => "Процент",
+                            _ => "Сумма, ₽",
+                        },
+                        &app.rate_value,
+                    )
+                    .on_input(Message::RateValueChanged)
+                    .width(Length::Fixed(120.0)),
+                )
+                .push(
+                    Button::new(icon_button_content(
+                        fa_icon_solid("floppy-disk").style(move |_| text::base(app.theme.target())),
+                        "Сохранить",
+                    ))
+                    .on_press(Message::SaveTeacherRate),
+                ),
+        );
+
+    for rate in &app.teacher_rates {
+        let rate_text = match rate.rate_type {
+            Some(_) => Text::new(rate.rate_display()),
+            None => Text::new(rate.rate_display()).color(Color::from_rgb8(150, 150, 150)),
+        };
+        rates_column = rates_column.push(
+            Row::new()
+                .spacing(10)
+                .push(cell(rate.teacher_name.clone(), 250.0))
+                .push(rate_text),
+        );
+    }
+
+    main_column = main_column.push(
+        Scrollable::new(Column::new().spacing(30).push(payroll_column).push(rates_column))
+            .width(Length::Fill)
+            .height(Length::Fill),
+    );
+
+    Container::new(main_column)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+}
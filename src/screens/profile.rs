@@ -1,9 +1,11 @@
-use crate::app::state::DEFAULT_AVATAR;
+use crate::app::state::{DEFAULT_AVATAR, RegistrationDecision, Role, StudentBalance};
 use crate::app::update::icon_button_content;
 use crate::app::{App, Message};
+use crate::screens::password::change_password_form;
+use crate::screens::two_factor::two_factor_setup_form;
 use iced::widget::container::{background, bordered_box};
 use iced::widget::image::Handle;
-use iced::widget::{Column, Row, rule, Scrollable, Stack, Text, image, mouse_area, row};
+use iced::widget::{Column, Row, rule, Scrollable, Stack, Text, image, mouse_area, row, text_input};
 use iced::{
     Alignment, Color, ContentFit, Length, Theme,
     widget::{Container, button, column, text},
@@ -35,7 +37,7 @@ pub fn profile_screen(app: &App) -> Container<Message> {
     };
 
     // Основное содержимое профиля
-    let main_profile_content = column![
+    let mut main_profile_content = column![
         row![
             Container::new(avatar_widget)
                 .style(move |_| bordered_box(&app.theme.target()))
@@ -58,7 +60,7 @@ pub fn profile_screen(app: &App) -> Container<Message> {
                 .size(24),
                 text(format!(
                     "Тип профиля: {}",
-                    user_data.map_or("Неизвестно".to_string(), |u| u.user_type.clone())
+                    user_data.map_or("Неизвестно".to_string(), |u| u.user_type.to_string())
                 ))
                 .size(24),
             ]
@@ -67,14 +69,48 @@ pub fn profile_screen(app: &App) -> Container<Message> {
         .width(Length::Fill)
         .spacing(20),
         text(&app.choose_avatar_message).size(10),
-        button(icon_button_content(
-            fa_icon_solid("pencil").style(move |_| text::base(&app.theme.target())),
-            "Изменить аватар"
-        ))
-        .on_press(Message::ChooseAvatar),
+        row![
+            button(icon_button_content(
+                fa_icon_solid("pencil").style(move |_| text::base(&app.theme.target())),
+                "Изменить аватар"
+            ))
+            .on_press(Message::ChooseAvatar),
+            button(icon_button_content(
+                fa_icon_solid("key").style(move |_| text::base(app.theme.target())),
+                "Сменить пароль"
+            ))
+            .on_press(Message::OpenChangePassword),
+            button(icon_button_content(
+                fa_icon_solid("life-ring").style(move |_| text::base(app.theme.target())),
+                "Новые коды восстановления"
+            ))
+            .on_press(Message::GenerateRecoveryCodes),
+            text(format!("Осталось кодов восстановления: {}", app.recovery_codes_left))
+                .color(Color::from_rgb8(150, 150, 150)),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
     ]
     .spacing(0);
 
+    if let Some(notice) = &app.change_password_notice {
+        main_profile_content =
+            main_profile_content.push(text(notice).color(Color::from_rgb8(0, 150, 0)));
+    }
+    main_profile_content = main_profile_content.push(two_factor_row(app));
+    if app.two_factor_setup_secret.is_none()
+        && let Some(error) = &app.two_factor_error
+    {
+        main_profile_content = main_profile_content.push(text(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    // Ошибка выдачи кодов; ошибки самой смены пароля показываются в её окне
+    if !app.show_change_password_modal
+        && let Some(error) = &app.change_password_error
+    {
+        main_profile_content = main_profile_content.push(text(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
     let user_info_widget = Container::new(main_profile_content)
         .style(move |_| bordered_box(&app.theme.target()))
         .width(Length::Fill)
@@ -82,18 +118,52 @@ pub fn profile_screen(app: &App) -> Container<Message> {
 
     let mut role_specific_content = Column::new().spacing(20).width(Length::Fill);
 
+    // Решение по заявке на регистрацию, если учётную запись подтверждали через очередь заявок
+    if let Some(review) = &app.registration_review
+        && review.decision == RegistrationDecision::Approved
+    {
+        let mut approved = Column::new().spacing(5).push(
+            Text::new(format!(
+                "Регистрация подтверждена {}{}",
+                review.decided_at,
+                review.role.map(|role| format!(", роль: {}", role)).unwrap_or_default()
+            ))
+            .color(Color::from_rgb8(0, 150, 0)),
+        );
+        if let Some(reason) = &review.reason {
+            approved = approved.push(Text::new(format!("Комментарий: {}", reason)));
+        }
+        role_specific_content = role_specific_content.push(
+            Container::new(approved)
+                .width(Length::Fill)
+                .padding(10)
+                .style(move |_| bordered_box(app.theme.target())),
+        );
+    }
+
     if let Some(user_info) = user_data {
-        match user_info.user_type.as_str() {
-            "unconfirmed" => {
+        match user_info.user_type {
+            Role::Unconfirmed => {
+                let status = match &app.registration_review {
+                    Some(review) if review.decision == RegistrationDecision::Rejected => column![
+                        text(format!("Заявка на регистрацию отклонена {}", review.decided_at))
+                            .size(24)
+                            .color(Color::from_rgb8(255, 0, 0)),
+                        text(format!("Причина: {}", review.reason.as_deref().unwrap_or("не указана"))),
+                    ]
+                    .spacing(5)
+                    .align_x(Alignment::Center),
+                    _ => column![text("Ваша учётная запись ещё не подтверждена").size(24)],
+                };
                 role_specific_content = role_specific_content.push(
-                    Container::new(text("Ваша учётная запись ещё не подтверждена").size(24))
+                    Container::new(status)
                         .width(Length::Fill)
                         .center_x(Length::Fill)
                         .padding(10)
                         .style(move |_| bordered_box(&app.theme.target())),
                 );
             }
-            "teacher" => {
+            Role::Teacher => {
                 // Если пользователь - преподаватель
                 role_specific_content =
                     role_specific_content.push(Text::new("Мои группы:").size(24));
@@ -130,15 +200,95 @@ pub fn profile_screen(app: &App) -> Container<Message> {
                     }
                 }
             }
-            "admin" => {}
-            _ => {
-                // Неизвестный тип пользователя
+            Role::Student => {
+                role_specific_content =
+                    role_specific_content.push(Text::new("Моя группа:").size(24));
+
+                let group_text = match &app.student_group_info {
+                    Some(group) => format!(
+                        "Группа: {} (Курс: {}, преподаватель: {})",
+                        group.name,
+                        group.course_name.as_deref().unwrap_or("Неизвестно"),
+                        group.teacher_name.as_deref().unwrap_or("Неизвестно")
+                    ),
+                    None => "Вы пока не записаны ни в одну группу.".to_string(),
+                };
+
                 role_specific_content = role_specific_content.push(
-                    Text::new("Неизвестный тип пользователя.")
-                        .size(24)
-                        .color(Color::from_rgb8(255, 0, 0)),
+                    Container::new(
+                        row![
+                            text(group_text).size(20).width(Length::Fill),
+                            button(icon_button_content(
+                                fa_icon_solid("book-open")
+                                    .style(move |_| text::base(app.theme.target())),
+                                "Моё обучение"
+                            ))
+                            .on_press(Message::GoToStudentProgress),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                    )
+                    .width(Length::Fill)
+                    .padding(5)
+                    .style(move |_| bordered_box(app.theme.target())),
                 );
+
+                if let Some(progress) = app.student_progress.first() {
+                    role_specific_content =
+                        role_specific_content.push(balances_column(&progress.balances));
+                }
             }
+            Role::Parent => {
+                role_specific_content =
+                    role_specific_content.push(Text::new("Мои дети:").size(24));
+
+                if app.student_progress.is_empty() {
+                    role_specific_content = role_specific_content
+                        .push(Text::new("К вашей учётной записи не привязан ни один ребёнок.").size(18));
+                } else {
+                    for progress in &app.student_progress {
+                        let group_name = progress
+                            .group
+                            .as_ref()
+                            .map_or("без группы".to_string(), |g| g.name.clone());
+                        let child_row = row![
+                            text(format!(
+                                "{} — группа: {}, посещено занятий: {} из {}, сертификатов: {}",
+                                progress.student.name,
+                                group_name,
+                                progress.attended_lessons,
+                                progress.past_sessions.len(),
+                                progress.certificates.len()
+                            ))
+                            .size(20)
+                            .width(Length::Fill),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center);
+
+                        role_specific_content = role_specific_content.push(
+                            Container::new(
+                                Column::new()
+                                    .spacing(10)
+                                    .push(child_row)
+                                    .push(balances_column(&progress.balances)),
+                            )
+                            .width(Length::Fill)
+                            .padding(5)
+                            .style(move |_| bordered_box(app.theme.target())),
+                        );
+                    }
+                }
+
+                role_specific_content = role_specific_content.push(
+                    button(icon_button_content(
+                        fa_icon_solid("children").style(move |_| text::base(app.theme.target())),
+                        "Подробнее об обучении"
+                    ))
+                    .on_press(Message::GoToStudentProgress),
+                );
+            }
+            Role::Admin => {}
         }
     } else {
         // Пользователь не вошел в систему
@@ -258,9 +408,168 @@ pub fn profile_screen(app: &App) -> Container<Message> {
         }
     }
 
+    // --- Модальное окно смены пароля ---
+    if app.show_change_password_modal {
+        let modal = Container::new(
+            Column::new()
+                .spacing(15)
+                .push(Text::new("Смена пароля").size(24))
+                .push(change_password_form(app)),
+        )
+        .style(move |_| bordered_box(app.theme.target()))
+        .padding(20)
+        .width(Length::Fixed(450.0));
+
+        ui_stack = ui_stack.push(modal_overlay(modal, Some(Message::CloseChangePassword)));
+    }
+
+    // --- Настройка двухфакторной аутентификации ---
+    if app.two_factor_setup_secret.is_some() && !app.two_factor_setup_required {
+        let modal = Container::new(
+            Column::new()
+                .spacing(15)
+                .push(Text::new("Двухфакторная аутентификация").size(24))
+                .push(two_factor_setup_form(app)),
+        )
+        .style(move |_| bordered_box(app.theme.target()))
+        .padding(20)
+        .width(Length::Fixed(520.0));
+
+        ui_stack = ui_stack.push(modal_overlay(modal, Some(Message::CancelTwoFactorSetup)));
+    }
+
+    // --- Только что выданные коды восстановления: показываются один раз ---
+    if !app.recovery_codes.is_empty() {
+        let mut codes = Column::new().spacing(5);
+        for code in &app.recovery_codes {
+            codes = codes.push(Text::new(code).size(20));
+        }
+
+        let modal = Container::new(
+            Column::new()
+                .spacing(15)
+                .push(Text::new("Коды восстановления").size(24))
+                .push(Text::new(
+                    "Сохраните коды в надёжном месте: они показываются только сейчас. \
+                     Каждый код позволяет один раз задать новый пароль на экране входа. \
+                     Прежние коды больше не действуют.",
+                ))
+                .push(codes)
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .push(
+                            button(icon_button_content(
+                                fa_icon_solid("copy").style(move |_| text::base(app.theme.target())),
+                                "Скопировать",
+                            ))
+                            .on_press(Message::CopyRecoveryCodes),
+                        )
+                        .push(
+                            button(icon_button_content(
+                                fa_icon_solid("check").style(move |_| text::base(app.theme.target())),
+                                "Я сохранил коды",
+                            ))
+                            .on_press(Message::DismissRecoveryCodes),
+                        ),
+                ),
+        )
+        .style(move |_| bordered_box(app.theme.target()))
+        .padding(20)
+        .width(Length::Fixed(500.0));
+
+        // Случайный клик мимо окна не должен скрыть коды, которые больше не покажут
+        ui_stack = ui_stack.push(modal_overlay(modal, None));
+    }
+
     Container::new(ui_stack)
         .width(Length::Fill)
         .height(Length::Fill)
         .center_x(Length::Fill)
         .center_y(Length::Fill)
 }
+
+// Состояние второго фактора: включить или (с кодом из приложения) отключить
+fn two_factor_row(app: &App) -> Row<'_, Message> {
+    let row = Row::new().spacing(10).align_y(Alignment::Center).padding([10, 0]);
+    if !app.two_factor_enabled {
+        return row
+            .push(text("Двухфакторная аутентификация выключена"))
+            .push(
+                button(icon_button_content(
+                    fa_icon_solid("shield-halved").style(move |_| text::base(app.theme.target())),
+                    "Включить",
+                ))
+                .on_press(Message::StartTwoFactorSetup),
+            );
+    }
+
+    row.push(text("Двухфакторная аутентификация включена").color(Color::from_rgb8(0, 150, 0)))
+        .push(
+            text_input("Код из приложения", &app.two_factor_code_input)
+                .on_input(Message::TwoFactorCodeChanged)
+                .on_submit(Message::DisableTwoFactor)
+                .width(Length::Fixed(180.0)),
+        )
+        .push(
+            button(icon_button_content(
+                fa_icon_solid("shield").style(move |_| text::base(app.theme.target())),
+                "Отключить",
+            ))
+            .on_press(Message::DisableTwoFactor),
+        )
+}
+
+// Затемнённый фон под модальным окном; клик мимо окна закрывает его, если задано `on_close`
+fn modal_overlay<'a>(modal: Container<'a, Message>, on_close: Option<Message>) -> Container<'a, Message> {
+    let area = mouse_area(Container::new(modal).center(Length::Fill).padding(40));
+    Container::new(match on_close {
+        Some(message) => area.on_press(message),
+        None => area,
+    })
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        })
+}
+
+// Оплата обучения по группам: начислено, оплачено и долг (выделен красным)
+fn balances_column(balances: &[StudentBalance]) -> Column<'_, Message> {
+    let mut column = Column::new().spacing(5).push(Text::new("Оплата обучения:").size(20));
+
+    if balances.is_empty() {
+        return column.push(Text::new("Начислений пока нет."));
+    }
+
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    for balance in balances {
+        let overdue = balance.overdue_amount(&today);
+        let status = if overdue >= 0.01 {
+            Text::new(format!("просрочено {:.2} ₽", overdue)).color(Color::from_rgb8(255, 0, 0))
+        } else if let Some(next) = balance.next_installment() {
+            Text::new(format!("следующий платёж {:.2} ₽ до {}", next.remaining(), next.due_date))
+        } else if balance.is_debtor() {
+            Text::new(format!("долг {:.2} ₽", balance.outstanding())).color(Color::from_rgb8(255, 0, 0))
+        } else {
+            Text::new("оплачено").color(Color::from_rgb8(0, 150, 0))
+        };
+        column = column.push(
+            row![
+                text(format!(
+                    "{} ({}): начислено {:.2} ₽, оплачено {:.2} ₽ —",
+                    balance.course_title, balance.group_name, balance.charged, balance.paid
+                )),
+                status,
+            ]
+            .spacing(5),
+        );
+    }
+
+    column
+}
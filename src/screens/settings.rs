@@ -1,12 +1,13 @@
 // settings_screen.rs
 use crate::app::{App, Message};
-use iced::widget::{button, container, pick_list, row, text_input, Row};
+use iced::widget::{button, checkbox, container, mouse_area, pick_list, row, text_input, Column, Row, Scrollable, Stack};
+use iced::widget::container::{background, bordered_box};
 use iced::{
     widget::{column, text, Container, tooltip, Space},
-    Length, Theme, Element, Border,
+    Alignment, Color, Length, Theme, Element, Border,
     theme::palette::{Extended, Pair}, // Импорт для палитры
 };
-use crate::app::state::{BackupInterval, BACKUP_INTERVALS};
+use crate::app::state::{BackupCompression, BackupFile, BackupInterval, OrganizationField, PdfBackend, Permission, Role, BACKUP_COMPRESSIONS, BACKUP_INTERVALS, IDLE_LOCK_OPTIONS, PAYMENT_LOCK_OPTIONS, PDF_BACKENDS, SIBLING_DISCOUNT_OPTIONS, WAITLIST_OFFER_OPTIONS};
 use crate::config::theme_to_str;
 use iced_anim::Animation;
 use iced_font_awesome::fa_icon_solid;
@@ -30,6 +31,7 @@ pub fn settings_screen(app: &App) -> Container<Message> {
                 })
                 .placeholder("Выберите тему"),
                 palette_grid(app.theme.value().extended_palette()),
+                organization_settings(app),
                 Space::new().height(Length::Fill),
                 
             ].spacing(10).padding(10),
@@ -46,8 +48,19 @@ pub fn settings_screen(app: &App) -> Container<Message> {
                     text_input("",&*app.backup_folder.clone().unwrap_or("Не выбрана".to_string())).on_input(Message::Er),
                     //vertical_space()
                 ].spacing(10),
+                row![
+                    text("Время бэкапа (ЧЧ:ММ): ").center(),
+                    text_input("любое", &app.backup_time)
+                        .on_input(Message::BackupTimeChanged)
+                        .width(Length::Fixed(100.0)),
+                ].spacing(10),
                 text("Последний бэкап:").size(26),
                 text(app.last_backup_time.clone().unwrap_or("Не найден".to_string())),
+                text(format!(
+                    "Следующий бэкап: {}",
+                    app.next_backup_time
+                        .map_or("не запланирован".to_string(), |t| t.format("%Y-%m-%d %H:%M").to_string())
+                )),
                 button(icon_button_content(
                     fa_icon_solid("folder-closed").style(move |_| text::base(&app.theme.target())),
                     "Открыть папку с бэкапами"
@@ -58,27 +71,341 @@ pub fn settings_screen(app: &App) -> Container<Message> {
                     app.max_backup_count,
                     |value| Message::MaxBackupCountSelected(Some(value)),
                 ).placeholder("Максимум резервных копий"),
+                text("Сжатие резервных копий").size(26),
+                pick_list(
+                    BACKUP_COMPRESSIONS.to_vec(),
+                    app.backup_compression.clone(),
+                    |value: BackupCompression| Message::BackupCompressionSelected(Some(value)),
+                ).placeholder("Сжатие резервных копий"),
                 button(icon_button_content(
                         fa_icon_solid("database").style(move |_| text::base(&app.theme.target())),
                         "Сделать резервную копию сейчас"
-                    )).on_press(Message::BackupNowPressed),
+                    )).on_press_maybe((!app.backup_in_progress).then_some(Message::BackupNowPressed)),
+                backup_status_text(app),
             ].spacing(10).padding(10)
         ],
     ].spacing(20);
-    
-    Container::new(
+
+    // Реквизиты, восстановление и матрица прав видны только ролям, которым они разрешены
+    let mut content = content;
+    if app.can(Permission::ManagePermissions) {
+        content = content.push(
+            button(icon_button_content(
+                fa_icon_solid("user-shield").style(move |_| text::base(app.theme.target())),
+                "Права доступа"
+            ))
+            .on_press(Message::TogglePermissionsModal(true)),
+        );
+    }
+    if app.can(Permission::ManageSettings) {
+        content = content.push(organization_section(app));
+    }
+    if app.can(Permission::ManageBackups) {
+        content = content.push(restore_section(app));
+    }
+
+    let mut ui_stack = Stack::new().push(
         Animation::new(
-            &app.theme, 
-            Container::new(content) 
+            &app.theme,
+            Container::new(content)
                 .width(Length::Fill)
                 .height(Length::Fill)
-                .padding(40) 
-        ).on_update(Message::ChangeTheme) 
-    )
+                .padding(40)
+        ).on_update(Message::ChangeTheme)
+    );
+
+    if let Some(backup) = &app.restore_candidate {
+        ui_stack = ui_stack.push(restore_confirm_modal(app, backup));
+    }
+    if app.show_permissions_modal {
+        ui_stack = ui_stack.push(permissions_modal(app));
+    }
+
+    Container::new(ui_stack)
         .width(Length::Fill)
         .height(Length::Fill)
 }
 
+// Общие для всей организации настройки — только для ролей с правом менять настройки
+fn organization_settings(app: &App) -> Column<'_, Message> {
+    if !app.can(Permission::ManageSettings) {
+        return Column::new();
+    }
+    column![
+        text("Формирование PDF").size(26),
+        pick_list(
+            PDF_BACKENDS.to_vec(),
+            app.pdf_backend.clone(),
+            |value: PdfBackend| Message::PdfBackendSelected(Some(value)),
+        ).placeholder("Формирование PDF"),
+        text("Удаление платежей").size(26),
+        row![
+            text("Запрещать удаление через, дней:"),
+            pick_list(
+                PAYMENT_LOCK_OPTIONS.to_vec(),
+                Some(app.payment_lock_days),
+                Message::PaymentLockDaysSelected,
+            ),
+        ].spacing(10).align_y(Alignment::Center),
+        text("Скидка для братьев и сестёр").size(26),
+        row![
+            text("Размер скидки, %:"),
+            pick_list(
+                SIBLING_DISCOUNT_OPTIONS.to_vec(),
+                Some(app.sibling_discount_percent),
+                Message::SiblingDiscountSelected,
+            ),
+        ].spacing(10).align_y(Alignment::Center),
+        text("Лист ожидания").size(26),
+        row![
+            text("Срок ответа на предложение места, дней:"),
+            pick_list(
+                WAITLIST_OFFER_OPTIONS.to_vec(),
+                Some(app.waitlist_offer_days),
+                Message::WaitlistOfferDaysSelected,
+            ),
+        ].spacing(10).align_y(Alignment::Center),
+        text("Блокировка при бездействии").size(26),
+        row![
+            text("Через, минут (0 — не блокировать):"),
+            pick_list(
+                IDLE_LOCK_OPTIONS.to_vec(),
+                Some(app.idle_lock_minutes),
+                Message::IdleLockMinutesSelected,
+            ),
+        ].spacing(10).align_y(Alignment::Center),
+        checkbox(app.require_admin_two_factor)
+            .label("Обязательная двухфакторная аутентификация для администраторов")
+            .on_toggle(Message::RequireAdminTwoFactorToggled),
+    ]
+    .spacing(10)
+}
+
+fn backup_status_text(app: &App) -> Element<'_, Message> {
+    if app.backup_in_progress {
+        text("Идёт резервное копирование...").into()
+    } else if let Some(error) = &app.backup_error {
+        text(error).color(Color::from_rgb8(255, 0, 0)).into()
+    } else if let Some(status) = &app.backup_status {
+        text(status).into()
+    } else {
+        Space::new().into()
+    }
+}
+
+// Размер файла в удобочитаемом виде
+fn format_size(size_bytes: u64) -> String {
+    if size_bytes >= 1024 * 1024 {
+        format!("{:.1} МБ", size_bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} КБ", size_bytes as f64 / 1024.0)
+    }
+}
+
+// Реквизиты организации для счетов и квитанций, по три поля в строке
+fn organization_section(app: &App) -> Element<'_, Message> {
+    let mut fields_column = Column::new().spacing(10);
+    for fields in OrganizationField::ALL.chunks(3) {
+        let mut fields_row = Row::new().spacing(10);
+        for field in fields {
+            let field = *field;
+            fields_row = fields_row.push(
+                Column::new()
+                    .spacing(3)
+                    .width(Length::FillPortion(1))
+                    .push(text(field.label()))
+                    .push(
+                        text_input(field.label(), app.organization.field(field))
+                            .on_input(move |value| Message::OrganizationFieldChanged(field, value)),
+                    ),
+            );
+        }
+        fields_column = fields_column.push(fields_row);
+    }
+
+    column![
+        text("Реквизиты для счетов и квитанций").size(26),
+        fields_column,
+        button(icon_button_content(
+            fa_icon_solid("floppy-disk").style(move |_| text::base(app.theme.target())),
+            "Сохранить реквизиты"
+        )).on_press(Message::SaveOrganization),
+    ]
+    .spacing(10)
+    .padding(10)
+    .into()
+}
+
+fn restore_section(app: &App) -> Element<'_, Message> {
+    let mut backups_column = Column::new().spacing(5);
+
+    if app.backup_files.is_empty() {
+        backups_column = backups_column.push(text("Резервные копии не найдены"));
+    } else {
+        for backup in &app.backup_files {
+            backups_column = backups_column.push(
+                Container::new(
+                    Row::new()
+                        .spacing(10)
+                        .align_y(Alignment::Center)
+                        .push(text(&backup.file_name).width(Length::Fill))
+                        .push(text(&backup.modified))
+                        .push(text(format_size(backup.size_bytes)).width(Length::Fixed(90.0)))
+                        .push(
+                            button(icon_button_content(
+                                fa_icon_solid("clock-rotate-left").style(move |_| text::base(app.theme.target())),
+                                "Восстановить"
+                            ))
+                            .on_press_maybe(
+                                (!app.restore_in_progress)
+                                    .then(|| Message::RequestRestoreBackup(backup.clone()))
+                            ),
+                        ),
+                )
+                .padding(5)
+                .style(move |_| bordered_box(app.theme.target())),
+            );
+        }
+    }
+
+    let mut section = column![
+        row![
+            text("Восстановление из резервной копии").size(26),
+            Space::new().width(Length::Fill),
+            button(icon_button_content(
+                fa_icon_solid("arrows-rotate").style(move |_| text::base(app.theme.target())),
+                "Обновить список"
+            ))
+            .on_press(Message::LoadBackupList),
+        ]
+        .align_y(Alignment::Center),
+        text("Перед восстановлением текущая база данных сохраняется в папку с бэкапами.").size(14),
+    ]
+    .spacing(10)
+    .padding(10);
+
+    if let Some(error) = &app.restore_error {
+        section = section.push(text(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    section
+        .push(Scrollable::new(backups_column).height(Length::Fixed(250.0)))
+        .into()
+}
+
+fn restore_confirm_modal<'a>(app: &'a App, backup: &'a BackupFile) -> Element<'a, Message> {
+    let actions = if app.restore_in_progress {
+        row![text("Идёт проверка и восстановление...")]
+    } else {
+        row![
+            button(icon_button_content(
+                fa_icon_solid("clock-rotate-left").style(move |_| text::base(app.theme.target())),
+                "Восстановить"
+            ))
+            .on_press(Message::ConfirmRestoreBackup),
+            button(icon_button_content(
+                fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+                "Отмена"
+            ))
+            .on_press(Message::CancelRestoreBackup),
+        ]
+        .spacing(10)
+    };
+
+    let modal = column![
+        text("Восстановление базы данных").size(24),
+        text(format!("Копия: {} от {}", backup.file_name, backup.modified)),
+        text("Все изменения, сделанные после создания копии, будут потеряны. \
+              Текущая база будет сохранена в папку с бэкапами, после восстановления потребуется войти заново."),
+        actions,
+    ]
+    .spacing(15)
+    .padding(20);
+
+    let modal_container = Container::new(modal)
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fixed(600.0));
+
+    Container::new(mouse_area(modal_container))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        })
+        .into()
+}
+
+// Матрица прав: строки — действия, столбцы — роли
+fn permissions_modal(app: &App) -> Element<'_, Message> {
+    let mut header = Row::new()
+        .spacing(10)
+        .push(Space::new().width(Length::Fixed(280.0)));
+    for role in Role::ALL {
+        header = header.push(text(role.to_string()).width(Length::Fixed(120.0)));
+    }
+
+    let mut matrix = Column::new().spacing(8).push(header);
+    for permission in Permission::ALL {
+        let mut permission_row = Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(text(permission.to_string()).width(Length::Fixed(280.0)));
+        for role in Role::ALL {
+            let mut cell = checkbox(app.role_can(role, permission));
+            if !permission.is_locked_for(role) {
+                cell = cell.on_toggle(move |allowed| Message::TogglePermission(role, permission, allowed));
+            }
+            permission_row = permission_row.push(Container::new(cell).width(Length::Fixed(120.0)));
+        }
+        matrix = matrix.push(permission_row);
+    }
+
+    let mut modal = column![
+        text("Права доступа").size(24),
+        text("Изменения применяются сразу. Право администратора менять матрицу снять нельзя.").size(14),
+        matrix,
+    ]
+    .spacing(15)
+    .padding(20);
+
+    if let Some(error) = &app.permissions_error {
+        modal = modal.push(text(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+    modal = modal.push(
+        button(icon_button_content(
+            fa_icon_solid("xmark").style(move |_| text::base(app.theme.target())),
+            "Закрыть"
+        ))
+        .on_press(Message::TogglePermissionsModal(false)),
+    );
+
+    let modal_container = Container::new(modal)
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fixed(1000.0));
+
+    Container::new(mouse_area(modal_container))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        })
+        .into()
+}
+
 // Функции для отображения цветовой палитры
 fn palette_grid<'a>(palette: &Extended) -> Element<'a, Message> {
     // Различные оттенки палитры
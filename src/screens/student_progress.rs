@@ -0,0 +1,425 @@
+use crate::app::state::{StudentAssignment, StudentProgress, Role, WaitlistStatus};
+use crate::app::update::icon_button_content;
+use crate::app::{App, Message};
+use iced::widget::container::{background, bordered_box};
+use iced::widget::{
+    Column, Container, Row, Scrollable, Space, Stack, Text, button, mouse_area, text, text_editor,
+};
+use iced::{Alignment, Color, Element, Length};
+use iced_font_awesome::fa_icon_solid;
+
+pub fn student_progress_screen(app: &App) -> Container<'_, Message> {
+    let is_parent = app
+        .current_user
+        .as_ref()
+        .is_some_and(|u| u.user_type == Role::Parent);
+
+    let title = if is_parent { "Обучение детей" } else { "Моё обучение" };
+
+    let mut main_column = Column::new()
+        .spacing(20)
+        .padding(20)
+        .push(Text::new(title).size(30));
+
+    if !app.error_message.is_empty() {
+        main_column = main_column.push(
+            Text::new(&app.error_message)
+                .size(16)
+                .color(Color::from_rgb8(255, 0, 0)),
+        );
+    }
+
+    let waitlist_entries: Vec<_> = app.waitlist.iter().filter(|w| w.status.is_active()).collect();
+    if !is_parent && !waitlist_entries.is_empty() {
+        let mut waitlist_column = Column::new()
+            .spacing(5)
+            .push(Text::new("Лист ожидания").size(22));
+        for entry in waitlist_entries {
+            let status_text = match (entry.status, entry.position) {
+                (WaitlistStatus::Offered, _) => Text::new(format!(
+                    "Освободилось место! Подтвердите запись у администратора до {}",
+                    entry
+                        .offer_deadline(app.waitlist_offer_days)
+                        .map_or(String::new(), |d| d.format("%d.%m.%Y %H:%M").to_string())
+                ))
+                .color(Color::from_rgb8(0, 150, 0)),
+                (_, Some(position)) => Text::new(format!("{}-й в очереди", position)),
+                _ => Text::new(entry.status.to_string()),
+            };
+            waitlist_column = waitlist_column.push(
+                Container::new(
+                    Row::new()
+                        .spacing(10)
+                        .align_y(Alignment::Center)
+                        .push(
+                            Column::new()
+                                .spacing(2)
+                                .push(Text::new(&entry.course_title).size(18))
+                                .push(status_text.size(14)),
+                        )
+                        .push(Space::new().width(Length::Fill))
+                        .push(
+                            button(icon_button_content(
+                                fa_icon_solid("xmark").style(move |_| text::base(app.theme.target())),
+                                "Отказаться",
+                            ))
+                            .on_press(Message::CloseWaitlistEntry(entry.id)),
+                        ),
+                )
+                .style(move |_| bordered_box(app.theme.target()))
+                .padding(10)
+                .width(Length::Fill),
+            );
+        }
+        if let Some(error) = &app.waitlist_error {
+            waitlist_column = waitlist_column.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+        }
+        main_column = main_column.push(waitlist_column);
+    }
+
+    let mut cards = Column::new().spacing(15);
+
+    if app.student_progress.is_empty() {
+        let empty_text = if is_parent {
+            "К вашей учётной записи не привязан ни один ребёнок."
+        } else {
+            "Данные об обучении пока отсутствуют."
+        };
+        cards = cards.push(
+            Text::new(empty_text)
+                .size(18)
+                .color(Color::from_rgb8(150, 150, 150)),
+        );
+    } else {
+        for progress in &app.student_progress {
+            cards = cards.push(progress_card(app, progress));
+        }
+    }
+
+    main_column = main_column.push(Scrollable::new(cards).height(Length::Fill));
+
+    let mut ui_stack = Stack::new().push(
+        Container::new(main_column)
+            .width(Length::Fill)
+            .height(Length::Fill),
+    );
+
+    if let Some(student_assignment) = &app.submission_assignment {
+        ui_stack = ui_stack.push(submission_modal(app, student_assignment));
+    }
+
+    Container::new(ui_stack)
+        .width(Length::Fill)
+        .height(Length::Fill)
+}
+
+fn submission_modal<'a>(
+    app: &'a App,
+    student_assignment: &'a StudentAssignment,
+) -> Element<'a, Message> {
+    let mut files_column = Column::new().spacing(5);
+
+    // Уже сохранённые файлы (при пересдаче новые файлы добавляются к ним)
+    if let Some(submission) = &student_assignment.submission {
+        for file in &submission.files {
+            files_column = files_column.push(Text::new(format!("• {}", file.file_name)));
+        }
+    }
+    for (index, (file_name, _)) in app.submission_files.iter().enumerate() {
+        files_column = files_column.push(
+            Row::new()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(Text::new(format!("• {} (новый)", file_name)))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(fa_icon_solid("xmark").style(move |_| text::base(app.theme.target())))
+                        .on_press(Message::RemoveSubmissionFile(index)),
+                ),
+        );
+    }
+
+    let mut modal = Column::new()
+        .spacing(15)
+        .padding(20)
+        .push(Text::new(format!("Сдача работы: {}", student_assignment.assignment.title)).size(24))
+        .push(Text::new(&student_assignment.assignment.description).size(14))
+        .push(Text::new("Ответ:"))
+        .push(
+            text_editor(&app.submission_text)
+                .on_action(Message::SubmissionTextEdited)
+                .height(Length::Fixed(200.0)),
+        )
+        .push(Text::new("Файлы:"))
+        .push(files_column)
+        .push(
+            button(icon_button_content(
+                fa_icon_solid("paperclip").style(move |_| text::base(app.theme.target())),
+                "Прикрепить файл",
+            ))
+            .on_press(Message::AttachSubmissionFile),
+        );
+
+    if let Some(error) = &app.submission_error {
+        modal = modal.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    modal = modal.push(
+        Row::new()
+            .spacing(10)
+            .push(
+                button(icon_button_content(
+                    fa_icon_solid("upload").style(move |_| text::base(app.theme.target())),
+                    "Отправить",
+                ))
+                .on_press(Message::SubmitAssignment),
+            )
+            .push(
+                button(icon_button_content(
+                    fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+                    "Отмена",
+                ))
+                .on_press(Message::CloseSubmissionModal),
+            ),
+    );
+
+    let modal_container = Container::new(Scrollable::new(modal))
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fixed(700.0))
+        .max_height(650.0);
+
+    Container::new(mouse_area(modal_container))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        })
+        .into()
+}
+
+fn progress_card<'a>(app: &'a App, progress: &'a StudentProgress) -> Element<'a, Message> {
+    let header = Container::new(Text::new(&progress.student.name).size(22))
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fill)
+        .padding(10);
+
+    let mut info = Column::new().spacing(5);
+
+    match &progress.group {
+        Some(group) => {
+            let conducted = progress.past_sessions.len();
+            info = info
+                .push(Text::new(format!("Группа: {}", group.name)))
+                .push(Text::new(format!(
+                    "Курс: {}",
+                    group.course_name.as_deref().unwrap_or("Неизвестно")
+                )))
+                .push(Text::new(format!(
+                    "Преподаватель: {}",
+                    group.teacher_name.as_deref().unwrap_or("Неизвестно")
+                )))
+                .push(Text::new(format!(
+                    "Пройдено занятий: {} из {}",
+                    conducted, progress.total_lessons
+                )))
+                .push(Text::new(format!(
+                    "Посещено занятий: {} из {}",
+                    progress.attended_lessons, conducted
+                )));
+            if progress.carried_attendance > 0 {
+                info = info.push(Text::new(format!(
+                    "Засчитано занятий до перевода: {}",
+                    progress.carried_attendance
+                )));
+            }
+        }
+        None => {
+            info = info.push(Text::new("Студент не состоит ни в одной группе."));
+        }
+    }
+
+    // История зачислений: заявки, переводы, отчисления и завершённые курсы
+    let mut history_column = Column::new()
+        .spacing(5)
+        .push(Text::new("История обучения").size(18));
+    for enrollment in &progress.enrollments {
+        let mut line = format!(
+            "{} — {}, с {}",
+            enrollment.group_name,
+            enrollment.status,
+            enrollment.enrolled_at.as_deref().unwrap_or("—")
+        );
+        if let Some(ended_at) = &enrollment.ended_at {
+            line = format!("{} по {}", line, ended_at);
+        }
+        if let Some(from) = &enrollment.transferred_from {
+            line = format!("{}; переведён из «{}»", line, from);
+        }
+        if let Some(to) = &enrollment.transferred_to {
+            line = format!("{}; переведён в «{}»", line, to);
+        }
+        history_column = history_column.push(Text::new(line).size(14));
+    }
+
+    // Проведённые занятия с отметкой о посещении
+    let mut sessions_column = Column::new()
+        .spacing(5)
+        .push(Text::new("Проведённые занятия").size(18));
+
+    if progress.past_sessions.is_empty() {
+        sessions_column = sessions_column.push(Text::new("Занятия ещё не проводились."));
+    } else {
+        for session in &progress.past_sessions {
+            let (icon, status) = match progress.was_present(session) {
+                Some(true) => ("circle-check", "Присутствовал"),
+                Some(false) => ("circle-xmark", "Отсутствовал"),
+                None => ("circle-question", "Нет отметки"),
+            };
+            sessions_column = sessions_column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(fa_icon_solid(icon).style(move |_| text::base(app.theme.target())))
+                    .push(Text::new(format!(
+                        "Урок {}: {} — {}",
+                        session
+                            .lesson_number
+                            .map_or("?".to_string(), |n| n.to_string()),
+                        session.lesson_title.as_deref().unwrap_or("Без названия"),
+                        session.date
+                    )))
+                    .push(Space::new().width(Length::Fill))
+                    .push(Text::new(status)),
+            );
+        }
+    }
+
+    // Задания курса, по которым сдаются работы
+    let can_submit = app
+        .current_user
+        .as_ref()
+        .is_some_and(|u| u.id == progress.student.id);
+
+    let mut assignments_column = Column::new()
+        .spacing(5)
+        .push(Text::new("Задания").size(18));
+
+    if progress.assignments.is_empty() {
+        assignments_column = assignments_column.push(Text::new("Заданий для сдачи нет."));
+    } else {
+        for student_assignment in &progress.assignments {
+            let status = match &student_assignment.submission {
+                None => "Не сдано".to_string(),
+                Some(submission) => match submission.score {
+                    None => format!("Сдано {}, ожидает проверки", submission.submitted_at),
+                    Some(score) => {
+                        let mut status = format!("Оценка: {} из 100", score);
+                        if let Some(graded_at) = &submission.graded_at {
+                            status.push_str(&format!(" (проверено {})", graded_at));
+                        }
+                        if let Some(comment) = submission.comment.as_ref().filter(|c| !c.is_empty()) {
+                            status.push_str(&format!(" — {}", comment));
+                        }
+                        status
+                    }
+                },
+            };
+
+            let mut assignment_row = Row::new()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(
+                    Column::new()
+                        .spacing(2)
+                        .push(Text::new(format!(
+                            "Урок {} «{}»: {} ({})",
+                            student_assignment.lesson_number,
+                            student_assignment.lesson_title,
+                            student_assignment.assignment.title,
+                            student_assignment.assignment.assignment_type
+                        )))
+                        .push(Text::new(status).size(14))
+                        .width(Length::Fill),
+                );
+
+            let is_graded = student_assignment
+                .submission
+                .as_ref()
+                .is_some_and(|s| s.score.is_some());
+            if can_submit && !is_graded {
+                let label = if student_assignment.submission.is_some() {
+                    "Пересдать"
+                } else {
+                    "Сдать работу"
+                };
+                assignment_row = assignment_row.push(
+                    button(icon_button_content(
+                        fa_icon_solid("upload").style(move |_| text::base(app.theme.target())),
+                        label,
+                    ))
+                    .on_press(Message::OpenSubmissionModal(student_assignment.clone())),
+                );
+            }
+
+            assignments_column = assignments_column.push(assignment_row);
+        }
+    }
+
+    // Полученные сертификаты, которые можно выгрузить в PDF
+    let mut certificates_column = Column::new()
+        .spacing(5)
+        .push(Text::new("Сертификаты").size(18));
+
+    if progress.certificates.is_empty() {
+        certificates_column = certificates_column.push(Text::new("Сертификатов пока нет."));
+    } else {
+        for certificate in &progress.certificates {
+            certificates_column = certificates_column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(Text::new(format!(
+                        "{} — {} (выдан {})",
+                        certificate.course_title, certificate.grade, certificate.issue_date
+                    )))
+                    .push(Space::new().width(Length::Fill))
+                    .push(
+                        button(icon_button_content(
+                            fa_icon_solid("file-pdf")
+                                .style(move |_| text::base(app.theme.target())),
+                            "PDF",
+                        ))
+                        .on_press(Message::GenerateCertificatePdf(
+                            certificate.clone(),
+                            progress.student.clone(),
+                        )),
+                    ),
+            );
+        }
+    }
+
+    let mut card = Column::new()
+        .spacing(10)
+        .push(header)
+        .push(Container::new(info).padding(10));
+    if !progress.enrollments.is_empty() {
+        card = card.push(Container::new(history_column).padding(10));
+    }
+
+    Container::new(
+        card.push(Container::new(sessions_column).padding(10))
+            .push(Container::new(assignments_column).padding(10))
+            .push(Container::new(certificates_column).padding(10)),
+    )
+    .style(move |_| bordered_box(app.theme.target()))
+    .width(Length::Fill)
+    .padding(10)
+    .into()
+}
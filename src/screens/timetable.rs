@@ -0,0 +1,281 @@
+use crate::app::state::Weekday;
+use crate::app::update::icon_button_content;
+use crate::app::{App, Message};
+use crate::schedule::{WEEKDAY_SHORT_NAMES, end_time};
+use chrono::Duration;
+use iced::widget::container::{background, bordered_box};
+use iced::widget::{
+    Column, Container, Row, Scrollable, Space, Text, TextInput, button, mouse_area, pick_list,
+    text,
+};
+use iced::{Alignment, Color, Element, Length};
+use iced_font_awesome::fa_icon_solid;
+
+// Затемнённый фон под модальным окном
+fn modal_overlay<'a>(modal_container: Container<'a, Message>) -> Element<'a, Message> {
+    Container::new(mouse_area(modal_container))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        })
+        .into()
+}
+
+/// Модальное окно расписания группы: еженедельные слоты и сформированные по ним занятия
+pub fn schedule_modal(app: &App) -> Element<'_, Message> {
+    let group_name = app
+        .schedule_group
+        .as_ref()
+        .map_or("".to_string(), |g| g.name.clone());
+
+    let mut slots_column = Column::new()
+        .spacing(5)
+        .push(Text::new("Еженедельные занятия").size(18));
+
+    if app.schedule_slots.is_empty() {
+        slots_column = slots_column.push(Text::new("Расписание ещё не задано."));
+    } else {
+        for slot in &app.schedule_slots {
+            let room = if slot.room.is_empty() {
+                "аудитория не указана".to_string()
+            } else {
+                format!("ауд. {}", slot.room)
+            };
+            slots_column = slots_column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(Text::new(format!(
+                        "{}, {}–{} ({} мин), {}",
+                        Weekday(slot.weekday),
+                        slot.start_time,
+                        end_time(&slot.start_time, slot.duration_minutes),
+                        slot.duration_minutes,
+                        room
+                    )))
+                    .push(Space::new().width(Length::Fill))
+                    .push(
+                        button(fa_icon_solid("trash").style(move |_| text::base(app.theme.target())))
+                            .on_press(Message::DeleteScheduleSlot(slot.id)),
+                    ),
+            );
+        }
+    }
+
+    let add_slot_row = Row::new()
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .push(
+            pick_list(
+                &Weekday::ALL[..],
+                app.new_slot_weekday,
+                Message::NewSlotWeekdaySelected,
+            )
+            .placeholder("День недели"),
+        )
+        .push(
+            TextInput::new("Начало (ЧЧ:ММ)", &app.new_slot_start)
+                .on_input(Message::NewSlotStartChanged)
+                .width(Length::Fixed(130.0)),
+        )
+        .push(
+            TextInput::new("Минут", &app.new_slot_duration)
+                .on_input(Message::NewSlotDurationChanged)
+                .width(Length::Fixed(80.0)),
+        )
+        .push(
+            TextInput::new("Аудитория", &app.new_slot_room)
+                .on_input(Message::NewSlotRoomChanged)
+                .width(Length::Fill),
+        )
+        .push(
+            button(icon_button_content(
+                fa_icon_solid("plus").style(move |_| text::base(app.theme.target())),
+                "Добавить",
+            ))
+            .on_press(Message::AddScheduleSlot),
+        );
+
+    let generate_row = Row::new()
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .push(Text::new("Начать с даты:"))
+        .push(
+            TextInput::new("ГГГГ-ММ-ДД", &app.schedule_start_date)
+                .on_input(Message::ScheduleStartDateChanged)
+                .width(Length::Fixed(130.0)),
+        )
+        .push(
+            button(icon_button_content(
+                fa_icon_solid("arrows-rotate").style(move |_| text::base(app.theme.target())),
+                "Сформировать занятия",
+            ))
+            .on_press(Message::GeneratePlannedSessions),
+        );
+
+    let mut sessions_column = Column::new()
+        .spacing(5)
+        .push(Text::new("Запланированные занятия").size(18));
+
+    if app.schedule_planned_sessions.is_empty() {
+        sessions_column = sessions_column.push(Text::new("Занятия ещё не сформированы."));
+    } else {
+        for session in &app.schedule_planned_sessions {
+            let status = if session.conducted { "проведено" } else { "" };
+            sessions_column = sessions_column.push(
+                Row::new()
+                    .spacing(10)
+                    .push(Text::new(format!(
+                        "{} {}–{}  Урок {}: {}",
+                        session.date,
+                        session.start_time,
+                        end_time(&session.start_time, session.duration_minutes),
+                        session.lesson_number,
+                        session.lesson_title
+                    )))
+                    .push(Space::new().width(Length::Fill))
+                    .push(Text::new(status)),
+            );
+        }
+    }
+
+    let mut modal = Column::new()
+        .spacing(15)
+        .padding(20)
+        .push(Text::new(format!("Расписание группы: {}", group_name)).size(24))
+        .push(slots_column)
+        .push(add_slot_row);
+
+    if let Some(error) = &app.schedule_error {
+        modal = modal.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    modal = modal
+        .push(generate_row)
+        .push(Scrollable::new(sessions_column).height(Length::Fixed(250.0)))
+        .push(
+            button(icon_button_content(
+                fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+                "Закрыть",
+            ))
+            .on_press(Message::CloseScheduleModal),
+        );
+
+    let modal_container = Container::new(Scrollable::new(modal))
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fixed(850.0))
+        .max_height(700.0);
+
+    modal_overlay(modal_container)
+}
+
+/// Модальное окно с календарём занятий на неделю
+pub fn week_calendar_modal(app: &App) -> Element<'_, Message> {
+    let week_end = app.calendar_week_start + Duration::days(6);
+
+    let navigation = Row::new()
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .push(
+            button(fa_icon_solid("chevron-left").style(move |_| text::base(app.theme.target())))
+                .on_press(Message::CalendarPrevWeek),
+        )
+        .push(
+            Text::new(format!(
+                "{} — {}",
+                app.calendar_week_start.format("%d.%m.%Y"),
+                week_end.format("%d.%m.%Y")
+            ))
+            .size(20),
+        )
+        .push(
+            button(fa_icon_solid("chevron-right").style(move |_| text::base(app.theme.target())))
+                .on_press(Message::CalendarNextWeek),
+        );
+
+    let mut week_row = Row::new().spacing(5).height(Length::Fill);
+
+    for (offset, day_name) in WEEKDAY_SHORT_NAMES.iter().enumerate() {
+        let date = app.calendar_week_start + Duration::days(offset as i64);
+        let date_str = date.format("%Y-%m-%d").to_string();
+
+        let mut day_column = Column::new()
+            .spacing(5)
+            .push(Text::new(format!("{} {}", day_name, date.format("%d.%m"))).size(18));
+
+        for session in app.calendar_sessions.iter().filter(|s| s.date == date_str) {
+            let mut session_info = Column::new()
+                .spacing(2)
+                .push(Text::new(format!(
+                    "{}–{}",
+                    session.start_time,
+                    end_time(&session.start_time, session.duration_minutes)
+                )))
+                .push(Text::new(&session.group_name).size(14))
+                .push(
+                    Text::new(format!(
+                        "Урок {}: {}",
+                        session.lesson_number, session.lesson_title
+                    ))
+                    .size(12),
+                );
+            if !session.room.is_empty() {
+                session_info = session_info.push(Text::new(format!("ауд. {}", session.room)).size(12));
+            }
+            if let Some(teacher_name) = &session.teacher_name {
+                session_info = session_info.push(Text::new(teacher_name).size(12));
+            }
+            if session.conducted {
+                session_info = session_info.push(
+                    Text::new("проведено")
+                        .size(12)
+                        .color(Color::from_rgb8(0, 150, 0)),
+                );
+            }
+
+            day_column = day_column.push(
+                Container::new(session_info)
+                    .style(move |_| bordered_box(app.theme.target()))
+                    .padding(5)
+                    .width(Length::Fill),
+            );
+        }
+
+        week_row = week_row.push(
+            Container::new(Scrollable::new(day_column))
+                .style(move |_| bordered_box(app.theme.target()))
+                .padding(5)
+                .width(Length::FillPortion(1))
+                .height(Length::Fill),
+        );
+    }
+
+    let modal = Column::new()
+        .spacing(15)
+        .padding(20)
+        .push(Text::new("Календарь занятий").size(24))
+        .push(navigation)
+        .push(week_row)
+        .push(
+            button(icon_button_content(
+                fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+                "Закрыть",
+            ))
+            .on_press(Message::CloseWeekCalendar),
+        );
+
+    let modal_container = Container::new(modal)
+        .style(move |_| bordered_box(app.theme.target()))
+        .width(Length::Fixed(1100.0))
+        .height(Length::Fixed(650.0));
+
+    modal_overlay(modal_container)
+}
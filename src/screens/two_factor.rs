@@ -0,0 +1,127 @@
+use crate::app::update::icon_button_content;
+use crate::app::{App, Message};
+use iced::widget::{Column, Row, Space, Text, button, column, image, text, text_input};
+use iced::{Center, Color, Length, widget::Container};
+use iced_font_awesome::fa_icon_solid;
+
+/// Второй шаг входа: пароль уже проверен, нужен код из приложения или код восстановления.
+pub fn two_factor_screen(app: &App) -> Container<'_, Message> {
+    let mut content = column![
+        Space::new().height(Length::Fill),
+        text("Подтверждение входа").size(30),
+        text("Введите шестизначный код из приложения-аутентификатора или один из кодов восстановления."),
+        text_input("Код", &app.two_factor_code_input)
+            .on_input(Message::TwoFactorCodeChanged)
+            .on_submit(Message::SubmitTwoFactorCode)
+            .padding(10)
+            .size(18)
+            .width(Length::Fixed(350.0)),
+        button(icon_button_content(
+            fa_icon_solid("shield-halved").style(move |_| text::base(app.theme.target())),
+            "Подтвердить"
+        ))
+        .on_press(Message::SubmitTwoFactorCode)
+        .padding(10),
+    ]
+    .spacing(15)
+    .width(Length::Fill)
+    .align_x(Center);
+
+    if let Some(error) = &app.two_factor_error {
+        content = content.push(text(error).size(18).color(Color::from_rgb8(255, 0, 0)));
+    }
+
+    content = content.push(Space::new().height(Length::Fill)).push(
+        button(icon_button_content(
+            fa_icon_solid("arrow-left").style(move |_| text::base(app.theme.target())),
+            "Назад ко входу",
+        ))
+        .on_press(Message::CancelTwoFactorLogin)
+        .padding(10),
+    );
+
+    Container::new(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(40)
+}
+
+/// QR-код и секрет для приложения-аутентификатора и поле для проверочного кода.
+pub fn two_factor_setup_form(app: &App) -> Column<'_, Message> {
+    let mut form = Column::new().spacing(10).push(Text::new(
+        "Отсканируйте QR-код в приложении-аутентификаторе (Google Authenticator, Яндекс Ключ и т.п.) \
+         или введите секрет вручную, затем введите код, который покажет приложение.",
+    ));
+
+    if let Some(qr) = &app.two_factor_qr {
+        form = form.push(
+            image(qr.clone())
+                .width(Length::Fixed(220.0))
+                .height(Length::Fixed(220.0)),
+        );
+    }
+    if let Some(secret) = &app.two_factor_setup_secret {
+        // По четыре символа, чтобы секрет было проще перепечатать
+        let grouped: Vec<String> = secret
+            .as_bytes()
+            .chunks(4)
+            .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+            .collect();
+        form = form.push(Text::new(format!("Секрет: {}", grouped.join(" "))).size(16));
+    }
+
+    let mut buttons = Row::new().spacing(10).push(
+        button(icon_button_content(
+            fa_icon_solid("check").style(move |_| text::base(app.theme.target())),
+            "Включить",
+        ))
+        .on_press(Message::ConfirmTwoFactorSetup),
+    );
+    if !app.two_factor_setup_required {
+        buttons = buttons.push(
+            button(icon_button_content(
+                fa_icon_solid("ban").style(move |_| text::base(app.theme.target())),
+                "Отмена",
+            ))
+            .on_press(Message::CancelTwoFactorSetup),
+        );
+    }
+
+    form = form
+        .push(
+            text_input("Код из приложения", &app.two_factor_code_input)
+                .on_input(Message::TwoFactorCodeChanged)
+                .on_submit(Message::ConfirmTwoFactorSetup)
+                .padding(10),
+        )
+        .push(buttons);
+
+    if let Some(error) = &app.two_factor_error {
+        form = form.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+    form
+}
+
+/// Обязательная настройка для администратора: пока второй фактор не включён, остальное недоступно.
+pub fn two_factor_setup_screen(app: &App) -> Container<'_, Message> {
+    let content = column![
+        text("Двухфакторная аутентификация").size(30),
+        text("Для учётных записей администраторов включение второго фактора обязательно."),
+        Container::new(two_factor_setup_form(app)).width(Length::Fixed(520.0)),
+        Space::new().height(Length::Fill),
+        button(icon_button_content(
+            fa_icon_solid("right-from-bracket").style(move |_| text::base(app.theme.target())),
+            "Выйти"
+        ))
+        .on_press(Message::Logout)
+        .padding(10),
+    ]
+    .spacing(15)
+    .width(Length::Fill)
+    .align_x(Center);
+
+    Container::new(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(40)
+}
@@ -10,41 +10,78 @@ use iced::{
 use iced::{Color, ContentFit, Theme}; // Добавляем Theme
 use iced_font_awesome::fa_icon_solid;
 // Импортируем button как button_widget, чтобы не конфликтовать с Button
-use crate::app::state::{DEFAULT_AVATAR, PATH_TO_DB};
+use crate::app::state::{APPROVAL_ROLES, DEFAULT_AVATAR, GroupStatus, PATH_TO_DB, RegistrationRequest, Role};
 use crate::app::update::icon_button_content;
 use crate::app::{App, Message};
 use crate::db;
 
+// Строка очереди заявок: заявитель, дата прошлого отказа (если был) и кнопка рассмотрения
+fn registration_request_view<'a>(app: &'a App, request: &'a RegistrationRequest) -> Container<'a, Message> {
+    let mut info = Column::new()
+        .spacing(3)
+        .width(Length::Fill)
+        .push(Text::new(&request.user.name).size(18))
+        .push(Text::new(format!("Email: {}", request.user.email)))
+        .push(Text::new(format!("Дата рождения: {}", request.user.birthday)));
+
+    match &request.review {
+        Some(review) => {
+            info = info.push(
+                Text::new(format!(
+                    "Отклонена {} ({}): {}",
+                    review.decided_at,
+                    review.decided_by.as_deref().unwrap_or("администратор удалён"),
+                    review.reason.as_deref().unwrap_or("без причины")
+                ))
+                .color(Color::from_rgb8(255, 0, 0)),
+            );
+        }
+        None => {
+            info = info.push(Text::new("Ожидает рассмотрения").color(Color::from_rgb8(150, 150, 150)));
+        }
+    }
+
+    Container::new(
+        Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(info)
+            .push(
+                button(icon_button_content(
+                    fa_icon_solid("user-check").style(move |_| text::base(app.theme.target())),
+                    "Рассмотреть",
+                ))
+                .on_press(Message::OpenRegistrationReview(request.user.clone())),
+            ),
+    )
+    .style(move |_| bordered_box(app.theme.target()))
+    .width(Length::Fill)
+    .padding(10)
+}
+
 pub fn user_list_screen(app: &App) -> Container<Message> {
     let conn = rusqlite::Connection::open(PATH_TO_DB).unwrap();
     let users =
-        db::get_all_users_for_list(&conn, app.user_type_filter.as_deref()).unwrap_or_default();
+        db::get_all_users_for_list(&conn, app.user_type_filter).unwrap_or_default();
 
     let mut list = Column::new().spacing(15);
 
-    let filter_options = vec![
-        "Все".to_string(),
-        "student".to_string(),
-        "parent".to_string(),
-        "teacher".to_string(),
-        "admin".to_string(),
-        "unconfirmed".to_string(),
-    ];
+    // «Все» плюс роли; выбранная подпись переводится обратно в роль
+    let filter_options: Vec<String> = std::iter::once("Все".to_string())
+        .chain(Role::ALL.iter().map(|role| role.to_string()))
+        .collect();
 
     let current_filter_selection = app
         .user_type_filter
-        .clone()
-        .unwrap_or_else(|| "Все".to_string());
+        .map_or_else(|| "Все".to_string(), |role| role.to_string());
 
     let filter_picklist = PickList::new(
         filter_options,
         Some(current_filter_selection),
         |selection| {
-            if selection == "Все" {
-                Message::UserTypeFilterChanged(None)
-            } else {
-                Message::UserTypeFilterChanged(Some(selection))
-            }
+            Message::UserTypeFilterChanged(
+                Role::ALL.into_iter().find(|role| role.to_string() == selection),
+            )
         },
     )
     .placeholder("Фильтровать по типу");
@@ -77,7 +114,7 @@ pub fn user_list_screen(app: &App) -> Container<Message> {
         .clip(true);
 
         let header = Row::new()
-            .push(if user.user_type == "parent".to_string() {
+            .push(if user.user_type == Role::Parent {
                 Row::new()
                     .spacing(10)
                     .push(
@@ -106,10 +143,22 @@ pub fn user_list_screen(app: &App) -> Container<Message> {
                 )
             })
             .push(Space::new().width(Length::Fill))
+            .push(
+                // Свой пароль администратор меняет в профиле
+                button(icon_button_content(
+                    fa_icon_solid("key").style(move |_| text::base(app.theme.target())),
+                    "Сбросить пароль",
+                ))
+                .on_press_maybe(
+                    (app.current_user.as_ref().map(|u| u.id) != Some(user.id))
+                        .then_some(Message::ResetUserPassword(user.id)),
+                ),
+            )
             .push(
                 Button::new(fa_icon_solid("xmark").style(move |_| text::base(&app.theme.target())))
                     .on_press(Message::DeleteUser(user.email.clone())),
             )
+            .spacing(10)
             .width(Length::Fill);
 
         let mut info = Column::new()
@@ -119,7 +168,7 @@ pub fn user_list_screen(app: &App) -> Container<Message> {
             .push(Text::new(format!("Дата рождения: {}", user.birthday)))
             .push(Text::new(format!("Тип: {}", user.user_type)));
 
-        if user.user_type == "student".to_string() {
+        if user.user_type == Role::Student {
             if let Some(group_names) = &user.group_id {
                 info = info.push(Text::new(format!("Группа: {}", group_names)));
             } else {
@@ -127,7 +176,7 @@ pub fn user_list_screen(app: &App) -> Container<Message> {
             }
         }
 
-        if user.user_type == "teacher".to_string() {
+        if user.user_type == Role::Teacher {
             if let Some(group_names) = &user.group_id {
                 info = info.push(Text::new(format!("Группа: {}", group_names)));
             } else {
@@ -135,7 +184,7 @@ pub fn user_list_screen(app: &App) -> Container<Message> {
             }
         }
 
-        if user.user_type == "parent" {
+        if user.user_type == Role::Parent {
             if let Some(count) = user.child_count {
                 if count > 0 {
                     info = info.push(Text::new(format!("Количество детей: {}", count)));
@@ -171,7 +220,21 @@ pub fn user_list_screen(app: &App) -> Container<Message> {
         );
     }
 
-    let scrollable = Scrollable::new(list.padding(20))
+    // Очередь заявок на регистрацию — над общим списком
+    let mut requests_column = Column::new().spacing(10).padding([0, 20]);
+    if let Some(error) = &app.reset_password_error {
+        requests_column = requests_column.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+    }
+    if !app.registration_requests.is_empty() {
+        requests_column = requests_column.push(
+            Text::new(format!("Заявки на регистрацию ({})", app.registration_requests.len())).size(22),
+        );
+        for request in &app.registration_requests {
+            requests_column = requests_column.push(registration_request_view(app, request));
+        }
+    }
+
+    let scrollable = Scrollable::new(Column::new().push(requests_column).push(list.padding(20)))
         .width(Length::Fill)
         .height(Length::Fill);
 
@@ -196,7 +259,10 @@ pub fn user_list_screen(app: &App) -> Container<Message> {
                 TextInput::new("Дата рождения", &app.edit_user_birthday)
                     .on_input(Message::EditUserBirthdayChanged),
             )
-            .push(TextInput::new("Тип", &app.edit_user_type).on_input(Message::EditUserTypeChanged))
+            .push(
+                PickList::new(&Role::ALL[..], Some(app.edit_user_type), Message::EditUserTypeChanged)
+                    .placeholder("Тип"),
+            )
             .push(
                 Row::new()
                     .spacing(10)
@@ -384,6 +450,141 @@ pub fn user_list_screen(app: &App) -> Container<Message> {
         current_ui_stack = current_ui_stack.push(modal_overlay);
     }
 
+    // Модалка рассмотрения заявки на регистрацию
+    if let Some(applicant) = &app.reviewing_registration {
+        let mut modal_content = Column::new()
+            .spacing(10)
+            .push(Text::new("Заявка на регистрацию").size(24))
+            .push(Text::new(format!("{} ({})", applicant.name, applicant.email)))
+            .push(
+                PickList::new(&APPROVAL_ROLES[..], app.review_role, Message::ReviewRoleSelected)
+                    .placeholder("Роль")
+                    .width(Length::Fill),
+            );
+
+        if app.review_role == Some(Role::Student) {
+            let active_groups: Vec<_> = app
+                .all_groups
+                .iter()
+                .filter(|group| group.status == GroupStatus::Active)
+                .cloned()
+                .collect();
+            modal_content = modal_content
+                .push(
+                    PickList::new(
+                        app.registration_parents.clone(),
+                        app.review_parent.clone(),
+                        Message::ReviewParentSelected,
+                    )
+                    .placeholder("Родитель (необязательно)")
+                    .width(Length::Fill),
+                )
+                .push(
+                    PickList::new(active_groups, app.review_group.clone(), Message::ReviewGroupSelected)
+                        .placeholder("Группа (необязательно)")
+                        .width(Length::Fill),
+                );
+        }
+
+        modal_content = modal_content
+            .push(
+                TextInput::new("Комментарий или причина отказа", &app.review_reason)
+                    .on_input(Message::ReviewReasonChanged),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(
+                        button(icon_button_content(
+                            fa_icon_solid("check").style(move |_| text::base(app.theme.target())),
+                            "Подтвердить",
+                        ))
+                        .on_press(Message::ApproveRegistration),
+                    )
+                    .push(
+                        button(icon_button_content(
+                            fa_icon_solid("user-xmark").style(move |_| text::base(app.theme.target())),
+                            "Отклонить",
+                        ))
+                        .on_press(Message::RejectRegistration),
+                    )
+                    .push(
+                        button(icon_button_content(
+                            fa_icon_solid("ban").style(move |_| text::base(app.theme.target())),
+                            "Отмена",
+                        ))
+                        .on_press(Message::CloseRegistrationReview),
+                    ),
+            );
+
+        if let Some(error) = &app.review_error {
+            modal_content = modal_content.push(Text::new(error).color(Color::from_rgb8(255, 0, 0)));
+        }
+
+        let modal = Container::new(modal_content)
+            .style(move |_| bordered_box(app.theme.target()))
+            .padding(20)
+            .width(Length::Fixed(500.0));
+
+        let modal_overlay = Container::new(
+            mouse_area(Container::new(modal).center(Length::Fill).padding(40))
+                .on_press(Message::CloseRegistrationReview),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        });
+
+        current_ui_stack = current_ui_stack.push(modal_overlay);
+    }
+
+    // Временный пароль после сброса — показывается администратору один раз
+    if let Some((name, temporary_password)) = &app.temporary_password {
+        let modal = Container::new(
+            Column::new()
+                .spacing(15)
+                .push(Text::new("Пароль сброшен").size(24))
+                .push(Text::new(format!("Временный пароль для пользователя {}:", name)))
+                .push(Text::new(temporary_password).size(24))
+                .push(Text::new(
+                    "Передайте его пользователю. При следующем входе он должен будет задать свой пароль.",
+                ))
+                .push(
+                    button(icon_button_content(
+                        fa_icon_solid("check").style(move |_| text::base(app.theme.target())),
+                        "Готово",
+                    ))
+                    .on_press(Message::DismissTemporaryPassword),
+                ),
+        )
+        .style(move |_| bordered_box(app.theme.target()))
+        .padding(20)
+        .width(Length::Fixed(450.0));
+
+        let modal_overlay = Container::new(
+            mouse_area(Container::new(modal).center(Length::Fill).padding(40))
+                .on_press(Message::DismissTemporaryPassword),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(move |_| {
+            background(Color {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.7,
+            })
+        });
+
+        current_ui_stack = current_ui_stack.push(modal_overlay);
+    }
+
     // Возвращаем итоговый стек UI
     Container::new(current_ui_stack)
         .center_x(Length::Fill)
@@ -0,0 +1,187 @@
+//! Одноразовые коды для двухфакторной аутентификации по RFC 6238 (TOTP): HMAC-SHA1,
+//! 6 цифр, шаг 30 секунд — параметры по умолчанию, которые понимают все приложения-
+//! аутентификаторы. Секрет хранится в `Users.totp_secret` в base32.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base32::Alphabet;
+use hmac::{Hmac, KeyInit, Mac};
+use qrcodegen::{QrCode, QrCodeEcc};
+use sha1::Sha1;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Под каким именем учётная запись появится в приложении-аутентификаторе
+pub const ISSUER: &str = "Platform";
+/// Длина шага, секунд
+pub const TIME_STEP: u64 = 30;
+/// Количество цифр в коде
+pub const DIGITS: u32 = 6;
+/// Сколько соседних шагов допускается из-за расхождения часов (в каждую сторону)
+const ALLOWED_SKEW: u64 = 1;
+
+const SECRET_ALPHABET: Alphabet = Alphabet::Rfc4648 { padding: false };
+
+/// Новый случайный секрет (160 бит, как рекомендует RFC 4226) в base32.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    OsRng.fill_bytes(&mut bytes);
+    base32::encode(SECRET_ALPHABET, &bytes)
+}
+
+/// Код HOTP (RFC 4226) для значения счётчика.
+pub fn hotp(key: &[u8], counter: u64, digits: u32) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC принимает ключ любой длины");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // Динамическое усечение: последние 4 бита хэша задают смещение 31-битного числа
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+    format!("{:0width$}", binary % 10u32.pow(digits), width = digits as usize)
+}
+
+/// Текущее время в секундах Unix.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Проверяет введённый код. Возвращает номер шага, которому код соответствует, — его нужно
+/// запомнить, чтобы тот же код нельзя было ввести второй раз. Пробелы в коде не важны.
+pub fn verify(secret: &str, code: &str, unix_time: u64) -> Option<u64> {
+    let key = base32::decode(SECRET_ALPHABET, secret)?;
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != DIGITS as usize {
+        return None;
+    }
+    let current = unix_time / TIME_STEP;
+    (current.saturating_sub(ALLOWED_SKEW)..=current + ALLOWED_SKEW)
+        .find(|&counter| constant_time_eq(hotp(&key, counter, DIGITS).as_bytes(), code.as_bytes()))
+}
+
+/// Ссылка `otpauth://` для добавления учётной записи в приложение-аутентификатор.
+pub fn provisioning_uri(secret: &str, account: &str, issuer: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer_label}:{account}?secret={secret}&issuer={issuer_param}&algorithm=SHA1&digits={DIGITS}&period={TIME_STEP}",
+        issuer_label = percent_encode(issuer),
+        account = percent_encode(account),
+        issuer_param = percent_encode(issuer),
+    )
+}
+
+/// QR-код ссылки в виде RGBA-картинки (ширина, высота, пиксели) с белой рамкой,
+/// по `scale` пикселей на модуль.
+pub fn qr_code_rgba(uri: &str, scale: u32) -> Option<(u32, u32, Vec<u8>)> {
+    let qr = QrCode::encode_text(uri, QrCodeEcc::Medium).ok()?;
+    let border = 4;
+    let modules = (qr.size() + border * 2) as u32;
+    let side = modules * scale;
+
+    let mut pixels = Vec::with_capacity((side * side * 4) as usize);
+    for y in 0..side {
+        for x in 0..side {
+            let module_x = (x / scale) as i32 - border;
+            let module_y = (y / scale) as i32 - border;
+            // get_module за пределами кода возвращает false — это и есть рамка
+            let value = if qr.get_module(module_x, module_y) { 0 } else { 255 };
+            pixels.extend_from_slice(&[value, value, value, 255]);
+        }
+    }
+    Some((side, side, pixels))
+}
+
+// Кодирование для otpauth-ссылки: всё, кроме неизменяемых символов RFC 3986
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Ключ из приложений RFC 4226 и RFC 6238 (ASCII "12345678901234567890")
+    const KEY: &[u8] = b"12345678901234567890";
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn hotp_matches_rfc4226_appendix_d() {
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314",
+            "254676", "287922", "162583", "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp(KEY, counter as u64, 6), *code, "счётчик {}", counter);
+        }
+    }
+
+    #[test]
+    fn totp_matches_rfc6238_appendix_b_sha1() {
+        let expected = [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+            (20000000000, "65353130"),
+        ];
+        for (time, code) in expected {
+            assert_eq!(hotp(KEY, time / TIME_STEP, 8), code, "время {}", time);
+        }
+    }
+
+    #[test]
+    fn secret_is_base32_of_key() {
+        assert_eq!(base32::encode(SECRET_ALPHABET, KEY), SECRET);
+        assert_eq!(generate_secret().len(), 32);
+    }
+
+    #[test]
+    fn verify_accepts_one_step_of_clock_skew() {
+        let code = hotp(KEY, 2, 6);
+        assert_eq!(verify(SECRET, &code, 2 * TIME_STEP), Some(2));
+        assert_eq!(verify(SECRET, &code, TIME_STEP), Some(2));
+        assert_eq!(verify(SECRET, &code, 3 * TIME_STEP + TIME_STEP - 1), Some(2));
+    }
+
+    #[test]
+    fn verify_rejects_two_steps_of_clock_skew() {
+        let code = hotp(KEY, 2, 6);
+        assert_eq!(verify(SECRET, &code, 0), None);
+        assert_eq!(verify(SECRET, &code, 4 * TIME_STEP), None);
+    }
+
+    #[test]
+    fn verify_ignores_spaces_and_rejects_wrong_length() {
+        let code = hotp(KEY, 2, 6);
+        let spaced = format!("{} {}", &code[..3], &code[3..]);
+        assert_eq!(verify(SECRET, &spaced, 2 * TIME_STEP), Some(2));
+        assert_eq!(verify(SECRET, &code[..5], 2 * TIME_STEP), None);
+        assert_eq!(verify(SECRET, &format!("{}0", code), 2 * TIME_STEP), None);
+        assert_eq!(verify(SECRET, &hotp(KEY, 2, 8), 2 * TIME_STEP), None);
+    }
+
+    #[test]
+    fn verify_rejects_invalid_secret() {
+        assert_eq!(verify("не base32", &hotp(KEY, 2, 6), 2 * TIME_STEP), None);
+    }
+
+    #[test]
+    fn provisioning_uri_encodes_account_and_issuer() {
+        let uri = provisioning_uri(SECRET, "ivan.petrov@mail.ru", "Учебный центр");
+        assert_eq!(
+            uri,
+            "otpauth://totp/%D0%A3%D1%87%D0%B5%D0%B1%D0%BD%D1%8B%D0%B9%20%D1%86%D0%B5%D0%BD%D1%82%D1%80:\
+             ivan.petrov%40mail.ru?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             &issuer=%D0%A3%D1%87%D0%B5%D0%B1%D0%BD%D1%8B%D0%B9%20%D1%86%D0%B5%D0%BD%D1%82%D1%80\
+             &algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}